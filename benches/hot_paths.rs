@@ -0,0 +1,113 @@
+//! Benchmarks of the hot paths: parsing, searching, filtering and tailing.
+//!
+//! `cargo bench` runs them on CI-sized synthetic logs; set
+//! `LOGROCKET_BENCH_ENTRIES` to run them on bigger ones.
+
+mod synthetic;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use log_rocket::app::LogViewerApp;
+use log_rocket::config;
+use log_rocket::log_parser::{LogEntry, LogLevel, LogParser};
+use log_rocket::search::SearchState;
+
+fn entry_count() -> usize {
+    std::env::var("LOGROCKET_BENCH_ENTRIES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(50_000)
+}
+
+/// An app with `content` loaded and parsed. Its settings, formats, plugins,
+/// scripts and profiles come from an empty scratch directory, so the user's
+/// own don't affect the numbers.
+fn loaded_app(content: String) -> LogViewerApp {
+    static CONFIG_DIR: std::sync::Once = std::sync::Once::new();
+    CONFIG_DIR.call_once(|| {
+        let dir = std::env::temp_dir().join("log-rocket-bench");
+        let _ = std::fs::remove_dir_all(&dir);
+        config::set_config_dir(dir);
+    });
+    let mut app = LogViewerApp::default();
+    app.load_text("bench".to_string(), content);
+    app.check_parse_progress(true);
+    app
+}
+
+fn parse_file(c: &mut Criterion) {
+    let count = entry_count();
+    let parser = LogParser::new();
+    let mut group = c.benchmark_group("parse_file");
+    group.sample_size(10);
+    for (name, content) in [
+        ("error_log", synthetic::error_log(count, 1)),
+        ("access_log", synthetic::access_log(count, 1)),
+        ("json", synthetic::json_log(count, 1)),
+    ] {
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parser.parse_file(&content)));
+    }
+    group.finish();
+}
+
+fn update_search(c: &mut Criterion) {
+    let entries = LogParser::new().parse_file(&synthetic::error_log(entry_count(), 2));
+    let mut group = c.benchmark_group("update_search");
+    group.throughput(Throughput::Elements(entries.len() as u64));
+    for (name, query, use_regex, case_sensitive) in [
+        ("plain", "timeout", false, false),
+        ("case_sensitive", "Session timeout", false, true),
+        ("regex", r"completed in \d{4}ms", true, false),
+    ] {
+        let mut search = SearchState::new();
+        search.query = query.to_string();
+        search.use_regex = use_regex;
+        search.case_sensitive = case_sensitive;
+        group.bench_function(name, |b| b.iter(|| search.update_search(&entries)));
+    }
+    group.finish();
+}
+
+fn apply_filters(c: &mut Criterion) {
+    let mut app = loaded_app(synthetic::error_log(entry_count(), 3));
+    let mut group = c.benchmark_group("apply_filters");
+    group.sample_size(20);
+    let problems = [LogLevel::Warn, LogLevel::Error];
+    group.bench_function("levels", |b| {
+        let mut only_problems = false;
+        b.iter(|| {
+            only_problems = !only_problems;
+            app.set_level_filter(if only_problems { &problems[..] } else { &LogLevel::ALL[..] });
+        })
+    });
+    app.set_search("timeout", false);
+    group.bench_function("levels_with_search", |b| {
+        let mut only_problems = false;
+        b.iter(|| {
+            only_problems = !only_problems;
+            app.set_level_filter(if only_problems { &problems[..] } else { &LogLevel::ALL[..] });
+            // The app searches a frame at a time; time the whole search
+            app.finish_search();
+        })
+    });
+    group.finish();
+}
+
+fn tail_append(c: &mut Criterion) {
+    let parser = LogParser::new();
+    let batch: Vec<LogEntry> = synthetic::error_log(1_000, 4)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parser.parse_line(line, i + 1))
+        .collect();
+    let mut app = loaded_app(synthetic::error_log(entry_count(), 5));
+    let mut group = c.benchmark_group("tail_append");
+    group.throughput(Throughput::Elements(batch.len() as u64));
+    group.bench_function("1k_lines", |b| {
+        b.iter_batched(|| batch.clone(), |batch| app.append_entries(batch), BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_file, update_search, apply_filters, tail_append);
+criterion_main!(benches);
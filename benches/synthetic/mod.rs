@@ -0,0 +1,127 @@
+//! Deterministic synthetic logs for the benches, shaped like real AEM logs:
+//! mostly INFO with some WARN/ERROR, stack traces on errors, repeated
+//! templates with varying IDs and numbers.
+
+/// Small xorshift generator, so runs are repeatable without a rand dependency.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    pub fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const THREADS: [&str; 5] = [
+    "qtp1234-56",
+    "sling-default-3-Thread",
+    "[0:0:0:0:0:0:0:1 [1710237600000] GET /content/site/en.html HTTP/1.1]",
+    "FelixStartLevel",
+    "oak-repository-executor-1",
+];
+
+const CLASSES: [&str; 5] = [
+    "org.apache.sling.engine.impl.SlingRequestProcessorImpl",
+    "com.day.cq.wcm.core.impl.PageManagerImpl",
+    "org.apache.jackrabbit.oak.plugins.index.IndexUpdate",
+    "com.adobe.granite.workflow.core.job.JobHandler",
+    "org.apache.sling.servlets.get.impl.DefaultGetServlet",
+];
+
+const PATHS: [&str; 4] = ["/content/site/en.html", "/content/dam/image.jpg", "/bin/querybuilder.json", "/libs/granite/core/content/login.html"];
+
+/// Timestamp parts for entry `i`, one entry every ~10ms from midnight.
+fn clock(i: usize) -> (usize, usize, usize, usize) {
+    let ms = i * 10;
+    (ms / 3_600_000 % 24, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+fn message(rng: &mut Rng) -> String {
+    match rng.below(5) {
+        0 => format!("Request {} completed in {}ms", rng.below(100_000), rng.below(5_000)),
+        1 => format!("Service [{}] ServiceEvent REGISTERED", rng.below(10_000)),
+        2 => format!("Reindexing Traversed #{} {}", rng.below(1_000_000), rng.pick(&PATHS)),
+        3 => format!("Session timeout for user user{}@example.com", rng.below(500)),
+        _ => format!("Job {} processed: topic=com/adobe/granite/workflow/job, retries={}", rng.below(50_000), rng.below(3)),
+    }
+}
+
+/// An AEM error log with `entries` entries; errors carry a short stack trace.
+pub fn error_log(entries: usize, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(entries * 160);
+    for i in 0..entries {
+        let (h, m, s, ms) = clock(i);
+        let level = match rng.below(100) {
+            0..=79 => "INFO",
+            80..=89 => "DEBUG",
+            90..=95 => "WARN",
+            96..=97 => "TRACE",
+            _ => "ERROR",
+        };
+        out.push_str(&format!(
+            "12.03.2024 {:02}:{:02}:{:02}.{:03} *{}* [{}] {} {}\n",
+            h, m, s, ms, level, rng.pick(&THREADS), rng.pick(&CLASSES), message(&mut rng)
+        ));
+        if level == "ERROR" {
+            out.push_str("java.lang.IllegalStateException: Resource not found\n");
+            for class in CLASSES.iter().take(3) {
+                out.push_str(&format!("\tat {}.handle({}.java:{})\n", class, "Impl", rng.below(900) + 10));
+            }
+        }
+    }
+    out
+}
+
+/// An access log with `entries` requests.
+pub fn access_log(entries: usize, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(entries * 150);
+    for i in 0..entries {
+        let (h, m, s, _) = clock(i);
+        let status = match rng.below(20) {
+            0 => 500,
+            1..=2 => 404,
+            3 => 302,
+            _ => 200,
+        };
+        out.push_str(&format!(
+            "10.0.{}.{} - admin 12/Mar/2024:{:02}:{:02}:{:02} +0000 \"GET {} HTTP/1.1\" {} {} \"-\" \"Mozilla/5.0\"\n",
+            rng.below(256), rng.below(256), h, m, s, rng.pick(&PATHS), status, rng.below(100_000)
+        ));
+    }
+    out
+}
+
+/// JSON lines with `entries` objects.
+pub fn json_log(entries: usize, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut out = String::with_capacity(entries * 200);
+    for i in 0..entries {
+        let (h, m, s, ms) = clock(i);
+        let level = match rng.below(10) {
+            0 => "ERROR",
+            1..=2 => "WARN",
+            _ => "INFO",
+        };
+        out.push_str(&format!(
+            "{{\"timestamp\":\"2024-03-12T{:02}:{:02}:{:02}.{:03}\",\"level\":\"{}\",\"thread\":\"{}\",\"logger\":\"{}\",\"message\":\"{}\"}}\n",
+            h, m, s, ms, level, rng.pick(&THREADS[..2]), rng.pick(&CLASSES), message(&mut rng)
+        ));
+    }
+    out
+}
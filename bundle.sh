@@ -63,6 +63,17 @@ cat > "$CONTENTS_DIR/Info.plist" <<EOF
             </array>
         </dict>
     </array>
+    <key>CFBundleURLTypes</key>
+    <array>
+        <dict>
+            <key>CFBundleURLName</key>
+            <string>com.jose.log-rocket.open</string>
+            <key>CFBundleURLSchemes</key>
+            <array>
+                <string>logrocket</string>
+            </array>
+        </dict>
+    </array>
 </dict>
 </plist>
 EOF
@@ -0,0 +1,537 @@
+use chrono::Datelike;
+use regex::Regex;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::file_open;
+use crate::log_parser::{LogEntry, LogLevel, SourceFields};
+
+/// How often a waiting thread checks whether adb has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How often the package's processes are looked up again.
+const PROCESS_INTERVAL: Duration = Duration::from_secs(2);
+/// Batches of lines held for the app. Beyond it logcat's output stops being
+/// read until the app catches up.
+const MAX_WAITING_BATCHES: usize = 256;
+/// Lines read from logcat before they are handed on together.
+const BATCH_LINES: usize = 500;
+
+/// `adb`, with `-s serial` when a device is given.
+fn adb(serial: &str) -> Command {
+    let mut command = Command::new(if cfg!(windows) { "adb.exe" } else { "adb" });
+    if !serial.is_empty() {
+        command.args(["-s", serial]);
+    }
+    command.stdin(Stdio::null());
+    command
+}
+
+/// Run an adb command to completion, returning its output.
+fn run_adb(serial: &str, args: &[&str]) -> Result<String, String> {
+    let output = adb(serial).args(args).output().map_err(|e| format!("Failed to run adb (is it on the PATH?): {}", e))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("adb {} failed: {}", args.join(" "), error.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Values to pick from, with the labels shown for them.
+type Items = Vec<(String, String)>;
+
+/// Devices or packages, listed in the background for the logcat window's pickers.
+pub struct AdbList {
+    /// The device packages were listed for; empty for the device list
+    pub key: String,
+    pub items: Items,
+    pub error: Option<String>,
+    receiver: Option<mpsc::Receiver<Result<Items, String>>>,
+}
+
+impl AdbList {
+    /// Connected devices, labelled with their model.
+    pub fn devices() -> Self {
+        Self::fetch(String::new(), || {
+            let output = run_adb("", &["devices", "-l"])?;
+            // List of devices attached
+            // emulator-5554  device product:sdk_gphone64 model:Pixel_7 device:emu64a transport_id:1
+            Ok(output
+                .lines()
+                .skip(1)
+                .filter_map(|line| {
+                    let mut words = line.split_whitespace();
+                    let serial = words.next()?;
+                    let state = words.next()?;
+                    let model = words.find_map(|word| word.strip_prefix("model:")).unwrap_or(serial).replace('_', " ");
+                    let label = match state {
+                        "device" => format!("{} ({})", model, serial),
+                        state => format!("{} ({}, {})", model, serial, state),
+                    };
+                    Some((serial.to_string(), label))
+                })
+                .collect())
+        })
+    }
+
+    /// The packages installed on a device, apart from the system's.
+    pub fn packages(serial: &str) -> Self {
+        let thread_serial = serial.to_string();
+        Self::fetch(serial.to_string(), move || {
+            let output = run_adb(&thread_serial, &["shell", "pm", "list", "packages", "-3"])?;
+            let mut packages: Vec<(String, String)> = output
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("package:"))
+                .map(|package| (package.to_string(), package.to_string()))
+                .collect();
+            packages.sort();
+            Ok(packages)
+        })
+    }
+
+    fn fetch(key: String, list: impl FnOnce() -> Result<Items, String> + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            sender.send(list()).ok();
+            file_open::wake_ui();
+        });
+        Self {
+            key,
+            items: Vec::new(),
+            error: None,
+            receiver: Some(receiver),
+        }
+    }
+
+    pub fn is_loading(&mut self) -> bool {
+        if let Some(result) = self.receiver.as_ref().and_then(|r| r.try_recv().ok()) {
+            match result {
+                Ok(items) => self.items = items,
+                Err(e) => self.error = Some(e),
+            }
+            self.receiver = None;
+        }
+        self.receiver.is_some()
+    }
+}
+
+/// What to stream, as edited in the logcat window.
+#[derive(Debug, Clone, Default)]
+pub struct LogcatConfig {
+    /// Device serial; empty when only one device is connected
+    pub serial: String,
+    /// Only show lines of this app's processes; empty for the whole device
+    pub package: String,
+}
+
+/// Streams `adb logcat` from a device: the buffered lines, then new ones
+/// as they are logged. Each line becomes an entry with its priority as
+/// level, its tag as class and its thread id as thread. A package filter
+/// follows the app's processes as they restart, and a disconnected device
+/// is waited for and resumed.
+pub struct Logcat {
+    description: String,
+    serial: String,
+    receiver: mpsc::Receiver<Vec<LogEntry>>,
+    stop: Arc<AtomicBool>,
+    /// The adb process currently running for the stream, killed on stop
+    child: Arc<Mutex<Option<Child>>>,
+    received: usize,
+}
+
+impl Logcat {
+    pub fn start(config: &LogcatConfig) -> Result<Self, String> {
+        let serial = config.serial.trim().to_string();
+        let package = config.package.trim().to_string();
+        // The app's processes are known before the buffered lines arrive
+        let pids = Arc::new(Mutex::new(HashSet::new()));
+        if !package.is_empty() {
+            *pids.lock().unwrap_or_else(|e| e.into_inner()) = package_pids(&serial, &package)?;
+        }
+        let child = Arc::new(Mutex::new(Some(spawn_logcat(&serial, None)?)));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(MAX_WAITING_BATCHES);
+        let reader = Reader {
+            serial: serial.clone(),
+            package: package.clone(),
+            pids: pids.clone(),
+            child: child.clone(),
+            sender,
+            stop: stop.clone(),
+            last: None,
+        };
+        thread::spawn(move || reader.run());
+        if !package.is_empty() {
+            let (serial, package, stop) = (serial.clone(), package.clone(), stop.clone());
+            thread::spawn(move || follow_processes(&serial, &package, &pids, &stop));
+        }
+
+        let device = if serial.is_empty() { "device" } else { serial.as_str() };
+        let description = if package.is_empty() { device.to_string() } else { format!("{} on {}", package, device) };
+        Ok(Self {
+            description,
+            serial,
+            receiver,
+            stop,
+            child,
+            received: 0,
+        })
+    }
+
+    /// The package and device streamed, e.g. "com.example.app on emulator-5554".
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Lines received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Entries received since the last call, in order. Their line numbers
+    /// are left for the caller to assign.
+    pub fn take_entries(&mut self) -> Vec<LogEntry> {
+        let entries: Vec<LogEntry> = self.receiver.try_iter().flatten().collect();
+        self.received += entries.len();
+        entries
+    }
+
+    /// Clear the device's log buffers (`logcat -c`).
+    pub fn clear(&self) -> Result<(), String> {
+        run_adb(&self.serial, &["logcat", "-c"]).map(|_| ())
+    }
+}
+
+impl Drop for Logcat {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+/// `adb logcat` in the threadtime format, from `since` (a logcat time) when given.
+fn spawn_logcat(serial: &str, since: Option<&str>) -> Result<Child, String> {
+    let mut command = adb(serial);
+    command.args(["logcat", "-v", "threadtime"]);
+    if let Some(since) = since {
+        command.args(["-T", since]);
+    }
+    command.stdout(Stdio::piped()).stderr(Stdio::null());
+    command.spawn().map_err(|e| format!("Failed to run adb (is it on the PATH?): {}", e))
+}
+
+/// The ids of the package's processes, including its `package:name` ones.
+fn package_pids(serial: &str, package: &str) -> Result<HashSet<u32>, String> {
+    let output = run_adb(serial, &["shell", "ps", "-A", "-o", "PID,NAME"])?;
+    let service = format!("{}:", package);
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let pid = words.next()?.parse().ok()?;
+            let name = words.next()?;
+            (name == package || name.starts_with(&service)).then_some(pid)
+        })
+        .collect())
+}
+
+/// Keep the package's process ids current as it is restarted.
+fn follow_processes(serial: &str, package: &str, pids: &Mutex<HashSet<u32>>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(PROCESS_INTERVAL);
+        if let Ok(found) = package_pids(serial, package) {
+            pids.lock().unwrap_or_else(|e| e.into_inner()).extend(found);
+        }
+    }
+}
+
+fn threadtime_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // 01-15 10:23:45.678  1234  1250 I ActivityManager: message
+    REGEX.get_or_init(|| {
+        Regex::new(r"^(\d\d-\d\d \d\d:\d\d:\d\d\.\d{3})\s+(\d+)\s+(\d+)\s+([VDIWEFAS])\s(.*?)\s*:(?: (.*))?$").unwrap()
+    })
+}
+
+fn start_proc_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // Start proc 4321:com.example.app/u0a123 for ...
+    REGEX.get_or_init(|| Regex::new(r"Start proc (\d+):([^/\s]+)").unwrap())
+}
+
+/// Reads logcat's output, restarting it when the device comes back.
+struct Reader {
+    serial: String,
+    package: String,
+    pids: Arc<Mutex<HashSet<u32>>>,
+    child: Arc<Mutex<Option<Child>>>,
+    sender: mpsc::SyncSender<Vec<LogEntry>>,
+    stop: Arc<AtomicBool>,
+    /// Time and text of the last line read, to resume after it
+    last: Option<(String, String)>,
+}
+
+impl Reader {
+    fn run(mut self) {
+        loop {
+            let stdout = self.child.lock().unwrap_or_else(|e| e.into_inner()).as_mut().and_then(|child| child.stdout.take());
+            if let Some(stdout) = stdout {
+                if !self.read(stdout) {
+                    return;
+                }
+            }
+            self.wait_child();
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+
+            // The device went away (or adb did); wait for it and resume
+            if !self.note("──────── logcat stopped, waiting for the device ────────") {
+                return;
+            }
+            let waiting = adb(&self.serial).arg("wait-for-device").stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+            match waiting {
+                Ok(waiting) => *self.child.lock().unwrap_or_else(|e| e.into_inner()) = Some(waiting),
+                Err(e) => {
+                    self.note(&format!("──────── Failed to run adb: {} ────────", e));
+                    return;
+                }
+            }
+            let found = self.wait_child();
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if !found {
+                // e.g. several devices connected and none picked
+                self.note("──────── adb can't wait for the device; pick one and start again ────────");
+                return;
+            }
+            let since = self.last.as_ref().map(|(time, _)| time.as_str());
+            match spawn_logcat(&self.serial, since) {
+                Ok(child) => *self.child.lock().unwrap_or_else(|e| e.into_inner()) = Some(child),
+                Err(e) => {
+                    self.note(&format!("──────── {} ────────", e));
+                    return;
+                }
+            }
+            if !self.note("──────── Device reconnected ────────") {
+                return;
+            }
+        }
+    }
+
+    /// Wait for the running adb process to exit, leaving it where stopping
+    /// can kill it. Whether it succeeded.
+    fn wait_child(&self) -> bool {
+        loop {
+            let mut slot = self.child.lock().unwrap_or_else(|e| e.into_inner());
+            let status = match slot.as_mut().map(Child::try_wait) {
+                Some(Ok(None)) => None,
+                Some(Ok(Some(status))) => Some(status.success()),
+                Some(Err(_)) | None => Some(false),
+            };
+            if let Some(success) = status {
+                slot.take();
+                return success;
+            }
+            drop(slot);
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn note(&self, text: &str) -> bool {
+        let sent = self.sender.send(vec![LogEntry::note(text, 0)]).is_ok();
+        file_open::wake_ui();
+        sent
+    }
+
+    /// Hand on logcat's lines until it exits. False once the app is gone.
+    fn read(&mut self, stdout: impl std::io::Read) -> bool {
+        let mut reader = BufReader::new(stdout);
+        let mut buffer = Vec::new();
+        let mut batch = Vec::new();
+        // Resuming from the last time repeats the lines logged in that millisecond
+        let mut resume = self.last.clone();
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer);
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+            let done = !matches!(read, Ok(n) if n > 0);
+            if !done && !line.is_empty() {
+                let caps = threadtime_regex().captures(line);
+                let time = caps.as_ref().map(|caps| caps[1].to_string());
+                if let (Some((last_time, last_line)), Some(time)) = (&resume, &time) {
+                    if time < last_time || (time == last_time && line != last_line) {
+                        continue;
+                    }
+                    let repeat = line == last_line;
+                    resume = None;
+                    if repeat {
+                        continue;
+                    }
+                }
+                if let Some(entry) = self.entry(line, caps.as_ref()) {
+                    batch.push(entry);
+                }
+                if let Some(time) = time {
+                    self.last = Some((time, line.to_string()));
+                }
+            }
+            // Hand on a batch when it's full or logcat has nothing more for now
+            if !batch.is_empty() && (done || batch.len() >= BATCH_LINES || reader.buffer().is_empty()) {
+                if self.sender.send(std::mem::take(&mut batch)).is_err() {
+                    return false;
+                }
+                file_open::wake_ui();
+            }
+            if done {
+                return !self.stop.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// The entry for a line, or none when it belongs to another app's process.
+    fn entry(&self, line: &str, caps: Option<&regex::Captures>) -> Option<LogEntry> {
+        let Some(caps) = caps else {
+            // "--------- beginning of main" and the like
+            return self.package.is_empty().then(|| LogEntry::note(line, 0));
+        };
+        let pid: u32 = caps[2].parse().unwrap_or_default();
+        let tag = caps.get(5).map_or("", |m| m.as_str());
+        let message = caps.get(6).map_or("", |m| m.as_str());
+        if !self.package.is_empty() {
+            let mut pids = self.pids.lock().unwrap_or_else(|e| e.into_inner());
+            // A restarted app shows up here before the next process lookup
+            if tag == "ActivityManager" {
+                if let Some(start) = start_proc_regex().captures(message) {
+                    let name = &start[2];
+                    if name == self.package || name.strip_prefix(self.package.as_str()).is_some_and(|rest| rest.starts_with(':')) {
+                        pids.extend(start[1].parse::<u32>().ok());
+                    }
+                }
+            }
+            if !pids.contains(&pid) {
+                return None;
+            }
+        }
+        let timestamp = caps.get(1).map(|m| m.as_str());
+        Some(LogEntry::from_source(line, 0, SourceFields {
+            level: priority_level(&caps[4]),
+            timestamp,
+            time_ms: timestamp.and_then(logcat_millis),
+            thread: caps.get(3).map(|m| m.as_str()),
+            class: Some(tag),
+            message,
+        }))
+    }
+}
+
+/// The level of a logcat priority letter.
+fn priority_level(priority: &str) -> LogLevel {
+    match priority {
+        "V" => LogLevel::Trace,
+        "D" => LogLevel::Debug,
+        "I" => LogLevel::Info,
+        "W" => LogLevel::Warn,
+        "E" | "F" | "A" => LogLevel::Error,
+        _ => LogLevel::Unknown,
+    }
+}
+
+/// A logcat time has no year; assume the current one.
+fn logcat_millis(timestamp: &str) -> Option<i64> {
+    let year = chrono::Local::now().year();
+    chrono::NaiveDateTime::parse_from_str(&format!("{}-{}", year, timestamp), "%Y-%m-%d %H:%M:%S%.3f")
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "--------- beginning of main
+01-15 10:23:45.678  1234  1250 I ActivityManager: Start proc 4321:com.example.app/u0a123 for activity
+01-15 10:23:45.700  4321  4321 D MainActivity: onCreate
+01-15 10:23:45.701  4321  4330 W OkHttp : slow response
+01-15 10:23:45.702  999   999  E Other: not ours
+01-15 10:23:45.703  4321  4321 F libc    : Fatal signal 11 (SIGSEGV)
+01-15 10:23:46.000  4321  4321 I Empty   :
+garbled \u{fffd} line
+";
+
+    fn reader(package: &str, last: Option<(&str, &str)>) -> (Reader, mpsc::Receiver<Vec<LogEntry>>) {
+        let (sender, receiver) = mpsc::sync_channel(16);
+        let reader = Reader {
+            serial: String::new(),
+            package: package.to_string(),
+            pids: Arc::new(Mutex::new(HashSet::new())),
+            child: Arc::new(Mutex::new(None)),
+            sender,
+            stop: Arc::new(AtomicBool::new(false)),
+            last: last.map(|(time, line)| (time.to_string(), line.to_string())),
+        };
+        (reader, receiver)
+    }
+
+    fn read(reader: &mut Reader, receiver: &mpsc::Receiver<Vec<LogEntry>>, output: &str) -> Vec<LogEntry> {
+        assert!(reader.read(output.as_bytes()));
+        receiver.try_iter().flatten().collect()
+    }
+
+    #[test]
+    fn parses_threadtime_lines() {
+        let (mut reader, receiver) = reader("", None);
+        let entries = read(&mut reader, &receiver, LOG);
+        assert_eq!(entries.len(), 8);
+        assert_eq!(entries[0].level, LogLevel::Unknown);
+        assert_eq!(entries[0].raw_line, "--------- beginning of main");
+
+        let entry = &entries[3];
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.timestamp(), Some("01-15 10:23:45.701"));
+        assert!(entry.time_ms().is_some());
+        assert_eq!(entry.thread(), Some("4330"));
+        assert_eq!(entry.class(), Some("OkHttp"));
+        assert_eq!(entry.message(), "slow response");
+
+        assert_eq!(entries[5].level, LogLevel::Error);
+        assert_eq!(entries[5].message(), "Fatal signal 11 (SIGSEGV)");
+        assert_eq!(entries[6].class(), Some("Empty"));
+        assert_eq!(entries[6].message(), "");
+        // Lines logcat didn't format are kept as they are
+        assert_eq!(entries[7].level, LogLevel::Unknown);
+        assert_eq!(reader.last.as_ref().map(|(time, _)| time.as_str()), Some("01-15 10:23:46.000"));
+    }
+
+    #[test]
+    fn keeps_only_the_package_processes() {
+        let (mut reader, receiver) = reader("com.example.app", None);
+        let entries = read(&mut reader, &receiver, LOG);
+        let tags: Vec<_> = entries.iter().map(|entry| entry.class().unwrap_or_default()).collect();
+        // The process start names the app's pid; other processes and notes are left out
+        assert_eq!(tags, ["MainActivity", "OkHttp", "libc", "Empty"]);
+        assert!(reader.pids.lock().unwrap().contains(&4321));
+    }
+
+    #[test]
+    fn resumes_after_the_last_line_read() {
+        let last = "01-15 10:23:45.701  4321  4330 W OkHttp : slow response";
+        let (mut reader, receiver) = reader("", Some(("01-15 10:23:45.701", last)));
+        let entries = read(&mut reader, &receiver, LOG);
+        let lines: Vec<_> = entries.iter().map(|entry| entry.raw_line.as_str()).collect();
+        // logcat's banner comes again with the restart; the lines up to the last one read don't
+        assert_eq!(lines[0], "--------- beginning of main");
+        assert!(lines[1].ends_with("E Other: not ours"));
+        assert_eq!(lines.len(), 5);
+    }
+}
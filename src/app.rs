@@ -1,21 +1,182 @@
 use eframe::egui;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::{self, BufRead, BufReader, Read, Seek};
-use crate::log_parser::{LogParser, LogEntry, LogLevel};
-use crate::file_watcher::FileWatcher;
-use crate::config::{AppConfig, ColorPalette, Theme};
-use crate::search::SearchState;
+use std::io::{self, Read, Seek};
+use crate::log_parser::{EntryStart, LogParser, LogEntry, LogLevel, LogFormat};
+use crate::file_watcher::{self, FileWatcher, TailOptions};
+use crate::config::{self, AppConfig, OverflowPolicy, StartPosition, Theme};
+use crate::search::{self, SearchState};
+use crate::redaction::{RedactionRules, Redactor};
+use crate::share::{ShareTarget, Upload};
+use crate::diagnostics::{self, Severity};
+use crate::update_check::{Release, UpdateCheck, UpdateDownload};
+use crate::file_open::{self, OpenRequest};
+use crate::dir_browser::{self, DirectoryBrowser};
+use crate::glob_watch::{self, GlobWatch};
+use crate::syslog::{ListenConfig, SyslogListener};
+use crate::otlp::{OtlpConfig, OtlpReceiver};
+use crate::fluent::{ForwardConfig, ForwardListener};
+use crate::adb::{AdbList, Logcat, LogcatConfig};
+use crate::unified_log::{LogDetail, UnifiedLog, UnifiedLogConfig};
+use crate::remote::{RemoteDialog, RemoteTail};
+use crate::kafka::{KafkaDialog, KafkaSource};
+use crate::loki::LokiDialog;
+use crate::elastic::ElasticDialog;
+use crate::cloudwatch::CloudWatchDialog;
+use crate::gcp::{CloudLogs, CloudLogsDialog};
+use crate::object_store::{self, Download};
+use crate::live_stream::{self, LiveStream, StreamDialog};
+use crate::source_event::{SourceAction, SourceEvent};
+use crate::ingest::IngestQueue;
+use crate::profiles::{self, Profile};
+use crate::custom_format::{self, CustomFormat};
+use crate::format_builder::FormatBuilder;
+use crate::merge::{self, MergeSource};
+use crate::source_pane::SourcePane;
+use crate::scratchpad::{self, PinnedEntry};
+use crate::investigations::{self, Investigation};
+use crate::plugins;
+use crate::metrics::{self, MetricExtractor, MetricSeries};
+use crate::timeline::{self, Timeline};
+use crate::patterns::PatternIndex;
+use crate::noise::{self, RepeatedMessage};
+use crate::transactions::{self, SortColumn, Transaction, TransactionRules};
+use crate::heatmap::{self, Heatmap};
+use crate::report::{self, ReportInput};
+use crate::export::{self, ExportFormat};
+use crate::file_state::{self, FileState};
+use crate::window_state::{self, WindowState};
+use crate::settings::{SettingsAction, SettingsDialog, SettingsTab};
+use crate::scripting::{EntryScript, ScriptVerdict};
+use crate::unparsed::Unparsed;
+use crate::parse_worker::ParseJob;
+use crate::disk_log::DiskLog;
+use crate::devtools;
+use crate::text_encoding::{self, TextFormat};
+use crate::index_cache::IndexKey;
+
+/// Text loaded without a backing file, e.g. pasted from the clipboard.
+struct MemorySource {
+    name: String,
+    content: String,
+}
+
+/// What the log view's layout depends on.
+#[derive(PartialEq)]
+struct ViewKey {
+    generation: u64,
+    search: (u64, Option<usize>),
+    font_size: f32,
+    wrap_width: f32,
+    theme: Theme,
+    scroll_target: Option<usize>,
+}
+
+/// Width of the gutter's margin for pins, left of the line numbers.
+const GUTTER_MARGIN: f32 = 16.0;
+
+/// Background of every other entry with zebra stripes on; gray, to suit both themes.
+const ZEBRA_STRIPE: egui::Color32 = egui::Color32::from_rgba_premultiplied(0x0c, 0x0c, 0x0c, 0x18);
+/// How long entries brought in by a manual reload stay tinted.
+const NEW_ENTRY_HIGHLIGHT: std::time::Duration = std::time::Duration::from_secs(4);
+/// Background of those entries, faint enough to leave level colors readable.
+const NEW_ENTRY_TINT: egui::Color32 = egui::Color32::from_rgba_premultiplied(0x14, 0x3c, 0x1e, 0x3c);
+
+/// The laid-out log view, reused across frames while its key is unchanged.
+struct ViewCache {
+    key: ViewKey,
+    text: String,
+    galley: std::sync::Arc<egui::Galley>,
+    target_char_index: Option<usize>, // Where the scroll target line starts in `text`
+    entry_starts: Vec<(usize, usize)>, // Where each entry starts in `text`, with its index
+    entry_tops: Vec<f32>, // Where each entry's first row is in the galley, for the gutter
+    expanders: Vec<(std::ops::Range<usize>, usize)>, // "show full line" markers in `text`, with their entry
+    occurrences: Option<(std::ops::Range<usize>, Vec<Occurrence>)>, // Of the selected word, for the selection they were found for
+}
+
+/// Where a word occurs in the laid-out view: its row in the galley and its
+/// first and past-the-end columns on that row.
+type Occurrence = (usize, usize, usize);
+
+/// Entries checked for a level when telling whether the format fits.
+const FORMAT_CHECK_SAMPLE: usize = 10_000;
+
+/// Entries a purge of the scrollback keeps, so a tail goes on with some context.
+const PURGE_KEEP: usize = 1_000;
+
+/// Most occurrences of a selected word that are highlighted.
+const MAX_OCCURRENCES: usize = 10_000;
 
 pub struct LogViewerApp {
     config: AppConfig,
+    redactor: Redactor, // Built from config.redaction
     parser: LogParser,
     file_watcher: FileWatcher,
+    config_watcher: FileWatcher,
+    formats_watcher: FileWatcher,
     search: SearchState,
+    dir_browser: DirectoryBrowser,
+    glob_watch: Option<GlobWatch>, // Following the newest file matching a pattern
+    glob_input: String, // Pattern typed in the folder panel
+    listener: Option<SyslogListener>, // Set instead of a file while receiving syslog messages
+    listen_config: ListenConfig,
+    listen_error: Option<String>,
+    show_listen: bool,
+    otlp: Option<OtlpReceiver>, // Set instead of a file while receiving OpenTelemetry logs
+    otlp_config: OtlpConfig,
+    otlp_error: Option<String>,
+    show_otlp: bool,
+    forward: Option<ForwardListener>, // Set instead of a file while receiving Fluentd or Fluent Bit records
+    forward_config: ForwardConfig,
+    forward_error: Option<String>,
+    show_forward: bool,
+    logcat: Option<Logcat>, // Set instead of a file while streaming an Android device's logcat
+    logcat_config: LogcatConfig,
+    logcat_error: Option<String>,
+    adb_devices: Option<AdbList>,
+    adb_packages: Option<AdbList>,
+    show_logcat: bool,
+    unified_log: Option<UnifiedLog>, // Set instead of a file while streaming the macOS unified log
+    unified_log_config: UnifiedLogConfig,
+    unified_log_error: Option<String>,
+    show_unified_log: bool,
+    remote: Option<RemoteTail>, // Set instead of a file while tailing one over SSH
+    remote_dialog: RemoteDialog,
+    live_stream: Option<LiveStream>, // Set instead of a file while reading a WebSocket or SSE stream
+    stream_dialog: StreamDialog,
+    loki_dialog: LokiDialog, // Loki results are read as a live stream
+    elastic_dialog: ElasticDialog, // Search hits are read as a live stream too
+    cloudwatch_dialog: CloudWatchDialog, // CloudWatch events are read as a live stream as well
+    cloud_logs_dialog: CloudLogsDialog, // Google Cloud and Azure results are read as live streams too
+    download: Option<Download>, // An s3:// or gs:// object being fetched, opened once done
+    bucket_url: String,
+    bucket_error: Option<String>,
+    show_bucket: bool,
+    kafka: Option<KafkaSource>, // Set instead of a file while consuming a Kafka topic
+    kafka_dialog: KafkaDialog,
+    
+    // Share Selection window
+    share_entries: Vec<usize>, // Indices into entries, captured when the window opened
+    share_upload: Option<Upload>,
+    share_result: Option<Result<String, String>>,
+    show_share: bool,
     
     current_file: Option<PathBuf>,
+    memory_source: Option<MemorySource>,
+    merge_sources: Vec<MergeSource>, // Non-empty when several files are merged into one view
+    source_pane: Option<SourcePane>, // A merged file shown beside the view
     entries: Vec<LogEntry>,
     filtered_entries: Vec<usize>, // Indices into entries
+    view_generation: u64, // Bumped whenever the filtered entries or their colors change
+    view_cache: Option<ViewCache>,
+    view_selection: Option<std::ops::Range<usize>>, // Characters of the view's text selected
+    parse_job: Option<ParseJob>, // Entries still arriving from a background parse
+    pending_goto: Option<(usize, usize)>, // Source and line to jump to once parsing finishes
+    start_position_pending: bool, // Jump to the configured start position once parsing finishes
+    disk_log: Option<DiskLog>, // Set instead of entries for a large file viewed from disk
+    ingest: IngestQueue, // Live entries waiting for the next refresh
+    live_entry_open: bool, // Streamed lines that don't start an entry go on the last one
     
     tail_log: bool,
     scroll_to_end: bool,
@@ -23,6 +184,7 @@ pub struct LogViewerApp {
     
     scroll_offset: f32,
     last_file_size: u64,
+    last_file_id: Option<u64>, // Inode of the tailed file, to notice it being replaced
     
     show_search: bool,
     show_sidebar: bool,
@@ -30,369 +192,4239 @@ pub struct LogViewerApp {
     
     // New state fields
     focus_search: bool,
+    jump_to_first_match: bool, // Scroll to the first match once the search finds one
+    match_levels: (u64, Vec<(LogLevel, usize)>), // Matches per level, for the search generation
+    expanded_entries: std::collections::HashSet<usize>, // Long lines shown in full
     scroll_to_match: bool,
     scroll_to_top: bool,
     scroll_target_line: Option<usize>, // Line to scroll to
     target_scroll_offset: Option<f32>, // Calculated Y offset to scroll to
     wrap_text: bool, // Whether to wrap long lines
+    
+    // Workspace profiles
+    profiles: Vec<Profile>,
+    active_profile: Option<String>,
+    show_save_profile: bool,
+    new_profile_name: String,
+    
+    custom_formats: Vec<CustomFormat>,
+    format_builder: FormatBuilder,
+    entry_start_pattern: String, // Edited regex for EntryStart::Pattern
+    entry_start_error: Option<String>,
+    
+    script: Option<EntryScript>,
+    script_verdicts: Vec<ScriptVerdict>, // Parallel to entries, filled lazily
+    
+    // Metric extraction
+    metric_extractors: Vec<MetricExtractor>,
+    metric_series: Vec<MetricSeries>,
+    show_metrics: bool,
+    new_metric_name: String,
+    new_metric_pattern: String,
+    metric_error: Option<String>,
+    
+    timeline: Option<Timeline>,
+    show_timeline: bool,
+    
+    // Message templates, built when the patterns window is first opened
+    patterns: Option<PatternIndex>,
+    show_patterns: bool,
+    pattern_filter: Option<usize>,
+    
+    // Noise report
+    noise_report: Option<Vec<RepeatedMessage>>,
+    show_noise: bool,
+    excluded_messages: std::collections::HashSet<String>,
+    
+    // Transaction grouping
+    transaction_rules: TransactionRules,
+    transactions: Vec<Transaction>,
+    transaction_error: Option<String>,
+    show_transactions: bool,
+    transaction_sort: (SortColumn, bool), // Column, descending
+    transaction_filter: Option<Vec<usize>>, // Entries of the drilled-down transaction
+    transaction_gantt: bool, // Gantt chart instead of the table
+    
+    // Volume heatmap, built when its window is open
+    heatmap: Option<Heatmap>,
+    show_heatmap: bool,
+    heatmap_errors_only: bool,
+    
+    // Remembered per-file view state, keyed by canonical path
+    file_states: std::collections::HashMap<String, FileState>,
+    window_state: WindowState,
+    settings: SettingsDialog,
+    /// Why the saved settings couldn't be loaded, shown until dismissed
+    config_error: Option<String>,
+    /// The last crash's report, shown once on the next launch
+    crash_report: Option<(PathBuf, String)>,
+    show_app_log: bool,
+    unparsed: Unparsed, // Lines of the loaded text no entry holds
+    show_unparsed: bool,
+    /// Entries pinned from any file, kept across sessions
+    scratchpad: Vec<PinnedEntry>,
+    show_scratchpad: bool,
+    investigations: Vec<Investigation>,
+    show_investigations: bool,
+    new_investigation_name: String,
+    /// What the last scratchpad import or export did
+    scratchpad_message: Option<Result<String, String>>,
+    relink_pending: bool, // Find pins and the reading position again once a reload is parsed
+    reload_anchor: Option<(usize, String)>, // Line and text of the entry at the top before the reload
+    reload_baseline: Option<std::collections::HashMap<u64, usize>>, // How often each entry occurred before a manual reload
+    new_entries: std::collections::HashSet<usize>, // Entries a manual reload brought in, tinted for a moment
+    new_entries_until: Option<std::time::Instant>,
+    palette_file: Option<Option<PathBuf>>, // The file the palette was picked for; None to pick it again
+    memory_estimate: Option<((usize, usize), usize)>, // Bytes the entries take, for the entry count and first line it was summed for
+    unknown_share: Option<(String, f32)>, // Share of sampled entries without a level, for the source and parser it was counted for
+    format_warning_dismissed: Option<String>, // The source and parser whose format warning was closed
+    
+    // Update check
+    update_check: Option<UpdateCheck>,
+    update: Option<Release>, // A newer release, shown in a banner until dismissed
+    update_download: Option<UpdateDownload>,
+    update_download_result: Option<Result<PathBuf, String>>,
+    show_release_notes: bool,
+}
+
+/// Read a log file for display. Returns the text and the offset tailing
+/// continues from, the end of what was read.
+fn read_log_content(path: &Path, config: &AppConfig) -> Result<(String, u64), String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let format = TextFormat::of_file(&mut file).map_err(|e| format!("Failed to read file: {}", e))?;
+    let bom = format.bom as u64;
+
+    // For very large files, read only the tail
+    let whole = metadata.len() <= config.large_file_threshold;
+    let start = if whole {
+        bom
+    } else {
+        format.encoding.align(metadata.len() - config.tail_window_size.min(metadata.len())).max(bom)
+    };
+    file.seek(io::SeekFrom::Start(start))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+    // To the end as it is now, which may be past the size above
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    // A last line still being written is left for the tail to read once it
+    // ends, rather than shown cut off and then again in full
+    let written = file.metadata().is_ok_and(|metadata| text_encoding::is_being_written(&metadata));
+    if config.tail_log && written && !format.ends_line(&buffer) {
+        buffer.truncate(format.last_line_end(&buffer).unwrap_or(0));
+    }
+    let end = start + buffer.len() as u64;
+
+    let content = format.decode(&buffer);
+    // HAR files and DevTools console exports are read as their requests and messages
+    let content = if whole { devtools::convert(&content).unwrap_or(content) } else { content };
+    Ok((content, end))
+}
+
+/// Line number for an entry added after `entries`, continuing from the last
+/// one as the oldest may have been dropped.
+fn next_line_after(entries: &[LogEntry]) -> usize {
+    entries.last()
+        .map_or(0, |e| e.line_number + e.raw_line.lines().count().saturating_sub(1))
+        + 1
+}
+
+/// Number `entries` as consecutive lines of their file from `first`, each
+/// taking as many lines as its text.
+fn number_lines(entries: &mut [LogEntry], first: usize) {
+    let mut next = first;
+    for entry in entries {
+        entry.line_number = next;
+        next += entry.raw_line.lines().count().max(1);
+    }
+}
+
+/// Identifies an entry by its source and text, to compare two loads of a file.
+fn entry_fingerprint(entry: &LogEntry) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    entry.source.hash(&mut hasher);
+    entry.raw_line.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Every occurrence in the view's `text` of the word the `selection` (a
+/// range of chars) covers, as whole words. Empty when the selection isn't a
+/// single word or identifier.
+fn word_occurrences(text: &str, galley: &egui::Galley, selection: &std::ops::Range<usize>) -> Vec<Occurrence> {
+    let byte_at = |char_index: usize| text.char_indices().nth(char_index).map_or(text.len(), |(i, _)| i);
+    let (start, end) = (byte_at(selection.start), byte_at(selection.end));
+    let word = &text[start..end];
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    if word.is_empty() || word.chars().count() > 200 || word.contains(char::is_whitespace) || !word.contains(is_word_char) {
+        return Vec::new();
+    }
+    // `id` shouldn't light up inside `userid`, but `.log` may follow anything
+    let check_before = word.starts_with(is_word_char);
+    let check_after = word.ends_with(is_word_char);
+    let found = text.match_indices(word).map(|(i, _)| i).filter(|&i| {
+        (!check_before || !text[..i].ends_with(is_word_char)) && (!check_after || !text[i + word.len()..].starts_with(is_word_char))
+    });
+    
+    // Byte offsets to rows and columns, in one pass over the text and the rows
+    let word_chars = word.chars().count();
+    let mut occurrences = Vec::new();
+    let (mut byte, mut char_index) = (0, 0);
+    let mut rows = galley.rows.iter().enumerate();
+    let mut row = rows.next();
+    let mut row_start = 0;
+    for offset in found.take(MAX_OCCURRENCES) {
+        char_index += text[byte..offset].chars().count();
+        byte = offset;
+        while let Some((_, current)) = row {
+            let row_chars = current.char_count_excluding_newline() + usize::from(current.ends_with_newline);
+            if row_start + row_chars > char_index {
+                break;
+            }
+            row_start += row_chars;
+            row = rows.next();
+        }
+        let Some((row_index, current)) = row else {
+            break;
+        };
+        let column = char_index - row_start;
+        // A word wrapped onto the next row is marked up to the end of its first
+        occurrences.push((row_index, column, (column + word_chars).min(current.char_count_excluding_newline())));
+    }
+    occurrences
+}
+
+/// A ▾ menu of the device's apps, listed again when the device changes.
+fn package_picker(ui: &mut egui::Ui, config: &mut LogcatConfig, slot: &mut Option<AdbList>) {
+    ui.menu_button("▾", |ui| {
+        let stale = match slot {
+            Some(list) => list.key != config.serial,
+            None => true,
+        };
+        if stale {
+            *slot = Some(AdbList::packages(&config.serial));
+        }
+        let Some(list) = slot.as_mut() else { return };
+        if list.is_loading() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading...");
+            });
+            return;
+        }
+        if let Some(ref error) = list.error {
+            ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+            if ui.button("Retry").clicked() {
+                *slot = Some(AdbList::packages(&config.serial));
+            }
+            return;
+        }
+        if list.items.is_empty() {
+            ui.label("No apps installed");
+        }
+        let mut picked = None;
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            if ui.selectable_label(config.package.is_empty(), "All of the device").clicked() {
+                picked = Some(String::new());
+            }
+            for (package, _) in &list.items {
+                if ui.selectable_label(config.package == *package, package.as_str()).clicked() {
+                    picked = Some(package.clone());
+                }
+            }
+        });
+        if let Some(package) = picked {
+            config.package = package;
+            ui.close_menu();
+        }
+    });
+}
+
+/// A short reference of the regex syntax search accepts.
+fn regex_cheatsheet(ui: &mut egui::Ui) {
+    const SYNTAX: [(&str, &str); 12] = [
+        (".", "Any character"),
+        (r"\d  \w  \s", "A digit, word character, whitespace"),
+        ("[abc]  [^abc]", "One of, or none of, a b c"),
+        ("[a-z]", "A character in the range"),
+        ("a*  a+  a?", "Any number, at least one, at most one a"),
+        ("a{2,5}", "Two to five a"),
+        ("^  $", "Start, end of the line"),
+        ("a|b", "a or b"),
+        ("(...)", "A group, e.g. (GET|POST) /api"),
+        (r"\b", "A word boundary"),
+        (r"\.  \(  \[", "A literal . ( ["),
+        ("(?i)", "Ignore case from here on"),
+    ];
+    egui::Grid::new("regex_cheatsheet").num_columns(2).spacing([16.0, 4.0]).show(ui, |ui| {
+        for (pattern, meaning) in SYNTAX {
+            ui.label(egui::RichText::new(pattern).monospace());
+            ui.label(meaning);
+            ui.end_row();
+        }
+    });
+    ui.add_space(4.0);
+    ui.label(egui::RichText::new(r"e.g. timeout|refused  or  user=\d+").size(12.0).weak());
 }
 
 impl LogViewerApp {
     pub fn load_file(&mut self, path: PathBuf) -> Result<(), String> {
-        // Read file efficiently
-        let file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
-        let metadata = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?;
-        self.last_file_size = metadata.len();
-        
-        // For large files, use memory-mapped reading
-        let content = if metadata.len() > 10_000_000 {
-            // For very large files, read only the tail (last 2MB or so)
-            let tail_size = 2_000_000.min(metadata.len());
-            let mut buffer = vec![0u8; tail_size as usize];
-            let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
-            file.seek(io::SeekFrom::End(-(tail_size as i64)))
-                .map_err(|e| format!("Failed to seek: {}", e))?;
-            file.read_exact(&mut buffer)
-                .map_err(|e| format!("Failed to read: {}", e))?;
-            String::from_utf8_lossy(&buffer).to_string()
+        // Opening anything else stops following a pattern
+        if !self.glob_watch.as_ref().is_some_and(|glob| glob.matches(&path)) {
+            self.glob_watch = None;
+        }
+        // Pipes have no size and block until written to, so they are streamed
+        if live_stream::is_pipe(&path) {
+            self.clear_for_stream();
+            self.live_stream = Some(LiveStream::pipe(&path));
+            return Ok(());
+        }
+        if self.config.low_memory_mode {
+            let size = fs::metadata(&path).map_err(|e| format!("Failed to read metadata: {}", e))?.len();
+            if size > self.config.large_file_threshold {
+                return self.open_disk_log(path);
+            }
+        }
+        let (content, file_size) = read_log_content(&path, &self.config)?;
+        self.disk_log = None;
+        self.stop_streams();
+        self.last_file_size = file_size;
+        self.last_file_id = fs::metadata(&path).ok().and_then(|m| file_watcher::file_id(&m));
+        self.merge_sources.clear();
+        
+        // Switching files: remember how the old one was viewed, restore the new one
+        let restored = if self.current_file.as_ref() != Some(&path) {
+            self.remember_file_state();
+            self.file_states.get(&file_state::key(&path)).cloned()
         } else {
-            // For smaller files, read entirely
-            fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?
+            None
         };
+        if let Some(ref state) = restored {
+            self.parser.format = state.format.clone();
+            if let EntryStart::Pattern(ref pattern) = state.entry_start {
+                self.entry_start_pattern = pattern.clone();
+            }
+            if let Err(e) = self.parser.set_entry_start(state.entry_start.clone()) {
+                app_warn!("Ignoring the entry start regex saved for {}: {}", path.display(), e);
+            }
+        }
         
-        self.entries = self.parser.parse_file(&content);
-        self.current_file = Some(path.clone());
+        let index = IndexKey::new(&path, &content, &self.parser);
+        self.start_parse(content, index);
         self.current_file = Some(path.clone());
+        self.memory_source = None;
+        self.start_position_pending = self.config.start_position != StartPosition::End;
         self.auto_scroll_frames = 5; // Force scroll for 5 frames to ensure layout settles
         self.scroll_offset = f32::MAX;
         
         // Start watching the file
         if self.tail_log {
-            self.file_watcher.watch_file(path).ok();
+            self.start_tail();
+        } else {
+            self.file_watcher.stop();
+        }
+        
+        if let Some(state) = restored {
+            if !state.levels.is_empty() {
+                self.enabled_levels = state.levels.iter().cloned().collect();
+            }
+            self.search.query = state.search_query;
+            self.search.use_regex = state.search_regex;
+            // Following the end wins over the remembered position
+            if !self.scroll_to_end {
+                self.target_scroll_offset = Some(state.scroll_offset);
+                self.auto_scroll_frames = 0;
+            }
         }
         
         // Update search and apply filters to populate filtered_entries
-        self.search.update_search(&self.entries);
+        self.search.start_search(&self.entries);
         self.apply_filters();
         
         Ok(())
     }
     
-    fn check_file_updates(&mut self) {
-        if !self.tail_log || !self.file_watcher.is_watching() {
+    /// View a large file in full from disk, keeping only its entry index in memory.
+    fn open_disk_log(&mut self, path: PathBuf) -> Result<(), String> {
+        let disk_log = DiskLog::open(&path, self.parser.clone())?;
+        if self.current_file.as_ref() != Some(&path) {
+            self.remember_file_state();
+        }
+        self.file_watcher.stop();
+        self.merge_sources.clear();
+        self.stop_streams();
+        self.parse_job = None;
+        self.pending_goto = None;
+        self.entries.clear();
+        self.entries_replaced();
+        self.apply_filters();
+        self.current_file = Some(path);
+        self.memory_source = None;
+        self.last_file_size = 0;
+        self.disk_log = Some(disk_log);
+        Ok(())
+    }
+    
+    /// Store the current file's format, filters and scroll position.
+    fn remember_file_state(&mut self) {
+        let Some(ref path) = self.current_file else {
             return;
+        };
+        if !self.merge_sources.is_empty() {
+            return;
+        }
+        let state = FileState {
+            format: self.parser.format.clone(),
+            entry_start: self.parser.entry_start().clone(),
+            levels: LogLevel::ALL.iter().filter(|l| self.enabled_levels.contains(l)).cloned().collect(),
+            search_query: self.search.query.clone(),
+            search_regex: self.search.use_regex,
+            scroll_offset: self.scroll_offset,
+            last_viewed: chrono::Utc::now().timestamp(),
+        };
+        self.file_states.insert(file_state::key(path), state);
+        if let Err(e) = file_state::save_file_states(&mut self.file_states) {
+            app_error!("Error saving file state: {}", e);
         }
+    }
+    
+    /// Load text that has no backing file (e.g. the clipboard) through the normal pipeline.
+    pub fn load_text(&mut self, name: String, content: String) {
+        self.remember_file_state();
+        self.file_watcher.stop();
+        self.merge_sources.clear();
+        self.disk_log = None;
+        self.glob_watch = None;
+        self.stop_streams();
+        self.current_file = None;
+        self.last_file_size = 0;
+        self.start_parse(content.clone(), None);
+        self.memory_source = Some(MemorySource { name, content });
+        self.start_position_pending = self.config.start_position != StartPosition::End;
+        self.auto_scroll_frames = 5;
+        self.scroll_offset = f32::MAX;
         
-        if self.file_watcher.check_for_changes() {
-            if let Some(ref path) = self.current_file {
-                if let Ok(metadata) = fs::metadata(path) {
-                    let new_size = metadata.len();
-                    if new_size > self.last_file_size {
-                        // Read new content
-                        if let Ok(file) = fs::File::open(path) {
-                            let mut reader = BufReader::new(file);
-                            reader.seek(io::SeekFrom::Start(self.last_file_size))
-                                .ok();
-                            
-                            let mut new_lines = Vec::new();
-                            let mut line_buf = String::new();
-                            let start_line = self.entries.len();
-                            
-                            while reader.read_line(&mut line_buf).unwrap_or(0) > 0 {
-                                let line = line_buf.trim_end();
-                                if !line.is_empty() {
-                                    let entry = self.parser.parse_line(line, start_line + new_lines.len() + 1);
-                                    new_lines.push(entry);
-                                }
-                                line_buf.clear();
-                            }
-                            
-                            if !new_lines.is_empty() {
-                                self.entries.extend(new_lines);
-                                self.filtered_entries = (0..self.entries.len()).collect();
-                                self.search.update_search(&self.entries);
-                                self.last_file_size = new_size;
-                                
-                                if self.scroll_to_end {
-                                    self.auto_scroll_frames = 3;
-                                }
-                            }
-                        }
-                    }
+        self.search.start_search(&self.entries);
+        self.apply_filters();
+    }
+    
+    /// Clear the entries and parse `content` on a background thread. Entries
+    /// are added by `check_parse_progress` as they arrive. With an index key,
+    /// a saved index of the same content is used instead when there is one.
+    fn start_parse(&mut self, content: String, index: Option<IndexKey>) {
+        self.pending_goto = None;
+        self.entries.clear();
+        self.entries_replaced();
+        self.parse_job = Some(ParseJob::start(self.parser.clone(), content, index));
+    }
+    
+    /// Take the entries parsed so far and update the view with them. With
+    /// `wait`, blocks until the parse is complete.
+    pub fn check_parse_progress(&mut self, wait: bool) {
+        let Some(ref mut job) = self.parse_job else {
+            return;
+        };
+        let ready = if wait { job.take_all() } else { job.take_ready() };
+        let finished = job.is_finished();
+        if let Some(found) = job.take_unparsed() {
+            self.unparsed = found;
+        }
+        let timeline = if finished { job.take_timeline() } else { None };
+        
+        if !ready.is_empty() {
+            let start = self.entries.len();
+            self.entries.extend(ready);
+            self.extend_filters(start);
+            if self.scroll_to_end {
+                self.auto_scroll_frames = 3;
+            }
+        }
+        if finished {
+            self.parse_job = None;
+            self.entries.shrink_to_fit();
+            match timeline {
+                Some(timeline) => {
+                    self.timeline = timeline;
+                    self.refresh_metrics();
                 }
+                None => self.refresh_analysis(),
+            }
+            if self.show_transactions {
+                self.group_transactions();
+            }
+            if let Some((source, line)) = self.pending_goto.take() {
+                self.goto_source_line(source, line);
             }
         }
     }
     
-    fn apply_filters(&mut self) {
-        // Update search first
-        if !self.search.query.is_empty() {
-            self.search.update_search(&self.entries);
+    /// Take the entries of merged files parsed so far, and interleave them
+    /// with the others once every file is parsed.
+    fn check_merge_progress(&mut self) {
+        let mut finished = false;
+        for source in &mut self.merge_sources {
+            let Some(ref mut job) = source.parse_job else {
+                continue;
+            };
+            source.entries.extend(job.take_ready());
+            if job.is_finished() {
+                source.parse_job = None;
+                source.entries.shrink_to_fit();
+                finished = true;
+            }
+        }
+        if !finished || self.is_parsing() {
+            return;
+        }
+        self.rebuild_merged();
+        if self.tail_log {
+            self.start_tail();
+        }
+        if let Some((source, line)) = self.pending_goto.take() {
+            self.goto_source_line(source, line);
+        }
+    }
+    
+    /// Whether the file, or a file merged into the view, is still being parsed.
+    fn is_parsing(&self) -> bool {
+        self.parse_job.is_some() || self.merge_sources.iter().any(|source| source.parse_job.is_some())
+    }
+    
+    /// Once a reload is parsed, find the pinned entries and the entry that was
+    /// at the top of the view again, wherever their lines went.
+    fn check_reload(&mut self) {
+        if !self.relink_pending || self.is_parsing() {
+            return;
         }
+        self.relink_pending = false;
         
-        self.filtered_entries = self.entries
-            .iter()
-            .enumerate()
-            .filter(|(idx, entry)| {
-                // Level filter - check if this level is enabled
-                if !self.enabled_levels.contains(&entry.level) {
-                    return false;
-                }
-                
-                // Search filter - only filter if "show only matches" is enabled
-                if self.search.show_only_matches && !self.search.query.is_empty() {
-                    if !self.search.is_match(*idx) {
-                        return false;
+        let merged = !self.merge_sources.is_empty();
+        let mut moved = 0;
+        for (source, path) in self.open_files().iter().enumerate() {
+            let entries: Vec<&LogEntry> = self.entries.iter().filter(|e| !merged || e.source == source).collect();
+            moved += scratchpad::relink(&mut self.scratchpad, path, &entries);
+        }
+        if moved > 0 {
+            self.save_scratchpad();
+            self.scratchpad_message = Some(Ok(format!("{} pins followed their entries to new lines", moved)));
+        }
+        
+        if let Some(mut baseline) = self.reload_baseline.take() {
+            // Entries are told apart by their text, so one that moved is not new
+            self.new_entries = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| match baseline.get_mut(&entry_fingerprint(entry)) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
                     }
-                }
-                
-                true
-            })
-            .map(|(idx, _)| idx)
-            .collect();
+                    _ => true,
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+            if !self.new_entries.is_empty() {
+                self.new_entries_until = Some(std::time::Instant::now() + NEW_ENTRY_HIGHLIGHT);
+                self.view_generation += 1;
+            }
+        }
+        
+        if let Some((line, text)) = self.reload_anchor.take() {
+            let found = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.raw_line == text)
+                .min_by_key(|(_, e)| e.line_number.abs_diff(line))
+                .map(|(idx, _)| idx);
+            if let Some(idx) = found {
+                self.scroll_target_line = Some(idx);
+                self.target_scroll_offset = None;
+                self.auto_scroll_frames = 0;
+            }
+        }
     }
     
-    fn get_color_for_level(&self, level: &LogLevel) -> egui::Color32 {
-        match level {
-            LogLevel::Info => self.config.color_palette.info,
-            LogLevel::Warn => self.config.color_palette.warn,
-            LogLevel::Error => self.config.color_palette.error,
-            LogLevel::Debug => self.config.color_palette.debug,
-            LogLevel::Trace => self.config.color_palette.trace,
-            LogLevel::Unknown => self.config.color_palette.default,
+    /// Drop the tint of reloaded entries once it has been shown long enough.
+    fn expire_new_entries(&mut self, ctx: &egui::Context) {
+        let Some(until) = self.new_entries_until else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        if now < until {
+            ctx.request_repaint_after(until - now);
+        } else {
+            self.new_entries.clear();
+            self.new_entries_until = None;
+            self.view_generation += 1;
         }
     }
     
-    fn get_bg_color_for_level(&self, level: &LogLevel) -> egui::Color32 {
-        match level {
-            LogLevel::Info => self.config.color_palette.info_bg,
-            LogLevel::Warn => self.config.color_palette.warn_bg,
-            LogLevel::Error => self.config.color_palette.error_bg,
-            LogLevel::Debug => self.config.color_palette.debug_bg,
-            LogLevel::Trace => self.config.color_palette.trace_bg,
-            LogLevel::Unknown => self.config.color_palette.default_bg,
+    /// Pick the palette for the file being viewed by the palette rules, once
+    /// another file is opened or the rules changed.
+    fn check_palette(&mut self) {
+        if self.palette_file.as_ref() == Some(&self.current_file) {
+            return;
         }
+        self.config.color_palette = self.config.palette_for(self.current_file.as_deref());
+        self.palette_file = Some(self.current_file.clone());
+        self.view_generation += 1;
     }
-}
-
-impl Default for LogViewerApp {
-    fn default() -> Self {
-        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-        Self {
-            config: AppConfig::default(),
-            parser: LogParser::new(),
-            file_watcher: FileWatcher::new(),
-            search: SearchState::new(),
-            current_file: None,
-            entries: Vec::new(),
-            filtered_entries: Vec::new(),
-            tail_log: true,
-            scroll_to_end: true,
-            auto_scroll_frames: 0,
-            scroll_offset: 0.0,
-            last_file_size: 0,
-            show_search: false,
-            show_sidebar: false, // Closed by default
-            enabled_levels: {
-                let mut set = std::collections::HashSet::new();
-                set.insert(LogLevel::Info);
-                set.insert(LogLevel::Warn);
-                set.insert(LogLevel::Error);
-                set.insert(LogLevel::Debug);
-                set.insert(LogLevel::Trace);
-                set.insert(LogLevel::Unknown);
-                set
-            },
-            focus_search: false,
-            scroll_to_match: false,
-            scroll_to_top: false,
-            scroll_target_line: None,
-            target_scroll_offset: None,
-            wrap_text: false, // Default: no wrapping, allow horizontal scroll
+    
+    /// Once a loaded file is parsed, show the first error or the first entry
+    /// from the chosen time, as set in the settings.
+    fn check_start_position(&mut self) {
+        if !self.start_position_pending || self.is_parsing() {
+            return;
+        }
+        self.start_position_pending = false;
+        let start = match self.config.start_position {
+            StartPosition::End => None,
+            StartPosition::FirstError => self.entries.iter().position(|e| e.level == LogLevel::Error),
+            StartPosition::AfterTime => self
+                .entries
+                .iter()
+                .find_map(|e| e.time_ms())
+                .and_then(|first| config::start_time_ms(&self.config.start_time, first))
+                .and_then(|start| self.entries.iter().position(|e| e.time_ms().is_some_and(|time| time >= start))),
+        };
+        if let Some(idx) = start {
+            self.scroll_target_line = Some(idx);
+            self.target_scroll_offset = None;
+            self.auto_scroll_frames = 0;
+            // New lines would pull the view back to the end
+            self.scroll_to_end = false;
         }
     }
-}
-
-impl LogViewerApp {
-
-}
-
-impl eframe::App for LogViewerApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        use egui::*;
-        // Handle keyboard shortcuts
-        ctx.input(|input| {
-            // Cmd+F or Ctrl+F to toggle search
-            if input.key_pressed(egui::Key::F) && 
-               (input.modifiers.command || input.modifiers.ctrl) {
-                self.show_search = !self.show_search;
-                if self.show_search {
-                    self.focus_search = true;
-                }
+    
+    /// Merge another file into the current view, interleaved by timestamp.
+    pub fn add_merge_source(&mut self, path: PathBuf) -> Result<(), String> {
+        if self.disk_log.is_some() {
+            return Err("Files viewed from disk can't be merged".to_string());
+        }
+        // The primary file has to be complete before it is merged, with the
+        // lines its tail already read
+        self.check_parse_progress(true);
+        if self.merge_sources.is_empty() {
+            let Some(primary) = self.current_file.clone() else {
+                return self.load_file(path);
+            };
+            self.check_file_updates();
+            let pending = self.ingest.take_due(0, 0);
+            self.release(pending);
+            self.merge_sources.push(MergeSource {
+                path: primary,
+                offset_ms: 0,
+                entries: self.entries.clone(),
+                tail_offset: self.last_file_size,
+                file_id: self.last_file_id,
+                parse_job: None,
+            });
+        }
+        
+        let (content, file_size) = read_log_content(&path, &self.config)?;
+        let file_id = fs::metadata(&path).ok().and_then(|m| file_watcher::file_id(&m));
+        let index = IndexKey::new(&path, &content, &self.parser);
+        let parse_job = Some(ParseJob::start(self.parser.clone(), content, index));
+        self.merge_sources.push(MergeSource { path, offset_ms: 0, entries: Vec::new(), tail_offset: file_size, file_id, parse_job });
+        // Interleaved and tailed by `check_merge_progress` once parsed
+        self.stop_merged_tail();
+        Ok(())
+    }
+    
+    /// Stop tailing a merged view's files while one of them is parsed, keeping
+    /// what the tail already read, so new lines can't arrive before the
+    /// file's own.
+    fn stop_merged_tail(&mut self) {
+        self.check_file_updates();
+        let pending = self.ingest.take_due(0, 0);
+        self.release(pending);
+        self.file_watcher.stop();
+    }
+    
+    /// Re-interleave the merged sources, e.g. after an offset changed.
+    fn rebuild_merged(&mut self) {
+        self.entries = merge::merge_sources(&self.merge_sources);
+        self.entries_replaced();
+        self.search.start_search(&self.entries);
+        self.apply_filters();
+    }
+    
+    /// Open the current clipboard text as an in-memory log.
+    fn load_clipboard(&mut self) -> Result<(), String> {
+        let text = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+        if text.trim().is_empty() {
+            return Err("Clipboard is empty".to_string());
+        }
+        let name = format!("Clipboard {}", chrono::Local::now().format("%H:%M:%S"));
+        self.load_text(name, text);
+        Ok(())
+    }
+    
+    /// Re-read the current file, or re-parse the in-memory text.
+    fn reload(&mut self) -> Result<(), String> {
+        self.relink_pending = true;
+        // Tailing shows new lines as they come, otherwise the reload tells what changed
+        self.reload_baseline = (!self.tail_log && !self.entries.is_empty()).then(|| {
+            let mut counts = std::collections::HashMap::new();
+            for entry in &self.entries {
+                *counts.entry(entry_fingerprint(entry)).or_insert(0) += 1;
             }
-            
-            // Cmd+S to toggle sidebar
-            if input.key_pressed(egui::Key::S) && 
-               (input.modifiers.command || input.modifiers.ctrl) {
-                self.show_sidebar = !self.show_sidebar;
+            counts
+        });
+        self.reload_anchor = self
+            .top_entry()
+            .filter(|_| !self.scroll_to_end)
+            .map(|idx| (self.entries[idx].line_number, self.entries[idx].raw_line.clone()));
+        let result = self.reload_sources();
+        // Reading goes on where it was, not at the start position of a fresh open
+        self.start_position_pending = false;
+        result
+    }
+    
+    fn reload_sources(&mut self) -> Result<(), String> {
+        if !self.merge_sources.is_empty() {
+            self.stop_merged_tail();
+            for source in &mut self.merge_sources {
+                let (content, file_size) = read_log_content(&source.path, &self.config)?;
+                let index = IndexKey::new(&source.path, &content, &self.parser);
+                source.entries.clear();
+                source.parse_job = Some(ParseJob::start(self.parser.clone(), content, index));
+                source.tail_offset = file_size;
+                source.file_id = fs::metadata(&source.path).ok().and_then(|m| file_watcher::file_id(&m));
             }
-            
-            // ESC to close search
-            if input.key_pressed(egui::Key::Escape) && self.show_search {
-                self.show_search = false;
+            Ok(())
+        } else if let Some(path) = self.current_file.clone() {
+            self.load_file(path)
+        } else if let Some(source) = self.memory_source.take() {
+            self.load_text(source.name, source.content);
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// Watch the config and formats files so hand edits apply without a restart.
+    fn watch_config(&mut self) {
+        if let Some(dir) = config::config_dir() {
+            // The watcher needs the directory to exist
+            fs::create_dir_all(dir).ok();
+        }
+        if let Some(path) = AppConfig::config_path() {
+            if let Err(e) = self.config_watcher.watch_file(path) {
+                app_error!("Error watching config: {}", e);
+            }
+        }
+        if let Some(path) = custom_format::formats_path() {
+            if let Err(e) = self.formats_watcher.watch_file(path) {
+                app_error!("Error watching formats: {}", e);
+            }
+        }
+    }
+    
+    /// Switch to a new configuration, e.g. from the settings window or a reloaded file.
+    fn apply_config(&mut self, config: AppConfig) {
+        let restart_tail = config.tail_log != self.config.tail_log
+            || config.backfill_rotated != self.config.backfill_rotated
+            || config.always_poll != self.config.always_poll
+            || config.poll_interval_ms != self.config.poll_interval_ms;
+        if config.check_for_updates && !self.config.check_for_updates {
+            self.update_check = Some(UpdateCheck::start());
+        }
+        if config.redaction != self.config.redaction {
+            self.redactor = Redactor::new(&config.redaction);
+            self.view_generation += 1;
+        }
+        if config.tint_merged_sources != self.config.tint_merged_sources
+            || config.label_merged_sources != self.config.label_merged_sources
+            || config.line_spacing != self.config.line_spacing
+            || config.zebra_stripes != self.config.zebra_stripes
+            || config.max_line_length != self.config.max_line_length
+        {
+            self.view_generation += 1;
+        }
+        let reparse = config.collapse_progress_lines != self.config.collapse_progress_lines;
+        self.parser.collapse_progress = config.collapse_progress_lines;
+        // Following from the command line is kept unless the setting itself changed
+        if config.tail_log != self.config.tail_log {
+            self.tail_log = config.tail_log;
+        }
+        if config.scroll_to_end != self.config.scroll_to_end {
+            self.scroll_to_end = config.scroll_to_end;
+        }
+        self.config = config;
+        // The new config comes with the theme's palette
+        self.palette_file = None;
+        self.check_palette();
+        if reparse {
+            if let Err(e) = self.reload() {
+                app_error!("Error reloading file: {}", e);
+            }
+        }
+        if restart_tail {
+            if self.tail_log {
+                self.start_tail();
+            } else {
+                self.file_watcher.stop();
+            }
+        }
+    }
+    
+    /// Search on through a large log, showing the matches as they are found.
+    fn check_search(&mut self) {
+        if self.search.continue_search(&self.entries) && self.search.show_only_matches {
+            self.refilter();
+        }
+        if self.jump_to_first_match {
+            if let Some(line_idx) = self.search.get_current_match_index() {
+                self.scroll_target_line = Some(line_idx);
+                self.auto_scroll_frames = 0;
+                self.jump_to_first_match = false;
+            } else if !self.search.is_searching() {
+                self.jump_to_first_match = false;
+            }
+        }
+    }
+    
+    /// How many matches fall on each level, e.g. "3 ERROR, 12 WARN", to tell a
+    /// pattern that goes with failures from benign noise.
+    fn show_match_levels(&mut self, ui: &mut egui::Ui) {
+        if self.match_levels.0 != self.search.generation {
+            let mut counts = [0; LogLevel::ALL.len()];
+            for entry in self.search.matches.iter().filter_map(|&idx| self.entries.get(idx)) {
+                if let Some(i) = LogLevel::ALL.iter().position(|level| *level == entry.level) {
+                    counts[i] += 1;
+                }
+            }
+            let mut levels: Vec<(LogLevel, usize)> =
+                LogLevel::ALL.iter().cloned().zip(counts).filter(|&(_, count)| count > 0).collect();
+            // Most severe first, Unknown last
+            levels.sort_by_key(|(level, _)| std::cmp::Reverse(level.severity()));
+            self.match_levels = (self.search.generation, levels);
+        }
+
+        for (i, (level, count)) in self.match_levels.1.iter().enumerate() {
+            let text = format!("{}{} {}", if i == 0 { "" } else { ", " }, count, level.as_str());
+            ui.label(egui::RichText::new(text).size(12.0).color(self.get_color_for_level(level)));
+        }
+    }
+    
+    /// Take the update check's answer and any finished download.
+    fn check_update(&mut self) {
+        if let Some(result) = self.update_check.as_ref().and_then(UpdateCheck::poll) {
+            self.update_check = None;
+            match result {
+                Ok(Some(release)) if release.version != self.config.skipped_update => self.update = Some(release),
+                Ok(_) => {}
+                Err(e) => app_warn!("{}", e),
+            }
+        }
+        if let Some(result) = self.update_download.as_ref().and_then(UpdateDownload::poll) {
+            self.update_download = None;
+            self.update_download_result = Some(result);
+        }
+    }
+    
+    fn check_config_updates(&mut self) {
+        if self.config_watcher.check_for_changes() {
+            // A half-written or broken file keeps the current settings
+            match AppConfig::try_load() {
+                Ok(config) => self.apply_config(config),
+                Err(e) => app_error!("Error reloading config: {}", e),
+            }
+        }
+        if self.formats_watcher.check_for_changes() {
+            let formats = custom_format::load_custom_formats();
+            if formats != self.custom_formats {
+                self.custom_formats = formats;
+                self.parser.set_custom_formats(&self.custom_formats);
+                if matches!(self.parser.format, LogFormat::Auto | LogFormat::Custom(_)) {
+                    if let Err(e) = self.reload() {
+                        app_error!("Error reloading file: {}", e);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Queue the entries the tail thread read since the last frame.
+    fn check_file_updates(&mut self) {
+        let mut recreated = Vec::new();
+        for batch in self.file_watcher.take_batches() {
+            if batch.recreated {
+                recreated.push(batch.source);
+            }
+            if let Some(ref rest) = batch.continuation {
+                self.continue_last_entry(batch.source, rest);
+            }
+            match self.merge_sources.get_mut(batch.source) {
+                // Merged entries keep their own file's line numbers and are
+                // shown shifted by the source's clock offset
+                Some(source) => {
+                    source.tail_offset = batch.offset;
+                    source.file_id = batch.file_id;
+                    let mut entries = batch.entries;
+                    number_lines(&mut entries, next_line_after(&source.entries));
+                    for entry in entries.iter_mut() {
+                        source.entries.push(entry.clone());
+                        entry.source = batch.source;
+                        entry.shift_time(source.offset_ms);
+                    }
+                    self.ingest.push(entries);
+                }
+                None => {
+                    self.last_file_size = batch.offset;
+                    self.last_file_id = batch.file_id;
+                    self.ingest.push(batch.entries);
+                }
+            }
+        }
+        // Watch the file afresh, in case its directory was recreated with it
+        for source in recreated {
+            self.tail_source(source);
+        }
+    }
+    
+    /// Continue the last entry of live `source` with `rest`: the rest of its
+    /// last line, if that was read before the writer finished it, then lines
+    /// continuing the entry.
+    fn continue_last_entry(&mut self, source: usize, rest: &str) {
+        let offset_ms = self.merge_sources.get(source).map_or(0, |merged| merged.offset_ms);
+        if let Some(entry) = self.merge_sources.get_mut(source).and_then(|merged| merged.entries.last_mut()) {
+            *entry = self.parser.continue_entry(entry, rest);
+        }
+        // It may not be shown yet
+        let (entry, shown) = match self.ingest.last_from(source) {
+            Some(entry) => (entry, None),
+            None => match self.entries.iter().rposition(|entry| entry.source == source) {
+                Some(idx) => (&mut self.entries[idx], Some(idx)),
+                None => return,
+            },
+        };
+        *entry = self.parser.continue_entry(entry, rest);
+        entry.shift_time(offset_ms);
+        if let Some(idx) = shown {
+            self.refilter_entry(idx);
+        }
+    }
+    
+    /// Search and filter the shown entry `idx` again after its text changed.
+    fn refilter_entry(&mut self, idx: usize) {
+        self.search.research_entry(&self.entries, idx);
+        if let (Some(ref script), Some(verdict)) = (&self.script, self.script_verdicts.get_mut(idx)) {
+            *verdict = script.evaluate(&self.entries[idx]);
+        }
+        match (self.filtered_entries.binary_search(&idx), self.passes_filters(idx)) {
+            (Ok(pos), false) => {
+                self.filtered_entries.remove(pos);
+            }
+            (Err(pos), true) => self.filtered_entries.insert(pos, idx),
+            _ => {}
+        }
+        self.view_generation += 1;
+    }
+    
+    /// Show the queued live entries that are due.
+    fn release_ingested(&mut self) {
+        // New lines wait until the initial parse is done, to keep entries in order
+        if self.parse_job.is_some() {
+            return;
+        }
+        let new_entries = self.ingest.take_due(self.config.tail_refresh_hz, self.config.max_lines_per_refresh);
+        self.release(new_entries);
+    }
+    
+    /// Append live entries, numbered after the last entry unless merged
+    /// entries already carry their file's numbers.
+    fn release(&mut self, mut new_entries: Vec<LogEntry>) {
+        if new_entries.is_empty() {
+            return;
+        }
+        if self.merge_sources.is_empty() {
+            number_lines(&mut new_entries, self.next_line_number());
+        }
+        self.append_entries(new_entries);
+    }
+    
+    /// Tail the current file, or every file of a merged view, from where
+    /// reading it stopped. Files viewed from disk aren't tailed.
+    fn start_tail(&mut self) {
+        self.file_watcher.stop();
+        if self.disk_log.is_some() {
+            return;
+        }
+        // Merged files are tailed once they are all parsed
+        if self.merge_sources.iter().any(|source| source.parse_job.is_some()) {
+            return;
+        }
+        let sources = if self.merge_sources.is_empty() { usize::from(self.current_file.is_some()) } else { self.merge_sources.len() };
+        for source in 0..sources {
+            self.tail_source(source);
+        }
+    }
+    
+    /// (Re)start tailing one file: the current file, or a merged source.
+    fn tail_source(&mut self, source: usize) {
+        let (path, offset, file_id) = match self.merge_sources.get(source) {
+            Some(merged) => (merged.path.clone(), merged.tail_offset, merged.file_id),
+            None => match self.current_file.clone() {
+                Some(path) => (path, self.last_file_size, self.last_file_id),
+                None => return,
+            },
+        };
+        // Network filesystems often don't report changes, so they are polled
+        let poll_interval = (self.config.always_poll || file_watcher::is_network_fs(&path))
+            .then(|| std::time::Duration::from_millis(self.config.poll_interval_ms.max(100)));
+        let options = TailOptions {
+            parser: self.parser.clone(),
+            backfill_rotated: self.config.backfill_rotated,
+            poll_interval,
+        };
+        let result = self.file_watcher.tail_file(source, path, offset, file_id, options);
+        if let Err(e) = result {
+            app_error!("Error watching file: {}", e);
+        }
+    }
+    
+    /// Line number for an entry added after the last one. Numbering continues
+    /// from the last entry, as the oldest may have been dropped.
+    fn next_line_number(&self) -> usize {
+        next_line_after(&self.entries)
+    }
+    
+    /// Add entries read from the end of the file while tailing.
+    pub fn append_entries(&mut self, new_entries: Vec<LogEntry>) {
+        let start = self.entries.len();
+        self.entries.extend(new_entries);
+        // Before any are dropped, so the ones dropped were counted
+        self.extend_analysis(start);
+        let dropped = self.enforce_entry_cap();
+        // Only the new entries are searched and filtered, a filtered view stays filtered
+        self.extend_filters(start.saturating_sub(dropped));
+        
+        if self.scroll_to_end {
+            self.auto_scroll_frames = 3;
+        }
+    }
+    
+    /// Drop everything derived from the previous entries.
+    fn entries_replaced(&mut self) {
+        // Live entries still queued belong after the old ones
+        self.ingest.clear();
+        self.script_verdicts.clear();
+        self.expanded_entries.clear();
+        self.unparsed = Unparsed::default();
+        self.new_entries.clear();
+        self.patterns = None;
+        self.pattern_filter = None;
+        self.transaction_filter = None;
+        self.refresh_analysis();
+        if self.show_transactions {
+            self.group_transactions();
+        }
+    }
+    
+    fn group_transactions(&mut self) {
+        match transactions::group(&self.transaction_rules, &self.entries) {
+            Ok(mut grouped) => {
+                let (column, descending) = self.transaction_sort;
+                transactions::sort(&mut grouped, column, descending);
+                self.transactions = grouped;
+                self.transaction_error = None;
+            }
+            Err(e) => {
+                self.transactions.clear();
+                self.transaction_error = Some(e);
+            }
+        }
+    }
+    
+    /// Name of what is being viewed, for titles and exports.
+    fn source_name(&self) -> String {
+        if let Some(ref path) = self.current_file {
+            path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| path.display().to_string())
+        } else if let Some(ref source) = self.memory_source {
+            source.name.clone()
+        } else {
+            "untitled".to_string()
+        }
+    }
+    
+    /// Entries the view's text selection touches, in view order.
+    fn selected_entries(&self) -> Vec<usize> {
+        let (Some(cache), Some(range)) = (&self.view_cache, &self.view_selection) else {
+            return Vec::new();
+        };
+        let first = cache.entry_starts.partition_point(|&(start, _)| start <= range.start).saturating_sub(1);
+        cache.entry_starts[first..]
+            .iter()
+            .take_while(|&&(start, _)| start < range.end)
+            .map(|&(_, idx)| idx)
+            .collect()
+    }
+    
+    /// The file an entry comes from and its name, as pins record them.
+    fn entry_source(&self, idx: usize) -> (Option<PathBuf>, String) {
+        match self.merge_sources.get(self.entries[idx].source) {
+            Some(source) => (Some(source.path.clone()), source.name()),
+            None => (self.current_file.clone(), self.source_name()),
+        }
+    }
+    
+    /// Where entry `idx` is in the scratchpad, if it is pinned.
+    fn pin_position(&self, idx: usize) -> Option<usize> {
+        let (path, source_name) = self.entry_source(idx);
+        let line_number = self.entries[idx].line_number;
+        self.scratchpad.iter().position(|pin| pin.path == path && pin.source_name == source_name && pin.line_number == line_number)
+    }
+    
+    fn pin_entry(&mut self, idx: usize) {
+        if self.pin_position(idx).is_some() {
+            return;
+        }
+        let (path, source_name) = self.entry_source(idx);
+        let entry = &self.entries[idx];
+        self.scratchpad.push(PinnedEntry {
+            path,
+            source_name,
+            line_number: entry.line_number,
+            level: entry.level.clone(),
+            time_ms: entry.time_ms(),
+            text: entry.raw_line.clone(),
+            note: String::new(),
+        });
+    }
+    
+    /// Pin the selected entries to the scratchpad, with where they came from.
+    fn pin_selection(&mut self) {
+        for idx in self.selected_entries() {
+            self.pin_entry(idx);
+        }
+        self.show_scratchpad = true;
+        self.save_scratchpad();
+    }
+    
+    fn toggle_pin(&mut self, idx: usize) {
+        match self.pin_position(idx) {
+            Some(position) => {
+                self.scratchpad.remove(position);
+            }
+            None => self.pin_entry(idx),
+        }
+        self.save_scratchpad();
+    }
+    
+    fn save_scratchpad(&self) {
+        if let Err(e) = scratchpad::save(&self.scratchpad) {
+            app_error!("Error saving scratchpad: {}", e);
+        }
+    }
+    
+    /// The file in view, or the files of a merged view.
+    fn open_files(&self) -> Vec<PathBuf> {
+        if self.merge_sources.is_empty() {
+            self.current_file.iter().cloned().collect()
+        } else {
+            self.merge_sources.iter().map(|source| source.path.clone()).collect()
+        }
+    }
+    
+    /// Add pins exported by someone else, relinked to the files open here.
+    fn import_pins(&mut self, path: &Path) -> Result<String, String> {
+        let imported = scratchpad::import(path)?;
+        let total = imported.len();
+        let open_files = self.open_files();
+        let added = scratchpad::merge_imported(&mut self.scratchpad, imported, &open_files);
+        self.save_scratchpad();
+        Ok(match total - added {
+            0 => format!("Imported {} pins", added),
+            already => format!("Imported {} pins; {} were pinned already", added, already),
+        })
+    }
+    
+    /// Go back to a pinned entry, opening its file unless it is in view.
+    fn open_pinned(&mut self, pin: &PinnedEntry) {
+        let Some(ref path) = pin.path else {
+            return;
+        };
+        if let Some(source) = self.merge_sources.iter().position(|source| &source.path == path) {
+            self.goto_source_line(source, pin.line_number);
+            return;
+        }
+        if self.current_file.as_ref() != Some(path) {
+            if let Err(e) = self.open_path(path.clone()) {
+                app_error!("Error opening pinned entry: {}", e);
+                return;
+            }
+        }
+        self.goto_line(pin.line_number);
+    }
+    
+    fn open_share(&mut self) {
+        self.share_entries = self.selected_entries();
+        self.share_result = None;
+        self.show_share = true;
+    }
+    
+    /// The lines being shared, masked if asked.
+    fn share_text(&self) -> String {
+        let rules = RedactionRules { enabled: self.config.share.redact, ..self.config.redaction.clone() };
+        let redactor = Redactor::new(&rules);
+        self.share_entries
+            .iter()
+            .filter_map(|&idx| self.entries.get(idx))
+            .map(|entry| redactor.redact(&entry.raw_line).into_owned())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    
+    /// Line numbers of the first and last shared entries.
+    fn share_line_range(&self) -> (usize, usize) {
+        let line = |idx: Option<&usize>| idx.and_then(|&idx| self.entries.get(idx)).map_or(0, |e| e.line_number);
+        (line(self.share_entries.first()), line(self.share_entries.last()))
+    }
+    
+    /// Ask where to save the given entries, `what` naming them in the
+    /// suggested file name (e.g. "matches"), and write them there masked.
+    /// The file's extension picks the format; `format` is the one suggested.
+    fn export_entries(&self, indices: &[usize], what: &str, format: ExportFormat) {
+        let mut dialog = rfd::FileDialog::new().set_file_name(&format!("{}_{}.{}", self.source_name(), what, format.extensions()[0]));
+        // The suggested format's filter first, so it is the one selected
+        for choice in std::iter::once(format).chain(ExportFormat::ALL.into_iter().filter(|&other| other != format)) {
+            dialog = dialog.add_filter(choice.label(), choice.extensions());
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        let redact = |value: &str| self.redactor.redact(value).into_owned();
+        let content = match ExportFormat::from_path(&path) {
+            ExportFormat::Lines => indices
+                .iter()
+                .filter_map(|&idx| self.entries.get(idx))
+                .map(|entry| self.redactor.redact(&entry.raw_line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ExportFormat::Csv => export::to_csv(&self.entries, indices, &self.script_verdicts, redact),
+            ExportFormat::Ndjson => {
+                let source = |idx: usize| match self.entry_source(idx) {
+                    (Some(path), _) => path.display().to_string(),
+                    (None, name) => name,
+                };
+                export::to_ndjson(&self.entries, indices, &self.script_verdicts, source, redact)
+            }
+        };
+        if let Err(e) = fs::write(&path, content) {
+            app_error!("Error exporting to {}: {}", path.display(), e);
+        }
+    }
+    
+    /// The search matches the filters show, in order.
+    fn visible_matches(&self) -> Vec<usize> {
+        self.filtered_entries.iter().copied().filter(|&idx| self.search.is_match(idx)).collect()
+    }
+    
+    /// Write the analysis report (stats, timeline, patterns, transactions) as Markdown.
+    fn export_report(&mut self, path: &Path) -> Result<(), String> {
+        self.patterns.get_or_insert_with(PatternIndex::default).extend(&self.entries);
+        let content = report::markdown(&ReportInput {
+            source: &self.source_name(),
+            entries: &self.entries,
+            filtered: &self.filtered_entries,
+            timeline: self.timeline.as_ref(),
+            patterns: self.patterns.as_ref().expect("patterns were just built"),
+            transactions: &self.transactions,
+        });
+        let content = self.redactor.redact(&content);
+        fs::write(path, content.as_bytes()).map_err(|e| format!("Failed to write report: {}", e))
+    }
+    
+    /// Recompute the timeline and metric series after the entries changed.
+    fn refresh_analysis(&mut self) {
+        self.timeline = timeline::build(&self.entries);
+        self.refresh_metrics();
+    }
+    
+    /// Add the entries appended from `start` on to the timeline and metric
+    /// series, and drop the analyses built on demand.
+    fn extend_analysis(&mut self, start: usize) {
+        let mut timeline = self.timeline.take().unwrap_or_default();
+        timeline.extend(&self.entries, start);
+        self.timeline = (!timeline.is_empty()).then_some(timeline);
+        for series in &mut self.metric_series {
+            series.extend(&self.entries, start);
+        }
+        self.noise_report = None;
+        self.heatmap = None;
+    }
+    
+    /// Recompute the metric series, and drop the analyses built on demand.
+    fn refresh_metrics(&mut self) {
+        self.noise_report = None;
+        self.heatmap = None;
+        self.metric_series = metrics::extract(&self.metric_extractors, &self.entries);
+    }
+    
+    fn add_metric_extractor(&mut self) -> Result<(), String> {
+        let extractor = MetricExtractor {
+            name: self.new_metric_name.trim().to_string(),
+            pattern: self.new_metric_pattern.clone(),
+        };
+        if extractor.name.is_empty() {
+            return Err("Give the metric a name".to_string());
+        }
+        extractor.compile()?;
+        self.metric_extractors.retain(|m| m.name != extractor.name);
+        self.metric_extractors.push(extractor);
+        self.new_metric_name.clear();
+        self.new_metric_pattern.clear();
+        self.show_metrics = true;
+        self.refresh_analysis();
+        metrics::save_extractors(&self.metric_extractors)
+    }
+    
+    /// Drop the oldest entries once tailing grows past `max_entries`. Trims to
+    /// 90% of the cap so a fast log doesn't drop lines on every update.
+    /// Returns how many entries were dropped.
+    fn enforce_entry_cap(&mut self) -> usize {
+        let cap = self.config.max_entries;
+        if cap == 0 || self.entries.len() <= cap {
+            return 0;
+        }
+        let dropped = self.entries.len() - cap * 9 / 10;
+        self.drop_oldest(dropped);
+        dropped
+    }
+    
+    /// Free the memory of all but the newest entries of a long-running tail,
+    /// without closing it.
+    fn purge_scrollback(&mut self) {
+        let dropped = self.entries.len().saturating_sub(PURGE_KEEP);
+        if dropped == 0 {
+            return;
+        }
+        self.drop_oldest(dropped);
+        self.entries.shrink_to_fit();
+        for source in &mut self.merge_sources {
+            source.entries.shrink_to_fit();
+        }
+        self.view_generation += 1;
+    }
+    
+    /// Drop the `dropped` oldest entries.
+    fn drop_oldest(&mut self, dropped: usize) {
+        if let Some(ref mut timeline) = self.timeline {
+            timeline.drop_front(&self.entries[..dropped], &self.entries[dropped..]);
+        }
+        // A merged view's files keep copies of their entries to re-interleave
+        merge::drop_front(&mut self.merge_sources, &self.entries[..dropped]);
+        self.entries.drain(..dropped);
+        for series in &mut self.metric_series {
+            series.drop_front(dropped);
+        }
+        self.noise_report = None;
+        self.heatmap = None;
+        
+        // Shift everything that refers to entries by index, rather than
+        // searching and filtering everything again
+        self.filtered_entries.retain(|&i| i >= dropped);
+        self.filtered_entries.iter_mut().for_each(|i| *i -= dropped);
+        self.expanded_entries = self.expanded_entries.iter().filter_map(|i| i.checked_sub(dropped)).collect();
+        self.new_entries = self.new_entries.iter().filter_map(|i| i.checked_sub(dropped)).collect();
+        self.search.drop_front(dropped);
+        self.script_verdicts.drain(..dropped.min(self.script_verdicts.len()));
+        if let Some(ref mut patterns) = self.patterns {
+            patterns.drop_front(dropped);
+        }
+        if let Some(ref mut members) = self.transaction_filter {
+            members.retain(|&i| i >= dropped);
+            members.iter_mut().for_each(|i| *i -= dropped);
+        }
+        if self.show_transactions {
+            self.group_transactions();
+        }
+        self.scroll_target_line = self.scroll_target_line.and_then(|i| i.checked_sub(dropped));
+    }
+    
+    /// Which source, parsed how, the format warning is about; a new file or
+    /// format asks again.
+    fn format_warning_key(&self) -> String {
+        format!("{}\n{}", self.source_name(), self.parser.signature())
+    }
+    
+    /// The share of the first entries with no level, once the file is parsed.
+    /// None while too few entries were read to tell.
+    fn unknown_level_share(&mut self) -> Option<f32> {
+        if self.is_parsing() || self.entries.len() < 20 {
+            return None;
+        }
+        let sampled = self.entries.len().min(FORMAT_CHECK_SAMPLE);
+        let first = self.entries.first().map_or(0, |entry| entry.line_number);
+        let key = format!("{}\n{}\n{}", self.format_warning_key(), first, sampled);
+        if let Some((ref counted_for, share)) = self.unknown_share {
+            if *counted_for == key {
+                return Some(share);
+            }
+        }
+        let sample = &self.entries[..sampled];
+        let unknown = sample.iter().filter(|entry| entry.level == LogLevel::Unknown).count();
+        let share = unknown as f32 / sample.len() as f32;
+        self.unknown_share = Some((key, share));
+        Some(share)
+    }
+    
+    /// Roughly how many bytes the view's entries and a merged view's copies
+    /// take, summed again only once entries came or went.
+    fn memory_usage(&mut self) -> usize {
+        let key = (self.entries.len(), self.entries.first().map_or(0, |entry| entry.line_number));
+        match self.memory_estimate {
+            Some((summed_for, bytes)) if summed_for == key => bytes,
+            _ => {
+                let bytes = self
+                    .entries
+                    .iter()
+                    .chain(self.merge_sources.iter().flat_map(|source| source.entries.iter()))
+                    .map(LogEntry::memory_size)
+                    .sum();
+                self.memory_estimate = Some((key, bytes));
+                bytes
+            }
+        }
+    }
+    
+    /// Bring the per-entry data the filters read (script verdicts, patterns)
+    /// up to date with new entries.
+    fn prepare_filters(&mut self) {
+        // Run the entry script over entries it hasn't seen yet
+        if let Some(ref script) = self.script {
+            let seen = self.script_verdicts.len();
+            self.script_verdicts.extend(self.entries[seen..].iter().map(|entry| script.evaluate(entry)));
+        }
+        
+        if self.show_patterns || self.pattern_filter.is_some() {
+            self.patterns.get_or_insert_with(PatternIndex::default).extend(&self.entries);
+        }
+    }
+    
+    fn passes_filters(&self, idx: usize) -> bool {
+        let entry = &self.entries[idx];
+        
+        // Level filter - check if this level is enabled
+        if !self.enabled_levels.contains(&entry.level) {
+            return false;
+        }
+        
+        // Search filter - only filter if "show only matches" is enabled
+        if self.search.show_only_matches && !self.search.query.is_empty() && !self.search.is_match(idx) {
+            return false;
+        }
+        
+        // Pattern filter
+        if let (Some(pattern), Some(ref patterns)) = (self.pattern_filter, &self.patterns) {
+            if patterns.entry_pattern.get(idx) != Some(&pattern) {
+                return false;
+            }
+        }
+        
+        // Transaction drill-down
+        if let Some(ref members) = self.transaction_filter {
+            if members.binary_search(&idx).is_err() {
+                return false;
+            }
+        }
+        
+        // Excluded noise
+        if !self.excluded_messages.is_empty() && self.excluded_messages.contains(noise::message_key(entry)) {
+            return false;
+        }
+        
+        // Script filter
+        !self.script_verdicts.get(idx).map_or(false, |v| v.hidden)
+    }
+    
+    /// Rebuild the filtered index from scratch.
+    fn apply_filters(&mut self) {
+        // Update search first
+        if !self.search.query.is_empty() {
+            self.search.start_search(&self.entries);
+        }
+        self.refilter();
+    }
+    
+    /// Search the rest of the log now rather than a frame at a time, e.g. to time it.
+    pub fn finish_search(&mut self) {
+        while self.search.is_searching() {
+            self.search.continue_search(&self.entries);
+        }
+        self.refilter();
+    }
+    
+    /// Rebuild the filtered index with the current search results.
+    fn refilter(&mut self) {
+        self.prepare_filters();
+        
+        let filtered = (0..self.entries.len()).filter(|&idx| self.passes_filters(idx)).collect();
+        self.filtered_entries = filtered;
+        self.view_generation += 1;
+    }
+    
+    /// Filter entries appended from `start` on (tail, background parse) without
+    /// re-checking the ones already indexed.
+    fn extend_filters(&mut self, start: usize) {
+        if !self.search.query.is_empty() {
+            self.search.extend_search(&self.entries, start);
+        }
+        self.prepare_filters();
+        
+        let added: Vec<usize> = (start..self.entries.len()).filter(|&idx| self.passes_filters(idx)).collect();
+        self.filtered_entries.extend(added);
+        self.view_generation += 1;
+    }
+    
+    /// Show a cut-short line in full when its "show full line" marker is
+    /// clicked, with a link cursor over the markers.
+    fn check_expanders(&mut self, ui: &egui::Ui, output: &egui::text_edit::TextEditOutput) {
+        let Some(ref cache) = self.view_cache else {
+            return;
+        };
+        if cache.expanders.is_empty() {
+            return;
+        }
+        let Some(pos) = output.response.hover_pos() else {
+            return;
+        };
+        let index = output.galley.cursor_from_pos(pos - output.text_draw_pos).ccursor.index;
+        let Some(&(_, entry_idx)) = cache.expanders.iter().find(|(range, _)| range.contains(&index)) else {
+            return;
+        };
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        if output.response.clicked() {
+            self.expanded_entries.insert(entry_idx);
+            self.view_generation += 1;
+        }
+    }
+    
+    /// Width of the line-number gutter: the pin margin and the longest line number.
+    fn gutter_width(&self, ui: &egui::Ui) -> f32 {
+        let last_line = self.merge_sources.iter().filter_map(|source| source.entries.last()).chain(self.entries.last()).map(|entry| entry.line_number).max();
+        let digits = last_line.map_or(1, |line| line.to_string().len()).max(4);
+        let font = egui::FontId::monospace(self.config.font_size * 0.85);
+        GUTTER_MARGIN + digits as f32 * ui.fonts(|f| f.glyph_width(&font, '0')) + 12.0
+    }
+    
+    /// Number the entries on screen beside the log text, whose first row is at
+    /// `text_top`. Clicking a number selects its entry (Shift extends the
+    /// selection); clicking the margin before it pins or unpins the entry.
+    /// Softly mark the other places the selected word occurs, in view and as
+    /// ticks along the right edge for the whole log. Search is left alone.
+    fn show_word_occurrences(&mut self, ui: &egui::Ui, rect: egui::Rect, text_top: f32, text_left: f32) {
+        let (Some(cache), Some(selection)) = (self.view_cache.as_mut(), self.view_selection.as_ref()) else {
+            return;
+        };
+        if cache.occurrences.as_ref().map(|(range, _)| range) != Some(selection) {
+            let found = word_occurrences(&cache.text, &cache.galley, selection);
+            cache.occurrences = Some((selection.clone(), found));
+        }
+        let Some((_, ref occurrences)) = cache.occurrences else {
+            return;
+        };
+        // Only the selection itself isn't worth marking
+        if occurrences.len() < 2 {
+            return;
+        }
+        let painter = ui.painter_at(rect);
+        let color = ui.visuals().selection.bg_fill.linear_multiply(0.35);
+        let rows = &cache.galley.rows;
+        let first = occurrences.partition_point(|&(row, _, _)| text_top + rows[row].rect.max.y < rect.top());
+        for &(row, start, end) in &occurrences[first..] {
+            let row = &rows[row];
+            if text_top + row.rect.min.y > rect.bottom() {
+                break;
+            }
+            let highlight = egui::Rect::from_min_max(
+                egui::pos2(text_left + row.x_offset(start), text_top + row.rect.min.y),
+                egui::pos2(text_left + row.x_offset(end), text_top + row.rect.max.y),
+            );
+            painter.rect_filled(highlight, 2.0, color);
+        }
+        
+        // Ticks at the occurrences' places in the whole view, like a minimap's
+        let height = cache.galley.rect.height().max(1.0);
+        let mut last_y = f32::NEG_INFINITY;
+        for &(row, _, _) in occurrences {
+            let y = rect.top() + rows[row].rect.min.y / height * rect.height();
+            if y - last_y < 2.0 {
+                continue;
+            }
+            last_y = y;
+            let tick = egui::Rect::from_min_size(egui::pos2(rect.right() - 6.0, y), egui::vec2(4.0, 2.0));
+            painter.rect_filled(tick, 0.0, ui.visuals().selection.bg_fill);
+        }
+    }
+    
+    /// Keep the first line of an entry in view while its continuation lines
+    /// (a long stack trace) fill the viewport; clicking it goes to the entry.
+    fn show_sticky_header(&mut self, ui: &mut egui::Ui, rect: egui::Rect, text_top: f32, text_left: f32, text_id: egui::Id) {
+        let Some(ref cache) = self.view_cache else {
+            return;
+        };
+        let font_id = egui::FontId::monospace(self.config.font_size);
+        let row_height = ui.fonts(|f| f.row_height(&font_id)) * self.config.line_spacing.max(1.0);
+        // The view's top edge, in the laid-out text
+        let top = rect.top() - text_top;
+        let i = cache.entry_tops.partition_point(|&entry_top| entry_top <= top).saturating_sub(1);
+        let Some(&entry_top) = cache.entry_tops.get(i) else {
+            return;
+        };
+        let next_top = cache.entry_tops.get(i + 1).copied().unwrap_or(cache.galley.rect.height());
+        // Only once the first line is out of view, and while the entry still fills the header's row
+        if entry_top >= top || next_top <= top + row_height {
+            return;
+        }
+        let idx = cache.entry_starts[i].1;
+        let entry = &self.entries[idx];
+        let first_line = entry.raw_line.lines().next().unwrap_or("");
+        let redacted = self.redactor.redact(first_line);
+        let limit = if self.config.max_line_length > 0 { self.config.max_line_length } else { usize::MAX };
+        let text: String = redacted.chars().take(limit).collect();
+        let color = self.script_verdicts.get(idx).and_then(|v| v.color).unwrap_or_else(|| self.get_color_for_level(&entry.level));
+        let mut background = self.get_bg_color_for_level(&entry.level);
+        if background == egui::Color32::TRANSPARENT {
+            background = ui.visuals().panel_fill;
+        }
+        
+        let header = egui::Rect::from_min_size(rect.min, egui::vec2(rect.width(), row_height));
+        let painter = ui.painter_at(rect);
+        // Opaque, so the lines scrolling under it don't show through
+        painter.rect_filled(header, 0.0, ui.visuals().panel_fill);
+        painter.rect_filled(header, 0.0, background);
+        painter.text(egui::pos2(text_left, header.top()), egui::Align2::LEFT_TOP, text, font_id, color);
+        painter.hline(header.x_range(), header.bottom(), ui.visuals().widgets.noninteractive.bg_stroke);
+        
+        let response = ui
+            .interact(header, text_id.with("sticky_header"), egui::Sense::click())
+            .on_hover_text(format!("Line {}, go to the start of this entry", entry.line_number));
+        if response.hovered() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        if response.clicked() {
+            self.scroll_target_line = Some(idx);
+            self.target_scroll_offset = None;
+            self.auto_scroll_frames = 0;
+            self.scroll_to_end = false;
+        }
+    }
+    
+    /// Vertical lines at the configured character columns of the log text,
+    /// which starts at `text_left` (scrolled sideways).
+    fn show_column_guides(&self, ui: &egui::Ui, rect: egui::Rect, text_left: f32) {
+        let char_width = ui.fonts(|f| f.glyph_width(&egui::FontId::monospace(self.config.font_size), ' '));
+        let stroke = egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color.linear_multiply(0.6));
+        let painter = ui.painter_at(rect);
+        for &column in &self.config.column_guides {
+            let x = text_left + column as f32 * char_width;
+            if rect.x_range().contains(x) {
+                painter.vline(x, rect.y_range(), stroke);
+            }
+        }
+    }
+    
+    /// Column numbers over the log text, and which of the widest line's
+    /// columns are in view, to keep one's bearings while scrolled sideways.
+    fn show_column_ruler(&self, ui: &egui::Ui, rect: egui::Rect, text_left: f32) {
+        let char_width = ui.fonts(|f| f.glyph_width(&egui::FontId::monospace(self.config.font_size), ' '));
+        let font = egui::FontId::monospace(self.config.font_size * 0.75);
+        let color = ui.visuals().weak_text_color();
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
+        
+        let first = ((rect.left() - text_left) / char_width).floor().max(0.0) as usize;
+        let last = ((rect.right() - text_left) / char_width).ceil() as usize;
+        for column in (first / 10 * 10..=last).step_by(10) {
+            let x = text_left + column as f32 * char_width;
+            let tall = column % 50 == 0;
+            let height = if tall { rect.height() * 0.5 } else { rect.height() * 0.25 };
+            painter.vline(x, (rect.bottom() - height)..=rect.bottom(), egui::Stroke::new(1.0, color));
+            if tall && column > 0 {
+                painter.text(egui::pos2(x + 2.0, rect.top()), egui::Align2::LEFT_TOP, column.to_string(), font.clone(), color);
+            }
+        }
+        for &column in self.config.column_guides.iter().filter(|_| self.config.show_column_guides) {
+            let x = text_left + column as f32 * char_width;
+            painter.vline(x, rect.y_range(), egui::Stroke::new(1.0, ui.visuals().selection.bg_fill));
+        }
+        
+        let widest = self.view_cache.as_ref().map_or(0, |cache| (cache.galley.rect.width() / char_width).round() as usize);
+        let range = format!(" cols {}–{} of {} ", first + 1, last.min(widest).max(first + 1), widest);
+        let galley = painter.layout_no_wrap(range, font, ui.visuals().text_color());
+        let pos = egui::pos2(rect.right() - galley.size().x - 4.0, rect.center().y - galley.size().y / 2.0);
+        painter.rect_filled(egui::Rect::from_min_size(pos, galley.size()), 2.0, ui.visuals().extreme_bg_color);
+        painter.galley(pos, galley);
+    }
+    
+    fn show_gutter(&mut self, ui: &mut egui::Ui, rect: egui::Rect, text_top: f32, text_id: egui::Id) {
+        let Some(ref cache) = self.view_cache else {
+            return;
+        };
+        let font = egui::FontId::monospace(self.config.font_size * 0.85);
+        let row_height = ui.fonts(|f| f.row_height(&egui::FontId::monospace(self.config.font_size)));
+        // The smaller numbers sit on the text's baseline
+        let baseline = row_height - ui.fonts(|f| f.row_height(&font));
+        let painter = ui.painter_at(rect);
+        let first = cache.entry_tops.partition_point(|&top| text_top + top + row_height < rect.top());
+        for (i, &top) in cache.entry_tops.iter().enumerate().skip(first) {
+            let y = text_top + top;
+            if y > rect.bottom() {
+                break;
+            }
+            let idx = cache.entry_starts[i].1;
+            let color = if self.search.is_current_match(idx) {
+                egui::Color32::from_rgb(255, 200, 0)
+            } else {
+                self.script_verdicts.get(idx).and_then(|v| v.color).unwrap_or_else(|| self.get_color_for_level(&self.entries[idx].level))
+            };
+            let number = self.entries[idx].line_number.to_string();
+            painter.text(egui::pos2(rect.right() - 8.0, y + baseline), egui::Align2::RIGHT_TOP, number, font.clone(), color);
+            if self.pin_position(idx).is_some() {
+                painter.text(egui::pos2(rect.left() + 2.0, y + baseline), egui::Align2::LEFT_TOP, "📌", egui::FontId::proportional(font.size * 0.8), color);
+            }
+        }
+        
+        let response = ui.interact(rect, text_id.with("gutter"), egui::Sense::click());
+        if response.hovered() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) else {
+            return;
+        };
+        let i = cache.entry_tops.partition_point(|&top| text_top + top <= pos.y).saturating_sub(1);
+        let Some(&(start, idx)) = cache.entry_starts.get(i) else {
+            return;
+        };
+        if pos.x < rect.left() + GUTTER_MARGIN {
+            self.toggle_pin(idx);
+            return;
+        }
+        // The entry's lines without the newline after them
+        let end = match cache.entry_starts.get(i + 1) {
+            Some(&(next, _)) => next - 1,
+            None => cache.text.chars().count(),
+        };
+        let (mut from, mut to) = (start, end);
+        if ui.input(|i| i.modifiers.shift) {
+            if let Some(ref selection) = self.view_selection {
+                from = from.min(selection.start);
+                to = to.max(selection.end);
+            }
+        }
+        let mut state = egui::TextEdit::load_state(ui.ctx(), text_id).unwrap_or_default();
+        let range = egui::text_edit::CCursorRange::two(egui::text::CCursor::new(from), egui::text::CCursor::new(to));
+        state.set_ccursor_range(Some(range));
+        egui::TextEdit::store_state(ui.ctx(), text_id, state);
+        ui.memory_mut(|m| m.request_focus(text_id));
+    }
+    
+    /// Show or hide one level, adding or removing just its entries from the filtered index.
+    fn set_level_enabled(&mut self, level: LogLevel, enabled: bool) {
+        if enabled {
+            self.enabled_levels.insert(level.clone());
+            let added: Vec<usize> = (0..self.entries.len())
+                .filter(|&idx| self.entries[idx].level == level && self.passes_filters(idx))
+                .collect();
+            
+            // Both lists are in entry order; merge them
+            let mut merged = Vec::with_capacity(self.filtered_entries.len() + added.len());
+            let mut added = added.into_iter().peekable();
+            for &idx in &self.filtered_entries {
+                while let Some(new_idx) = added.next_if(|&new_idx| new_idx < idx) {
+                    merged.push(new_idx);
+                }
+                merged.push(idx);
+            }
+            merged.extend(added);
+            self.filtered_entries = merged;
+        } else {
+            self.enabled_levels.remove(&level);
+            let entries = &self.entries;
+            self.filtered_entries.retain(|&idx| entries[idx].level != level);
+        }
+        self.view_generation += 1;
+    }
+    
+    fn get_color_for_level(&self, level: &LogLevel) -> egui::Color32 {
+        match level {
+            LogLevel::Info => self.config.color_palette.info,
+            LogLevel::Warn => self.config.color_palette.warn,
+            LogLevel::Error => self.config.color_palette.error,
+            LogLevel::Debug => self.config.color_palette.debug,
+            LogLevel::Trace => self.config.color_palette.trace,
+            LogLevel::Unknown => self.config.color_palette.default,
+        }
+    }
+    
+    fn get_bg_color_for_level(&self, level: &LogLevel) -> egui::Color32 {
+        match level {
+            LogLevel::Info => self.config.color_palette.info_bg,
+            LogLevel::Warn => self.config.color_palette.warn_bg,
+            LogLevel::Error => self.config.color_palette.error_bg,
+            LogLevel::Debug => self.config.color_palette.debug_bg,
+            LogLevel::Trace => self.config.color_palette.trace_bg,
+            LogLevel::Unknown => self.config.color_palette.default_bg,
+        }
+    }
+}
+
+impl Default for LogViewerApp {
+    fn default() -> Self {
+        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        let (config, config_error) = AppConfig::load();
+        let mut app = Self {
+            parser: LogParser::new(),
+            file_watcher: FileWatcher::new(),
+            config_watcher: FileWatcher::new(),
+            formats_watcher: FileWatcher::new(),
+            search: SearchState::new(),
+            dir_browser: DirectoryBrowser::new(),
+            glob_watch: None,
+            glob_input: "*.log".to_string(),
+            listener: None,
+            listen_config: ListenConfig::default(),
+            listen_error: None,
+            show_listen: false,
+            otlp: None,
+            otlp_config: OtlpConfig::default(),
+            otlp_error: None,
+            show_otlp: false,
+            forward: None,
+            forward_config: ForwardConfig::default(),
+            forward_error: None,
+            show_forward: false,
+            logcat: None,
+            logcat_config: LogcatConfig::default(),
+            logcat_error: None,
+            adb_devices: None,
+            adb_packages: None,
+            show_logcat: false,
+            unified_log: None,
+            unified_log_config: UnifiedLogConfig::default(),
+            unified_log_error: None,
+            show_unified_log: false,
+            remote: None,
+            remote_dialog: RemoteDialog::default(),
+            live_stream: None,
+            stream_dialog: StreamDialog::default(),
+            loki_dialog: LokiDialog::default(),
+            elastic_dialog: ElasticDialog::default(),
+            cloudwatch_dialog: CloudWatchDialog::default(),
+            cloud_logs_dialog: CloudLogsDialog::default(),
+            download: None,
+            bucket_url: String::new(),
+            bucket_error: None,
+            show_bucket: false,
+            kafka: None,
+            kafka_dialog: KafkaDialog::default(),
+            share_entries: Vec::new(),
+            share_upload: None,
+            share_result: None,
+            show_share: false,
+            current_file: None,
+            memory_source: None,
+            merge_sources: Vec::new(),
+            source_pane: None,
+            entries: Vec::new(),
+            view_generation: 0,
+            view_cache: None,
+            view_selection: None,
+            parse_job: None,
+            disk_log: None,
+            ingest: IngestQueue::new(),
+            live_entry_open: false,
+            pending_goto: None,
+            start_position_pending: false,
+            filtered_entries: Vec::new(),
+            tail_log: config.tail_log,
+            scroll_to_end: config.scroll_to_end,
+            auto_scroll_frames: 0,
+            scroll_offset: 0.0,
+            last_file_size: 0,
+            last_file_id: None,
+            show_search: false,
+            show_sidebar: false, // Closed by default
+            enabled_levels: {
+                let mut set = std::collections::HashSet::new();
+                set.insert(LogLevel::Info);
+                set.insert(LogLevel::Warn);
+                set.insert(LogLevel::Error);
+                set.insert(LogLevel::Debug);
+                set.insert(LogLevel::Trace);
+                set.insert(LogLevel::Unknown);
+                set
+            },
+            focus_search: false,
+            jump_to_first_match: false,
+            match_levels: (0, Vec::new()),
+            expanded_entries: std::collections::HashSet::new(),
+            scroll_to_match: false,
+            scroll_to_top: false,
+            scroll_target_line: None,
+            target_scroll_offset: None,
+            wrap_text: false, // Default: no wrapping, allow horizontal scroll
+            profiles: profiles::load_profiles(),
+            active_profile: None,
+            show_save_profile: false,
+            new_profile_name: String::new(),
+            custom_formats: custom_format::load_custom_formats(),
+            format_builder: FormatBuilder::new(),
+            entry_start_pattern: r"^\d{4}-\d{2}-\d{2}".to_string(),
+            entry_start_error: None,
+            script: None,
+            script_verdicts: Vec::new(),
+            metric_extractors: metrics::load_extractors(),
+            metric_series: Vec::new(),
+            show_metrics: true,
+            new_metric_name: String::new(),
+            new_metric_pattern: String::new(),
+            metric_error: None,
+            timeline: None,
+            show_timeline: true,
+            patterns: None,
+            show_patterns: false,
+            pattern_filter: None,
+            noise_report: None,
+            show_noise: false,
+            excluded_messages: std::collections::HashSet::new(),
+            transaction_rules: transactions::load_rules(),
+            transactions: Vec::new(),
+            transaction_error: None,
+            show_transactions: false,
+            transaction_sort: (SortColumn::Duration, true),
+            transaction_filter: None,
+            transaction_gantt: false,
+            heatmap: None,
+            show_heatmap: false,
+            heatmap_errors_only: false,
+            file_states: file_state::load_file_states(),
+            window_state: window_state::load(),
+            settings: SettingsDialog::new(),
+            config_error,
+            crash_report: diagnostics::take_unseen_crash_report(),
+            show_app_log: false,
+            unparsed: Unparsed::default(),
+            show_unparsed: false,
+            scratchpad: scratchpad::load(),
+            show_scratchpad: false,
+            investigations: investigations::load_investigations(),
+            show_investigations: false,
+            new_investigation_name: String::new(),
+            scratchpad_message: None,
+            relink_pending: false,
+            reload_anchor: None,
+            reload_baseline: None,
+            new_entries: std::collections::HashSet::new(),
+            new_entries_until: None,
+            palette_file: None,
+            memory_estimate: None,
+            unknown_share: None,
+            format_warning_dismissed: None,
+            update_check: config.check_for_updates.then(UpdateCheck::start),
+            update: None,
+            update_download: None,
+            update_download_result: None,
+            show_release_notes: false,
+            redactor: Redactor::new(&config.redaction),
+            config,
+        };
+        app.parser.set_custom_formats(&app.custom_formats);
+        app.parser.plugins = plugins::load_plugins();
+        app.parser.collapse_progress = app.config.collapse_progress_lines;
+        app.watch_config();
+        app
+    }
+}
+
+impl LogViewerApp {
+    /// Switch the parser format, re-parsing the current file if one is loaded.
+    pub fn set_format(&mut self, format: LogFormat) {
+        if self.parser.format == format {
+            return;
+        }
+        self.parser.format = format;
+        if let Err(e) = self.reload() {
+            app_error!("Error reloading file: {}", e);
+        }
+    }
+    
+    /// Change how entry boundaries are found, re-parsing the current file.
+    /// An invalid regex is reported and leaves the rule as it was.
+    pub fn set_entry_start(&mut self, rule: EntryStart) {
+        if *self.parser.entry_start() == rule {
+            return;
+        }
+        match self.parser.set_entry_start(rule) {
+            Ok(()) => {
+                self.entry_start_error = None;
+                if let Err(e) = self.reload() {
+                    app_error!("Error reloading file: {}", e);
+                }
+            }
+            Err(e) => self.entry_start_error = Some(e),
+        }
+    }
+    
+    fn save_custom_format(&mut self, format: CustomFormat) {
+        self.custom_formats.retain(|f| f.name != format.name);
+        self.custom_formats.push(format.clone());
+        if let Err(e) = custom_format::save_custom_formats(&self.custom_formats) {
+            app_error!("Error saving custom formats: {}", e);
+        }
+        self.parser.set_custom_formats(&self.custom_formats);
+        // Force a re-parse even if this format was already selected
+        self.parser.format = LogFormat::Auto;
+        self.set_format(LogFormat::Custom(format.name));
+    }
+    
+    fn delete_custom_format(&mut self, name: &str) {
+        self.custom_formats.retain(|f| f.name != name);
+        if let Err(e) = custom_format::save_custom_formats(&self.custom_formats) {
+            app_error!("Error saving custom formats: {}", e);
+        }
+        self.parser.set_custom_formats(&self.custom_formats);
+        if self.parser.format == LogFormat::Custom(name.to_string()) {
+            self.set_format(LogFormat::Auto);
+        }
+    }
+    
+    /// First lines of the current file, used to seed the format builder.
+    fn sample_lines(&self) -> String {
+        self.entries
+            .iter()
+            .flat_map(|e| e.raw_line.lines())
+            .take(10)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    
+    /// Tail and follow the end for this session only; the saved settings are left alone.
+    pub fn set_follow(&mut self, follow: bool) {
+        self.tail_log = follow;
+        self.scroll_to_end = follow;
+    }
+    
+    /// Show only the given levels. An empty list leaves the filter untouched.
+    pub fn set_level_filter(&mut self, levels: &[LogLevel]) {
+        if levels.is_empty() {
+            return;
+        }
+        self.enabled_levels = levels.iter().cloned().collect();
+        self.apply_filters();
+    }
+    
+    pub fn set_search(&mut self, query: &str, use_regex: bool) {
+        self.search.query = query.to_string();
+        self.search.use_regex = use_regex;
+        self.show_search = true;
+        self.apply_filters();
+        self.jump_to_first_match = true;
+    }
+    
+    /// Open a log file, or show a directory in the file browser.
+    pub fn open_path(&mut self, path: PathBuf) -> Result<(), String> {
+        if object_store::is_object_url(&path) {
+            self.open_object(&path.to_string_lossy())
+        } else if glob_watch::is_glob(&path) {
+            self.follow_pattern(path)
+        } else if path.is_dir() {
+            self.dir_browser.open(path)
+        } else {
+            self.load_file(path)
+        }
+    }
+    
+    /// Download an `s3://` or `gs://` object to the cache and open it when done.
+    pub fn open_object(&mut self, url: &str) -> Result<(), String> {
+        self.download = Some(Download::start(url)?);
+        self.bucket_error = None;
+        Ok(())
+    }
+    
+    /// Open a finished download, or show why it failed.
+    fn check_download(&mut self) {
+        let Some(result) = self.download.as_mut().and_then(|download| download.poll()) else {
+            return;
+        };
+        self.download = None;
+        if let Err(e) = result.and_then(|path| self.load_file(path)) {
+            app_error!("Error opening object: {}", e);
+            self.bucket_error = Some(e);
+            self.show_bucket = true;
+        }
+    }
+    
+    /// Tail the newest file matching `pattern`, switching to newer ones as they appear.
+    pub fn follow_pattern(&mut self, pattern: PathBuf) -> Result<(), String> {
+        let mut glob = GlobWatch::new(pattern)?;
+        let newest = glob.newest().ok_or_else(|| format!("No files match {}", glob.pattern().display()))?;
+        self.set_follow(true);
+        self.load_file(newest)?;
+        self.glob_watch = Some(glob);
+        Ok(())
+    }
+    
+    /// Continue with a newer file matching the followed pattern, keeping the
+    /// entries read so far.
+    fn check_glob_updates(&mut self) {
+        if self.parse_job.is_some() {
+            return;
+        }
+        let Some(newer) = self.glob_watch.as_mut().and_then(|glob| glob.check_for_newer()) else {
+            return;
+        };
+        // Lines the old file's tail already read go first
+        self.check_file_updates();
+        let name = newer.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let marker = format!("──────── Switched to newer file {} ────────", name);
+        self.ingest.push([LogEntry::note(&marker, 0)]);
+        // The tail reads the new file from its start
+        self.last_file_size = 0;
+        self.last_file_id = fs::metadata(&newer).ok().and_then(|m| file_watcher::file_id(&m));
+        self.current_file = Some(newer);
+        self.start_tail();
+    }
+    
+    /// Replace the view with syslog messages received on the configured ports.
+    fn start_listening(&mut self) -> Result<(), String> {
+        let listener = SyslogListener::start(&self.listen_config)?;
+        self.clear_for_stream();
+        self.listener = Some(listener);
+        Ok(())
+    }
+    
+    /// Replace the view with OpenTelemetry log records exported to the configured port.
+    fn start_otlp(&mut self) -> Result<(), String> {
+        let otlp = OtlpReceiver::start(&self.otlp_config)?;
+        self.clear_for_stream();
+        self.otlp = Some(otlp);
+        Ok(())
+    }
+    
+    /// Replace the view with records sent by Fluentd or Fluent Bit to the configured port.
+    fn start_forward(&mut self) -> Result<(), String> {
+        let forward = ForwardListener::start(&self.forward_config)?;
+        self.clear_for_stream();
+        self.forward = Some(forward);
+        Ok(())
+    }
+    
+    /// Replace the view with an Android device's logcat.
+    fn open_logcat(&mut self) -> Result<(), String> {
+        let logcat = Logcat::start(&self.logcat_config)?;
+        self.clear_for_stream();
+        self.logcat = Some(logcat);
+        Ok(())
+    }
+    
+    /// Replace the view with the macOS unified log, or the iOS simulator's.
+    fn open_unified_log(&mut self) -> Result<(), String> {
+        let unified_log = UnifiedLog::start(&self.unified_log_config)?;
+        self.clear_for_stream();
+        self.unified_log = Some(unified_log);
+        Ok(())
+    }
+    
+    /// Clear the device's log buffers along with the lines shown so far.
+    fn clear_logcat(&mut self) -> Result<(), String> {
+        if let Some(ref logcat) = self.logcat {
+            logcat.clear()?;
+            self.entries.clear();
+            self.entries_replaced();
+            self.apply_filters();
+        }
+        Ok(())
+    }
+    
+    /// Replace the view with a file tailed over SSH.
+    fn open_remote(&mut self) -> Result<(), String> {
+        let remote = RemoteTail::start(&self.remote_dialog.config, self.config.tail_window_size)?;
+        self.clear_for_stream();
+        self.remote = Some(remote);
+        Ok(())
+    }
+    
+    /// Replace the view with a WebSocket or Server-Sent Events stream.
+    fn open_stream(&mut self) -> Result<(), String> {
+        let stream = LiveStream::connect(&self.stream_dialog.url)?;
+        self.clear_for_stream();
+        self.live_stream = Some(stream);
+        Ok(())
+    }
+    
+    /// Replace the view with the results of a Loki query, followed live if asked.
+    fn open_loki(&mut self) -> Result<(), String> {
+        let stream = LiveStream::loki(&self.loki_dialog.query)?;
+        self.clear_for_stream();
+        self.live_stream = Some(stream);
+        Ok(())
+    }
+    
+    /// Replace the view with the hits of an Elasticsearch or OpenSearch search.
+    fn open_elastic(&mut self) -> Result<(), String> {
+        let stream = LiveStream::elastic(&self.elastic_dialog.query)?;
+        self.clear_for_stream();
+        self.live_stream = Some(stream);
+        Ok(())
+    }
+    
+    /// Replace the view with the events of a CloudWatch Logs group, followed live if asked.
+    fn open_cloudwatch(&mut self) -> Result<(), String> {
+        let stream = LiveStream::cloudwatch(&self.cloudwatch_dialog.query)?;
+        self.clear_for_stream();
+        self.live_stream = Some(stream);
+        Ok(())
+    }
+    
+    /// Replace the view with the results of a Google Cloud Logging or Azure Monitor query.
+    fn open_cloud_logs(&mut self) -> Result<(), String> {
+        let dialog = &self.cloud_logs_dialog;
+        let stream = match dialog.service {
+            CloudLogs::Google => LiveStream::gcp(&dialog.gcp_query)?,
+            CloudLogs::Azure => LiveStream::azure(&dialog.azure_query)?,
+        };
+        self.clear_for_stream();
+        self.live_stream = Some(stream);
+        Ok(())
+    }
+    
+    /// Replace the view with the records of a Kafka topic.
+    fn open_kafka(&mut self) -> Result<(), String> {
+        let kafka = KafkaSource::start(&self.kafka_dialog.config)?;
+        self.clear_for_stream();
+        self.kafka = Some(kafka);
+        Ok(())
+    }
+    
+    /// Disconnect any syslog, OTLP or Fluent Forward receiver, remote tail,
+    /// live stream, Kafka consumer, logcat or unified log stream.
+    fn stop_streams(&mut self) {
+        self.listener = None;
+        self.otlp = None;
+        self.forward = None;
+        self.logcat = None;
+        self.unified_log = None;
+        self.remote = None;
+        self.live_stream = None;
+        self.kafka = None;
+    }
+    
+    /// Drop the current source and its entries before a live stream starts.
+    fn clear_for_stream(&mut self) {
+        self.remember_file_state();
+        self.file_watcher.stop();
+        self.merge_sources.clear();
+        self.disk_log = None;
+        self.glob_watch = None;
+        self.stop_streams();
+        self.parse_job = None;
+        self.pending_goto = None;
+        self.current_file = None;
+        self.memory_source = None;
+        self.last_file_size = 0;
+        self.entries.clear();
+        self.live_entry_open = false;
+        self.entries_replaced();
+        self.apply_filters();
+    }
+    
+    /// Whether live sources wait for the queue of lines to drain before
+    /// being read again.
+    fn sources_paused(&self) -> bool {
+        self.config.overflow_policy == OverflowPolicy::PauseSource && self.ingest.is_full(self.config.max_queued_lines)
+    }
+    
+    /// Live lines dropped since the source was opened, by the queue or by
+    /// the syslog or OTLP receiver.
+    fn dropped_lines(&self) -> usize {
+        self.ingest.dropped()
+            + self.listener.as_ref().map_or(0, |listener| listener.dropped())
+            + self.otlp.as_ref().map_or(0, |otlp| otlp.dropped())
+    }
+    
+    /// Queue the syslog messages, OpenTelemetry and Fluent Forward records
+    /// received since the last frame.
+    fn check_listener(&mut self) {
+        if let Some(ref mut listener) = self.listener {
+            self.ingest.push(listener.take_entries());
+        }
+        if let Some(ref mut otlp) = self.otlp {
+            self.ingest.push(otlp.take_entries());
+        }
+        let lines = self.forward.as_mut().map(ForwardListener::take_lines).unwrap_or_default();
+        if !lines.is_empty() {
+            self.queue_lines(&lines);
+        }
+    }
+    
+    /// Queue the lines a remote tail, live stream or Kafka consumer received
+    /// since the last frame, with a marker where its connection dropped, its
+    /// file was truncated or it ended.
+    fn check_sources(&mut self) {
+        let events = if let Some(ref mut remote) = self.remote {
+            remote.take_events()
+        } else if let Some(ref mut stream) = self.live_stream {
+            stream.take_events()
+        } else if let Some(ref mut kafka) = self.kafka {
+            kafka.take_events()
+        } else {
+            return;
+        };
+        for event in events {
+            match event {
+                SourceEvent::Lines(lines) => self.queue_lines(&lines),
+                SourceEvent::Connected => {}
+                SourceEvent::Disconnected(e) => self.queue_marker(&format!("──────── Connection lost ({}), reconnecting ────────", e)),
+                SourceEvent::Truncated => self.queue_marker("──────── File was truncated, continuing from its start ────────"),
+                SourceEvent::Ended => self.queue_marker("──────── End of stream ────────"),
+            }
+        }
+    }
+    
+    /// Queue the logcat lines and unified log messages read since the last frame.
+    fn check_logcat(&mut self) {
+        if let Some(ref mut logcat) = self.logcat {
+            self.ingest.push(logcat.take_entries());
+        }
+        if let Some(ref mut unified_log) = self.unified_log {
+            self.ingest.push(unified_log.take_entries());
+        }
+    }
+    
+    /// Parse lines from a live source into entries and queue them. Lines
+    /// continuing the last entry received go on it.
+    fn queue_lines(&mut self, lines: &[String]) {
+        let mut new_entries = Vec::new();
+        let mut continuation = String::new();
+        for line in lines {
+            if let Some(line) = self.parser.append_line(line, &mut new_entries, &mut self.live_entry_open) {
+                continuation.push('\n');
+                continuation.push_str(&line);
+            }
+        }
+        if !continuation.is_empty() {
+            self.continue_last_entry(0, &continuation);
+        }
+        self.ingest.push(new_entries);
+    }
+    
+    /// Queue a marker from the viewer in a live source, which ends its last entry.
+    fn queue_marker(&mut self, marker: &str) {
+        self.ingest.push([LogEntry::note(marker, 0)]);
+        self.live_entry_open = false;
+    }
+    
+    /// Load the requested file, view it as asked and jump to its line, if any.
+    pub fn open_request(&mut self, request: OpenRequest) {
+        if request.follow {
+            self.set_follow(true);
+        }
+        if let Err(e) = self.open_path(request.path) {
+            app_error!("Error loading file from open request: {}", e);
+            return;
+        }
+        // After loading, so an explicit format beats the file's remembered one
+        if request.format != LogFormat::Auto {
+            self.set_format(request.format);
+        }
+        self.set_level_filter(&request.levels);
+        if let Some(ref query) = request.search {
+            self.set_search(query, request.regex);
+        }
+        if let Some(line) = request.line {
+            self.goto_line(line);
+        }
+    }
+    
+    /// Open each group of files handed over since the last frame, in turn.
+    fn open_pending(&mut self, groups: Vec<Vec<OpenRequest>>) {
+        for requests in groups {
+            self.open_requests(requests);
+        }
+    }
+    
+    /// Open the first of several files handed over together, e.g. by a
+    /// multi-file "Open With", and merge the others into the view, each
+    /// viewed as its request asks.
+    fn open_requests(&mut self, requests: Vec<OpenRequest>) {
+        let mut requests = requests.into_iter();
+        let Some(first) = requests.next() else {
+            return;
+        };
+        self.open_request(first);
+        for request in requests {
+            if request.follow {
+                self.set_follow(true);
+            }
+            if let Err(e) = self.add_merge_source(request.path) {
+                app_error!("Error merging file from open request: {}", e);
+                continue;
+            }
+            self.set_level_filter(&request.levels);
+            if let Some(ref query) = request.search {
+                self.set_search(query, request.regex);
+            }
+            if let Some(line) = request.line {
+                let source = self.merge_sources.len().saturating_sub(1);
+                self.goto_source_line(source, line);
+            }
+        }
+    }
+    
+    /// Apply a workspace profile: format, level filter, search, wrapping and default folder.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        self.set_format(profile.format.clone());
+        
+        self.enabled_levels = if profile.levels.is_empty() {
+            LogLevel::ALL.iter().cloned().collect()
+        } else {
+            profile.levels.iter().cloned().collect()
+        };
+        self.search.query = profile.search_query.clone();
+        self.search.use_regex = profile.search_regex;
+        self.search.start_search(&self.entries);
+        self.wrap_text = profile.wrap_text;
+        
+        if let Some(ref dir) = profile.default_directory {
+            if self.dir_browser.dir() != Some(dir.as_path()) {
+                if let Err(e) = self.dir_browser.open(dir.clone()) {
+                    app_error!("Error opening profile folder: {}", e);
+                }
+            }
+        }
+        
+        self.apply_filters();
+        self.active_profile = Some(profile.name.clone());
+    }
+    
+    /// Capture the current settings as a profile with the given name.
+    fn profile_from_current(&self, name: String) -> Profile {
+        Profile {
+            name,
+            format: self.parser.format.clone(),
+            levels: LogLevel::ALL.iter().filter(|l| self.enabled_levels.contains(l)).cloned().collect(),
+            search_query: self.search.query.clone(),
+            search_regex: self.search.use_regex,
+            wrap_text: self.wrap_text,
+            default_directory: self.dir_browser.dir().map(|d| d.to_path_buf()),
+        }
+    }
+    
+    fn save_profile(&mut self, name: String) {
+        let profile = self.profile_from_current(name);
+        self.active_profile = Some(profile.name.clone());
+        self.profiles.retain(|p| p.name != profile.name);
+        self.profiles.push(profile);
+        if let Err(e) = profiles::save_profiles(&self.profiles) {
+            app_error!("Error saving profiles: {}", e);
+        }
+    }
+    
+    fn delete_profile(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        if let Err(e) = profiles::save_profiles(&self.profiles) {
+            app_error!("Error saving profiles: {}", e);
+        }
+    }
+    
+    /// The entry at the top of the view.
+    fn top_entry(&self) -> Option<usize> {
+        let cache = self.view_cache.as_ref()?;
+        let i = cache.entry_tops.partition_point(|&top| top <= self.scroll_offset).saturating_sub(1);
+        let &(_, idx) = cache.entry_starts.get(i)?;
+        (idx < self.entries.len()).then_some(idx)
+    }
+    
+    /// Capture the open files, filters, search, pins and position as an investigation.
+    fn investigation_from_current(&self, name: String) -> Investigation {
+        Investigation {
+            name,
+            saved_at: chrono::Utc::now().timestamp(),
+            files: self.open_files(),
+            format: self.parser.format.clone(),
+            entry_start: self.parser.entry_start().clone(),
+            levels: LogLevel::ALL.iter().filter(|l| self.enabled_levels.contains(l)).cloned().collect(),
+            search_query: self.search.query.clone(),
+            search_regex: self.search.use_regex,
+            search_case_sensitive: self.search.case_sensitive,
+            show_only_matches: self.search.show_only_matches,
+            excluded_messages: self.excluded_messages.iter().cloned().collect(),
+            pins: self.scratchpad.clone(),
+            line: self.top_entry().map(|idx| self.entries[idx].line_number),
+        }
+    }
+    
+    fn save_investigation(&mut self, name: String) {
+        let investigation = self.investigation_from_current(name);
+        self.investigations.retain(|i| i.name != investigation.name);
+        self.investigations.push(investigation);
+        if let Err(e) = investigations::save_investigations(&self.investigations) {
+            app_error!("Error saving investigations: {}", e);
+        }
+    }
+    
+    fn delete_investigation(&mut self, name: &str) {
+        self.investigations.retain(|i| i.name != name);
+        if let Err(e) = investigations::save_investigations(&self.investigations) {
+            app_error!("Error saving investigations: {}", e);
+        }
+    }
+    
+    /// Reopen an investigation's files as they were viewed, add back its pins
+    /// and go to where reading stopped.
+    fn open_investigation(&mut self, investigation: &Investigation) -> Result<(), String> {
+        if let Some((first, others)) = investigation.files.split_first() {
+            if self.open_files() != investigation.files {
+                self.load_file(first.clone())?;
+                for path in others {
+                    self.add_merge_source(path.clone())?;
+                }
+            }
+        }
+        self.set_format(investigation.format.clone());
+        if let EntryStart::Pattern(ref pattern) = investigation.entry_start {
+            self.entry_start_pattern = pattern.clone();
+        }
+        self.set_entry_start(investigation.entry_start.clone());
+        
+        self.enabled_levels = if investigation.levels.is_empty() {
+            LogLevel::ALL.iter().cloned().collect()
+        } else {
+            investigation.levels.iter().cloned().collect()
+        };
+        self.search.query = investigation.search_query.clone();
+        self.search.use_regex = investigation.search_regex;
+        self.search.case_sensitive = investigation.search_case_sensitive;
+        self.search.show_only_matches = investigation.show_only_matches;
+        self.excluded_messages = investigation.excluded_messages.iter().cloned().collect();
+        self.apply_filters();
+        
+        if !investigation.pins.is_empty() {
+            let open_files = self.open_files();
+            scratchpad::merge_imported(&mut self.scratchpad, investigation.pins.clone(), &open_files);
+            self.save_scratchpad();
+            self.show_scratchpad = true;
+        }
+        
+        // Line numbers of merged files overlap, so only a single file is positioned
+        if let Some(line) = investigation.line.filter(|_| self.merge_sources.is_empty()) {
+            // New lines would pull the view back to the end
+            self.scroll_to_end = false;
+            self.target_scroll_offset = None;
+            self.reload_anchor = None;
+            self.goto_line(line);
+        }
+        Ok(())
+    }
+    
+    /// Scroll to the entry containing the given 1-based file line number (of
+    /// the first file, in a merged view).
+    pub fn goto_line(&mut self, line_number: usize) {
+        self.goto_source_line(0, line_number);
+    }
+    
+    /// Scroll to the entry containing the given 1-based line of merged file
+    /// `source`, or of the file when nothing is merged.
+    pub fn goto_source_line(&mut self, source: usize, line_number: usize) {
+        // An explicit line wins over the start position
+        self.start_position_pending = false;
+        if let Some(ref mut disk_log) = self.disk_log {
+            disk_log.goto_line(line_number);
+            return;
+        }
+        if self.is_parsing() {
+            self.pending_goto = Some((source, line_number));
+            return;
+        }
+        let idx = if self.merge_sources.is_empty() {
+            self.entries.partition_point(|e| e.line_number <= line_number).checked_sub(1)
+        } else {
+            // Merged entries are in time order, so each file's line numbers
+            // needn't ascend through them
+            self.entries
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.source == source && e.line_number <= line_number)
+                .max_by_key(|(_, e)| e.line_number)
+                .map(|(idx, _)| idx)
+        };
+        if let Some(idx) = idx {
+            self.scroll_target_line = Some(idx);
+            self.auto_scroll_frames = 0;
+        }
+    }
+    
+    /// The time of the entry at the top of the view, or of the last entry
+    /// before it with one.
+    fn top_entry_time(&self) -> Option<i64> {
+        let idx = self.top_entry()?;
+        self.entries[..=idx].iter().rev().find_map(|entry| entry.time_ms())
+    }
+    
+    /// Scroll the view to the first shown entry at or after `time_ms`.
+    fn goto_time(&mut self, time_ms: i64) {
+        let row = self.filtered_entries.partition_point(|&idx| self.entries[idx].time_ms().is_none_or(|time| time < time_ms));
+        let row = row.min(self.filtered_entries.len().saturating_sub(1));
+        let Some(&idx) = self.filtered_entries.get(row) else {
+            return;
+        };
+        // Laid out already, the view can scroll there without laying it out again
+        match self.view_cache.as_ref().and_then(|cache| cache.entry_tops.get(row)) {
+            Some(&top) => self.target_scroll_offset = Some(top),
+            None => self.scroll_target_line = Some(idx),
+        }
+        self.auto_scroll_frames = 0;
+    }
+}
+
+impl eframe::App for LogViewerApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.remember_file_state();
+        if let Err(e) = self.config.save() {
+            app_error!("Error saving config: {}", e);
+        }
+        if let Err(e) = window_state::save(&self.window_state) {
+            app_error!("Error saving window state: {}", e);
+        }
+    }
+    
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        use egui::*;
+        // Handle keyboard shortcuts
+        ctx.input(|input| {
+            // Cmd+F or Ctrl+F to toggle search
+            if input.key_pressed(egui::Key::F) && 
+               (input.modifiers.command || input.modifiers.ctrl) {
+                self.show_search = !self.show_search;
+                if self.show_search {
+                    self.focus_search = true;
+                }
+            }
+            
+            // Cmd+S to toggle sidebar
+            if input.key_pressed(egui::Key::S) && 
+               (input.modifiers.command || input.modifiers.ctrl) {
+                self.show_sidebar = !self.show_sidebar;
+            }
+            
+            // ESC to close search
+            if input.key_pressed(egui::Key::Escape) && self.show_search {
+                self.show_search = false;
+            }
+            
+            // Navigation shortcuts: Cmd+ArrowUp/Down to jump to top/bottom
+            if input.modifiers.command || input.modifiers.ctrl {
+                if input.key_pressed(egui::Key::ArrowUp) {
+                    // Jump to top
+                    self.scroll_to_top = true;
+                }
+                if input.key_pressed(egui::Key::ArrowDown) {
+                    // Jump to bottom
+                    self.auto_scroll_frames = 3;
+                }
+            }
+
+            // Font size shortcuts: Cmd+= to increase, Cmd+- to decrease (like VS Code/Sublime)
+            if input.modifiers.command || input.modifiers.ctrl {
+                // Decrease with Cmd+-
+                if input.key_pressed(egui::Key::Minus) {
+                    self.config.font_size = (self.config.font_size - 1.0).max(8.0);
+                }
+                
+                // Increase with Cmd+= or Cmd++
+                // Try multiple approaches to catch the equals key
+                let mut should_increase = false;
+                
+                // Check key events
+                for event in &input.events {
+                    match event {
+                        egui::Event::Key { key, pressed: true, .. } => {
+                            // Some keyboards report equals as a specific key
+                            if format!("{:?}", key).contains("Num0") || 
+                               format!("{:?}", key).contains("Equals") {
+                                should_increase = true;
+                            }
+                        }
+                        egui::Event::Text(text) => {
+                            if text == "=" || text == "+" {
+                                should_increase = true;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                
+                if should_increase {
+                    self.config.font_size = (self.config.font_size + 1.0).min(30.0);
+                }
+            }
+        });
+        
+        // Apply theme
+        match self.config.effective_theme() {
+            Theme::Dark => {
+                let mut visuals = egui::Visuals::dark();
+                visuals.panel_fill = egui::Color32::from_rgb(0x2e, 0x2e, 0x2e);
+                visuals.extreme_bg_color = egui::Color32::from_rgb(0x2e, 0x2e, 0x2e);
+                ctx.set_visuals(visuals);
+            }
+            Theme::Light => ctx.set_visuals(egui::Visuals::light()),
+        }
+        
+        // Check for file updates
+        self.check_parse_progress(false);
+        self.check_merge_progress();
+        self.check_start_position();
+        self.check_reload();
+        self.expire_new_entries(ctx);
+        self.check_palette();
+        if let Some(ref mut disk_log) = self.disk_log {
+            disk_log.update(&self.enabled_levels);
+        }
+        // A full queue that pauses sources leaves new lines with them until it drains
+        let paused = self.sources_paused();
+        if !paused {
+            self.check_file_updates();
+        }
+        self.check_config_updates();
+        self.check_update();
+        self.check_search();
+        diagnostics::set_open_file(self.current_file.as_deref());
+        self.dir_browser.refresh_if_changed();
+        self.check_glob_updates();
+        self.check_download();
+        if !paused {
+            self.check_listener();
+            self.check_sources();
+            self.check_logcat();
+        }
+        if self.config.overflow_policy == OverflowPolicy::DropOldest {
+            self.ingest.enforce_capacity(self.config.max_queued_lines);
+        }
+        self.release_ingested();
+        self.window_state.update(&frame.info().window_info);
+        if self.script.as_mut().map_or(false, |script| script.reload_if_changed()) {
+            self.script_verdicts.clear();
+            self.apply_filters();
+        }
+        
+        // Handle files opened through the OS (macOS Finder / "Open With") or handed
+        // over by another launch of the app
+        if file_open::take_focus_request() {
+            frame.focus();
+        }
+        self.open_pending(file_open::take_pending());
+        
+        // Handle Drag & Drop
+        if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
+            let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+            if let Some(file) = dropped_files.first() {
+                if let Some(path) = &file.path {
+                    if path.exists() {
+                        if let Err(e) = self.open_path(path.clone()) {
+                            app_error!("Error loading dropped file: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+        
+        // Modern UI Layout
+        
+        // 1. Top Header
+        egui::TopBottomPanel::top("header").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.heading("Log Viewer");
+                
+                ui.add_space(20.0);
+                
+                // File Controls
+                let icon_size = 20.0;
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("📁")).on_hover_text("Open File").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Log files", &["log", "txt"])
+                        .pick_file()
+                    {
+                        if let Err(e) = self.load_file(path) {
+                            app_error!("Error loading file: {}", e);
+                        }
+                    }
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("📂")).on_hover_text("Open Folder").clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        if let Err(e) = self.dir_browser.open(dir) {
+                            app_error!("Error opening folder: {}", e);
+                        }
+                    }
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("➕")).on_hover_text("Merge File into View").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Log files", &["log", "txt"])
+                        .pick_file()
+                    {
+                        if let Err(e) = self.add_merge_source(path) {
+                            app_error!("Error merging file: {}", e);
+                        }
+                    }
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("📋")).on_hover_text("New from Clipboard").clicked() {
+                    if let Err(e) = self.load_clipboard() {
+                        app_error!("Error loading clipboard: {}", e);
+                    }
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("📥").selected(self.show_bucket)).on_hover_text("Open from S3 or GCS").clicked() {
+                    self.show_bucket = !self.show_bucket;
+                }
+                
+                // Sources behind a cargo feature only get a button in builds with it
+                if cfg!(feature = "ssh") && ui.add_sized([icon_size, icon_size], egui::Button::new("🌐").selected(self.remote_dialog.open)).on_hover_text("Open Remote File").clicked() {
+                    self.remote_dialog.open = !self.remote_dialog.open;
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("🔌").selected(self.stream_dialog.open)).on_hover_text("Connect to Log Stream").clicked() {
+                    self.stream_dialog.open = !self.stream_dialog.open;
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("📜").selected(self.loki_dialog.open)).on_hover_text("Query Loki").clicked() {
+                    self.loki_dialog.open = !self.loki_dialog.open;
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("🗂").selected(self.elastic_dialog.open)).on_hover_text("Search Elasticsearch").clicked() {
+                    self.elastic_dialog.open = !self.elastic_dialog.open;
+                }
+                
+                if cfg!(feature = "aws") && ui.add_sized([icon_size, icon_size], egui::Button::new("☁").selected(self.cloudwatch_dialog.open)).on_hover_text("Read CloudWatch Logs").clicked() {
+                    self.cloudwatch_dialog.open = !self.cloudwatch_dialog.open;
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("🔭").selected(self.cloud_logs_dialog.open)).on_hover_text("Query Google Cloud or Azure Logs").clicked() {
+                    self.cloud_logs_dialog.open = !self.cloud_logs_dialog.open;
+                }
+                
+                if cfg!(feature = "kafka") && ui.add_sized([icon_size, icon_size], egui::Button::new("📨").selected(self.kafka_dialog.open)).on_hover_text("Consume Kafka Topic").clicked() {
+                    self.kafka_dialog.open = !self.kafka_dialog.open;
+                }
+                
+                let listen_btn = egui::Button::new("📡").selected(self.show_listen || self.listener.is_some());
+                if ui.add_sized([icon_size, icon_size], listen_btn).on_hover_text("Listen for Syslog").clicked() {
+                    self.show_listen = !self.show_listen;
+                }
+                
+                let otlp_btn = egui::Button::new("📶").selected(self.show_otlp || self.otlp.is_some());
+                if ui.add_sized([icon_size, icon_size], otlp_btn).on_hover_text("Receive OpenTelemetry Logs").clicked() {
+                    self.show_otlp = !self.show_otlp;
+                }
+                
+                let forward_btn = egui::Button::new("🚚").selected(self.show_forward || self.forward.is_some());
+                if ui.add_sized([icon_size, icon_size], forward_btn).on_hover_text("Receive Fluentd / Fluent Bit Forward").clicked() {
+                    self.show_forward = !self.show_forward;
+                }
+                
+                let logcat_btn = egui::Button::new("📱").selected(self.show_logcat || self.logcat.is_some());
+                if ui.add_sized([icon_size, icon_size], logcat_btn).on_hover_text("Android Logcat").clicked() {
+                    self.show_logcat = !self.show_logcat;
+                }
+                
+                if cfg!(target_os = "macos") {
+                    let unified_btn = egui::Button::new("🍎").selected(self.show_unified_log || self.unified_log.is_some());
+                    if ui.add_sized([icon_size, icon_size], unified_btn).on_hover_text("macOS Unified Log").clicked() {
+                        self.show_unified_log = !self.show_unified_log;
+                    }
+                }
+                
+                if ui.add_sized([icon_size, icon_size], egui::Button::new("🔄")).on_hover_text("Reload").clicked() {
+                    if let Err(e) = self.reload() {
+                        app_error!("Error reloading file: {}", e);
+                    }
+                }
+                
+                // Breadcrumb / File Info
+                ui.add_space(20.0);
+                if let Some(ref path) = self.current_file {
+                    ui.label(egui::RichText::new(path.file_name().unwrap_or_default().to_string_lossy()).strong());
+                    
+                    // File Size
+                    if let Ok(metadata) = fs::metadata(path) {
+                        let size_mb = metadata.len() as f64 / 1_000_000.0;
+                        ui.label(format!("({:.2} MB)", size_mb));
+                    }
+                    if self.merge_sources.len() > 1 {
+                        ui.label(format!("+ {} merged", self.merge_sources.len() - 1));
+                    }
+                } else if let Some(ref source) = self.memory_source {
+                    ui.label(egui::RichText::new(&source.name).strong());
+                    ui.label("(in memory)");
+                } else if let Some(ref listener) = self.listener {
+                    ui.label(egui::RichText::new("Syslog").strong());
+                    ui.label(format!("({}, {} received)", listener.description(), listener.received()));
+                } else if let Some(ref otlp) = self.otlp {
+                    ui.label(egui::RichText::new("OpenTelemetry").strong());
+                    ui.label(format!("({}, {} received)", otlp.description(), otlp.received()));
+                } else if let Some(ref forward) = self.forward {
+                    ui.label(egui::RichText::new("Fluent Forward").strong());
+                    ui.label(format!("({}, {} received)", forward.description(), forward.received()));
+                } else if let Some(ref logcat) = self.logcat {
+                    ui.label(egui::RichText::new("Logcat").strong());
+                    ui.label(format!("({}, {} lines)", logcat.description(), logcat.received()));
+                } else if let Some(ref unified_log) = self.unified_log {
+                    ui.label(egui::RichText::new("Unified Log").strong());
+                    ui.label(format!("({}, {} messages)", unified_log.description(), unified_log.received()));
+                } else if let Some(ref remote) = self.remote {
+                    ui.label(egui::RichText::new(remote.description()).strong());
+                    ui.label(if remote.events().is_connected() { "(connected)" } else { "(connecting…)" });
+                } else if let Some(ref stream) = self.live_stream {
+                    ui.label(egui::RichText::new(stream.url()).strong());
+                    ui.label(if stream.events().has_ended() {
+                        "(ended)"
+                    } else if stream.events().is_connected() {
+                        "(connected)"
+                    } else {
+                        "(connecting…)"
+                    });
+                } else if let Some(ref kafka) = self.kafka {
+                    ui.label(egui::RichText::new(kafka.description()).strong());
+                    ui.label(if kafka.events().is_connected() { "(consuming)" } else { "(connecting…)" });
+                } else {
+                    ui.label("No file loaded");
+                }
+                
+                // Live ingestion rate, and the backlog when lines arrive faster than they are shown
+                let lines_per_sec = self.ingest.lines_per_sec();
+                let dropped = self.dropped_lines();
+                if lines_per_sec > 0 || self.ingest.pending() > 0 || dropped > 0 {
+                    let mut rate = format!("{} lines/s", lines_per_sec);
+                    if self.ingest.pending() > self.config.max_lines_per_refresh.max(1) {
+                        rate.push_str(&format!(", {} queued", self.ingest.pending()));
+                    }
+                    if self.sources_paused() {
+                        rate.push_str(", reading paused");
+                    }
+                    ui.label(egui::RichText::new(rate).weak());
+                    if dropped > 0 {
+                        ui.label(egui::RichText::new(format!("{} dropped", dropped)).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)))
+                            .on_hover_text("Lines that arrived faster than they could be shown (see ⚙ Settings → General)");
+                    }
+                }
+                
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    // Settings
+                    let settings_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("⚙").selected(self.settings.open)).on_hover_text("Settings");
+                    if settings_btn.clicked() {
+                        self.settings.open(&self.config, SettingsTab::General);
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Investigations
+                    let investigations_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🕵").selected(self.show_investigations))
+                        .on_hover_text("Investigations: save this session to pick it up later");
+                    if investigations_btn.clicked() {
+                        self.show_investigations = !self.show_investigations;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Scratchpad Toggle
+                    let scratchpad_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("📌").selected(self.show_scratchpad))
+                        .on_hover_text("Scratchpad: entries pinned from any file");
+                    if scratchpad_btn.clicked() {
+                        self.show_scratchpad = !self.show_scratchpad;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // App Log Toggle
+                    let app_log_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🐞").selected(self.show_app_log))
+                        .on_hover_text("App Log: errors inside Log Rocket itself");
+                    if app_log_btn.clicked() {
+                        self.show_app_log = !self.show_app_log;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Redaction Toggle
+                    let redact_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🕶").selected(self.config.redaction.enabled))
+                        .on_hover_text("Redact sensitive data (right-click for the rules)");
+                    if redact_btn.clicked() {
+                        let mut config = self.config.clone();
+                        config.redaction.enabled = !config.redaction.enabled;
+                        self.apply_config(config);
+                        if let Err(e) = self.config.save() {
+                            app_error!("Error saving config: {}", e);
+                        }
+                    }
+                    if redact_btn.secondary_clicked() {
+                        self.settings.open(&self.config, SettingsTab::Privacy);
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Sidebar Toggle
+                    let sidebar_icon = if self.show_sidebar { "⏵" } else { "⏴" };
+                    let sidebar_btn = ui.add_sized([icon_size, icon_size], egui::Button::new(sidebar_icon)).on_hover_text("Toggle Sidebar");
+                    if sidebar_btn.clicked() {
+                        self.show_sidebar = !self.show_sidebar;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Search Toggle
+                    let search_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🔍").selected(self.show_search)).on_hover_text("Toggle Search");
+                    if search_btn.clicked() {
+                        self.show_search = !self.show_search;
+                        if self.show_search {
+                            self.focus_search = true;
+                        }
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Heatmap Toggle
+                    let heatmap_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🗓").selected(self.show_heatmap)).on_hover_text("Volume Heatmap");
+                    if heatmap_btn.clicked() {
+                        self.show_heatmap = !self.show_heatmap;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Transactions Toggle
+                    let transactions_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("⏱").selected(self.show_transactions)).on_hover_text("Transactions");
+                    if transactions_btn.clicked() {
+                        self.show_transactions = !self.show_transactions;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Noise Report Toggle
+                    let noise_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("📊").selected(self.show_noise)).on_hover_text("Noise Report");
+                    if noise_btn.clicked() {
+                        self.show_noise = !self.show_noise;
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Patterns Toggle
+                    let patterns_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🧩").selected(self.show_patterns)).on_hover_text("Message Patterns");
+                    if patterns_btn.clicked() {
+                        self.show_patterns = !self.show_patterns;
+                        if self.show_patterns {
+                            self.apply_filters();
+                        }
+                    }
+                    
+                    ui.add_space(10.0);
+                    
+                    // Workspace profile selector
+                    let mut chosen_profile = None;
+                    let mut delete_profile = None;
+                    egui::ComboBox::from_id_source("profile_selector")
+                        .selected_text(self.active_profile.as_deref().unwrap_or("No profile"))
+                        .show_ui(ui, |ui| {
+                            for profile in &self.profiles {
+                                let selected = self.active_profile.as_deref() == Some(profile.name.as_str());
+                                if ui.selectable_label(selected, &profile.name).clicked() {
+                                    chosen_profile = Some(profile.clone());
+                                }
+                            }
+                            if !self.profiles.is_empty() {
+                                ui.separator();
+                            }
+                            if ui.button("Save current as…").clicked() {
+                                self.new_profile_name = self.active_profile.clone().unwrap_or_default();
+                                self.show_save_profile = true;
+                            }
+                            if let Some(ref name) = self.active_profile {
+                                if ui.button(format!("Delete \"{}\"", name)).clicked() {
+                                    delete_profile = Some(name.clone());
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Workspace Profile");
+                    if let Some(profile) = chosen_profile {
+                        self.apply_profile(&profile);
+                    }
+                    if let Some(name) = delete_profile {
+                        self.delete_profile(&name);
+                    }
+                });
+            });
+            ui.add_space(4.0);
+        });
+
+        // Save profile dialog
+        if self.show_save_profile {
+            let mut open = true;
+            let mut save = false;
+            egui::Window::new("Save Profile")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Saves the format, level filter, search, wrapping and open folder.");
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        let response = ui.text_edit_singleline(&mut self.new_profile_name);
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            save = true;
+                        }
+                    });
+                    if ui.button("Save").clicked() {
+                        save = true;
+                    }
+                });
+            let name = self.new_profile_name.trim().to_string();
+            if save && !name.is_empty() {
+                self.save_profile(name);
+                open = false;
+            }
+            self.show_save_profile = open;
+        }
+
+        // Investigations
+        if self.show_investigations {
+            let mut open = true;
+            let mut save = false;
+            let mut chosen = None;
+            let mut delete = None;
+            egui::Window::new("Investigations")
+                .open(&mut open)
+                .default_width(420.0)
+                .show(ctx, |ui| {
+                    ui.label("Saves the open files, format, filters, search, pins and where you are reading.");
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        let response = ui.text_edit_singleline(&mut self.new_investigation_name);
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            save = true;
+                        }
+                        let name = self.new_investigation_name.trim();
+                        let replaces = self.investigations.iter().any(|i| i.name == name);
+                        if ui.add_enabled(!name.is_empty(), egui::Button::new(if replaces { "Update" } else { "Save" })).clicked() {
+                            save = true;
+                        }
+                    });
+                    ui.separator();
+                    
+                    if self.investigations.is_empty() {
+                        ui.label(egui::RichText::new("No saved investigations").weak());
+                    }
+                    egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                        // Most recently saved first
+                        let mut order: Vec<&Investigation> = self.investigations.iter().collect();
+                        order.sort_by_key(|i| std::cmp::Reverse(i.saved_at));
+                        for investigation in order {
+                            ui.horizontal(|ui| {
+                                ui.vertical(|ui| {
+                                    ui.label(egui::RichText::new(&investigation.name).strong());
+                                    let saved = chrono::DateTime::from_timestamp(investigation.saved_at, 0)
+                                        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                                        .unwrap_or_default();
+                                    let mut details = format!("{} · saved {}", investigation.files_label(), saved);
+                                    if !investigation.pins.is_empty() {
+                                        details.push_str(&format!(" · {} pins", investigation.pins.len()));
+                                    }
+                                    ui.label(egui::RichText::new(details).size(12.0).weak());
+                                });
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                                        delete = Some(investigation.name.clone());
+                                    }
+                                    if ui.button("Open").clicked() {
+                                        chosen = Some(investigation.clone());
+                                    }
+                                });
+                            });
+                            ui.separator();
+                        }
+                    });
+                });
+            let name = self.new_investigation_name.trim().to_string();
+            if save && !name.is_empty() {
+                self.save_investigation(name);
+            }
+            if let Some(name) = delete {
+                self.delete_investigation(&name);
+            }
+            if let Some(investigation) = chosen {
+                self.new_investigation_name = investigation.name.clone();
+                if let Err(e) = self.open_investigation(&investigation) {
+                    app_error!("Error opening investigation \"{}\": {}", investigation.name, e);
+                }
+            }
+            self.show_investigations = open;
+        }
+
+        // Syslog listener dialog
+        if self.show_listen {
+            let mut open = true;
+            let (mut start, mut stop) = (false, false);
+            egui::Window::new("Syslog Listener")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let listening = self.listener.is_some();
+                    ui.add_enabled_ui(!listening, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Address:");
+                            ui.add(egui::TextEdit::singleline(&mut self.listen_config.bind).desired_width(120.0));
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut self.listen_config.port).clamp_range(1..=65535));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.listen_config.tcp, "TCP");
+                            ui.checkbox(&mut self.listen_config.udp, "UDP");
+                        });
+                    });
+                    if let Some(ref listener) = self.listener {
+                        ui.label(format!("Listening on {}, {} messages received", listener.description(), listener.received()));
+                        stop = ui.button("Stop").clicked();
+                    } else {
+                        start = ui.button("Start").clicked();
+                    }
+                    if let Some(ref error) = self.listen_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                });
+            if start {
+                self.listen_error = self.start_listening().err();
+            }
+            if stop {
+                self.listener = None;
+            }
+            self.show_listen = open;
+        }
+
+        // OpenTelemetry receiver dialog
+        if self.show_otlp {
+            let mut open = true;
+            let (mut start, mut stop) = (false, false);
+            egui::Window::new("OpenTelemetry Receiver")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.add_enabled_ui(self.otlp.is_none(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Address:");
+                            ui.add(egui::TextEdit::singleline(&mut self.otlp_config.bind).desired_width(120.0));
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut self.otlp_config.port).clamp_range(1..=65535));
+                        });
+                    });
+                    ui.label(egui::RichText::new("OTLP over HTTP (protobuf or JSON); set OTEL_EXPORTER_OTLP_PROTOCOL=http/protobuf for SDKs defaulting to gRPC").weak());
+                    if let Some(ref otlp) = self.otlp {
+                        ui.label(format!("Receiving at {}, {} records received", otlp.description(), otlp.received()));
+                        stop = ui.button("Stop").clicked();
+                    } else {
+                        start = ui.button("Start").clicked();
+                    }
+                    if let Some(ref error) = self.otlp_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                });
+            if start {
+                self.otlp_error = self.start_otlp().err();
+            }
+            if stop {
+                self.otlp = None;
+            }
+            self.show_otlp = open;
+        }
+
+        // Fluent Forward listener dialog
+        if self.show_forward {
+            let mut open = true;
+            let (mut start, mut stop) = (false, false);
+            egui::Window::new("Fluent Forward Listener")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.add_enabled_ui(self.forward.is_none(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Address:");
+                            ui.add(egui::TextEdit::singleline(&mut self.forward_config.bind).desired_width(120.0));
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut self.forward_config.port).clamp_range(1..=65535));
+                        });
+                    });
+                    if let Some(ref forward) = self.forward {
+                        ui.label(format!("Listening on {}, {} records received", forward.description(), forward.received()));
+                        stop = ui.button("Stop").clicked();
+                    } else {
+                        start = ui.button("Start").clicked();
+                    }
+                    if let Some(ref error) = self.forward_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                });
+            if start {
+                self.forward_error = self.start_forward().err();
+            }
+            if stop {
+                self.forward = None;
+            }
+            self.show_forward = open;
+        }
+
+        // Android logcat dialog
+        if self.show_logcat {
+            let mut open = true;
+            let (mut start, mut stop, mut clear) = (false, false, false);
+            if self.adb_devices.is_none() {
+                self.adb_devices = Some(AdbList::devices());
+            }
+            egui::Window::new("Android Logcat")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.add_enabled_ui(self.logcat.is_none(), |ui| {
+                        egui::Grid::new("logcat_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Device:");
+                            ui.horizontal(|ui| {
+                                let devices = self.adb_devices.as_mut().is_some_and(|list| !list.is_loading());
+                                let items = match self.adb_devices {
+                                    Some(ref list) if devices => list.items.clone(),
+                                    _ => Vec::new(),
+                                };
+                                let selected = items
+                                    .iter()
+                                    .find(|(serial, _)| *serial == self.logcat_config.serial)
+                                    .map(|(_, label)| label.clone())
+                                    .unwrap_or_else(|| match items.len() {
+                                        _ if !devices => "Looking for devices…".to_string(),
+                                        0 => "No devices".to_string(),
+                                        _ => "Pick a device".to_string(),
+                                    });
+                                egui::ComboBox::from_id_source("logcat_device").width(260.0).selected_text(selected).show_ui(ui, |ui| {
+                                    for (serial, label) in &items {
+                                        ui.selectable_value(&mut self.logcat_config.serial, serial.clone(), label);
+                                    }
+                                });
+                                if items.len() == 1 && self.logcat_config.serial.is_empty() {
+                                    self.logcat_config.serial = items[0].0.clone();
+                                }
+                                if ui.button("🔄").on_hover_text("Look for devices again").clicked() {
+                                    self.adb_devices = Some(AdbList::devices());
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label("Package:");
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut self.logcat_config.package).hint_text("all of the device").desired_width(230.0));
+                                package_picker(ui, &mut self.logcat_config, &mut self.adb_packages);
+                            });
+                            ui.end_row();
+                        });
+                    });
+                    if let Some(ref list) = self.adb_devices {
+                        if let Some(ref error) = list.error {
+                            ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                        }
+                    }
+                    if let Some(ref logcat) = self.logcat {
+                        ui.label(format!("Streaming {}, {} lines received", logcat.description(), logcat.received()));
+                        ui.horizontal(|ui| {
+                            stop = ui.button("Stop").clicked();
+                            clear = ui.button("🗑 Clear").on_hover_text("Clear the device's log buffers and the view").clicked();
+                        });
+                    } else {
+                        start = ui.button("Start").clicked();
+                    }
+                    if let Some(ref error) = self.logcat_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                });
+            if start {
+                self.logcat_error = self.open_logcat().err();
+            }
+            if stop {
+                self.logcat = None;
+            }
+            if clear {
+                self.logcat_error = self.clear_logcat().err();
+            }
+            self.show_logcat = open;
+        }
+
+        // macOS unified log dialog
+        if self.show_unified_log {
+            let mut open = true;
+            let (mut start, mut stop) = (false, false);
+            egui::Window::new("macOS Unified Log")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.add_enabled_ui(self.unified_log.is_none(), |ui| {
+                        egui::Grid::new("unified_log_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Source:");
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut self.unified_log_config.simulator, false, "This Mac");
+                                ui.radio_value(&mut self.unified_log_config.simulator, true, "Booted iOS Simulator");
+                            });
+                            ui.end_row();
+
+                            ui.label("Predicate:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.unified_log_config.predicate)
+                                    .hint_text("subsystem == \"com.example.app\"")
+                                    .desired_width(320.0),
+                            );
+                            ui.end_row();
+
+                            ui.label("Level:");
+                            ui.horizontal(|ui| {
+                                for level in [LogDetail::Default, LogDetail::Info, LogDetail::Debug] {
+                                    ui.radio_value(&mut self.unified_log_config.level, level, level.name());
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label("History:");
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut self.unified_log_config.lookback_minutes).clamp_range(0..=1440).suffix(" min"));
+                                ui.label(egui::RichText::new("before streaming").weak());
+                            });
+                            ui.end_row();
+                        });
+                    });
+                    if let Some(ref unified_log) = self.unified_log {
+                        ui.label(format!("Streaming, {} messages received", unified_log.received()));
+                        stop = ui.button("Stop").clicked();
+                    } else {
+                        start = ui.button("Start").clicked();
+                    }
+                    if let Some(ref error) = self.unified_log_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                });
+            if start {
+                self.unified_log_error = self.open_unified_log().err();
+            }
+            if stop {
+                self.unified_log = None;
+            }
+            self.show_unified_log = open;
+        }
+
+        // Live source dialogs
+        match self.remote_dialog.show(ctx, self.remote.as_ref()) {
+            Some(SourceAction::Open) => self.remote_dialog.error = self.open_remote().err(),
+            Some(SourceAction::Disconnect) => self.remote = None,
+            None => {}
+        }
+        match self.stream_dialog.show(ctx, self.live_stream.as_ref()) {
+            Some(SourceAction::Open) => self.stream_dialog.error = self.open_stream().err(),
+            Some(SourceAction::Disconnect) => self.live_stream = None,
+            None => {}
+        }
+        match self.loki_dialog.show(ctx, self.live_stream.as_ref()) {
+            Some(SourceAction::Open) => self.loki_dialog.error = self.open_loki().err(),
+            Some(SourceAction::Disconnect) => self.live_stream = None,
+            None => {}
+        }
+        if let Some(SourceAction::Open) = self.elastic_dialog.show(ctx, self.live_stream.as_ref()) {
+            self.elastic_dialog.error = self.open_elastic().err();
+        }
+        match self.cloudwatch_dialog.show(ctx, self.live_stream.as_ref()) {
+            Some(SourceAction::Open) => self.cloudwatch_dialog.error = self.open_cloudwatch().err(),
+            Some(SourceAction::Disconnect) => self.live_stream = None,
+            None => {}
+        }
+        match self.kafka_dialog.show(ctx, self.kafka.as_ref()) {
+            Some(SourceAction::Open) => self.kafka_dialog.error = self.open_kafka().err(),
+            Some(SourceAction::Disconnect) => self.kafka = None,
+            None => {}
+        }
+        if let Some(SourceAction::Open) = self.cloud_logs_dialog.show(ctx, self.live_stream.as_ref()) {
+            self.cloud_logs_dialog.error = self.open_cloud_logs().err();
+        }
+
+        // Object store dialog
+        if self.show_bucket {
+            let mut open = true;
+            let mut fetch = false;
+            egui::Window::new("Open from S3 or GCS")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("URL:");
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.bucket_url)
+                                .desired_width(360.0)
+                                .hint_text("s3://bucket/logs/app.log.gz or gs://bucket/app.log"),
+                        );
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            fetch = true;
+                        }
+                    });
+                    ui.label("Gzipped objects are decompressed as they download. S3 uses the standard AWS credentials, GCS those of gcloud.");
+                    if let Some(progress) = self.download.as_ref().map(|download| download.progress().unwrap_or(0.0)) {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::ProgressBar::new(progress).desired_width(300.0));
+                            if ui.button("Cancel").clicked() {
+                                self.download = None;
+                            }
+                        });
+                    }
+                    if let Some(ref error) = self.bucket_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                    }
+                    fetch |= ui.add_enabled(self.download.is_none(), egui::Button::new("Open")).clicked();
+                });
+            if fetch {
+                let url = self.bucket_url.clone();
+                self.bucket_error = self.open_object(&url).err();
+            }
+            self.show_bucket = open;
+        }
+
+        // Crash report from the last run
+        if let Some((ref path, ref report)) = self.crash_report {
+            let mut open = true;
+            let mut dismissed = false;
+            egui::Window::new("Log Rocket Crashed Last Time")
+                .open(&mut open)
+                .collapsible(false)
+                .default_width(600.0)
+                .show(ctx, |ui| {
+                    ui.label("Please include this report when filing an issue. It was saved to:");
+                    ui.label(egui::RichText::new(path.display().to_string()).monospace().size(12.0));
+                    ui.add_space(4.0);
+                    egui::ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                        ui.label(egui::RichText::new(report).monospace().size(12.0));
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy Report").clicked() {
+                            ctx.output_mut(|o| o.copied_text = report.clone());
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            dismissed = true;
+                        }
+                    });
+                });
+            if !open || dismissed {
+                self.crash_report = None;
+            }
+        }
+        
+        // Lines left out of every entry
+        if self.show_unparsed {
+            let mut open = true;
+            let mut every_line = false;
+            egui::Window::new("Unparsed Lines")
+                .open(&mut open)
+                .default_width(600.0)
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new("Lines before the first entry, or after a blank line where no entry starts, belong to no entry and aren't in the view").size(12.0).weak());
+                    ui.add_space(4.0);
+                    if self.unparsed.count == 0 {
+                        ui.label("Every line is part of an entry.");
+                    }
+                    egui::ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                        egui::Grid::new("unparsed_lines").num_columns(2).show(ui, |ui| {
+                            for (number, line) in &self.unparsed.lines {
+                                ui.label(egui::RichText::new(number.to_string()).monospace().size(12.0).weak());
+                                ui.label(egui::RichText::new(self.redactor.redact(line)).monospace().size(12.0));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if self.unparsed.count > self.unparsed.lines.len() {
+                        ui.label(egui::RichText::new(format!("…and {} more", self.unparsed.count - self.unparsed.lines.len())).size(12.0).weak());
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!self.unparsed.lines.is_empty(), egui::Button::new("Copy All")).clicked() {
+                            let text = self.unparsed.lines.iter().map(|(_, line)| self.redactor.redact(line)).collect::<Vec<_>>().join("\n");
+                            ctx.output_mut(|o| o.copied_text = text);
+                        }
+                        if ui
+                            .add_enabled(self.unparsed.count > 0, egui::Button::new("Show Every Line as an Entry"))
+                            .on_hover_text("Parse again with every line starting an entry (sidebar → Format)")
+                            .clicked()
+                        {
+                            every_line = true;
+                        }
+                    });
+                });
+            if every_line {
+                self.set_entry_start(EntryStart::EveryLine);
+            }
+            self.show_unparsed = open;
+        }
+        
+        // App log window
+        if self.show_app_log {
+            let mut open = true;
+            let mut open_as_log = false;
+            let lines = diagnostics::app_log();
+            egui::Window::new("App Log")
+                .open(&mut open)
+                .default_width(600.0)
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new("Errors and warnings from Log Rocket itself, e.g. files that couldn't be watched or settings that couldn't be read").size(12.0).weak());
+                    ui.add_space(4.0);
+                    if lines.is_empty() {
+                        ui.label("Nothing has gone wrong so far.");
+                    }
+                    egui::ScrollArea::both().max_height(300.0).stick_to_bottom(true).show(ui, |ui| {
+                        for line in &lines {
+                            let level = match line.severity {
+                                Severity::Warn => LogLevel::Warn,
+                                Severity::Error => LogLevel::Error,
+                            };
+                            let (color, background) = self.config.color_palette.for_level(&level);
+                            ui.label(egui::RichText::new(line.text()).monospace().size(12.0).color(color).background_color(background));
+                        }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!lines.is_empty(), egui::Button::new("Copy All")).clicked() {
+                            let text = lines.iter().map(|line| line.text()).collect::<Vec<_>>().join("\n");
+                            ctx.output_mut(|o| o.copied_text = text);
+                        }
+                        if ui.add_enabled(!lines.is_empty(), egui::Button::new("Open as Log"))
+                            .on_hover_text("View it in the main window to filter and search it")
+                            .clicked()
+                        {
+                            open_as_log = true;
+                        }
+                        if ui.add_enabled(!lines.is_empty(), egui::Button::new("Clear")).clicked() {
+                            diagnostics::clear_app_log();
+                        }
+                    });
+                    if let Some(dir) = diagnostics::crash_dir() {
+                        ui.label(egui::RichText::new(format!("Crash reports are saved in {}", dir.display())).size(12.0).weak());
+                    }
+                });
+            if open_as_log {
+                let text = lines.iter().map(|line| line.text()).collect::<Vec<_>>().join("\n");
+                self.load_text("Log Rocket app log".to_string(), text);
+            }
+            self.show_app_log = open;
+        }
+        
+        // Unreadable config notice
+        if let Some(ref error) = self.config_error {
+            let mut open = true;
+            let mut dismissed = false;
+            egui::Window::new("Settings Not Loaded")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(error);
+                    if ui.button("OK").clicked() {
+                        dismissed = true;
+                    }
+                });
+            if !open || dismissed {
+                self.config_error = None;
+            }
+        }
+
+        // Share Selection dialog
+        if self.show_share {
+            let mut open = true;
+            let mut upload = false;
+            egui::Window::new("Share Selection")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let (first, last) = self.share_line_range();
+                    ui.label(format!("{} entries, lines {}–{} of {}", self.share_entries.len(), first, last, self.source_name()));
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.config.share.target, ShareTarget::Gist, "GitHub Gist");
+                        ui.selectable_value(&mut self.config.share.target, ShareTarget::PasteService, "Paste service");
+                    });
+                    match self.config.share.target {
+                        ShareTarget::Gist => {
+                            ui.checkbox(&mut self.config.share.public_gist, "Public gist")
+                                .on_hover_text("Secret gists are only visible to those with the link");
+                            ui.label(egui::RichText::new("Signs in with GITHUB_TOKEN or the GitHub CLI (gh auth login)").size(12.0).weak());
+                        }
+                        ShareTarget::PasteService => {
+                            ui.horizontal(|ui| {
+                                ui.label("URL:");
+                                ui.add(egui::TextEdit::singleline(&mut self.config.share.paste_url).desired_width(260.0));
+                            });
+                            ui.label(egui::RichText::new("Receives the text as a POST body and replies with the paste's URL").size(12.0).weak());
+                        }
+                    }
+                    ui.checkbox(&mut self.config.share.redact, "Redact sensitive data")
+                        .on_hover_text("Mask with the rules in ⚙ Settings → Privacy");
+                    egui::CollapsingHeader::new("Preview").show(ui, |ui| {
+                        egui::ScrollArea::both().max_height(200.0).show(ui, |ui| {
+                            ui.label(egui::RichText::new(self.share_text()).monospace());
+                        });
+                    });
+                    
+                    ui.separator();
+                    if let Some(result) = self.share_upload.as_ref().and_then(Upload::poll) {
+                        if let Ok(ref url) = result {
+                            ctx.output_mut(|o| o.copied_text = url.clone());
+                        }
+                        self.share_result = Some(result);
+                        self.share_upload = None;
+                    }
+                    if self.share_upload.is_some() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Uploading...");
+                        });
+                    } else {
+                        match self.share_result {
+                            Some(Ok(ref url)) => {
+                                ui.label("Link copied to the clipboard:");
+                                ui.hyperlink(url);
+                            }
+                            Some(Err(ref error)) => {
+                                ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                            }
+                            None => {}
+                        }
+                        if ui.add_enabled(!self.share_entries.is_empty(), egui::Button::new("Upload")).clicked() {
+                            upload = true;
+                        }
+                    }
+                });
+            if upload {
+                let (first, last) = self.share_line_range();
+                let name = format!("{}_lines_{}-{}.log", self.source_name(), first, last);
+                self.share_upload = Some(Upload::start(&self.config.share, &name, self.share_text()));
+                self.share_result = None;
+                if let Err(e) = self.config.save() {
+                    app_error!("Error saving config: {}", e);
+                }
+            }
+            if !open {
+                self.show_share = false;
+                self.share_upload = None;
+            }
+        }
+        
+        // Settings window
+        match self.settings.show(ctx, &self.custom_formats) {
+            Some(SettingsAction::Apply(config)) => {
+                self.apply_config(*config);
+                if let Err(e) = self.config.save() {
+                    app_error!("Error saving config: {}", e);
+                }
+            }
+            Some(SettingsAction::NewFormat) => {
+                let sample = self.sample_lines();
+                self.format_builder.open_with_sample(sample);
+            }
+            Some(SettingsAction::EditFormat(name)) => {
+                if let Some(format) = self.custom_formats.iter().find(|f| f.name == name) {
+                    self.format_builder.edit(format);
+                }
+            }
+            Some(SettingsAction::DeleteFormat(name)) => self.delete_custom_format(&name),
+            None => {}
+        }
+
+        // Custom format builder dialog
+        if let Some(format) = self.format_builder.show(ctx, self.config.font_size) {
+            self.save_custom_format(format);
+        }
+
+        // Message patterns window
+        if self.show_patterns {
+            let mut open = true;
+            let mut new_filter = None;
+            let mut jump_to = None;
+            egui::Window::new("Message Patterns")
+                .open(&mut open)
+                .default_width(760.0)
+                .default_height(420.0)
+                .show(ctx, |ui| {
+                    let Some(ref patterns) = self.patterns else {
+                        return;
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} patterns in {} entries", patterns.patterns.len(), patterns.entry_pattern.len()));
+                        if let Some(p) = self.pattern_filter {
+                            ui.separator();
+                            ui.label(egui::RichText::new(format!("Filtered to pattern #{}", p + 1)).strong());
+                            if ui.small_button("Clear filter").clicked() {
+                                new_filter = Some(None);
+                            }
+                        }
+                    });
+                    ui.separator();
+                    
+                    let total = patterns.entry_pattern.len().max(1) as f32;
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        egui::Grid::new("patterns_table").num_columns(5).striped(true).show(ui, |ui| {
+                            ui.label(egui::RichText::new("Count").strong());
+                            ui.label(egui::RichText::new("%").strong());
+                            ui.label(egui::RichText::new("First").strong());
+                            ui.label(egui::RichText::new("Last").strong());
+                            ui.label(egui::RichText::new("Template").strong());
+                            ui.end_row();
+                            
+                            // The long tail is rarely interesting and slow to lay out
+                            for p in patterns.by_count().into_iter().take(500) {
+                                let pattern = &patterns.patterns[p];
+                                ui.label(pattern.count.to_string());
+                                ui.label(format!("{:.1}", pattern.count as f32 * 100.0 / total));
+                                for entry_idx in [pattern.first_entry, pattern.last_entry] {
+                                    let line = self.entries.get(entry_idx).map_or(0, |e| e.line_number);
+                                    if ui.link(line.to_string()).on_hover_text("Jump to entry").clicked() {
+                                        jump_to = Some(entry_idx);
+                                    }
+                                }
+                                let selected = self.pattern_filter == Some(p);
+                                if ui.selectable_label(selected, egui::RichText::new(self.redactor.redact(&pattern.template())).monospace())
+                                    .on_hover_text("Show only entries with this pattern")
+                                    .clicked()
+                                {
+                                    new_filter = Some(if selected { None } else { Some(p) });
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            if !open {
+                self.show_patterns = false;
+            }
+            if let Some(filter) = new_filter {
+                self.pattern_filter = filter;
+                self.apply_filters();
+            }
+            if let Some(idx) = jump_to {
+                self.scroll_target_line = Some(idx);
+                self.auto_scroll_frames = 0;
+            }
+        }
+
+        // Noise report window
+        if self.show_noise {
+            let report = self.noise_report.get_or_insert_with(|| noise::top_messages(&self.entries, 200));
+            let mut open = true;
+            let mut exclude = None;
+            let mut restore = None;
+            let mut jump_to = None;
+            egui::Window::new("Noise Report")
+                .open(&mut open)
+                .default_width(760.0)
+                .default_height(420.0)
+                .show(ctx, |ui| {
+                    let total = self.entries.len().max(1) as f32;
+                    let excluded_count = report
+                        .iter()
+                        .filter(|r| self.excluded_messages.contains(&r.message))
+                        .map(|r| r.count)
+                        .sum::<usize>();
+                    ui.label(format!(
+                        "Most repeated messages · {} excluded ({:.1}% of entries)",
+                        self.excluded_messages.len(),
+                        excluded_count as f32 * 100.0 / total
+                    ));
+                    ui.separator();
+                    
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        egui::Grid::new("noise_table").num_columns(4).striped(true).show(ui, |ui| {
+                            ui.label(egui::RichText::new("Count").strong());
+                            ui.label(egui::RichText::new("%").strong());
+                            ui.label("");
+                            ui.label(egui::RichText::new("Message").strong());
+                            ui.end_row();
+                            
+                            for repeated in report.iter() {
+                                let excluded = self.excluded_messages.contains(&repeated.message);
+                                ui.label(repeated.count.to_string());
+                                ui.label(format!("{:.1}", repeated.count as f32 * 100.0 / total));
+                                if excluded {
+                                    if ui.small_button("Restore").clicked() {
+                                        restore = Some(repeated.message.clone());
+                                    }
+                                } else if ui.small_button("Exclude").on_hover_text("Hide every entry with this message").clicked() {
+                                    exclude = Some(repeated.message.clone());
+                                }
+                                let mut text = egui::RichText::new(self.redactor.redact(&repeated.message)).monospace();
+                                if excluded {
+                                    text = text.strikethrough().weak();
+                                }
+                                if ui.link(text).on_hover_text("Jump to first occurrence").clicked() {
+                                    jump_to = Some(repeated.first_entry);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            if !open {
+                self.show_noise = false;
+            }
+            if let Some(message) = exclude {
+                self.excluded_messages.insert(message);
+                self.apply_filters();
+            }
+            if let Some(message) = restore {
+                self.excluded_messages.remove(&message);
+                self.apply_filters();
+            }
+            if let Some(idx) = jump_to {
+                self.scroll_target_line = Some(idx);
+                self.auto_scroll_frames = 0;
+            }
+        }
+
+        // Volume heatmap window
+        if self.show_heatmap {
+            let heatmap = self.heatmap.get_or_insert_with(|| heatmap::build(&self.entries));
+            let mut open = true;
+            let mut jump_to = None;
+            egui::Window::new("Volume Heatmap")
+                .open(&mut open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.heatmap_errors_only, false, "All entries");
+                        ui.selectable_value(&mut self.heatmap_errors_only, true, "Errors");
+                    });
+                    ui.separator();
+                    if heatmap.is_empty() {
+                        ui.label("No timestamped entries.");
+                        return;
+                    }
+                    let color = if self.heatmap_errors_only {
+                        self.config.color_palette.error
+                    } else {
+                        self.config.color_palette.info
+                    };
+                    egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                        jump_to = heatmap.show(ui, self.heatmap_errors_only, color);
+                    });
+                });
+            if !open {
+                self.show_heatmap = false;
             }
-            
-            // Navigation shortcuts: Cmd+ArrowUp/Down to jump to top/bottom
-            if input.modifiers.command || input.modifiers.ctrl {
-                if input.key_pressed(egui::Key::ArrowUp) {
-                    // Jump to top
-                    self.scroll_to_top = true;
-                }
-                if input.key_pressed(egui::Key::ArrowDown) {
-                    // Jump to bottom
-                    self.auto_scroll_frames = 3;
-                }
+            if let Some(idx) = jump_to {
+                self.scroll_target_line = Some(idx);
+                self.auto_scroll_frames = 0;
             }
+        }
 
-            // Font size shortcuts: Cmd+= to increase, Cmd+- to decrease (like VS Code/Sublime)
-            if input.modifiers.command || input.modifiers.ctrl {
-                // Decrease with Cmd+-
-                if input.key_pressed(egui::Key::Minus) {
-                    self.config.font_size = (self.config.font_size - 1.0).max(8.0);
-                }
-                
-                // Increase with Cmd+= or Cmd++
-                // Try multiple approaches to catch the equals key
-                let mut should_increase = false;
-                
-                // Check key events
-                for event in &input.events {
-                    match event {
-                        egui::Event::Key { key, pressed: true, .. } => {
-                            // Some keyboards report equals as a specific key
-                            if format!("{:?}", key).contains("Num0") || 
-                               format!("{:?}", key).contains("Equals") {
-                                should_increase = true;
-                            }
+        // Transactions window
+        if self.show_transactions {
+            let mut open = true;
+            let mut regroup = false;
+            let mut drill_down = None;
+            let mut jump_to = None;
+            let mut resort = None;
+            egui::Window::new("Transactions")
+                .open(&mut open)
+                .default_width(760.0)
+                .default_height(460.0)
+                .show(ctx, |ui| {
+                    egui::Grid::new("transaction_rules").num_columns(2).show(ui, |ui| {
+                        ui.label("Correlation ID:");
+                        ui.add(egui::TextEdit::singleline(&mut self.transaction_rules.id_pattern)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY)
+                            .hint_text(r"requestId=(\w+)"));
+                        ui.end_row();
+                        ui.label("Start pattern:");
+                        ui.add(egui::TextEdit::singleline(&mut self.transaction_rules.start_pattern)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("optional, e.g. Request started"));
+                        ui.end_row();
+                        ui.label("End pattern:");
+                        ui.add(egui::TextEdit::singleline(&mut self.transaction_rules.end_pattern)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("optional, e.g. Request completed"));
+                        ui.end_row();
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Group").clicked() {
+                            regroup = true;
                         }
-                        egui::Event::Text(text) => {
-                            if text == "=" || text == "+" {
-                                should_increase = true;
-                            }
+                        if !self.transactions.is_empty() {
+                            let complete = self.transactions.iter().filter(|t| t.complete).count();
+                            let failed = self.transactions.iter().filter(|t| t.failed).count();
+                            ui.label(format!("{} transactions, {} complete, {} with errors", self.transactions.len(), complete, failed));
                         }
-                        _ => {}
+                        if self.transaction_filter.is_some() && ui.small_button("Clear drill-down").clicked() {
+                            drill_down = Some(None);
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.selectable_value(&mut self.transaction_gantt, true, "Gantt");
+                            ui.selectable_value(&mut self.transaction_gantt, false, "Table");
+                        });
+                    });
+                    if let Some(ref error) = self.transaction_error {
+                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
                     }
+                    ui.separator();
+                    
+                    if self.transaction_gantt {
+                        let palette = &self.config.color_palette;
+                        if let Some(idx) = transactions::show_gantt(ui, &self.transactions, palette.info, palette.error) {
+                            jump_to = Some(idx);
+                        }
+                        return;
+                    }
+                    
+                    let (sort_column, descending) = self.transaction_sort;
+                    let mut sort_header = |ui: &mut egui::Ui, title: &str, column: SortColumn| {
+                        let arrow = if sort_column != column { "" } else if descending { " ⏷" } else { " ⏶" };
+                        if ui.button(egui::RichText::new(format!("{}{}", title, arrow)).strong()).clicked() {
+                            let descending = if sort_column == column { !descending } else { column != SortColumn::Id };
+                            resort = Some((column, descending));
+                        }
+                    };
+                    egui_extras::TableBuilder::new(ui)
+                        .striped(true)
+                        .column(egui_extras::Column::auto().at_least(160.0))
+                        .column(egui_extras::Column::auto().at_least(60.0))
+                        .column(egui_extras::Column::auto().at_least(180.0))
+                        .column(egui_extras::Column::auto().at_least(90.0))
+                        .column(egui_extras::Column::remainder())
+                        .header(22.0, |mut header| {
+                            header.col(|ui| sort_header(ui, "ID", SortColumn::Id));
+                            header.col(|ui| sort_header(ui, "Entries", SortColumn::Entries));
+                            header.col(|ui| sort_header(ui, "Start", SortColumn::Start));
+                            header.col(|ui| sort_header(ui, "Duration", SortColumn::Duration));
+                            header.col(|ui| {
+                                ui.strong("Status");
+                            });
+                        })
+                        .body(|body| {
+                            body.rows(18.0, self.transactions.len(), |row_index, mut row| {
+                                let transaction = &self.transactions[row_index];
+                                row.col(|ui| {
+                                    let selected = self.transaction_filter.as_ref() == Some(&transaction.entries);
+                                    if ui.selectable_label(selected, egui::RichText::new(self.redactor.redact(&transaction.id)).monospace())
+                                        .on_hover_text("Show only this transaction's entries")
+                                        .clicked()
+                                    {
+                                        drill_down = Some(if selected { None } else { Some(transaction.entries.clone()) });
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.label(transaction.entries.len().to_string());
+                                });
+                                row.col(|ui| {
+                                    let start = transaction.start_ms
+                                        .and_then(chrono::DateTime::from_timestamp_millis)
+                                        .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+                                        .unwrap_or_default();
+                                    ui.label(start);
+                                });
+                                row.col(|ui| {
+                                    ui.label(transaction.duration_ms().map(transactions::format_duration).unwrap_or_default());
+                                });
+                                row.col(|ui| {
+                                    if transaction.failed {
+                                        ui.colored_label(self.config.color_palette.error, "error");
+                                    } else if transaction.complete {
+                                        ui.label("ok");
+                                    } else {
+                                        ui.label(egui::RichText::new("incomplete").weak());
+                                    }
+                                });
+                            });
+                        });
+                });
+            if !open {
+                self.show_transactions = false;
+            }
+            if regroup {
+                if let Err(e) = transactions::save_rules(&self.transaction_rules) {
+                    app_error!("Error saving transaction rules: {}", e);
                 }
-                
-                if should_increase {
-                    self.config.font_size = (self.config.font_size + 1.0).min(30.0);
-                }
+                self.transaction_filter = None;
+                self.group_transactions();
+                self.apply_filters();
             }
-        });
-        
-        // Apply theme
-        match self.config.theme {
-            Theme::Dark => {
-                let mut visuals = egui::Visuals::dark();
-                visuals.panel_fill = egui::Color32::from_rgb(0x2e, 0x2e, 0x2e);
-                visuals.extreme_bg_color = egui::Color32::from_rgb(0x2e, 0x2e, 0x2e);
-                ctx.set_visuals(visuals);
+            if let Some((column, descending)) = resort {
+                self.transaction_sort = (column, descending);
+                transactions::sort(&mut self.transactions, column, descending);
+            }
+            if let Some(filter) = drill_down {
+                self.transaction_filter = filter;
+                self.apply_filters();
+            }
+            if let Some(idx) = jump_to {
+                self.scroll_target_line = Some(idx);
+                self.auto_scroll_frames = 0;
             }
-            Theme::Light => ctx.set_visuals(egui::Visuals::light()),
         }
-        
-        // Check for file updates
-        self.check_file_updates();
-        
-        // Handle Drag & Drop (and macOS File Open events)
-        if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
-            let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
-            if let Some(file) = dropped_files.first() {
-                if let Some(path) = &file.path {
-                    if path.exists() {
-                        if let Err(e) = self.load_file(path.clone()) {
-                            eprintln!("Error loading dropped file: {}", e);
+
+        // Format warning: most entries without a level means the format likely doesn't fit
+        let threshold = self.config.unknown_level_warning;
+        let share = if threshold > 0 { self.unknown_level_share() } else { None };
+        if let Some(share) = share.filter(|&share| share * 100.0 > threshold as f32) {
+            let key = self.format_warning_key();
+            if self.format_warning_dismissed.as_ref() != Some(&key) {
+                let mut chosen_format = None;
+                let mut build = false;
+                let mut dismiss = false;
+                egui::TopBottomPanel::top("format_banner").show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!(
+                            "{:.0}% of the entries in {} have no level with the {} format, so level filters can't sort them",
+                            share * 100.0,
+                            self.source_name(),
+                            self.parser.format.label()
+                        )).size(13.0));
+                        ui.menu_button("Choose Format ▾", |ui| {
+                            let custom = self.custom_formats.iter().map(|f| LogFormat::Custom(f.name.clone()));
+                            for format in LogFormat::BUILT_IN.into_iter().chain(custom) {
+                                if ui.selectable_label(self.parser.format == format, format.label()).clicked() {
+                                    chosen_format = Some(format);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        if ui.small_button("Build a Format…").on_hover_text("Describe the lines' layout with the format builder").clicked() {
+                            build = true;
                         }
-                    }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("✖").on_hover_text("Don't suggest this again for this file and format").clicked() {
+                                dismiss = true;
+                            }
+                        });
+                    });
+                });
+                if let Some(format) = chosen_format {
+                    self.set_format(format);
+                }
+                if build {
+                    let sample = self.sample_lines();
+                    self.format_builder.open_with_sample(sample);
+                }
+                if dismiss {
+                    self.format_warning_dismissed = Some(key);
                 }
             }
         }
-        
-        // Modern UI Layout
-        
-        // 1. Top Header
-        egui::TopBottomPanel::top("header").show(ctx, |ui| {
-            ui.add_space(4.0);
-            ui.horizontal(|ui| {
-                ui.heading("Log Viewer");
-                
-                ui.add_space(20.0);
-                
-                // File Controls
-                let icon_size = 20.0;
-                if ui.add_sized([icon_size, icon_size], egui::Button::new("📁")).on_hover_text("Open File").clicked() {
-                    if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("Log files", &["log", "txt"])
-                        .pick_file()
-                    {
-                        if let Err(e) = self.load_file(path) {
-                            eprintln!("Error loading file: {}", e);
-                        }
+
+        // Update banner
+        if let Some(release) = self.update.clone() {
+            let mut dismiss = false;
+            egui::TopBottomPanel::top("update_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("Log Rocket v{} is available (you have v{})", release.version, env!("CARGO_PKG_VERSION"))).size(13.0));
+                    if !release.notes.is_empty() && ui.small_button("Release Notes").clicked() {
+                        self.show_release_notes = !self.show_release_notes;
                     }
-                }
-                
-                if ui.add_sized([icon_size, icon_size], egui::Button::new("🔄")).on_hover_text("Reload").clicked() {
-                    if let Some(ref path) = self.current_file {
-                        if let Err(e) = self.load_file(path.clone()) {
-                            eprintln!("Error reloading file: {}", e);
+                    match (&self.update_download, &release.asset) {
+                        (Some(download), _) => {
+                            match download.progress() {
+                                Some(progress) => {
+                                    ui.add(egui::ProgressBar::new(progress).desired_width(120.0).show_percentage());
+                                }
+                                None => {
+                                    ui.spinner();
+                                }
+                            }
+                            if ui.small_button("Cancel").clicked() {
+                                self.update_download = None;
+                            }
                         }
+                        (None, Some((name, url))) => {
+                            if ui.small_button("Download").on_hover_text(format!("Save {} to the Downloads folder", name)).clicked() {
+                                self.update_download = Some(UpdateDownload::start(name, url));
+                                self.update_download_result = None;
+                            }
+                        }
+                        (None, None) => {}
                     }
-                }
-                
-                // Breadcrumb / File Info
-                ui.add_space(20.0);
-                if let Some(ref path) = self.current_file {
-                    ui.label(egui::RichText::new(path.file_name().unwrap_or_default().to_string_lossy()).strong());
-                    
-                    // File Size
-                    if let Ok(metadata) = fs::metadata(path) {
-                        let size_mb = metadata.len() as f64 / 1_000_000.0;
-                        ui.label(format!("({:.2} MB)", size_mb));
-                    }
-                } else {
-                    ui.label("No file loaded");
-                }
-                
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    // Sidebar Toggle
-                    let sidebar_icon = if self.show_sidebar { "⏵" } else { "⏴" };
-                    let sidebar_btn = ui.add_sized([icon_size, icon_size], egui::Button::new(sidebar_icon)).on_hover_text("Toggle Sidebar");
-                    if sidebar_btn.clicked() {
-                        self.show_sidebar = !self.show_sidebar;
+                    if !release.page_url.is_empty() {
+                        ui.hyperlink_to("Release page", &release.page_url);
                     }
-                    
-                    ui.add_space(10.0);
-                    
-                    // Search Toggle
-                    let search_btn = ui.add_sized([icon_size, icon_size], egui::Button::new("🔍").selected(self.show_search)).on_hover_text("Toggle Search");
-                    if search_btn.clicked() {
-                        self.show_search = !self.show_search;
-                        if self.show_search {
-                            self.focus_search = true;
+                    match self.update_download_result {
+                        Some(Ok(ref path)) => {
+                            ui.label(egui::RichText::new(format!("Saved to {}", path.display())).size(12.0).weak());
                         }
+                        Some(Err(ref error)) => {
+                            ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                        }
+                        None => {}
                     }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✖").on_hover_text("Hide until the next launch").clicked() {
+                            dismiss = true;
+                        }
+                        if ui.small_button("Skip This Version").clicked() {
+                            self.config.skipped_update = release.version.clone();
+                            if let Err(e) = self.config.save() {
+                                app_error!("Error saving config: {}", e);
+                            }
+                            dismiss = true;
+                        }
+                    });
                 });
             });
-            ui.add_space(4.0);
-        });
-
+            if self.show_release_notes {
+                let mut open = true;
+                egui::Window::new(format!("What's New in v{}", release.version))
+                    .open(&mut open)
+                    .default_width(500.0)
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                            ui.label(&release.notes);
+                        });
+                    });
+                self.show_release_notes = open;
+            }
+            if dismiss {
+                self.update = None;
+                self.update_download = None;
+                self.show_release_notes = false;
+            }
+        }
+        
         // 2. Search Bar (Floating / Top)
         if self.show_search {
             egui::TopBottomPanel::top("search_bar").show(ctx, |ui| {
                 ui.add_space(4.0);
                 ui.horizontal(|ui| {
                     ui.label("🔍");
-                    let response = ui.add(egui::TextEdit::singleline(&mut self.search.query).desired_width(300.0));
+                    let invalid = self.search.regex_error.is_some();
+                    let response = ui.scope(|ui| {
+                        if invalid {
+                            let stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(0xe0, 0x50, 0x50));
+                            let visuals = ui.visuals_mut();
+                            visuals.widgets.inactive.bg_stroke = stroke;
+                            visuals.widgets.hovered.bg_stroke = stroke;
+                            visuals.selection.stroke = stroke;
+                        }
+                        ui.add(egui::TextEdit::singleline(&mut self.search.query).desired_width(300.0))
+                    }).inner;
                     
                     // Handle focus request
                     if self.focus_search {
@@ -414,14 +4446,9 @@ impl eframe::App for LogViewerApp {
                     }
                     
                     if response.changed() {
-                        self.search.update_search(&self.entries);
-                        // Navigate to first match when typing
-                        if self.search.matches.len() > 0 {
-                            self.search.current_match = Some(0);
-                            if let Some(line_idx) = self.search.get_current_match_index() {
-                                self.scroll_target_line = Some(line_idx);
-                            }
-                        }
+                        self.search.start_search(&self.entries);
+                        // Navigate to first match when typing, once one is found
+                        self.jump_to_first_match = true;
                     }
                     
                     if ui.button("⬆").on_hover_text("Previous Match").clicked() {
@@ -438,30 +4465,52 @@ impl eframe::App for LogViewerApp {
                         }
                     }
                     
-                    if !self.search.matches.is_empty() {
+                    if self.search.is_searching() {
+                        ui.spinner();
+                        let progress = self.search.progress(self.entries.len()).unwrap_or_default();
+                        ui.label(format!("{} matches so far ({:.0}%)", self.search.matches.len(), progress * 100.0));
+                        if ui.small_button("✖").on_hover_text("Stop searching, keeping the matches found so far").clicked() {
+                            self.search.cancel_search();
+                        }
+                    } else if let Some(progress) = self.search.progress(self.entries.len()) {
+                        ui.label(format!("{} matches (stopped at {:.0}%)", self.search.matches.len(), progress * 100.0));
+                    } else if !self.search.matches.is_empty() {
                         if let Some(idx) = self.search.current_match {
                             ui.label(format!("{}/{}", idx + 1, self.search.matches.len()));
                         } else {
                             ui.label(format!("{} matches", self.search.matches.len()));
                         }
+                    } else if invalid {
+                        ui.label(egui::RichText::new("Invalid regex").color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)));
                     } else if !self.search.query.is_empty() {
                         ui.label("No matches");
                     }
+                    if !self.search.matches.is_empty() {
+                        self.show_match_levels(ui);
+                    }
                     
                     ui.separator();
                     
-                    ui.checkbox(&mut self.search.case_sensitive, "Aa").on_hover_text("Case Sensitive");
-                    ui.checkbox(&mut self.search.use_regex, ".*").on_hover_text("Regex");
+                    let case_changed = ui.checkbox(&mut self.search.case_sensitive, "Aa").on_hover_text("Case Sensitive").changed();
+                    let regex_changed = ui.checkbox(&mut self.search.use_regex, ".*").on_hover_text("Regex").changed();
+                    if case_changed || regex_changed {
+                        self.search.start_search(&self.entries);
+                        self.jump_to_first_match = true;
+                    }
+                    ui.menu_button("?", regex_cheatsheet).response.on_hover_text("Regex Syntax");
                 });
+                if let Some(ref error) = self.search.regex_error {
+                    ui.label(egui::RichText::new(error).monospace().size(12.0).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)));
+                }
                 ui.add_space(4.0);
             });
         }
 
         // 3. Right Sidebar (Control Center)
         if self.show_sidebar {
-            egui::SidePanel::right("sidebar")
+            let sidebar = egui::SidePanel::right("sidebar")
                 .resizable(true)
-                .default_width(250.0)
+                .default_width(self.window_state.sidebar_width)
                 .show(ctx, |ui| {
                     ui.add_space(10.0);
                     ui.heading("Control Center");
@@ -473,33 +4522,360 @@ impl eframe::App for LogViewerApp {
                             .default_open(true)
                             .show(ui, |ui| {
                             ui.label(egui::RichText::new("Log Levels:").size(15.0));
-                            let mut filter_changed = false;
-                            
                             let levels = [
                                 (LogLevel::Info, "Info", self.config.color_palette.info),
                                 (LogLevel::Warn, "Warn", self.config.color_palette.warn),
                                 (LogLevel::Error, "Error", self.config.color_palette.error),
                                 (LogLevel::Debug, "Debug", self.config.color_palette.debug),
+                                (LogLevel::Trace, "Trace", self.config.color_palette.trace),
+                                (LogLevel::Unknown, "Unknown", self.config.color_palette.default),
                             ];
                             
+                            let mut solo = None;
+                            let mut and_above = None;
+                            let mut show_all = false;
                             for (level, label, color) in levels {
                                 let mut enabled = self.enabled_levels.contains(&level);
-                                if ui.checkbox(&mut enabled, egui::RichText::new(label).color(color).size(15.0)).changed() {
-                                    if enabled {
-                                        self.enabled_levels.insert(level);
+                                let hover = match level {
+                                    LogLevel::Unknown => "Lines without a recognized level. Alt-click to show only these, right-click for more",
+                                    _ => "Alt-click to show only this level, right-click for more",
+                                };
+                                let response = ui.checkbox(&mut enabled, egui::RichText::new(label).color(color).size(15.0)).on_hover_text(hover);
+                                if response.changed() {
+                                    if ui.input(|i| i.modifiers.alt) {
+                                        solo = Some(level.clone());
                                     } else {
-                                        self.enabled_levels.remove(&level);
+                                        self.set_level_enabled(level.clone(), enabled);
+                                    }
+                                }
+                                response.context_menu(|ui| {
+                                    if ui.button(format!("Show Only {}", label)).clicked() {
+                                        solo = Some(level.clone());
+                                        ui.close_menu();
+                                    }
+                                    if level.severity().is_some_and(|severity| severity > 0) && ui.button(format!("Show {} and Above", label)).clicked() {
+                                        and_above = Some(level.clone());
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("Show All Levels").clicked() {
+                                        show_all = true;
+                                        ui.close_menu();
+                                    }
+                                });
+                            }
+                            if let Some(level) = solo {
+                                // Soloing the level shown alone brings the others back
+                                if self.enabled_levels.len() == 1 && self.enabled_levels.contains(&level) {
+                                    show_all = true;
+                                } else {
+                                    self.set_level_filter(&[level]);
+                                }
+                            }
+                            if let Some(level) = and_above {
+                                let levels: Vec<LogLevel> = LogLevel::ALL
+                                    .iter()
+                                    .filter(|l| l.severity().is_some_and(|severity| Some(severity) >= level.severity()))
+                                    .cloned()
+                                    .collect();
+                                self.set_level_filter(&levels);
+                            }
+                            if show_all {
+                                self.set_level_filter(&LogLevel::ALL);
+                            }
+                            
+                            ui.add_space(5.0);
+                            ui.label(egui::RichText::new(format!("Showing: {} / {} lines", self.filtered_entries.len(), self.entries.len())).size(13.0));
+                            if self.unparsed.count > 0 {
+                                let text = format!("⚠ {} lines aren't part of any entry", self.unparsed.count);
+                                if ui.link(egui::RichText::new(text).size(13.0)).on_hover_text("Show the lines the view leaves out").clicked() {
+                                    self.show_unparsed = true;
+                                }
+                            }
+                            if !self.entries.is_empty() {
+                                let bytes = self.memory_usage();
+                                ui.horizontal(|ui| {
+                                    let label = ui.label(egui::RichText::new(format!("In memory: ~{}", dir_browser::format_size(bytes as u64))).size(13.0));
+                                    if !self.merge_sources.is_empty() {
+                                        label.on_hover_ui(|ui| {
+                                            ui.label("Each file keeps its own copy to merge from:");
+                                            for source in &self.merge_sources {
+                                                let bytes: usize = source.entries.iter().map(LogEntry::memory_size).sum();
+                                                ui.label(format!("{}: {} entries, ~{}", source.name(), source.entries.len(), dir_browser::format_size(bytes as u64)));
+                                            }
+                                        });
+                                    }
+                                    let purge = ui
+                                        .add_enabled(self.entries.len() > PURGE_KEEP, egui::Button::new("Purge").small())
+                                        .on_hover_text(format!("Free the scrollback, keeping the newest {} entries", PURGE_KEEP));
+                                    if purge.clicked() {
+                                        self.purge_scrollback();
+                                    }
+                                });
+                            }
+                            if self.is_parsing() {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(egui::RichText::new("Parsing…").size(13.0));
+                                });
+                            }
+                            if let Some(ref download) = self.download {
+                                let text = format!("Downloading {}", download.url());
+                                match download.progress() {
+                                    Some(progress) => {
+                                        ui.add(egui::ProgressBar::new(progress).text(text));
+                                    }
+                                    None => {
+                                        ui.horizontal(|ui| {
+                                            ui.spinner();
+                                            ui.label(egui::RichText::new(format!("{} ({:.1} MB)", text, download.received() as f64 / 1e6)).size(13.0));
+                                        });
+                                    }
+                                }
+                            }
+                            if let Some(ref glob) = self.glob_watch {
+                                ui.label(egui::RichText::new(format!("Following newest of {}", glob.pattern().display())).size(13.0));
+                            }
+                            if let Some(ref disk_log) = self.disk_log {
+                                ui.label(egui::RichText::new(format!("Read from disk: {} / {} entries", disk_log.shown_count(), disk_log.entry_count())).size(13.0));
+                                if let Some((first, last)) = disk_log.time_range() {
+                                    let format = |ms| chrono::DateTime::from_timestamp_millis(ms)
+                                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                                        .unwrap_or_default();
+                                    ui.label(egui::RichText::new(format!("{} – {}", format(first), format(last))).size(13.0));
+                                }
+                                if disk_log.is_indexing() {
+                                    ui.add(egui::ProgressBar::new(disk_log.progress()).text("Indexing…"));
+                                }
+                                ui.label(egui::RichText::new("Search, analysis and tailing are off for files read from disk").weak().size(12.0));
+                            }
+                        });
+                        
+                        ui.separator();
+                        
+                        // Section: Merged Sources
+                        if !self.merge_sources.is_empty() {
+                            egui::CollapsingHeader::new("Merged Sources")
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                ui.label(egui::RichText::new("Clock offset applied before interleaving:").size(13.0));
+                                let mut merge_changed = false;
+                                let mut remove_source = None;
+                                let mut show_beside = None;
+                                for (idx, source) in self.merge_sources.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        let (swatch, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                                        ui.painter().rect_filled(swatch, 2.0, merge::source_color(idx));
+                                        let mut offset_secs = source.offset_ms as f64 / 1000.0;
+                                        if ui.add(egui::DragValue::new(&mut offset_secs).speed(0.1).suffix(" s")).changed() {
+                                            source.offset_ms = (offset_secs * 1000.0).round() as i64;
+                                            merge_changed = true;
+                                        }
+                                        ui.label(source.name()).on_hover_text(source.path.display().to_string());
+                                        if ui.small_button("◫").on_hover_text("Show Beside the View").clicked() {
+                                            show_beside = Some(idx);
+                                        }
+                                        if idx > 0 && ui.small_button("✖").on_hover_text("Remove from View").clicked() {
+                                            remove_source = Some(idx);
+                                        }
+                                    });
+                                }
+                                if let Some(idx) = show_beside {
+                                    self.source_pane = Some(SourcePane::new(idx));
+                                }
+                                if let Some(idx) = remove_source {
+                                    self.merge_sources.remove(idx);
+                                    self.source_pane = None;
+                                    merge_changed = true;
+                                    // The sources after it moved up
+                                    if self.tail_log {
+                                        self.start_tail();
+                                    }
+                                }
+                                if merge_changed {
+                                    self.rebuild_merged();
+                                }
+                            });
+                            
+                            ui.separator();
+                        }
+                        
+                        // Section: Format
+                        egui::CollapsingHeader::new("Format")
+                            .default_open(true)
+                            .show(ui, |ui| {
+                            let mut chosen_format = None;
+                            egui::ComboBox::from_id_source("format_selector")
+                                .selected_text(self.parser.format.label().to_string())
+                                .show_ui(ui, |ui| {
+                                    let custom = self.custom_formats.iter().map(|f| LogFormat::Custom(f.name.clone()));
+                                    for format in LogFormat::BUILT_IN.into_iter().chain(custom) {
+                                        if ui.selectable_label(self.parser.format == format, format.label()).clicked() {
+                                            chosen_format = Some(format);
+                                        }
+                                    }
+                                });
+                            if let Some(format) = chosen_format {
+                                self.set_format(format);
+                            }
+                            
+                            // Which lines begin an entry; the lines after one continue it
+                            ui.label("Entries start at:");
+                            let mut chosen_rule = None;
+                            let current_rule = self.parser.entry_start().clone();
+                            egui::ComboBox::from_id_source("entry_start_selector")
+                                .selected_text(current_rule.label().to_string())
+                                .show_ui(ui, |ui| {
+                                    let pattern = EntryStart::Pattern(self.entry_start_pattern.clone());
+                                    for rule in [EntryStart::Format, EntryStart::Unindented, EntryStart::EveryLine, pattern] {
+                                        let selected = std::mem::discriminant(&current_rule) == std::mem::discriminant(&rule);
+                                        if ui.selectable_label(selected, rule.label()).clicked() {
+                                            chosen_rule = Some(rule);
+                                        }
+                                    }
+                                });
+                            if let EntryStart::Pattern(_) = current_rule {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut self.entry_start_pattern)
+                                        .font(egui::TextStyle::Monospace)
+                                        .desired_width(f32::INFINITY),
+                                ).on_hover_text("Regex for lines that begin an entry (Enter to apply)");
+                                if response.lost_focus() {
+                                    chosen_rule = Some(EntryStart::Pattern(self.entry_start_pattern.clone()));
+                                }
+                            }
+                            // A shortcut for when grouping gets it wrong and the raw lines are wanted
+                            let mut each_line = current_rule == EntryStart::EveryLine;
+                            if ui
+                                .checkbox(&mut each_line, "One entry per line")
+                                .on_hover_text("No multi-line grouping: every line of the file is an entry of its own")
+                                .changed()
+                            {
+                                chosen_rule = Some(if each_line { EntryStart::EveryLine } else { EntryStart::Format });
+                            }
+                            if let Some(ref error) = self.entry_start_error {
+                                ui.label(egui::RichText::new(error).monospace().size(12.0).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)));
+                            }
+                            if let Some(rule) = chosen_rule {
+                                self.set_entry_start(rule);
+                            }
+                            
+                            if ui.button("Manage Formats…").clicked() {
+                                self.settings.open(&self.config, SettingsTab::Formats);
+                            }
+                        });
+                        
+                        ui.separator();
+                        
+                        // Section: Plugins
+                        egui::CollapsingHeader::new("Plugins")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                            let mut plugins_changed = false;
+                            for plugin in &mut self.parser.plugins {
+                                if let Some(error) = plugin.error.clone().or_else(|| plugin.failure()) {
+                                    ui.label(egui::RichText::new(format!("⚠ {}", plugin.name)).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)))
+                                        .on_hover_text(error);
+                                } else if ui.checkbox(&mut plugin.enabled, &plugin.name)
+                                    .on_hover_text(plugin.path.display().to_string())
+                                    .changed()
+                                {
+                                    plugins_changed = true;
+                                }
+                            }
+                            if self.parser.plugins.is_empty() {
+                                ui.label(egui::RichText::new("No plugins installed.").size(13.0));
+                            }
+                            if let Some(dir) = plugins::plugins_dir() {
+                                ui.label(egui::RichText::new(format!("Put .wasm plugins in {}", dir.display())).size(12.0).weak());
+                            }
+                            if plugins_changed {
+                                if let Err(e) = plugins::save_enabled_state(&self.parser.plugins) {
+                                    app_error!("Error saving plugin state: {}", e);
+                                }
+                                if let Err(e) = self.reload() {
+                                    app_error!("Error reloading file: {}", e);
+                                }
+                            }
+                        });
+                        
+                        ui.separator();
+                        
+                        // Section: Script
+                        egui::CollapsingHeader::new("Script")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                            let mut script_changed = false;
+                            ui.horizontal(|ui| {
+                                if ui.button("Load Script…").on_hover_text("Rhai script with keep/color/fields functions").clicked() {
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .add_filter("Rhai scripts", &["rhai"])
+                                        .pick_file()
+                                    {
+                                        self.script = Some(EntryScript::load(path));
+                                        script_changed = true;
                                     }
-                                    filter_changed = true;
+                                }
+                                if self.script.is_some() && ui.button("Clear").clicked() {
+                                    self.script = None;
+                                    script_changed = true;
+                                }
+                            });
+                            match self.script {
+                                Some(ref script) => {
+                                    let name = script.path.file_name().unwrap_or_default().to_string_lossy();
+                                    ui.label(egui::RichText::new(name).size(13.0))
+                                        .on_hover_text(script.path.display().to_string());
+                                    if let Some(ref error) = script.error {
+                                        ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                                    }
+                                }
+                                None => {
+                                    ui.label(egui::RichText::new("No script loaded.").size(13.0));
                                 }
                             }
-                            
-                            if filter_changed {
+                            if script_changed {
+                                self.script_verdicts.clear();
                                 self.apply_filters();
                             }
+                        });
+                        
+                        ui.separator();
+                        
+                        // Section: Metrics
+                        egui::CollapsingHeader::new("Metrics")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                            let mut removed = None;
+                            for (i, extractor) in self.metric_extractors.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                        removed = Some(i);
+                                    }
+                                    ui.label(egui::RichText::new(&extractor.name).size(14.0).color(metrics::series_color(i)))
+                                        .on_hover_text(&extractor.pattern);
+                                });
+                            }
+                            if let Some(i) = removed {
+                                self.metric_extractors.remove(i);
+                                self.refresh_analysis();
+                                if let Err(e) = metrics::save_extractors(&self.metric_extractors) {
+                                    app_error!("Error saving metrics: {}", e);
+                                }
+                            }
                             
-                            ui.add_space(5.0);
-                            ui.label(egui::RichText::new(format!("Showing: {} / {} lines", self.filtered_entries.len(), self.entries.len())).size(13.0));
+                            ui.add(egui::TextEdit::singleline(&mut self.new_metric_name).hint_text("Name, e.g. latency"));
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.new_metric_pattern)
+                                    .font(egui::TextStyle::Monospace)
+                                    .hint_text(r"took (\d+)ms"),
+                            );
+                            if ui.button("Add Metric").clicked() {
+                                self.metric_error = self.add_metric_extractor().err();
+                            }
+                            if let Some(ref error) = self.metric_error {
+                                ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                            }
+                            ui.checkbox(&mut self.show_metrics, "Show charts");
                         });
                         
                         ui.separator();
@@ -509,56 +4885,39 @@ impl eframe::App for LogViewerApp {
                             .default_open(true)
                             .show(ui, |ui| {
                             // Tail Log
-                            ui.checkbox(&mut self.tail_log, egui::RichText::new("Tail Log (Auto-refresh)").size(15.0));
-                            if self.tail_log != self.config.tail_log {
+                            if ui.checkbox(&mut self.tail_log, egui::RichText::new("Tail Log (Auto-refresh)").size(15.0)).changed() {
                                 self.config.tail_log = self.tail_log;
                                 if self.tail_log {
-                                    if let Some(ref path) = self.current_file {
-                                        self.file_watcher.watch_file(path.clone()).ok();
-                                    }
+                                    self.start_tail();
                                 } else {
                                     self.file_watcher.stop();
                                 }
                             }
                             
                             // Scroll to End
-                            ui.checkbox(&mut self.scroll_to_end, egui::RichText::new("Auto-scroll to End").size(15.0));
+                            if ui.checkbox(&mut self.scroll_to_end, egui::RichText::new("Auto-scroll to End").size(15.0)).changed() {
+                                self.config.scroll_to_end = self.scroll_to_end;
+                            }
                             
                             // Wrap Text
                             ui.checkbox(&mut self.wrap_text, egui::RichText::new("Wrap Text").size(15.0));
-                            if self.scroll_to_end != self.config.scroll_to_end {
-                                self.config.scroll_to_end = self.scroll_to_end;
-                            }
+                            
+                            // Timeline
+                            ui.checkbox(&mut self.show_timeline, egui::RichText::new("Show Timeline").size(15.0))
+                                .on_hover_text("Entries over time, with markers where the error/warning rate spikes");
                         });
                         
                         ui.separator();
                         
-                        // Section: Appearance
-                        egui::CollapsingHeader::new("Appearance")
+                        // Section: Export
+                        egui::CollapsingHeader::new("Export")
                             .default_open(true)
                             .show(ui, |ui| {
-                            ui.label(egui::RichText::new("Theme:").size(15.0));
-                            ui.horizontal(|ui| {
-                                if ui.selectable_label(self.config.theme == Theme::Dark, "Dark").clicked() {
-                                    self.config.theme = Theme::Dark;
-                                    self.config.color_palette = ColorPalette::dark();
-                                }
-                                if ui.selectable_label(self.config.theme == Theme::Light, "Light").clicked() {
-                                    self.config.theme = Theme::Light;
-                                    self.config.color_palette = ColorPalette::light();
-                                }
-                            });
-                            
-                            ui.add_space(5.0);
-                            ui.label("Font Size:");
-                            ui.add(egui::DragValue::new(&mut self.config.font_size).speed(0.5).clamp_range(8.0..=30.0));
-                            
-                            ui.add_space(5.0);
                             if ui.button("Export Filtered Logs").clicked() {
                                 if !self.filtered_entries.is_empty() {
                                     let content: String = self.filtered_entries
                                         .iter()
-                                        .map(|&idx| self.entries[idx].raw_line.as_str())
+                                        .map(|&idx| self.redactor.redact(&self.entries[idx].raw_line))
                                         .collect::<Vec<_>>()
                                         .join("\n");
                                     
@@ -572,19 +4931,323 @@ impl eframe::App for LogViewerApp {
                                     let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
                                     let export_path = current_dir.join(&default_name);
                                     if let Err(e) = fs::write(&export_path, content) {
-                                        eprintln!("Error exporting: {}", e);
+                                        app_error!("Error exporting: {}", e);
                                     } else {
-                                        eprintln!("Exported to: {}", export_path.display());
+                                        app_error!("Exported to: {}", export_path.display());
+                                    }
+                                }
+                            }
+                            
+                            let selection_btn = ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Export Selected Lines…"))
+                                .on_hover_text("Only the entries the selection in the log view touches")
+                                .on_disabled_hover_text("Select lines in the log view first");
+                            if selection_btn.clicked() {
+                                self.export_entries(&self.selected_entries(), "selection", ExportFormat::Lines);
+                            }
+                            
+                            let matches_btn = ui.add_enabled(!self.search.matches.is_empty(), egui::Button::new("Export Search Matches…"))
+                                .on_hover_text("Only the entries matching the search, among those the filters show")
+                                .on_disabled_hover_text("Search for something first");
+                            if matches_btn.clicked() {
+                                self.export_entries(&self.visible_matches(), "matches", ExportFormat::Lines);
+                            }
+                            
+                            if ui.add_enabled(!self.filtered_entries.is_empty(), egui::Button::new("Export as CSV…"))
+                                .on_hover_text("The filtered entries with a column per field: line, timestamp, level, thread, class, message and script fields. Selection and match exports also write CSV when saved as .csv")
+                                .clicked()
+                            {
+                                self.export_entries(&self.filtered_entries, "filtered", ExportFormat::Csv);
+                            }
+                            
+                            if ui.add_enabled(!self.filtered_entries.is_empty(), egui::Button::new("Export as NDJSON…"))
+                                .on_hover_text("The filtered entries as one JSON object per line with the same keys for every format: source, line, time, level, thread, class, message, fields and raw. Selection and match exports also write NDJSON when saved as .ndjson")
+                                .clicked()
+                            {
+                                self.export_entries(&self.filtered_entries, "filtered", ExportFormat::Ndjson);
+                            }
+                            
+                            if ui.button("Export Report…").on_hover_text("Summary, timeline, top patterns and slowest transactions as Markdown").clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("Markdown", &["md"])
+                                    .set_file_name(&format!("{}_report.md", self.source_name()))
+                                    .save_file()
+                                {
+                                    if let Err(e) = self.export_report(&path) {
+                                        app_error!("Error exporting report: {}", e);
                                     }
                                 }
                             }
+                            
+                            let share_btn = ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Share Selection…"))
+                                .on_hover_text("Upload the selected lines to a GitHub Gist or paste service and copy the link")
+                                .on_disabled_hover_text("Select lines in the log view first");
+                            if share_btn.clicked() {
+                                self.open_share();
+                            }
+                        });
+                    });
+                });
+            self.window_state.sidebar_width = sidebar.response.rect.width();
+        }
+
+        // 4. Left File Browser (directory mode)
+        if let Some(dir) = self.dir_browser.dir().map(|d| d.to_path_buf()) {
+            let mut file_to_open = None;
+            let mut pattern_to_follow = None;
+            let mut close_browser = false;
+            egui::SidePanel::left("file_browser")
+                .resizable(true)
+                .default_width(260.0)
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("Files");
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("✖").on_hover_text("Close Folder").clicked() {
+                                close_browser = true;
+                            }
+                        });
+                    });
+                    ui.label(egui::RichText::new(dir.display().to_string()).size(12.0).weak());
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.glob_input).desired_width(150.0));
+                        if ui.button("Follow").on_hover_text("Tail the newest file matching this pattern (e.g. app-*.log), switching to newer ones as they appear").clicked() {
+                            pattern_to_follow = Some(dir.join(&self.glob_input));
+                        }
+                    });
+                    ui.separator();
+                    
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for file in self.dir_browser.files() {
+                            let is_open = self.current_file.as_ref() == Some(&file.path);
+                            if ui.selectable_label(is_open, egui::RichText::new(file.name()).size(14.0)).clicked() {
+                                file_to_open = Some(file.path.clone());
+                            }
+                            ui.label(
+                                egui::RichText::new(format!("{}  ·  {}", dir_browser::format_size(file.size), file.modified_label()))
+                                    .size(11.0)
+                                    .weak(),
+                            );
+                            ui.add_space(4.0);
+                        }
+                    });
+                });
+            
+            if close_browser {
+                self.dir_browser.close();
+            }
+            if let Some(path) = file_to_open {
+                if let Err(e) = self.load_file(path) {
+                    app_error!("Error loading file: {}", e);
+                }
+            }
+            if let Some(pattern) = pattern_to_follow {
+                if let Err(e) = self.follow_pattern(pattern) {
+                    app_error!("Error following pattern: {}", e);
+                }
+            }
+        }
+
+        // Scratchpad
+        if self.show_scratchpad {
+            let mut open_pin = None;
+            let mut remove = None;
+            let mut move_up = None;
+            let mut changed = false;
+            let count = self.scratchpad.len();
+            egui::SidePanel::right("scratchpad")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("Scratchpad");
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("✖").on_hover_text("Close Scratchpad").clicked() {
+                                self.show_scratchpad = false;
+                            }
                         });
                     });
+                    ui.label(egui::RichText::new("Entries pinned from any file, linked back to it. Select lines in the log and right-click to pin them.").size(12.0).weak());
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(count > 0, egui::Button::new("Copy All"))
+                            .on_hover_text("Copy as a numbered Markdown list with locations and notes")
+                            .clicked()
+                        {
+                            let text = scratchpad::to_markdown(&self.scratchpad, |text| self.redactor.redact(text).into_owned());
+                            ctx.output_mut(|o| o.copied_text = text);
+                        }
+                        if ui.add_enabled(count > 1, egui::Button::new("Sort by Time")).clicked() {
+                            self.scratchpad.sort_by_key(|pin| (pin.time_ms.is_none(), pin.time_ms));
+                            changed = true;
+                        }
+                        if ui.add_enabled(count > 0, egui::Button::new("Clear")).clicked() {
+                            self.scratchpad.clear();
+                            changed = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Import…").on_hover_text("Add pins exported by a teammate; files are matched by name to the ones open here").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                                self.scratchpad_message = Some(self.import_pins(&path));
+                            }
+                        }
+                        if ui.add_enabled(count > 0, egui::Button::new("Export…"))
+                            .on_hover_text("Save the pins and notes to hand over with the log")
+                            .clicked()
+                        {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("JSON", &["json"])
+                                .set_file_name(&format!("{}_pins.json", self.source_name()))
+                                .save_file()
+                            {
+                                self.scratchpad_message = Some(
+                                    scratchpad::export(&self.scratchpad, &path).map(|()| format!("Exported {} pins to {}", count, path.display())),
+                                );
+                            }
+                        }
+                    });
+                    match self.scratchpad_message {
+                        Some(Ok(ref message)) => {
+                            ui.label(egui::RichText::new(message).size(12.0).weak());
+                        }
+                        Some(Err(ref error)) => {
+                            ui.label(egui::RichText::new(error).size(12.0).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)));
+                        }
+                        None => {}
+                    }
+                    ui.separator();
+                    if self.scratchpad.is_empty() {
+                        ui.label("Nothing pinned yet.");
+                    }
+                    
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (i, pin) in self.scratchpad.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                let hover = match pin.path {
+                                    Some(ref path) => format!("Go to line {} of {}", pin.line_number, path.display()),
+                                    None => "Pinned from a stream or pasted text, which can't be opened again".to_string(),
+                                };
+                                if ui.link(egui::RichText::new(pin.location()).size(12.0)).on_hover_text(hover).clicked() {
+                                    open_pin = Some(i);
+                                }
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.small_button("✖").on_hover_text("Unpin").clicked() {
+                                        remove = Some(i);
+                                    }
+                                    if i + 1 < count && ui.small_button("⬇").on_hover_text("Move Down").clicked() {
+                                        move_up = Some(i + 1);
+                                    }
+                                    if i > 0 && ui.small_button("⬆").on_hover_text("Move Up").clicked() {
+                                        move_up = Some(i);
+                                    }
+                                });
+                            });
+                            let (color, background) = self.config.color_palette.for_level(&pin.level);
+                            let text = self.redactor.redact(&pin.text);
+                            let first_line = text.lines().next().unwrap_or("");
+                            let line = egui::RichText::new(first_line).monospace().size(12.0).color(color).background_color(background);
+                            let response = ui.add(egui::Label::new(line).wrap(true));
+                            if text.len() > first_line.len() {
+                                response.on_hover_text(egui::RichText::new(text.as_ref()).monospace());
+                            }
+                            let note = ui.add(egui::TextEdit::singleline(&mut pin.note).hint_text("Note").desired_width(f32::INFINITY));
+                            if note.lost_focus() {
+                                changed = true;
+                            }
+                            ui.add_space(8.0);
+                        }
+                    });
                 });
+            
+            if let Some(i) = remove {
+                self.scratchpad.remove(i);
+                changed = true;
+            }
+            if let Some(i) = move_up {
+                self.scratchpad.swap(i - 1, i);
+                changed = true;
+            }
+            if changed {
+                self.save_scratchpad();
+            }
+            if let Some(pin) = open_pin.and_then(|i| self.scratchpad.get(i).cloned()) {
+                self.open_pinned(&pin);
+            }
+        }
+
+        // Timeline and metric charts, sharing the time axis of the loaded entries
+        let show_charts = self.show_metrics && !self.metric_series.is_empty();
+        if let Some(timeline) = self.timeline.as_ref().filter(|_| self.show_timeline || show_charts) {
+            let mut clicked_entry = None;
+            egui::TopBottomPanel::bottom("timeline").resizable(true).show(ctx, |ui| {
+                ui.add_space(4.0);
+                if self.show_timeline {
+                    let palette = &self.config.color_palette;
+                    if let Some(idx) = timeline::show(ui, timeline, palette.error, palette.warn) {
+                        clicked_entry = Some(idx);
+                    }
+                }
+                if show_charts {
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for (i, series) in self.metric_series.iter().enumerate() {
+                            if let Some(idx) = metrics::show_chart(ui, series, timeline.range, metrics::series_color(i)) {
+                                clicked_entry = Some(idx);
+                            }
+                        }
+                    });
+                }
+                ui.add_space(4.0);
+            });
+            if let Some(idx) = clicked_entry {
+                self.scroll_target_line = Some(idx);
+                self.auto_scroll_frames = 0;
+            }
+        }
+
+        // A merged file beside the view, scrolled in step with it by time
+        if self.merge_sources.len() < 2 {
+            self.source_pane = None;
+        }
+        if let Some(mut pane) = self.source_pane.take() {
+            let (moved, closed) = pane.show(ctx, &self.merge_sources, self.config.font_size, &self.config.color_palette, &self.redactor);
+            if let Some(time) = moved {
+                self.goto_time(time);
+            } else if !pane.is_leading() {
+                if let Some(time) = self.top_entry_time() {
+                    if pane.follow(&self.merge_sources, time) {
+                        ctx.request_repaint();
+                    }
+                }
+            }
+            if !closed {
+                self.source_pane = Some(pane);
+            }
         }
 
-        // 4. Central Panel (Log View)
+        // 5. Central Panel (Log View)
         egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(ref mut disk_log) = self.disk_log {
+                disk_log.show(ui, self.config.font_size, &self.config.color_palette, &self.redactor);
+                return;
+            }
+            // Line numbers get a gutter of their own, which stays put under horizontal scrolling
+            let gutter_width = if self.filtered_entries.is_empty() { 0.0 } else { self.gutter_width(ui) };
+            let mut full_rect = ui.available_rect_before_wrap();
+            // The column ruler takes a strip above the text, lines that wrap have no columns to number
+            let show_ruler = self.config.show_column_ruler && !self.wrap_text && !self.filtered_entries.is_empty();
+            let ruler_height = if show_ruler { self.config.font_size + 6.0 } else { 0.0 };
+            let ruler_rect = egui::Rect::from_min_max(
+                egui::pos2(full_rect.left() + gutter_width, full_rect.top()),
+                egui::pos2(full_rect.right(), full_rect.top() + ruler_height),
+            );
+            full_rect.min.y += ruler_height;
+            let gutter_rect = egui::Rect::from_min_size(full_rect.min, egui::vec2(gutter_width, full_rect.height()));
+            let text_rect = egui::Rect::from_min_max(egui::pos2(gutter_rect.right(), full_rect.top()), full_rect.max);
+            let mut text_top = None;
+            let mut text_left = None;
+            let mut text_id = None;
+            
             // Use both scrolls when wrapping is disabled, vertical only when wrapping
             let mut scroll_area = if self.wrap_text {
                 ScrollArea::vertical()
@@ -602,226 +5265,342 @@ impl eframe::App for LogViewerApp {
                 self.scroll_to_top = false;
             }
             
-            // Apply calculated scroll offset if available
-            if let Some(offset) = self.target_scroll_offset {
+            // Apply calculated scroll offset if available, once the content is all there
+            if let Some(offset) = self.target_scroll_offset.filter(|_| !self.is_parsing()) {
                 scroll_area = scroll_area.vertical_scroll_offset(offset);
                 self.target_scroll_offset = None;
                 self.scroll_target_line = None; // Clear the target after scroll is applied
             }
             
-            scroll_area.show(ui, |ui| {
-                // Track Y position as we render
-                let mut current_y = 0.0;
-                    ui.spacing_mut().item_spacing = egui::vec2(0.0, 0.0); // Zero spacing between all items
-                    
-                    if self.entries.is_empty() {
-                        ui.centered_and_justified(|ui| {
-                            ui.label("No log file loaded. Use 'Open' in the top bar to load a log file.");
-                        });
-                    } else if self.filtered_entries.is_empty() {
-                        ui.centered_and_justified(|ui| {
-                            ui.label("No entries match the current filters.");
-                        });
-                    } else {
-                        // Render all filtered entries as a single TextEdit (allows multi-line selection)
-                        let mut all_text = String::new();
-                        let mut job = egui::text::LayoutJob::default();
+            let output = ui.allocate_ui_at_rect(text_rect, |ui| {
+                scroll_area.show(ui, |ui| {
+                    // Track Y position as we render
+                    let mut current_y = 0.0;
+                        ui.spacing_mut().item_spacing = egui::vec2(0.0, 0.0); // Zero spacing between all items
+                    
+                        if self.entries.is_empty() {
+                            ui.centered_and_justified(|ui| {
+                                ui.label("No log file loaded. Use 'Open' in the top bar to load a log file.");
+                            });
+                        } else if self.filtered_entries.is_empty() {
+                            ui.centered_and_justified(|ui| {
+                                ui.label("No entries match the current filters.");
+                            });
+                        } else {
+                            // Laying out the whole log is expensive, so the galley is reused
+                            // until something it depends on changes
+                            let wrap_width = if self.wrap_text { ui.available_width() } else { f32::INFINITY };
+                            let key = ViewKey {
+                                generation: self.view_generation,
+                                search: (self.search.generation, self.search.current_match),
+                                font_size: self.config.font_size,
+                                wrap_width,
+                                theme: self.config.effective_theme().clone(),
+                                scroll_target: self.scroll_target_line,
+                            };
+                            if !matches!(self.view_cache, Some(ref cache) if cache.key == key) {
+                                // Render all filtered entries as a single TextEdit (allows multi-line selection)
+                                let mut all_text = String::new();
+                                let mut job = egui::text::LayoutJob::default();
                         
-                        // Track character count to find the exact position of the target line
-                        let mut current_char_count = 0;
-                        let mut target_char_index = None;
+                                // Track character count to find the exact position of the target line
+                                let mut current_char_count = 0;
+                                let mut target_char_index = None;
+                                let mut entry_starts = Vec::with_capacity(self.filtered_entries.len());
+                                let mut expanders = Vec::new();
+
+                                // Rows of merged sources are told apart by a tint and a label chip
+                                let merged = self.merge_sources.len() > 1;
+                                let tint_sources = merged && self.config.tint_merged_sources;
+                                let source_labels: Vec<String> = if merged && self.config.label_merged_sources {
+                                    let labels: Vec<String> = self.merge_sources.iter().map(MergeSource::label).collect();
+                                    let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+                                    labels.iter().map(|label| format!(" {:<width$} ", label)).collect()
+                                } else {
+                                    Vec::new()
+                                };
+                                let label_indent = source_labels.first().map(|label| " ".repeat(label.chars().count() + 1));
                         
-                        for (_entry_idx_in_filtered, &entry_idx) in self.filtered_entries.iter().enumerate() {
-                            let entry = &self.entries[entry_idx];
-                            let color = self.get_color_for_level(&entry.level);
-                            
-                            let is_search_match = self.search.is_match(entry_idx);
-                            let is_current_match = self.search.is_current_match(entry_idx);
-                            
-                            // Check if this is the scroll target
-                            if let Some(target) = self.scroll_target_line {
-                                if entry_idx == target && target_char_index.is_none() {
-                                    target_char_index = Some(current_char_count);
-                                }
-                            }
+                                for (row, &entry_idx) in self.filtered_entries.iter().enumerate() {
+                                    let entry = &self.entries[entry_idx];
+                                    let verdict = self.script_verdicts.get(entry_idx);
+                                    let color = verdict
+                                        .and_then(|v| v.color)
+                                        .unwrap_or_else(|| self.get_color_for_level(&entry.level));
                             
-                            for (line_idx, line) in entry.raw_line.lines().enumerate() {
-                                if line_idx == 0 {
-                                    // Line number
-                                    let line_num_text = format!("{:6}   ", entry.line_number);
-                                    let text_color = if is_current_match {
-                                        Color32::from_rgb(255, 200, 0)
+                                    let level_background = self.get_bg_color_for_level(&entry.level);
+                                    let background = if self.new_entries.contains(&entry_idx) {
+                                        NEW_ENTRY_TINT
+                                    } else if tint_sources && level_background == Color32::TRANSPARENT {
+                                        merge::source_color(entry.source).linear_multiply(0.12)
+                                    } else if self.config.zebra_stripes && row % 2 == 1 && level_background == Color32::TRANSPARENT {
+                                        ZEBRA_STRIPE
                                     } else {
-                                        color
+                                        level_background
                                     };
-                                    job.append(
-                                        &line_num_text,
-                                        0.0,
-                                        egui::TextFormat {
-                                            font_id: egui::FontId::monospace(self.config.font_size * 0.85),
-                                            color: text_color,
-                                            ..Default::default()
-                                        },
-                                    );
-                                    all_text.push_str(&line_num_text);
-                                    current_char_count += line_num_text.chars().count();
-                                } else {
-                                    // Indentation for continuation lines
-                                    let indent = "         ";
-                                    job.append(
-                                        indent,
-                                        0.0,
-                                        egui::TextFormat {
-                                            font_id: egui::FontId::monospace(self.config.font_size),
-                                            color: Color32::TRANSPARENT,
-                                            ..Default::default()
-                                        },
-                                    );
-                                    all_text.push_str(indent);
-                                    current_char_count += indent.chars().count();
-                                }
+                            
+                                    let is_search_match = self.search.is_match(entry_idx);
+                                    let is_current_match = self.search.is_current_match(entry_idx);
+                                    entry_starts.push((current_char_count, entry_idx));
+                            
+                                    // Check if this is the scroll target
+                                    if let Some(target) = self.scroll_target_line {
+                                        if entry_idx == target && target_char_index.is_none() {
+                                            target_char_index = Some(current_char_count);
+                                        }
+                                    }
+                            
+                                    for (line_idx, line) in entry.raw_line.lines().enumerate() {
+                                        if line_idx == 0 {
+                                            if let Some(label) = source_labels.get(entry.source) {
+                                                job.append(
+                                                    label,
+                                                    0.0,
+                                                    egui::TextFormat {
+                                                        font_id: egui::FontId::monospace(self.config.font_size * 0.85),
+                                                        color: Color32::BLACK,
+                                                        background: merge::source_color(entry.source),
+                                                        ..Default::default()
+                                                    },
+                                                );
+                                                job.append(
+                                                    " ",
+                                                    0.0,
+                                                    egui::TextFormat {
+                                                        font_id: egui::FontId::monospace(self.config.font_size * 0.85),
+                                                        color: Color32::TRANSPARENT,
+                                                        ..Default::default()
+                                                    },
+                                                );
+                                                all_text.push_str(label);
+                                                all_text.push(' ');
+                                                current_char_count += label.chars().count() + 1;
+                                            }
+                                        } else {
+                                            // Line up with the text after the source label
+                                            if let Some(ref label_indent) = label_indent {
+                                                job.append(
+                                                    label_indent,
+                                                    0.0,
+                                                    egui::TextFormat {
+                                                        font_id: egui::FontId::monospace(self.config.font_size * 0.85),
+                                                        color: Color32::TRANSPARENT,
+                                                        ..Default::default()
+                                                    },
+                                                );
+                                                all_text.push_str(label_indent);
+                                                current_char_count += label_indent.chars().count();
+                                            }
+                                        }
                                 
-                                // Log content with search highlighting
-                                if is_search_match {
-                                    if let Some(positions) = self.search.get_match_positions(entry_idx) {
-                                        let mut last_end = 0;
+                                        // Match positions are relative to the entry's text, `line` is a slice of it
+                                        let line_offset = line.as_ptr() as usize - entry.raw_line.as_ptr() as usize;
+
+                                        // Match positions no longer line up once something is masked
+                                        let redacted = self.redactor.redact(line);
+                                        let unchanged = matches!(redacted, Cow::Borrowed(_));
+                                        let line = redacted.as_ref();
                                         
-                                        for &(start, end) in positions {
-                                            if start > line.len() || end > line.len() || start > end {
-                                                continue;
-                                            }
+                                        // A huge line would stall layout, so only its start is shown
+                                        let limit = self.config.max_line_length;
+                                        let cut = (limit > 0 && !self.expanded_entries.contains(&entry_idx))
+                                            .then(|| line.char_indices().nth(limit).map(|(cut, _)| cut))
+                                            .flatten();
+                                        let hidden = cut.map_or(0, |cut| line.len() - cut);
+                                        let line = &line[..cut.unwrap_or(line.len())];
+                                    
+                                        // Log content with search highlighting
+                                        if is_search_match && unchanged {
+                                            if let Some(positions) = self.search.get_match_positions(entry_idx) {
+                                                let mut last_end = 0;
+                                        
+                                                for (start, end) in search::line_ranges(positions, line_offset, line) {
+                                                    if start > last_end {
+                                                        job.append(
+                                                            &line[last_end..start],
+                                                            0.0,
+                                                            egui::TextFormat {
+                                                                font_id: egui::FontId::monospace(self.config.font_size),
+                                                                color,
+                                                                background,
+                                                                ..Default::default()
+                                                            },
+                                                        );
+                                                    }
+                                            
+                                                    let highlight_color = if is_current_match {
+                                                        Color32::from_rgb(255, 200, 0)
+                                                    } else {
+                                                        Color32::from_rgb(255, 255, 150)
+                                                    };
+                                            
+                                                    job.append(
+                                                        &line[start..end],
+                                                        0.0,
+                                                        egui::TextFormat {
+                                                            font_id: egui::FontId::monospace(self.config.font_size),
+                                                            color: Color32::BLACK,
+                                                            background: highlight_color,
+                                                            underline: egui::Stroke::new(1.0, Color32::from_rgb(200, 150, 0)),
+                                                            ..Default::default()
+                                                        },
+                                                    );
                                             
-                                            if start > last_end && last_end < line.len() {
-                                                let safe_start = last_end.min(line.len());
-                                                let safe_end = start.min(line.len());
-                                                if safe_start < safe_end {
+                                                    last_end = end;
+                                                }
+                                        
+                                                if last_end < line.len() {
                                                     job.append(
-                                                        &line[safe_start..safe_end],
+                                                        &line[last_end..],
                                                         0.0,
                                                         egui::TextFormat {
                                                             font_id: egui::FontId::monospace(self.config.font_size),
                                                             color,
-                                                            background: self.get_bg_color_for_level(&entry.level),
+                                                            background,
                                                             ..Default::default()
                                                         },
                                                     );
                                                 }
-                                            }
-                                            
-                                            let highlight_color = if is_current_match {
-                                                Color32::from_rgb(255, 200, 0)
                                             } else {
-                                                Color32::from_rgb(255, 255, 150)
-                                            };
-                                            
-                                            if start < line.len() && end <= line.len() {
                                                 job.append(
-                                                    &line[start..end],
+                                                    line,
                                                     0.0,
                                                     egui::TextFormat {
                                                         font_id: egui::FontId::monospace(self.config.font_size),
-                                                        color: Color32::BLACK,
-                                                        background: highlight_color,
-                                                        underline: egui::Stroke::new(1.0, Color32::from_rgb(200, 150, 0)),
+                                                        color,
+                                                        background,
                                                         ..Default::default()
                                                     },
                                                 );
                                             }
-                                            
-                                            last_end = end;
-                                        }
-                                        
-                                        if last_end < line.len() {
+                                        } else {
                                             job.append(
-                                                &line[last_end..],
+                                                line,
                                                 0.0,
                                                 egui::TextFormat {
                                                     font_id: egui::FontId::monospace(self.config.font_size),
                                                     color,
-                                                    background: self.get_bg_color_for_level(&entry.level),
+                                                    background,
                                                     ..Default::default()
                                                 },
                                             );
                                         }
-                                    } else {
+                                        all_text.push_str(line);
+                                        current_char_count += line.chars().count();
+                                        
+                                        if hidden > 0 {
+                                            let expander = format!(" … show full line ({} more)", dir_browser::format_size(hidden as u64));
+                                            job.append(
+                                                &expander,
+                                                0.0,
+                                                egui::TextFormat {
+                                                    font_id: egui::FontId::monospace(self.config.font_size * 0.85),
+                                                    color: ui.visuals().hyperlink_color,
+                                                    underline: egui::Stroke::new(1.0, ui.visuals().hyperlink_color),
+                                                    ..Default::default()
+                                                },
+                                            );
+                                            let length = expander.chars().count();
+                                            expanders.push((current_char_count..current_char_count + length, entry_idx));
+                                            all_text.push_str(&expander);
+                                            current_char_count += length;
+                                        }
+                                
+                                        // Extra fields computed by the entry script
+                                        if line_idx == 0 {
+                                            if let Some(verdict) = verdict.filter(|v| !v.fields.is_empty()) {
+                                                let fields: String = verdict.fields
+                                                    .iter()
+                                                    .map(|(key, value)| format!("  {}={}", key, value))
+                                                    .collect();
+                                                let fields = self.redactor.redact(&fields);
+                                                job.append(
+                                                    &fields,
+                                                    0.0,
+                                                    egui::TextFormat {
+                                                        font_id: egui::FontId::monospace(self.config.font_size * 0.85),
+                                                        color: ui.visuals().weak_text_color(),
+                                                        ..Default::default()
+                                                    },
+                                                );
+                                                all_text.push_str(&fields);
+                                                current_char_count += fields.chars().count();
+                                            }
+                                        }
+                                
+                                        // Newline
                                         job.append(
-                                            line,
+                                            "\n",
                                             0.0,
                                             egui::TextFormat {
                                                 font_id: egui::FontId::monospace(self.config.font_size),
-                                                color,
-                                                background: self.get_bg_color_for_level(&entry.level),
+                                                color: Color32::TRANSPARENT,
                                                 ..Default::default()
                                             },
                                         );
+                                        all_text.push('\n');
+                                        current_char_count += 1; // Count newline char
                                     }
-                                } else {
-                                    job.append(
-                                        line,
-                                        0.0,
-                                        egui::TextFormat {
-                                            font_id: egui::FontId::monospace(self.config.font_size),
-                                            color,
-                                            background: self.get_bg_color_for_level(&entry.level),
-                                            ..Default::default()
-                                        },
-                                    );
-                                }
-                                all_text.push_str(line);
-                                current_char_count += line.chars().count();
-                                
-                                // Newline
-                                job.append(
-                                    "\n",
-                                    0.0,
-                                    egui::TextFormat {
-                                        font_id: egui::FontId::monospace(self.config.font_size),
-                                        color: Color32::TRANSPARENT,
-                                        ..Default::default()
-                                    },
-                                );
-                                all_text.push('\n');
-                                current_char_count += 1; // Count newline char
-                            }
-                        }
-                        
-                        // Configure layout job wrapping
-                        let wrap_enabled = self.wrap_text;
-                        if wrap_enabled {
-                            job.wrap.max_width = ui.available_width();
-                        } else {
-                            job.wrap.max_width = f32::INFINITY;
-                        }
-                        
-                        // Calculate Galley to find exact scroll position
-                        let galley = ui.fonts(|f| f.layout_job(job));
+                                }
                         
-                        // If we have a target, calculate exact offset from Galley
-                        if let Some(char_idx) = target_char_index {
-                            if self.target_scroll_offset.is_none() {
-                                // Find the row containing the target character index
-                                let mut accumulated_chars = 0;
-                                let mut y_offset = 0.0;
-                                for row in &galley.rows {
-                                    let row_char_count = row.char_count_excluding_newline() + if row.ends_with_newline { 1 } else { 0 };
-                                    if accumulated_chars + row_char_count > char_idx {
-                                        // Found the row containing the character
-                                        y_offset = row.rect.min.y;
-                                        break;
-                                    }
-                                    accumulated_chars += row_char_count;
+                                if self.config.line_spacing > 1.0 {
+                                    let font_id = egui::FontId::monospace(self.config.font_size);
+                                    let line_height = ui.fonts(|f| f.row_height(&font_id)) * self.config.line_spacing;
+                                    for section in &mut job.sections {
+                                        section.format.line_height = Some(line_height.round());
+                                    }
+                                }
+                                job.wrap.max_width = wrap_width;
+                                let galley = ui.fonts(|f| f.layout_job(job));
+                            
+                                // Where each entry's first row is, so the gutter can number it
+                                let mut entry_tops = Vec::with_capacity(entry_starts.len());
+                                let mut rows = galley.rows.iter();
+                                let mut row = rows.next();
+                                let mut row_start = 0;
+                                for &(start, _) in &entry_starts {
+                                    while let Some(current) = row {
+                                        let row_chars = current.char_count_excluding_newline() + usize::from(current.ends_with_newline);
+                                        if row_start + row_chars > start {
+                                            break;
+                                        }
+                                        row_start += row_chars;
+                                        row = rows.next();
+                                    }
+                                    entry_tops.push(row.map_or(0.0, |current| current.rect.min.y));
                                 }
+                                self.view_cache = Some(ViewCache { key, text: all_text, galley, target_char_index, entry_starts, entry_tops, expanders, occurrences: None });
+                            }
+                            let cache = self.view_cache.as_ref().expect("view cache was just filled");
+                            let galley = cache.galley.clone();
+                            let target_char_index = cache.target_char_index;
+                            let mut all_text = cache.text.as_str();
+                        
+                            // If we have a target, calculate exact offset from Galley
+                            if let Some(char_idx) = target_char_index {
+                                if self.target_scroll_offset.is_none() {
+                                    // Find the row containing the target character index
+                                    let mut accumulated_chars = 0;
+                                    let mut y_offset = 0.0;
+                                    for row in &galley.rows {
+                                        let row_char_count = row.char_count_excluding_newline() + if row.ends_with_newline { 1 } else { 0 };
+                                        if accumulated_chars + row_char_count > char_idx {
+                                            // Found the row containing the character
+                                            y_offset = row.rect.min.y;
+                                            break;
+                                        }
+                                        accumulated_chars += row_char_count;
+                                    }
                                 
-                                // Center the target line in viewport
-                                let viewport_height = ui.available_height();
-                                let centered_offset = (y_offset - viewport_height / 2.0).max(0.0);
-                                self.target_scroll_offset = Some(centered_offset);
+                                    // Center the target line in viewport
+                                    let viewport_height = ui.available_height();
+                                    let centered_offset = (y_offset - viewport_height / 2.0).max(0.0);
+                                    self.target_scroll_offset = Some(centered_offset);
+                                }
                             }
-                        }
                         
-                        // Render using the pre-calculated Galley
-                        ui.add(
-                            egui::TextEdit::multiline(&mut all_text)
+                            // Render using the pre-calculated Galley
+                            let mut text_output = egui::TextEdit::multiline(&mut all_text)
                                 .layouter(&mut |ui, _string, _wrap_width| {
                                     // Return the pre-calculated galley (cloned because layouter might be called multiple times)
                                     // Note: we ignore the passed wrap_width because we already used the correct one
@@ -830,25 +5609,85 @@ impl eframe::App for LogViewerApp {
                                 .frame(false)
                                 .margin(egui::vec2(0.0, 0.0))
                                 .desired_width(f32::INFINITY)
-                        );
+                                .show(ui);
+                            text_top = Some(text_output.text_draw_pos.y);
+                            text_left = Some(text_output.text_draw_pos.x);
+                            text_id = Some(text_output.response.id);
+                            self.check_expanders(ui, &text_output);
+                            // Kept after the view loses focus, so the selection can still be shared
+                            self.view_selection = text_output.state
+                                .cursor_range(&text_output.galley)
+                                .map(|range| range.as_sorted_char_range())
+                                .filter(|range| !range.is_empty());
+                            text_output.response.context_menu(|ui| {
+                                if ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Pin to Scratchpad")).clicked() {
+                                    self.pin_selection();
+                                    ui.close_menu();
+                                }
+                                if ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Share Selection…")).clicked() {
+                                    self.open_share();
+                                    ui.close_menu();
+                                }
+                                if ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Export Selection…")).clicked() {
+                                    ui.close_menu();
+                                    self.export_entries(&self.selected_entries(), "selection", ExportFormat::Lines);
+                                }
+                                let cut_short = self.view_cache.as_ref().is_some_and(|cache| !cache.expanders.is_empty());
+                                if cut_short && ui.add_enabled(self.view_selection.is_some(), egui::Button::new("Show Full Lines")).clicked() {
+                                    let selected = self.selected_entries();
+                                    self.expanded_entries.extend(selected);
+                                    self.view_generation += 1;
+                                    ui.close_menu();
+                                }
+                            });
                         
-                        // Add a spacer at the bottom to ensure we can scroll to the very end
-                        ui.allocate_space(egui::vec2(ui.available_width(), 0.0));
+                            // Add a spacer at the bottom to ensure we can scroll to the very end
+                            ui.allocate_space(egui::vec2(ui.available_width(), 0.0));
                         
-                        // Auto-scroll to end on first load or refresh - must be after all content is rendered
-                        if self.auto_scroll_frames > 0 && self.scroll_to_end && !self.filtered_entries.is_empty() {
-                            // Scroll to the very bottom
-                            ui.scroll_to_cursor(Some(Align::BOTTOM));
-                            self.auto_scroll_frames -= 1;
-                            ui.ctx().request_repaint(); // Ensure we keep repainting until scroll settles
+                            // Auto-scroll to end on first load or refresh - must be after all content is rendered
+                            if self.auto_scroll_frames > 0 && self.scroll_to_end && !self.filtered_entries.is_empty() {
+                                // Scroll to the very bottom
+                                ui.scroll_to_cursor(Some(Align::BOTTOM));
+                                self.auto_scroll_frames -= 1;
+                                ui.ctx().request_repaint(); // Ensure we keep repainting until scroll settles
+                            }
                         }
-                    }
-                });
+                    })
+            }).inner;
+            self.scroll_offset = output.state.offset.y;
+            if let (Some(top), Some(id)) = (text_top, text_id) {
+                self.show_gutter(ui, gutter_rect, top, id);
+            }
+            if let (Some(top), Some(left)) = (text_top, text_left) {
+                self.show_word_occurrences(ui, text_rect, top, left);
+            }
+            if let (Some(top), Some(left), Some(id)) = (text_top, text_left, text_id) {
+                self.show_sticky_header(ui, text_rect, top, left, id);
+            }
+            if let Some(left) = text_left.filter(|_| !self.wrap_text) {
+                if self.config.show_column_guides {
+                    self.show_column_guides(ui, text_rect, left);
+                }
+                if show_ruler {
+                    self.show_column_ruler(ui, ruler_rect, left);
+                }
+            }
         });
         
-
-        
-        ctx.request_repaint();
+        // Watchers and the parse worker wake the UI themselves. Tailing keeps a
+        // timed repaint as a safety net for a missed wake-up, and a computed
+        // scroll target is applied on the next frame.
+        if self.tail_log && self.file_watcher.is_watching() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(500));
+        }
+        // Queued live entries are shown at the next refresh
+        if self.ingest.pending() > 0 {
+            ctx.request_repaint_after(self.ingest.time_to_next(self.config.tail_refresh_hz).unwrap_or_default());
+        }
+        // A large search goes on a slice per frame
+        if self.target_scroll_offset.is_some() || self.search.is_searching() {
+            ctx.request_repaint();
+        }
     }
 }
 
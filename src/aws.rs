@@ -0,0 +1,169 @@
+#[cfg(feature = "aws")]
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+#[cfg(feature = "aws")]
+use aws_credential_types::provider::ProvideCredentials;
+#[cfg(feature = "aws")]
+use aws_credential_types::Credentials;
+#[cfg(feature = "aws")]
+use aws_sigv4::http_request::{
+    sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest, SigningSettings, UriPathNormalizationMode,
+};
+#[cfg(feature = "aws")]
+use aws_sigv4::sign::v4;
+#[cfg(feature = "aws")]
+use std::time::{Duration, SystemTime};
+
+/// Credentials this close to expiring are fetched again.
+#[cfg(feature = "aws")]
+const CREDENTIALS_REFRESH: Duration = Duration::from_secs(300);
+
+/// How a request is signed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signing {
+    /// SigV4 as most services take it
+    Standard,
+    /// S3's variant: the path is encoded once and not normalized, and the
+    /// payload's hash is sent along
+    S3,
+}
+
+/// Credentials and region from the standard AWS chain (environment, shared
+/// config and SSO, web identity, container or instance roles), for signing
+/// requests made with the blocking HTTP client.
+#[cfg(feature = "aws")]
+pub struct AwsClient {
+    runtime: tokio::runtime::Runtime,
+    config: SdkConfig,
+    region: Option<String>,
+    credentials: Option<Credentials>,
+}
+
+#[cfg(feature = "aws")]
+impl AwsClient {
+    /// Load the chain for `profile` (empty for the default), with `region`
+    /// overriding the configured one when set.
+    pub fn new(profile: &str, region: &str) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start the AWS client: {}", e))?;
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if !profile.trim().is_empty() {
+            loader = loader.profile_name(profile.trim());
+        }
+        if !region.trim().is_empty() {
+            loader = loader.region(Region::new(region.trim().to_string()));
+        }
+        let config = runtime.block_on(loader.load());
+        Ok(Self {
+            runtime,
+            region: config.region().map(|region| region.to_string()),
+            config,
+            credentials: None,
+        })
+    }
+
+    /// The region requests are signed for: the configured one, if any.
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn set_region(&mut self, region: &str) {
+        self.region = Some(region.to_string());
+    }
+
+    /// `AWS_ENDPOINT_URL` or the profile's `endpoint_url`, e.g. for LocalStack or MinIO.
+    pub fn endpoint_url(&self) -> Option<&str> {
+        self.config.endpoint_url()
+    }
+
+    /// Current credentials, fetched again when close to expiring.
+    fn credentials(&mut self) -> Result<Credentials, String> {
+        if let Some(ref credentials) = self.credentials {
+            match credentials.expiry() {
+                Some(expiry) if expiry <= SystemTime::now() + CREDENTIALS_REFRESH => {}
+                _ => return Ok(credentials.clone()),
+            }
+        }
+        let provider = self.config.credentials_provider().ok_or_else(|| "No AWS credentials found".to_string())?;
+        let credentials = self
+            .runtime
+            .block_on(provider.provide_credentials())
+            .map_err(|e| format!("Failed to load AWS credentials: {}", e))?;
+        self.credentials = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    /// The headers that sign a request to `service` (SigV4). `url` must
+    /// already be percent-encoded.
+    pub fn sign(
+        &mut self,
+        service: &str,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        signing: Signing,
+    ) -> Result<Vec<(String, String)>, String> {
+        let mut settings = SigningSettings::default();
+        if signing == Signing::S3 {
+            settings.percent_encoding_mode = PercentEncodingMode::Single;
+            settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+            settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        }
+        let region = self.region.clone().ok_or_else(|| "No region: enter one or set it in the AWS config".to_string())?;
+        let identity = self.credentials()?.into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&region)
+            .name(service)
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()
+            .map_err(|e| format!("Failed to sign the request: {}", e))?
+            .into();
+        let signable = SignableRequest::new(method, url, headers.iter().copied(), SignableBody::Bytes(body))
+            .map_err(|e| format!("Failed to sign the request: {}", e))?;
+        let (instructions, _) = sign(signable, &params).map_err(|e| format!("Failed to sign the request: {}", e))?.into_parts();
+        Ok(instructions.headers().map(|(name, value)| (name.to_string(), value.to_string())).collect())
+    }
+}
+
+/// Without the `aws` feature no client can be made, and reading CloudWatch
+/// or S3 fails with the reason.
+#[cfg(not(feature = "aws"))]
+pub struct AwsClient {
+    _private: (),
+}
+
+#[cfg(not(feature = "aws"))]
+const DISABLED: &str = "Log Rocket was built without AWS support; rebuild it with `--features aws`";
+
+#[cfg(not(feature = "aws"))]
+impl AwsClient {
+    pub fn new(_profile: &str, _region: &str) -> Result<Self, String> {
+        Err(DISABLED.to_string())
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn set_region(&mut self, _region: &str) {}
+
+    pub fn endpoint_url(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn sign(
+        &mut self,
+        _service: &str,
+        _method: &str,
+        _url: &str,
+        _headers: &[(&str, &str)],
+        _body: &[u8],
+        _signing: Signing,
+    ) -> Result<Vec<(String, String)>, String> {
+        Err(DISABLED.to_string())
+    }
+}
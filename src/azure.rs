@@ -0,0 +1,178 @@
+use serde_json::{json, Map, Value};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::file_open;
+use crate::source_event::SourceEvent;
+
+const QUERY_URL: &str = "https://api.loganalytics.io/v1/workspaces";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180);
+/// Columns holding a row's time, message and level in the common tables
+/// (AppTraces, ContainerLogV2, Syslog, AzureDiagnostics, ...), most likely first.
+const TIME_COLUMNS: &[&str] = &["TimeGenerated", "timestamp", "Timestamp"];
+const MESSAGE_COLUMNS: &[&str] = &["Message", "message", "LogMessage", "SyslogMessage", "RenderedDescription", "ResultDescription", "Description"];
+const LEVEL_COLUMNS: &[&str] = &["SeverityLevel", "Level", "LogLevel", "SeverityText", "Severity"];
+
+/// A Log Analytics (Azure Monitor Logs) query, as edited in the cloud logs window.
+#[derive(Debug, Clone)]
+pub struct AzureQuery {
+    /// Workspace id (a GUID)
+    pub workspace: String,
+    /// KQL query, e.g. `AppTraces | where SeverityLevel >= 2`
+    pub query: String,
+    /// How far back to query, in minutes
+    pub lookback_minutes: u32,
+}
+
+impl Default for AzureQuery {
+    fn default() -> Self {
+        Self {
+            workspace: String::new(),
+            query: String::new(),
+            lookback_minutes: 60,
+        }
+    }
+}
+
+impl AzureQuery {
+    /// The query's first line, as shown in the header.
+    pub fn description(&self) -> String {
+        let query = self.query.trim().lines().next().unwrap_or_default();
+        format!("{} @ {}", query, self.workspace.trim())
+    }
+}
+
+/// Run the query and send its rows, oldest first, each becoming one JSON
+/// line of its non-empty columns with `timestamp`, `level` and `message`.
+pub fn run(query: &AzureQuery, sender: &mpsc::SyncSender<SourceEvent>) {
+    match run_query(query) {
+        Ok(lines) => {
+            if sender.send(SourceEvent::Connected).is_ok() && !lines.is_empty() {
+                sender.send(SourceEvent::Lines(lines)).ok();
+            }
+        }
+        Err(e) => {
+            sender.send(SourceEvent::Disconnected(e)).ok();
+        }
+    }
+    sender.send(SourceEvent::Ended).ok();
+    file_open::wake_ui();
+}
+
+fn run_query(query: &AzureQuery) -> Result<Vec<String>, String> {
+    if query.workspace.trim().is_empty() || query.query.trim().is_empty() {
+        return Err("Workspace id and query are required".to_string());
+    }
+    let token = access_token()?;
+    let url = format!("{}/{}/query", QUERY_URL, query.workspace.trim());
+    let body = json!({ "query": query.query.trim(), "timespan": format!("PT{}M", query.lookback_minutes) });
+    let response = ureq::post(&url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                let text = response.into_string().unwrap_or_default();
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(error) => format!("Query failed ({}): {}", status, error_message(&error["error"])),
+                    Err(_) => format!("Query failed ({}): {}", status, text.trim()),
+                }
+            }
+            e => format!("Failed to reach Log Analytics: {}", e),
+        })?;
+    let result: Value =
+        serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the response: {}", e))?;
+    // A partial result carries an error next to the rows that did come back
+    let mut lines = table_lines(&result["tables"][0]);
+    if result["error"].is_object() {
+        lines.push(format!("──────── Partial result: {} ────────", error_message(&result["error"])));
+    }
+    Ok(lines)
+}
+
+/// The innermost message of `{"message": .., "innererror": {..}}`.
+fn error_message(error: &Value) -> String {
+    let mut error = error;
+    while error["innererror"].is_object() {
+        error = &error["innererror"];
+    }
+    error["message"].as_str().unwrap_or("unknown error").trim().to_string()
+}
+
+/// An access token for Log Analytics from `AZURE_LOG_ANALYTICS_TOKEN`, else from the Azure CLI's login.
+fn access_token() -> Result<String, String> {
+    if let Ok(token) = std::env::var("AZURE_LOG_ANALYTICS_TOKEN") {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let az = if cfg!(windows) { "az.cmd" } else { "az" };
+    let output = Command::new(az)
+        .args(["account", "get-access-token", "--resource", "https://api.loganalytics.io", "--query", "accessToken", "-o", "tsv"])
+        .output()
+        .map_err(|e| format!("Failed to run the Azure CLI for a token: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("No Azure credentials: run `az login` ({})", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A result table's rows as JSON lines, in time order when it has a time column.
+fn table_lines(table: &Value) -> Vec<String> {
+    let columns: Vec<&str> = table["columns"].as_array().into_iter().flatten().map(|c| c["name"].as_str().unwrap_or_default()).collect();
+    let time_column = TIME_COLUMNS.iter().find_map(|name| columns.iter().position(|c| c == name));
+    let mut rows: Vec<&Vec<Value>> = table["rows"].as_array().into_iter().flatten().filter_map(Value::as_array).collect();
+    if let Some(index) = time_column {
+        // ISO 8601 times in UTC sort as text
+        rows.sort_by(|a, b| a[index].as_str().cmp(&b[index].as_str()));
+    }
+    rows.iter().map(|row| row_line(&columns, row)).collect()
+}
+
+fn row_line(columns: &[&str], row: &[Value]) -> String {
+    let mut fields = Map::new();
+    for (name, value) in columns.iter().zip(row) {
+        let empty = value.is_null() || value.as_str().is_some_and(str::is_empty);
+        if !empty {
+            fields.insert(name.to_string(), value.clone());
+        }
+    }
+    // The time and message move to the parser's names; the level column stays as it was
+    let time = TIME_COLUMNS.iter().find_map(|name| fields.remove(*name));
+    let message = MESSAGE_COLUMNS.iter().find_map(|name| fields.remove(*name));
+    let level = LEVEL_COLUMNS.iter().find_map(|name| fields.get(*name)).and_then(level_name);
+    if let Some(time) = time {
+        fields.insert("timestamp".to_string(), time);
+    }
+    if let Some(message) = message {
+        fields.insert("message".to_string(), message);
+    }
+    if let Some(level) = level {
+        fields.insert("level".to_string(), json!(level));
+    }
+    Value::Object(fields).to_string()
+}
+
+/// The parser's level for a severity column: Application Insights' 0-4,
+/// syslog names (`err`, `crit`, ...) or the usual level names.
+fn level_name(level: &Value) -> Option<&'static str> {
+    if let Some(number) = level.as_i64() {
+        return match number {
+            0 => Some("DEBUG"),
+            1 => Some("INFO"),
+            2 => Some("WARN"),
+            3 | 4 => Some("ERROR"),
+            _ => None,
+        };
+    }
+    match level.as_str()?.to_lowercase().as_str() {
+        "trace" | "verbose" => Some("TRACE"),
+        "debug" => Some("DEBUG"),
+        "info" | "information" | "informational" | "notice" => Some("INFO"),
+        "warn" | "warning" => Some("WARN"),
+        "err" | "error" | "crit" | "critical" | "alert" | "emerg" | "fatal" => Some("ERROR"),
+        _ => None,
+    }
+}
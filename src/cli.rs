@@ -0,0 +1,75 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::config::Theme;
+use crate::file_open::OpenRequest;
+use crate::log_parser::{LogFormat, LogLevel};
+
+/// Log Rocket - fast log viewer for AEM logs
+#[derive(Parser, Debug)]
+#[command(name = "log-rocket", version, about)]
+pub struct Cli {
+    /// Log file to open: file.log, file.log:LINE or a logrocket://open?path=...&line=... URL
+    pub file: Option<PathBuf>,
+
+    /// Only show these levels (comma separated: error,warn,info,debug,trace,unknown)
+    #[arg(long, value_delimiter = ',')]
+    pub level: Vec<LogLevel>,
+
+    /// Search for this text on startup
+    #[arg(long)]
+    pub search: Option<String>,
+
+    /// Treat the search as a regular expression
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Tail the file and keep scrolled to the end
+    #[arg(long)]
+    pub follow: bool,
+
+    /// Jump to this line number after loading
+    #[arg(long, value_name = "LINE")]
+    pub goto: Option<usize>,
+
+    /// Log format: auto, error, access or json
+    #[arg(long, default_value = "auto")]
+    pub format: LogFormat,
+
+    /// Don't open a window: print the filtered (and searched) entries to stdout
+    #[arg(long)]
+    pub no_gui: bool,
+
+    /// Colorize headless output by level
+    #[arg(long, requires = "no_gui")]
+    pub color: bool,
+
+    /// Print headless output as one JSON object per line
+    #[arg(long, requires = "no_gui", conflicts_with = "color")]
+    pub json: bool,
+
+    /// Color theme for this run (dark or light), without changing the saved setting
+    #[arg(long, env = "LOGROCKET_THEME")]
+    pub theme: Option<Theme>,
+
+    /// Read and save settings in this file instead of config.json in the config directory
+    #[arg(long, env = "LOGROCKET_CONFIG", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+impl Cli {
+    /// The file to open, the line to jump to and how to view it; `--goto` wins
+    /// over a `:LINE` suffix.
+    pub fn open_request(&self) -> Option<OpenRequest> {
+        let mut request = OpenRequest::parse(&self.file.as_ref()?.to_string_lossy())?;
+        if self.goto.is_some() {
+            request.line = self.goto;
+        }
+        request.levels = self.level.clone();
+        request.search = self.search.clone();
+        request.regex = self.regex;
+        request.format = self.format.clone();
+        request.follow = self.follow;
+        Some(request)
+    }
+}
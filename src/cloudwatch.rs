@@ -0,0 +1,462 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+use crate::aws::{AwsClient, Signing};
+use crate::file_open;
+use crate::live_stream::{self, LiveStream};
+use crate::source_event::{self, SourceAction, SourceEvent};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the live tail asks for new events.
+const TAIL_INTERVAL: Duration = Duration::from_secs(2);
+/// How far behind the newest event each live poll starts, since events can
+/// become searchable a little after later ones.
+const LATE_WINDOW_MS: i64 = 60_000;
+
+/// A CloudWatch Logs log group to read, as edited in the CloudWatch window.
+#[derive(Debug, Clone)]
+pub struct CloudWatchQuery {
+    /// Profile of the shared AWS config; empty for the default chain
+    pub profile: String,
+    /// Empty for the profile's or environment's region
+    pub region: String,
+    pub group: String,
+    /// One log stream of the group; empty for all of them
+    pub stream: String,
+    /// CloudWatch filter pattern, e.g. `ERROR` or `{ $.level = "error" }`
+    pub filter: String,
+    /// How far back to read, in minutes
+    pub lookback_minutes: u32,
+    /// Keep polling for new events
+    pub live: bool,
+}
+
+impl Default for CloudWatchQuery {
+    fn default() -> Self {
+        Self {
+            profile: String::new(),
+            region: String::new(),
+            group: String::new(),
+            stream: String::new(),
+            filter: String::new(),
+            lookback_minutes: 60,
+            live: true,
+        }
+    }
+}
+
+impl CloudWatchQuery {
+    /// `group/stream @ region`, as shown in the header.
+    pub fn description(&self) -> String {
+        let mut description = self.group.trim().to_string();
+        if !self.stream.trim().is_empty() {
+            description = format!("{}/{}", description, self.stream.trim());
+        }
+        if !self.region.trim().is_empty() {
+            description = format!("{} @ {}", description, self.region.trim());
+        }
+        description
+    }
+}
+
+/// The Read CloudWatch Logs window.
+#[derive(Default)]
+pub struct CloudWatchDialog {
+    pub open: bool,
+    pub query: CloudWatchQuery,
+    /// Why the last read couldn't be started
+    pub error: Option<String>,
+    groups: Option<NameList>,
+    streams: Option<NameList>,
+}
+
+impl CloudWatchDialog {
+    pub fn show(&mut self, ctx: &egui::Context, stream: Option<&LiveStream>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Read CloudWatch Logs")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let query = &mut self.query;
+                egui::Grid::new("cloudwatch_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Profile:");
+                    ui.add(egui::TextEdit::singleline(&mut query.profile).hint_text("default"));
+                    ui.end_row();
+                    ui.label("Region:");
+                    ui.add(egui::TextEdit::singleline(&mut query.region).hint_text("from the profile"));
+                    ui.end_row();
+                    ui.label("Log group:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut query.group).desired_width(260.0).hint_text("/aws/lambda/my-function"));
+                        name_picker(ui, query, &mut self.groups, false);
+                    });
+                    ui.end_row();
+                    ui.label("Log stream:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut query.stream).desired_width(260.0).hint_text("all streams"));
+                        name_picker(ui, query, &mut self.streams, true);
+                    });
+                    ui.end_row();
+                    ui.label("Filter:");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut query.filter)
+                            .desired_width(290.0)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("ERROR or { $.level = \"error\" }"),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        action = Some(SourceAction::Open);
+                    }
+                    ui.end_row();
+                    ui.label("Last:");
+                    ui.horizontal(|ui| source_event::lookback_choices(ui, &mut query.lookback_minutes));
+                    ui.end_row();
+                });
+                ui.checkbox(&mut query.live, "Follow new events");
+                ui.label("Credentials come from the environment, the AWS config files or SSO, or the instance role.");
+                source_event::show_errors(ui, stream.map(LiveStream::events), self.error.as_deref());
+                ui.horizontal(|ui| {
+                    if ui.button("Read").clicked() {
+                        action = Some(SourceAction::Open);
+                    }
+                    if stream.is_some() && ui.button("Disconnect").clicked() {
+                        action = Some(SourceAction::Disconnect);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// A signed CloudWatch Logs client for one region.
+struct Client {
+    aws: AwsClient,
+    endpoint: Url,
+}
+
+impl Client {
+    fn new(query: &CloudWatchQuery) -> Result<Self, String> {
+        let aws = AwsClient::new(&query.profile, &query.region)?;
+        let region = aws.region().ok_or_else(|| "No region: enter one or set it in the AWS config".to_string())?;
+        let endpoint = match aws.endpoint_url() {
+            Some(url) => url.to_string(),
+            None if region.starts_with("cn-") => format!("https://logs.{}.amazonaws.com.cn/", region),
+            None => format!("https://logs.{}.amazonaws.com/", region),
+        };
+        let endpoint = Url::parse(&endpoint).map_err(|e| format!("Invalid endpoint {}: {}", endpoint, e))?;
+        Ok(Self { aws, endpoint })
+    }
+
+    /// Call a Logs API action with a JSON body.
+    fn call(&mut self, action: &str, body: &Value) -> Result<Value, String> {
+        let body = body.to_string();
+        let target = format!("Logs_20140328.{}", action);
+        let headers = [("content-type", "application/x-amz-json-1.1"), ("x-amz-target", target.as_str())];
+        let signature =
+            self.aws.sign("logs", "POST", self.endpoint.as_str(), &headers, body.as_bytes(), Signing::Standard)?;
+
+        let mut request = ureq::request_url("POST", &self.endpoint).timeout(REQUEST_TIMEOUT);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        for (name, value) in &signature {
+            request = request.set(name, value);
+        }
+        let response = request.send_string(&body).map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                let text = response.into_string().unwrap_or_default();
+                // Errors come as {"__type": "...#ResourceNotFoundException", "message": ...}
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(error) => {
+                        let kind = error["__type"].as_str().unwrap_or_default();
+                        let kind = kind.rsplit('#').next().unwrap_or(kind);
+                        let message = error["message"].as_str().or(error["Message"].as_str()).unwrap_or_default();
+                        format!("{} failed ({}): {}: {}", action, status, kind, message.trim())
+                    }
+                    Err(_) => format!("{} failed ({}): {}", action, status, text.trim()),
+                }
+            }
+            e => format!("Failed to reach CloudWatch Logs: {}", e),
+        })?;
+        serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the response: {}", e))
+    }
+}
+
+/// Read the lookback window, then keep polling for new events when live.
+/// (Live Tail sessions need an HTTP/2 event stream; polling FilterLogEvents
+/// works with the same permissions and through any proxy.)
+pub fn run(query: &CloudWatchQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    let end = chrono::Utc::now().timestamp_millis();
+    let start = end - i64::from(query.lookback_minutes) * 60_000;
+    let mut reader = match Client::new(query) {
+        Ok(client) => Reader::new(client, query, start),
+        Err(e) => return fail(e, sender),
+    };
+    let mut delay = Duration::ZERO;
+    match reader.filter(start, Some(end), &mut delay, sender, stop) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => return fail(e, sender),
+    }
+    if !query.live {
+        sender.send(SourceEvent::Ended).ok();
+        file_open::wake_ui();
+        return;
+    }
+    reader.newest = reader.newest.max(end);
+    live_stream::retry(sender, stop, |delay| {
+        while !stop.load(Ordering::Relaxed) {
+            let start = reader.newest - LATE_WINDOW_MS;
+            if !reader.filter(start, None, delay, sender, stop)? {
+                return Ok(());
+            }
+            let oldest = reader.newest - LATE_WINDOW_MS;
+            reader.seen.retain(|_, time| *time >= oldest);
+            thread::sleep(TAIL_INTERVAL);
+        }
+        Ok(())
+    });
+}
+
+fn fail(error: String, sender: &mpsc::SyncSender<SourceEvent>) {
+    sender.send(SourceEvent::Disconnected(error)).ok();
+    sender.send(SourceEvent::Ended).ok();
+    file_open::wake_ui();
+}
+
+/// Reads a group's events, remembering which were sent so overlapping
+/// polls don't repeat them.
+struct Reader {
+    client: Client,
+    query: CloudWatchQuery,
+    connected: bool,
+    /// Time of the newest event sent, in milliseconds
+    newest: i64,
+    /// Event ids sent, with their times
+    seen: HashMap<String, i64>,
+}
+
+impl Reader {
+    fn new(client: Client, query: &CloudWatchQuery, start: i64) -> Self {
+        Self {
+            client,
+            query: query.clone(),
+            connected: false,
+            newest: start,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Page through the events from `start` (to `end`, if given), sending
+    /// those not sent before. False once the receiver is gone.
+    fn filter(
+        &mut self,
+        start: i64,
+        end: Option<i64>,
+        retry_delay: &mut Duration,
+        sender: &mpsc::SyncSender<SourceEvent>,
+        stop: &AtomicBool,
+    ) -> Result<bool, String> {
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut body = json!({ "logGroupName": self.query.group.trim(), "startTime": start });
+            if let Some(end) = end {
+                body["endTime"] = json!(end);
+            }
+            if !self.query.stream.trim().is_empty() {
+                body["logStreamNames"] = json!([self.query.stream.trim()]);
+            }
+            if !self.query.filter.trim().is_empty() {
+                body["filterPattern"] = json!(self.query.filter.trim());
+            }
+            if let Some(token) = next_token.take() {
+                body["nextToken"] = json!(token);
+            }
+            let response = match self.client.call("FilterLogEvents", &body) {
+                Ok(response) => response,
+                Err(e) => {
+                    self.connected = false;
+                    return Err(e);
+                }
+            };
+            if !self.connected {
+                self.connected = true;
+                if !live_stream::connected(retry_delay, sender) {
+                    return Ok(false);
+                }
+            }
+
+            let mut events: Vec<(i64, &str)> = Vec::new();
+            for event in response["events"].as_array().into_iter().flatten() {
+                let time = event["timestamp"].as_i64().unwrap_or_default();
+                let id = event["eventId"].as_str().unwrap_or_default();
+                if self.seen.insert(id.to_string(), time).is_some() {
+                    continue;
+                }
+                self.newest = self.newest.max(time);
+                events.push((time, event["message"].as_str().unwrap_or_default()));
+            }
+            // Pages interleave the group's streams; show each in time order
+            events.sort_by_key(|(time, _)| *time);
+            let lines: Vec<String> = events
+                .iter()
+                .flat_map(|(_, message)| message.lines())
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim_end().to_string())
+                .collect();
+            if !lines.is_empty() && sender.send(SourceEvent::Lines(lines)).is_err() {
+                return Ok(false);
+            }
+            file_open::wake_ui();
+
+            match response["nextToken"].as_str() {
+                Some(token) if !stop.load(Ordering::Relaxed) => next_token = Some(token.to_string()),
+                _ => return Ok(true),
+            }
+        }
+    }
+}
+
+/// Log group or stream names, fetched in the background for the pickers.
+struct NameList {
+    /// The query the names were listed for
+    key: (String, String, String),
+    names: Vec<String>,
+    error: Option<String>,
+    receiver: Option<mpsc::Receiver<Result<Vec<String>, String>>>,
+}
+
+impl NameList {
+    /// The log groups of the query's region, or when `streams` is set the
+    /// streams of its group, most recently written first.
+    fn fetch(query: &CloudWatchQuery, streams: bool) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let thread_query = query.clone();
+        thread::spawn(move || {
+            let names = Client::new(&thread_query).and_then(|mut client| list(&mut client, &thread_query, streams));
+            sender.send(names).ok();
+            file_open::wake_ui();
+        });
+        Self {
+            key: Self::key_for(query, streams),
+            names: Vec::new(),
+            error: None,
+            receiver: Some(receiver),
+        }
+    }
+
+    /// What a listing depends on; a different key means fetching again.
+    fn key_for(query: &CloudWatchQuery, streams: bool) -> (String, String, String) {
+        let scope = if streams { query.group.trim() } else { "" };
+        (query.profile.trim().to_string(), query.region.trim().to_string(), scope.to_string())
+    }
+
+    fn is_loading(&mut self) -> bool {
+        if let Some(result) = self.receiver.as_ref().and_then(|r| r.try_recv().ok()) {
+            match result {
+                Ok(names) => self.names = names,
+                Err(e) => self.error = Some(e),
+            }
+            self.receiver = None;
+        }
+        self.receiver.is_some()
+    }
+}
+
+/// A ▾ menu of the CloudWatch log groups, or of the group's streams, to
+/// fill the field from; the names are fetched when it opens.
+fn name_picker(ui: &mut egui::Ui, query: &mut CloudWatchQuery, slot: &mut Option<NameList>, streams: bool) {
+    ui.menu_button("▾", |ui| {
+        let key = NameList::key_for(query, streams);
+        let stale = match slot {
+            Some(list) => list.key != key,
+            None => true,
+        };
+        if stale {
+            *slot = Some(NameList::fetch(query, streams));
+        }
+        let Some(list) = slot.as_mut() else { return };
+        if list.is_loading() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading...");
+            });
+            return;
+        }
+        if let Some(ref error) = list.error {
+            ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+            if ui.button("Retry").clicked() {
+                *slot = Some(NameList::fetch(query, streams));
+            }
+            return;
+        }
+        let field = if streams { &mut query.stream } else { &mut query.group };
+        // Typing part of a group's name narrows the list
+        let narrow = !streams && !list.names.iter().any(|name| name == field.trim());
+        let filter = field.trim().to_lowercase();
+        let names: Vec<&String> = list.names.iter().filter(|name| !narrow || name.to_lowercase().contains(&filter)).collect();
+        if names.is_empty() {
+            ui.label(if streams { "No log streams" } else { "No log groups" });
+        }
+        let mut picked = None;
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for name in names {
+                if ui.selectable_label(*field == *name, name.as_str()).clicked() {
+                    picked = Some(name.clone());
+                }
+            }
+        });
+        if let Some(name) = picked {
+            *field = name;
+            if !streams {
+                query.stream.clear();
+            }
+            ui.close_menu();
+        }
+    });
+}
+
+/// Most names listed for a picker.
+const MAX_NAMES: usize = 500;
+
+fn list(client: &mut Client, query: &CloudWatchQuery, streams: bool) -> Result<Vec<String>, String> {
+    let (action, field, name) = if streams {
+        ("DescribeLogStreams", "logStreams", "logStreamName")
+    } else {
+        ("DescribeLogGroups", "logGroups", "logGroupName")
+    };
+    let mut names = Vec::new();
+    let mut next_token: Option<String> = None;
+    while names.len() < MAX_NAMES {
+        let mut body = if streams {
+            if query.group.trim().is_empty() {
+                return Err("Pick a log group first".to_string());
+            }
+            json!({ "logGroupName": query.group.trim(), "orderBy": "LastEventTime", "descending": true })
+        } else {
+            json!({})
+        };
+        if let Some(token) = next_token.take() {
+            body["nextToken"] = json!(token);
+        }
+        let response = client.call(action, &body)?;
+        names.extend(response[field].as_array().into_iter().flatten().filter_map(|item| item[name].as_str()).map(str::to_string));
+        match response["nextToken"].as_str() {
+            Some(token) => next_token = Some(token.to_string()),
+            None => break,
+        }
+    }
+    names.truncate(MAX_NAMES);
+    Ok(names)
+}
@@ -1,5 +1,95 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use egui::Color32;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::glob_watch;
+use crate::log_parser::LogLevel;
+use crate::redaction::RedactionRules;
+use crate::share::ShareConfig;
+
+/// Directory holding Log Rocket's persisted files (profiles, ...).
+pub fn config_dir() -> Option<PathBuf> {
+    CONFIG_DIR_OVERRIDE.get().cloned().or_else(|| dirs::config_dir().map(|dir| dir.join("log-rocket")))
+}
+
+/// Keep every persisted file (settings, formats, plugins, scripts, profiles, ...)
+/// in `dir` for this run, e.g. to run apart from the user's own. Call before
+/// anything is loaded.
+pub fn set_config_dir(dir: PathBuf) {
+    CONFIG_DIR_OVERRIDE.set(dir).ok();
+}
+
+/// Layout of config.json written by this build; bump it and add a step to
+/// `migrate` whenever a change would not read back correctly.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Copy a file that couldn't be parsed to `<name>.bak`, so saving the defaults
+/// over it later doesn't lose the user's settings.
+pub fn back_up_unreadable(path: &Path) -> Option<PathBuf> {
+    let mut name = path.file_name()?.to_os_string();
+    name.push(".bak");
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup).ok().map(|_| backup)
+}
+
+/// Load `name` from the config directory. A missing file yields the defaults,
+/// and so does an unreadable one, once it is backed up.
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    let Some(path) = config_dir().map(|dir| dir.join(name)) else {
+        return T::default();
+    };
+    match read_file(&path, |content| serde_json::from_str(content).map_err(|e| e.to_string())) {
+        Ok(value) => value.unwrap_or_default(),
+        Err(e) => {
+            app_error!("Error loading {}: {}", name, e);
+            // Keep a copy, the next save would replace it
+            back_up_unreadable(&path);
+            T::default()
+        }
+    }
+}
+
+/// Write `value` as `name` in the config directory.
+pub fn save_json<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<(), String> {
+    let path = config_dir().ok_or("No config directory available")?.join(name);
+    write_file(&path, value)
+}
+
+/// Read `path` with `parse`, or None when there is no such file.
+fn read_file<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse(&content).map(Some).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Per-run overrides from the command line or environment, never saved
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static THEME_OVERRIDE: OnceLock<Theme> = OnceLock::new();
+
+/// Use another config file and/or theme for this run. Call before the config is loaded.
+pub fn set_overrides(config_path: Option<PathBuf>, theme: Option<Theme>) {
+    if let Some(path) = config_path {
+        CONFIG_PATH_OVERRIDE.set(path).ok();
+    }
+    if let Some(theme) = theme {
+        THEME_OVERRIDE.set(theme).ok();
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ColorPalette {
@@ -17,13 +107,83 @@ pub struct ColorPalette {
     pub default_bg: Color32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Theme {
     Dark,
     Light,
 }
 
+/// What happens to live lines arriving while the queue of lines waiting to
+/// be shown is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// Drop the oldest waiting lines, noting how many where they were
+    DropOldest,
+    /// Stop reading sources until the queue drains. Syslog messages can't
+    /// wait, so they are dropped regardless
+    PauseSource,
+}
+
+/// Where the view starts once a file has loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartPosition {
+    /// The end, or where the file was last left when not auto-scrolling
+    End,
+    /// The first ERROR entry, where post-mortems usually begin
+    FirstError,
+    /// The first entry at or after `AppConfig::start_time`
+    AfterTime,
+}
+
+/// `text` as milliseconds on the logs' own clock (see `LogEntry::time_ms`):
+/// a date and time, or a time of day on the date of `first_ms`.
+pub fn start_time_ms(text: &str, first_ms: i64) -> Option<i64> {
+    let text = text.trim();
+    let date_times = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"];
+    if let Some(time) = date_times.iter().find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok()) {
+        return Some(time.and_utc().timestamp_millis());
+    }
+    let time = ["%H:%M:%S%.f", "%H:%M"].iter().find_map(|format| chrono::NaiveTime::parse_from_str(text, format).ok())?;
+    let date = chrono::DateTime::from_timestamp_millis(first_ms)?.date_naive();
+    Some(date.and_time(time).and_utc().timestamp_millis())
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            _ => Err(format!("unknown theme '{}' (expected dark or light)", s)),
+        }
+    }
+}
+
 impl ColorPalette {
+    /// Text and background colors for entries of `level`.
+    pub fn for_level(&self, level: &LogLevel) -> (Color32, Color32) {
+        match level {
+            LogLevel::Info => (self.info, self.info_bg),
+            LogLevel::Warn => (self.warn, self.warn_bg),
+            LogLevel::Error => (self.error, self.error_bg),
+            LogLevel::Debug => (self.debug, self.debug_bg),
+            LogLevel::Trace => (self.trace, self.trace_bg),
+            LogLevel::Unknown => (self.default, self.default_bg),
+        }
+    }
+
+    /// Text and background colors of the levels other than Unknown.
+    fn levels_mut(&mut self) -> [(&mut Color32, &mut Color32); 5] {
+        [
+            (&mut self.info, &mut self.info_bg),
+            (&mut self.warn, &mut self.warn_bg),
+            (&mut self.error, &mut self.error_bg),
+            (&mut self.debug, &mut self.debug_bg),
+            (&mut self.trace, &mut self.trace_bg),
+        ]
+    }
+
     pub fn dark() -> Self {
         Self {
             // INFO - keep as is (white text, transparent background)
@@ -75,6 +235,68 @@ impl ColorPalette {
     }
 }
 
+/// A take on the theme's palette, so classes of logs can look different.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteVariant {
+    Default,
+    /// Soft level colors, no row backgrounds
+    Muted,
+    /// No level colors at all
+    Plain,
+}
+
+impl PaletteVariant {
+    pub const ALL: [PaletteVariant; 3] = [PaletteVariant::Default, PaletteVariant::Muted, PaletteVariant::Plain];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteVariant::Default => "Default",
+            PaletteVariant::Muted => "Muted",
+            PaletteVariant::Plain => "Plain",
+        }
+    }
+
+    /// `palette` changed to this variant.
+    pub fn apply(&self, palette: &ColorPalette) -> ColorPalette {
+        let mut palette = palette.clone();
+        match self {
+            PaletteVariant::Default => {}
+            PaletteVariant::Muted => {
+                let text = palette.default;
+                // Levels with a background get its color, toned down towards the plain text
+                for (color, background) in palette.levels_mut() {
+                    if background.a() > 0 {
+                        *color = mix(text, *background, 0.6);
+                        *background = Color32::TRANSPARENT;
+                    }
+                }
+            }
+            PaletteVariant::Plain => {
+                let (text, plain_background) = (palette.default, palette.default_bg);
+                for (color, background) in palette.levels_mut() {
+                    *color = text;
+                    *background = plain_background;
+                }
+            }
+        }
+        palette
+    }
+}
+
+/// `from` moved `amount` of the way to the opaque version of `to`.
+fn mix(from: Color32, to: Color32, amount: f32) -> Color32 {
+    let [r, g, b, _] = to.to_srgba_unmultiplied();
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    Color32::from_rgb(channel(from.r(), r), channel(from.g(), g), channel(from.b(), b))
+}
+
+/// Files whose name matches `pattern` (e.g. `access*.log`) are shown with `variant`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteRule {
+    pub pattern: String,
+    pub variant: PaletteVariant,
+}
+
 impl Default for ColorPalette {
     fn default() -> Self {
         Self::dark()
@@ -82,24 +304,210 @@ impl Default for ColorPalette {
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    /// Layout version of the saved file, see `CONFIG_VERSION`
+    pub version: u32,
+    #[serde(skip)] // Derived from the theme
     pub color_palette: ColorPalette,
     pub tail_log: bool,
     pub scroll_to_end: bool,
+    /// Where a loaded file is first shown
+    pub start_position: StartPosition,
+    /// For `StartPosition::AfterTime`: `2024-01-15 10:30` or a time of day like `10:30:00`
+    pub start_time: String,
+    /// When a tailed file is rotated, read the lines written to it after the last poll
+    pub backfill_rotated: bool,
+    /// Check tailed files on an interval instead of waiting for change events.
+    /// Files on network filesystems are polled regardless where they can be detected
+    pub always_poll: bool,
+    /// How often polled files are checked (milliseconds)
+    pub poll_interval_ms: u64,
+    /// Most times a second new live lines are added to the view (0 = every frame)
+    pub tail_refresh_hz: u32,
+    /// Most live lines added per refresh; the rest wait for the next (0 = no limit)
+    pub max_lines_per_refresh: usize,
+    /// Most live lines waiting to be shown; beyond it `overflow_policy` applies (0 = no limit)
+    pub max_queued_lines: usize,
+    pub overflow_policy: OverflowPolicy,
     pub theme: Theme,
+    /// Theme forced for this run (--theme / LOGROCKET_THEME)
+    #[serde(skip)]
+    pub theme_override: Option<Theme>,
     pub font_size: f32,
+    /// Height of the log view's rows, relative to the font's own line height
+    pub line_spacing: f32,
+    /// Give every other entry a faint background
+    pub zebra_stripes: bool,
+    /// Draw vertical lines at `column_guides` while lines don't wrap
+    pub show_column_guides: bool,
+    /// Character columns marked by guides, e.g. 120
+    pub column_guides: Vec<usize>,
+    /// Number the columns above the log view while lines don't wrap
+    pub show_column_ruler: bool,
+    /// Files larger than this (bytes) are opened tail-only
+    pub large_file_threshold: u64,
+    /// How much of the end of a large file is loaded (bytes)
+    pub tail_window_size: u64,
+    /// Most entries kept in memory while tailing; the oldest are dropped beyond it (0 = no limit)
+    pub max_entries: usize,
+    /// Lines longer than this many characters are shown cut short, as laying
+    /// them out would stall the view (0 = no limit)
+    pub max_line_length: usize,
+    /// Show lines that redraw themselves with `\r` (progress bars) as they ended up
+    pub collapse_progress_lines: bool,
+    /// Suggest another format once more than this share of entries (percent)
+    /// has no level, as the format likely doesn't fit (0 = never)
+    pub unknown_level_warning: u8,
+    /// View files above the large-file threshold in full from disk instead of tail-only
+    pub low_memory_mode: bool,
+    /// Tint the rows of each merged source with its own color
+    pub tint_merged_sources: bool,
+    /// Start the rows of merged views with a chip naming their source
+    pub label_merged_sources: bool,
+    /// Palettes picked by file name as files are opened; the first match wins
+    pub palette_rules: Vec<PaletteRule>,
+    /// Masking of sensitive data in the view, copies and exports
+    pub redaction: RedactionRules,
+    /// Where Share Selection uploads
+    pub share: ShareConfig,
+    /// Ask GitHub for a newer release at startup
+    pub check_for_updates: bool,
+    /// A release the user chose not to be told about again
+    pub skipped_update: String,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             color_palette: ColorPalette::default(),
             tail_log: true,
             scroll_to_end: true,
+            start_position: StartPosition::End,
+            start_time: String::new(),
+            backfill_rotated: true,
+            always_poll: false,
+            poll_interval_ms: 1000,
+            tail_refresh_hz: 10,
+            max_lines_per_refresh: 10_000,
+            max_queued_lines: 200_000,
+            overflow_policy: OverflowPolicy::DropOldest,
             theme: Theme::Dark,
+            theme_override: None,
             font_size: 14.0,
+            line_spacing: 1.0,
+            zebra_stripes: false,
+            show_column_guides: false,
+            column_guides: vec![120, 200],
+            show_column_ruler: false,
+            large_file_threshold: 10_000_000,
+            tail_window_size: 2_000_000,
+            max_entries: 500_000,
+            max_line_length: 10_000,
+            collapse_progress_lines: true,
+            unknown_level_warning: 50,
+            low_memory_mode: false,
+            tint_merged_sources: true,
+            label_merged_sources: true,
+            palette_rules: Vec::new(),
+            redaction: RedactionRules::default(),
+            share: ShareConfig::default(),
+            check_for_updates: false,
+            skipped_update: String::new(),
         }
     }
 }
 
+impl AppConfig {
+    pub fn config_path() -> Option<PathBuf> {
+        CONFIG_PATH_OVERRIDE.get().cloned().or_else(|| config_dir().map(|dir| dir.join("config.json")))
+    }
+
+    /// The theme in use: the per-run override if any, else the saved one.
+    pub fn effective_theme(&self) -> &Theme {
+        self.theme_override.as_ref().unwrap_or(&self.theme)
+    }
+
+    /// Parse a saved configuration, upgrading it from older versions.
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        let settings = value.as_object_mut().ok_or("expected a JSON object")?;
+        // Files from before versioning have no version field
+        let version = settings.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        if version > CONFIG_VERSION {
+            return Err(format!(
+                "written by a newer Log Rocket (config version {}, this one reads up to {})",
+                version, CONFIG_VERSION
+            ));
+        }
+        // Each step upgrades the settings from version `step` to `step + 1`
+        for step in version..CONFIG_VERSION {
+            match step {
+                // Unversioned files already have the version 1 layout
+                0 => {}
+                _ => unreachable!("no config migration from version {}", step),
+            }
+        }
+        settings.insert("version".to_string(), CONFIG_VERSION.into());
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    /// Read the saved configuration. A missing file yields the defaults.
+    pub fn try_load() -> Result<Self, String> {
+        let config = match Self::config_path() {
+            Some(path) => read_file(&path, Self::parse)?.unwrap_or_default(),
+            None => AppConfig::default(),
+        };
+        Ok(config.with_overrides())
+    }
+
+    /// Apply the per-run theme override and derive the palette.
+    fn with_overrides(mut self) -> Self {
+        self.theme_override = THEME_OVERRIDE.get().cloned();
+        self.color_palette = self.theme_palette();
+        self
+    }
+
+    /// The theme's palette, before any palette rule.
+    pub fn theme_palette(&self) -> ColorPalette {
+        match self.effective_theme() {
+            Theme::Dark => ColorPalette::dark(),
+            Theme::Light => ColorPalette::light(),
+        }
+    }
+
+    /// The palette for viewing `path`, by the first palette rule its file name matches.
+    pub fn palette_for(&self, path: Option<&Path>) -> ColorPalette {
+        let name = path.and_then(|path| path.file_name()).map(|name| name.to_string_lossy());
+        let variant = name
+            .and_then(|name| {
+                self.palette_rules
+                    .iter()
+                    .find(|rule| glob_watch::name_regex(&rule.pattern).is_ok_and(|regex| regex.is_match(&name)))
+            })
+            .map_or(PaletteVariant::Default, |rule| rule.variant);
+        variant.apply(&self.theme_palette())
+    }
+
+    /// Load the saved configuration, falling back to the defaults. An unreadable
+    /// file is backed up first and the problem returned for the user to see.
+    pub fn load() -> (Self, Option<String>) {
+        match Self::try_load() {
+            Ok(config) => (config, None),
+            Err(mut e) => {
+                app_error!("Error loading config: {}", e);
+                if let Some(backup) = Self::config_path().as_deref().and_then(back_up_unreadable) {
+                    e = format!("{}. The file was copied to {}; defaults are in use.", e, backup.display());
+                }
+                (Self::default().with_overrides(), Some(e))
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::config_path().ok_or("No config directory available")?;
+        write_file(&path, self)
+    }
+}
@@ -0,0 +1,53 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+
+/// Named capture groups a custom format regex can use to fill entry fields.
+pub const FIELD_GROUPS: [&str; 5] = ["timestamp", "level", "thread", "class", "message"];
+
+/// A user-defined line format: a regex with named groups plus the chrono
+/// format of the timestamp group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFormat {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub timestamp_format: String,
+    /// Regex for lines that begin an entry; empty when every line matching
+    /// `pattern` does and the others continue it
+    #[serde(default)]
+    pub entry_start: String,
+}
+
+impl CustomFormat {
+    pub fn compile(&self) -> Result<Regex, String> {
+        Regex::new(&self.pattern).map_err(|e| e.to_string())
+    }
+
+    pub fn compile_entry_start(&self) -> Result<Option<Regex>, String> {
+        if self.entry_start.trim().is_empty() {
+            return Ok(None);
+        }
+        Regex::new(&self.entry_start).map(Some).map_err(|e| e.to_string())
+    }
+
+    /// Check a captured timestamp against `timestamp_format`.
+    pub fn parse_timestamp(&self, value: &str) -> Result<chrono::NaiveDateTime, String> {
+        chrono::NaiveDateTime::parse_from_str(value, &self.timestamp_format).map_err(|e| e.to_string())
+    }
+}
+
+pub fn formats_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("formats.json"))
+}
+
+/// Load saved custom formats. A missing or unreadable file yields an empty list.
+pub fn load_custom_formats() -> Vec<CustomFormat> {
+    config::load_json("formats.json")
+}
+
+pub fn save_custom_formats(formats: &[CustomFormat]) -> Result<(), String> {
+    config::save_json("formats.json", formats)
+}
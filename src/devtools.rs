@@ -0,0 +1,188 @@
+use serde_json::{json, Map, Value};
+
+/// Browser debugging artifacts read as logs: HAR files, and console messages
+/// saved as JSON from Chrome DevTools (`Log.entryAdded`,
+/// `Runtime.consoleAPICalled` and `Runtime.exceptionThrown` events, bare or
+/// as their params, and Puppeteer/Playwright-style `{type, text, location}`
+/// messages). Returns one JSON line per request or message, with the
+/// parser's `timestamp`, `level`, `logger` and `message`, or None when the
+/// content is neither.
+pub fn convert(content: &str) -> Option<String> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    // JSON lines fail here on the second line, before anything else is read
+    let document: Value = serde_json::from_str(trimmed).ok()?;
+    let lines: Vec<String> = if let Some(entries) = document["log"]["entries"].as_array() {
+        entries.iter().map(request_line).collect()
+    } else {
+        let messages = match &document {
+            Value::Array(messages) => messages,
+            _ => document["messages"].as_array()?,
+        };
+        if messages.is_empty() || !messages.iter().all(is_console_message) {
+            return None;
+        }
+        messages.iter().map(console_line).collect()
+    };
+    Some(lines.join("\n"))
+}
+
+/// Milliseconds since the epoch, or RFC 3339, as local time with millis.
+fn timestamp(value: &Value) -> Option<String> {
+    let time = match value {
+        Value::Number(millis) => chrono::DateTime::from_timestamp_millis(millis.as_f64()? as i64)?,
+        Value::String(text) => chrono::DateTime::parse_from_rfc3339(text).ok()?.to_utc(),
+        _ => return None,
+    };
+    Some(time.with_timezone(&chrono::Local).to_rfc3339_opts(chrono::SecondsFormat::Millis, false))
+}
+
+/// A HAR request as `METHOD url status statusText (time ms)`. Requests that
+/// failed or got a 5xx are errors and 4xx are warnings.
+fn request_line(entry: &Value) -> String {
+    let request = &entry["request"];
+    let response = &entry["response"];
+    let method = request["method"].as_str().unwrap_or("GET");
+    let url = request["url"].as_str().unwrap_or_default();
+    let status = response["status"].as_i64().unwrap_or(0);
+    // Chrome notes why a request never got a response, e.g. net::ERR_BLOCKED_BY_CLIENT
+    let error = response["_error"].as_str().filter(|error| !error.is_empty());
+    let level = match status {
+        500.. => "ERROR",
+        400.. => "WARN",
+        0 => "ERROR",
+        _ => "INFO",
+    };
+
+    let mut message = format!("{} {}", method, url);
+    match error {
+        Some(error) if status == 0 => message.push_str(&format!(" failed: {}", error)),
+        _ => {
+            message.push_str(&format!(" {}", status));
+            if let Some(text) = response["statusText"].as_str().filter(|text| !text.is_empty()) {
+                message.push_str(&format!(" {}", text));
+            }
+        }
+    }
+    let duration = entry["time"].as_f64().filter(|time| *time >= 0.0);
+    if let Some(duration) = duration {
+        message.push_str(&format!(" ({:.0} ms)", duration));
+    }
+
+    let mut fields = Map::new();
+    if let Some(time) = timestamp(&entry["startedDateTime"]) {
+        fields.insert("timestamp".to_string(), json!(time));
+    }
+    fields.insert("level".to_string(), json!(level));
+    let host = url.split("://").nth(1).and_then(|rest| rest.split(['/', '?', '#']).next()).unwrap_or(url);
+    fields.insert("logger".to_string(), json!(host));
+    fields.insert("message".to_string(), json!(message));
+    fields.insert("http.method".to_string(), json!(method));
+    fields.insert("http.url".to_string(), json!(url));
+    fields.insert("http.status".to_string(), json!(status));
+    if let Some(duration) = duration {
+        fields.insert("duration_ms".to_string(), json!(duration.round() as i64));
+    }
+    let optional = [
+        ("resource_type", &entry["_resourceType"]),
+        ("mime_type", &response["content"]["mimeType"]),
+        ("size", &response["content"]["size"]),
+        ("server_ip", &entry["serverIPAddress"]),
+        ("error", &response["_error"]),
+    ];
+    for (name, value) in optional {
+        if !value.is_null() && value.as_str() != Some("") {
+            fields.insert(name.to_string(), value.clone());
+        }
+    }
+    Value::Object(fields).to_string()
+}
+
+/// The message itself, unwrapping `{method, params}` protocol events and
+/// `Log.entryAdded`'s `{entry}`.
+fn console_message(value: &Value) -> &Value {
+    let value = if value["params"].is_object() { &value["params"] } else { value };
+    if value["entry"].is_object() {
+        &value["entry"]
+    } else {
+        value
+    }
+}
+
+fn is_console_message(value: &Value) -> bool {
+    let message = console_message(value);
+    let has_kind = message["level"].is_string() || message["type"].is_string() || message["exceptionDetails"].is_object();
+    let has_text = message["text"].is_string() || message["args"].is_array() || message["exceptionDetails"].is_object();
+    has_kind && has_text
+}
+
+/// How DevTools shows a console argument: strings as they are, other values
+/// by their description.
+fn argument_text(argument: &Value) -> String {
+    match (&argument["value"], argument["description"].as_str()) {
+        (Value::String(text), _) => text.clone(),
+        (_, Some(description)) => description.to_string(),
+        (Value::Null, None) => argument["type"].as_str().unwrap_or("undefined").to_string(),
+        (value, None) => value.to_string(),
+    }
+}
+
+/// A console message with DevTools' severity as level and its source
+/// location as logger.
+fn console_line(value: &Value) -> String {
+    let message = console_message(value);
+    let exception = &message["exceptionDetails"];
+    let kind = message["level"].as_str().or(message["type"].as_str()).unwrap_or("error");
+    let level = match kind {
+        "verbose" | "debug" => "DEBUG",
+        "trace" => "TRACE",
+        "warning" | "warn" => "WARN",
+        "error" | "assert" => "ERROR",
+        _ if exception.is_object() => "ERROR",
+        _ => "INFO",
+    };
+
+    let text = if exception.is_object() {
+        // The thrown value's description holds the message and stack
+        exception["exception"]["description"].as_str().or(exception["text"].as_str()).unwrap_or_default().to_string()
+    } else if let Some(text) = message["text"].as_str() {
+        text.to_string()
+    } else {
+        let arguments = message["args"].as_array().map(Vec::as_slice).unwrap_or_default();
+        arguments.iter().map(argument_text).collect::<Vec<_>>().join(" ")
+    };
+
+    // Where it was logged from: the message's own location, else the top stack frame
+    let frame = &message["stackTrace"]["callFrames"][0];
+    let location = [&message["location"], exception, frame, message]
+        .into_iter()
+        .find_map(|place| Some((place["url"].as_str().filter(|url| !url.is_empty())?, place["lineNumber"].as_i64())));
+    let source = match location {
+        Some((url, line)) => {
+            let file = url.split(['?', '#']).next().unwrap_or(url).rsplit('/').next().unwrap_or(url);
+            match line {
+                // Protocol line numbers start at 0
+                Some(line) => format!("{}:{}", file, line + 1),
+                None => file.to_string(),
+            }
+        }
+        None => message["source"].as_str().unwrap_or("console").to_string(),
+    };
+
+    let mut fields = Map::new();
+    if let Some(time) = timestamp(&message["timestamp"]) {
+        fields.insert("timestamp".to_string(), json!(time));
+    }
+    fields.insert("level".to_string(), json!(level));
+    fields.insert("logger".to_string(), json!(source));
+    fields.insert("message".to_string(), json!(text));
+    if let Some((url, _)) = location {
+        fields.insert("url".to_string(), json!(url));
+    }
+    if let Some(kind) = message["source"].as_str() {
+        fields.insert("source".to_string(), json!(kind));
+    }
+    Value::Object(fields).to_string()
+}
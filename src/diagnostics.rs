@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::{self, AppConfig};
+
+/// Lines kept in the app log; the oldest are dropped beyond it.
+const MAX_LINES: usize = 1_000;
+/// App log lines included at the end of a crash report.
+const CRASH_LOG_LINES: usize = 50;
+
+/// How serious an app log line is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warn,
+    Error,
+}
+
+/// Something that went wrong inside the viewer itself.
+#[derive(Debug, Clone)]
+pub struct AppLogLine {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl AppLogLine {
+    /// `2024-01-15 10:00:00.123 ERROR message`, which the log parser reads back.
+    pub fn text(&self) -> String {
+        let severity = match self.severity {
+            Severity::Warn => "WARN",
+            Severity::Error => "ERROR",
+        };
+        format!("{} {} {}", self.time.format("%Y-%m-%d %H:%M:%S%.3f"), severity, self.message)
+    }
+}
+
+static APP_LOG: Mutex<VecDeque<AppLogLine>> = Mutex::new(VecDeque::new());
+// What was open, for crash reports
+static OPEN_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Print an error to stderr and keep it in the app log.
+#[macro_export]
+macro_rules! app_error {
+    ($($arg:tt)*) => {
+        $crate::diagnostics::record($crate::diagnostics::Severity::Error, format!($($arg)*))
+    };
+}
+
+/// Print a warning to stderr and keep it in the app log.
+#[macro_export]
+macro_rules! app_warn {
+    ($($arg:tt)*) => {
+        $crate::diagnostics::record($crate::diagnostics::Severity::Warn, format!($($arg)*))
+    };
+}
+
+pub fn record(severity: Severity, message: String) {
+    eprintln!("{}", message);
+    let mut log = APP_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.len() >= MAX_LINES {
+        log.pop_front();
+    }
+    log.push_back(AppLogLine {
+        time: chrono::Local::now(),
+        severity,
+        message,
+    });
+}
+
+/// The app log, oldest first.
+pub fn app_log() -> Vec<AppLogLine> {
+    APP_LOG.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+}
+
+pub fn clear_app_log() {
+    APP_LOG.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Remember the file being viewed, named in crash reports.
+pub fn set_open_file(path: Option<&Path>) {
+    let mut open_file = OPEN_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    if open_file.as_deref() != path {
+        *open_file = path.map(Path::to_path_buf);
+    }
+}
+
+/// Where crash reports are written.
+pub fn crash_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("crashes"))
+}
+
+/// Write a crash report to `crash_dir()` whenever any thread panics, then
+/// print the panic as usual.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_crash_report(info) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_crash_report(info: &PanicHookInfo) -> Result<PathBuf, String> {
+    let dir = crash_dir().ok_or("No config directory available")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let now = chrono::Local::now();
+    let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+    fs::write(&path, crash_report(info, now)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn crash_report(info: &PanicHookInfo, time: chrono::DateTime<chrono::Local>) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "Log Rocket {} crashed at {}", env!("CARGO_PKG_VERSION"), time.format("%Y-%m-%d %H:%M:%S"));
+    let _ = writeln!(report, "OS: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    let _ = writeln!(report, "Thread: {}", std::thread::current().name().unwrap_or("unnamed"));
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let _ = writeln!(report, "Panic: {}", message);
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "Location: {}:{}:{}", location.file(), location.line(), location.column());
+    }
+
+    // The lock may be held by the panicking thread itself
+    let open_file = OPEN_FILE.try_lock().ok().and_then(|path| path.clone());
+    let _ = writeln!(report, "\n## Open file");
+    match open_file {
+        Some(path) => match fs::metadata(&path) {
+            Ok(metadata) => {
+                let modified = metadata.modified().ok().map(chrono::DateTime::<chrono::Local>::from);
+                let _ = writeln!(report, "{} ({} bytes, modified {})", path.display(), metadata.len(),
+                    modified.map_or("unknown".to_string(), |time| time.format("%Y-%m-%d %H:%M:%S").to_string()));
+            }
+            Err(e) => {
+                let _ = writeln!(report, "{} ({})", path.display(), e);
+            }
+        },
+        None => {
+            let _ = writeln!(report, "None");
+        }
+    }
+
+    let _ = writeln!(report, "\n## Config");
+    match AppConfig::try_load() {
+        Ok(config) => {
+            let _ = writeln!(report, "tail_log: {}, theme: {:?}, font_size: {}", config.tail_log, config.effective_theme(), config.font_size);
+            let _ = writeln!(report, "large_file_threshold: {}, tail_window_size: {}, max_entries: {}, low_memory_mode: {}",
+                config.large_file_threshold, config.tail_window_size, config.max_entries, config.low_memory_mode);
+            let _ = writeln!(report, "tail_refresh_hz: {}, max_lines_per_refresh: {}, max_queued_lines: {}, overflow_policy: {:?}",
+                config.tail_refresh_hz, config.max_lines_per_refresh, config.max_queued_lines, config.overflow_policy);
+            let _ = writeln!(report, "always_poll: {}, poll_interval_ms: {}, redaction: {}",
+                config.always_poll, config.poll_interval_ms, config.redaction.enabled);
+        }
+        Err(e) => {
+            let _ = writeln!(report, "{}", e);
+        }
+    }
+
+    let _ = writeln!(report, "\n## Backtrace\n{}", std::backtrace::Backtrace::force_capture());
+
+    if let Ok(log) = APP_LOG.try_lock() {
+        let _ = writeln!(report, "\n## Recent app log");
+        for line in log.iter().skip(log.len().saturating_sub(CRASH_LOG_LINES)) {
+            let _ = writeln!(report, "{}", line.text());
+        }
+    }
+    report
+}
+
+/// The newest crash report if it hasn't been shown yet, marking it shown.
+pub fn take_unseen_crash_report() -> Option<(PathBuf, String)> {
+    let dir = crash_dir()?;
+    let newest = fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            name.starts_with("crash-") && name.ends_with(".txt")
+        })
+        // The names sort by time
+        .max()?;
+    let name = newest.file_name()?.to_string_lossy().into_owned();
+    let seen = dir.join("last_shown");
+    if fs::read_to_string(&seen).is_ok_and(|shown| shown.trim() == name) {
+        return None;
+    }
+    if let Err(e) = fs::write(&seen, &name) {
+        app_error!("Error saving shown crash report: {}", e);
+    }
+    let report = fs::read_to_string(&newest).ok()?;
+    Some((newest, report))
+}
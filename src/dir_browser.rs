@@ -0,0 +1,161 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::SystemTime;
+
+use crate::file_open;
+
+#[derive(Debug, Clone)]
+pub struct DirFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl DirFile {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn modified_label(&self) -> String {
+        self.modified
+            .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Lists the files of a directory (newest first) and keeps the list fresh as
+/// files are created, rotated or removed.
+pub struct DirectoryBrowser {
+    dir: Option<PathBuf>,
+    files: Vec<DirFile>,
+    watcher: Option<RecommendedWatcher>,
+    receiver: Option<mpsc::Receiver<notify::Result<Event>>>,
+}
+
+impl DirectoryBrowser {
+    pub fn new() -> Self {
+        Self {
+            dir: None,
+            files: Vec::new(),
+            watcher: None,
+            receiver: None,
+        }
+    }
+
+    pub fn open(&mut self, dir: PathBuf) -> Result<(), String> {
+        if !dir.is_dir() {
+            return Err(format!("{} is not a directory", dir.display()));
+        }
+        self.close();
+
+        let (tx, rx) = mpsc::channel();
+        let handler = move |event| {
+            if tx.send(event).is_ok() {
+                file_open::wake_ui();
+            }
+        };
+        match notify::recommended_watcher(handler) {
+            Ok(mut watcher) => {
+                if watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok() {
+                    self.watcher = Some(watcher);
+                    self.receiver = Some(rx);
+                }
+            }
+            Err(e) => app_error!("Error watching directory: {}", e),
+        }
+
+        self.dir = Some(dir);
+        self.rescan();
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.dir = None;
+        self.files.clear();
+        self.watcher = None;
+        self.receiver = None;
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn files(&self) -> &[DirFile] {
+        &self.files
+    }
+
+    /// Drain watcher events and rescan if anything in the directory changed.
+    /// Returns true when the list was refreshed.
+    pub fn refresh_if_changed(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        let mut changed = false;
+        while let Ok(event) = receiver.try_recv() {
+            if let Ok(event) = event {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.rescan();
+        }
+        changed
+    }
+
+    pub fn rescan(&mut self) {
+        let Some(ref dir) = self.dir else {
+            return;
+        };
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            self.files.clear();
+            return;
+        };
+
+        self.files = read_dir
+            .flatten()
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| {
+                let metadata = e.metadata().ok()?;
+                if !metadata.is_file() {
+                    return None;
+                }
+                Some(DirFile {
+                    path: e.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                })
+            })
+            .collect();
+
+        // Newest first: the file currently being written to ends up on top
+        self.files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+    }
+}
+
+impl Default for DirectoryBrowser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,656 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use egui::{FontId, RichText, ScrollArea, Sense};
+
+use crate::config::ColorPalette;
+use crate::file_open;
+use crate::log_parser::{LogLevel, LogParser};
+use crate::redaction::Redactor;
+
+/// Entries sent to the UI per message while indexing.
+const CHUNK_SIZE: usize = 50_000;
+/// Entry texts kept in memory for the rows on screen and around them.
+const CACHE_CAPACITY: usize = 4_096;
+/// Longer entries are cut when read from disk.
+const MAX_ENTRY_LEN: u64 = 1_000_000;
+/// Bytes read at a time from the part of the file the index hasn't reached.
+const REGION_LEN: u64 = 1 << 20;
+/// Bytes read for the preview shown while dragging the scrollbar.
+const PREVIEW_LEN: u64 = 8 * 1024;
+
+/// Where an entry starts, as found by the indexing thread.
+struct IndexedEntry {
+    offset: u64,
+    line_number: usize,
+    level: LogLevel,
+    time_ms: Option<i64>,
+}
+
+/// An entry read from ahead of the index, whose line number isn't known yet.
+struct RegionEntry {
+    offset: u64,
+    level: LogLevel,
+    text: String,
+}
+
+/// Entries read from a stretch of the file the index hasn't reached, shown
+/// until it does.
+struct Region {
+    /// Byte range covered, from the first entry's start
+    start: u64,
+    end: u64,
+    entries: Vec<RegionEntry>,
+    /// Indices of the entries whose level is shown
+    filtered: Vec<usize>,
+}
+
+/// A region being read on a background thread.
+struct RegionRead {
+    /// Where the scrollbar was released
+    target: u64,
+    /// Reading on from the end of the shown region rather than replacing it
+    append: bool,
+    receiver: mpsc::Receiver<Result<Region, String>>,
+}
+
+/// Recently read entry texts, dropping the least recently used beyond capacity.
+struct TextCache {
+    texts: HashMap<usize, (String, u64)>,
+    clock: u64,
+}
+
+impl TextCache {
+    fn get(&mut self, idx: usize) -> Option<&str> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.texts.get_mut(&idx).map(|(text, used)| {
+            *used = clock;
+            text.as_str()
+        })
+    }
+
+    fn insert(&mut self, idx: usize, text: String) {
+        if self.texts.len() >= CACHE_CAPACITY {
+            if let Some(oldest) = self.texts.iter().min_by_key(|(_, (_, used))| *used).map(|(idx, _)| *idx) {
+                self.texts.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.texts.insert(idx, (text, self.clock));
+    }
+}
+
+/// A log viewed straight from disk. Only where each entry starts and its
+/// level are kept in memory; the text of the rows on screen is read on demand.
+pub struct DiskLog {
+    path: PathBuf,
+    parser: LogParser,
+    file: File,
+    size: u64,
+    offsets: Vec<u64>,
+    line_numbers: Vec<usize>,
+    levels: Vec<LogLevel>,
+    /// Earliest and latest entry time indexed so far
+    time_range: Option<(i64, i64)>,
+    /// New entries from the indexing thread, until it is done
+    receiver: Option<mpsc::Receiver<Vec<IndexedEntry>>>,
+    /// Bytes indexed so far
+    indexed: Arc<AtomicU64>,
+    cache: TextCache,
+    /// Indices of the entries whose level is shown
+    filtered: Vec<usize>,
+    filter_levels: HashSet<LogLevel>,
+    /// Row to scroll to on the next frame
+    scroll_to: Option<usize>,
+    /// Byte range of the rows on screen, for the scrollbar
+    visible: (u64, u64),
+    /// Shown instead of the indexed entries after seeking past the index
+    region: Option<Region>,
+    region_read: Option<RegionRead>,
+    /// The lines at a byte offset, shown while dragging and loading
+    preview: Option<(u64, Vec<String>)>,
+}
+
+impl DiskLog {
+    /// Open `path` and start indexing it on a background thread.
+    pub fn open(path: &Path, parser: LogParser) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let size = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?.len();
+        let (sender, receiver) = mpsc::channel();
+        let indexed = Arc::new(AtomicU64::new(0));
+        let thread_indexed = indexed.clone();
+        let thread_path = path.to_path_buf();
+        let thread_parser = parser.clone();
+        thread::spawn(move || {
+            if let Err(e) = index_file(&thread_path, &thread_parser, &sender, &thread_indexed) {
+                app_error!("Error indexing {}: {}", thread_path.display(), e);
+            }
+            drop(sender);
+            file_open::wake_ui();
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            parser,
+            file,
+            size,
+            offsets: Vec::new(),
+            line_numbers: Vec::new(),
+            levels: Vec::new(),
+            time_range: None,
+            receiver: Some(receiver),
+            indexed,
+            cache: TextCache { texts: HashMap::new(), clock: 0 },
+            filtered: Vec::new(),
+            filter_levels: HashSet::new(),
+            scroll_to: None,
+            visible: (0, 0),
+            region: None,
+            region_read: None,
+            preview: None,
+        })
+    }
+
+    /// Take newly indexed entries and follow changes to the shown levels.
+    pub fn update(&mut self, enabled_levels: &HashSet<LogLevel>) {
+        if *enabled_levels != self.filter_levels {
+            self.filter_levels = enabled_levels.clone();
+            self.filtered = (0..self.offsets.len()).filter(|&i| enabled_levels.contains(&self.levels[i])).collect();
+            if let Some(ref mut region) = self.region {
+                region.filtered = filter_region(&region.entries, enabled_levels);
+            }
+        }
+        self.poll_region();
+        // Once the index reaches the region, its entries can be shown with line numbers
+        let caught_up = |end: u64| self.offsets.last().is_some_and(|&last| last >= end) || !self.is_indexing();
+        if self.region.as_ref().is_some_and(|region| caught_up(region.end))
+            || self.region_read.as_ref().is_some_and(|read| !read.append && caught_up(read.target))
+        {
+            let offset = match self.region {
+                Some(_) => self.visible.0,
+                None => self.region_read.as_ref().map_or(0, |read| read.target),
+            };
+            self.region = None;
+            self.region_read = None;
+            self.goto_offset(offset);
+        }
+        let Some(ref receiver) = self.receiver else {
+            return;
+        };
+        let mut finished = false;
+        let mut ready = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(chunk) => ready.extend(chunk),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+        if finished {
+            self.receiver = None;
+        }
+        if ready.is_empty() && !finished {
+            return;
+        }
+        // The last entry may have been read before all of its lines were indexed
+        if let Some(last) = self.offsets.len().checked_sub(1) {
+            self.cache.texts.remove(&last);
+        }
+        for entry in ready {
+            if self.filter_levels.contains(&entry.level) {
+                self.filtered.push(self.offsets.len());
+            }
+            self.offsets.push(entry.offset);
+            self.line_numbers.push(entry.line_number);
+            self.levels.push(entry.level);
+            if let Some(time) = entry.time_ms {
+                self.time_range = Some(self.time_range.map_or((time, time), |(min, max)| (min.min(time), max.max(time))));
+            }
+        }
+        if finished {
+            self.offsets.shrink_to_fit();
+            self.line_numbers.shrink_to_fit();
+            self.levels.shrink_to_fit();
+        }
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Share of the file indexed so far, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.size == 0 {
+            1.0
+        } else {
+            self.indexed.load(Ordering::Relaxed) as f32 / self.size as f32
+        }
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn shown_count(&self) -> usize {
+        self.filtered.len()
+    }
+
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.time_range
+    }
+
+    /// Scroll to the shown entry containing `line_number`, or the next one after it.
+    pub fn goto_line(&mut self, line_number: usize) {
+        self.region = None;
+        self.region_read = None;
+        let entry = self.line_numbers.partition_point(|&n| n <= line_number).saturating_sub(1);
+        self.scroll_to = Some(self.filtered.partition_point(|&i| i < entry));
+    }
+
+    /// Scroll to the shown indexed entry containing byte `offset`, or the next one after it.
+    fn goto_offset(&mut self, offset: u64) {
+        let entry = self.offsets.partition_point(|&start| start <= offset).saturating_sub(1);
+        self.scroll_to = Some(self.filtered.partition_point(|&i| i < entry));
+    }
+
+    /// Show the file from byte `offset`: straight away when it is indexed,
+    /// else by reading the region around it in the background.
+    fn seek(&mut self, offset: u64) {
+        if self.offsets.last().is_some_and(|&last| offset <= last) || !self.is_indexing() {
+            self.region = None;
+            self.region_read = None;
+            self.goto_offset(offset);
+            return;
+        }
+        // Near the end, read the file's last stretch so there is something to show
+        let start = offset.min(self.size.saturating_sub(REGION_LEN));
+        self.read_region(offset, start, false);
+    }
+
+    fn read_region(&mut self, target: u64, start: u64, append: bool) {
+        let (sender, receiver) = mpsc::channel();
+        let (path, parser) = (self.path.clone(), self.parser.clone());
+        thread::spawn(move || {
+            sender.send(read_region(&path, &parser, start)).ok();
+            file_open::wake_ui();
+        });
+        self.region_read = Some(RegionRead { target, append, receiver });
+    }
+
+    /// Take a region read once it is done.
+    fn poll_region(&mut self) {
+        let Some(ref read) = self.region_read else {
+            return;
+        };
+        let result = match read.receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err("The read stopped unexpectedly".to_string()),
+        };
+        let (target, append) = (read.target, read.append);
+        self.region_read = None;
+        let read = match result {
+            Ok(region) => region,
+            Err(e) => {
+                app_error!("Error reading {}: {}", self.path.display(), e);
+                return;
+            }
+        };
+        match self.region {
+            Some(ref mut region) if append => {
+                region.entries.extend(read.entries);
+                region.end = read.end;
+                region.filtered = filter_region(&region.entries, &self.filter_levels);
+            }
+            _ => {
+                let filtered = filter_region(&read.entries, &self.filter_levels);
+                self.scroll_to = Some(filtered.partition_point(|&i| read.entries[i].offset < target).saturating_sub(1));
+                self.region = Some(Region { filtered, ..read });
+            }
+        }
+    }
+
+    /// The lines from the first one starting at or after byte `offset`.
+    fn preview(&mut self, offset: u64) -> &[String] {
+        if self.preview.as_ref().is_none_or(|(at, _)| *at != offset) {
+            let lines = self.read_preview(offset).unwrap_or_else(|e| vec![format!("<{}>", e)]);
+            self.preview = Some((offset, lines));
+        }
+        self.preview.as_ref().map(|(_, lines)| lines.as_slice()).unwrap_or_default()
+    }
+
+    fn read_preview(&mut self, offset: u64) -> Result<Vec<String>, String> {
+        self.file.seek(SeekFrom::Start(offset)).map_err(|e| format!("Failed to seek: {}", e))?;
+        let mut buffer = Vec::new();
+        (&mut self.file).take(PREVIEW_LEN).read_to_end(&mut buffer).map_err(|e| format!("Failed to read: {}", e))?;
+        let text = String::from_utf8_lossy(&buffer);
+        let mut lines = text.lines();
+        // The first line is likely cut, and so is the last
+        if offset > 0 {
+            lines.next();
+        }
+        let mut lines: Vec<String> = lines.map(str::to_string).collect();
+        if buffer.len() as u64 == PREVIEW_LEN {
+            lines.pop();
+        }
+        Ok(lines)
+    }
+
+    /// The text of entry `idx`, read from disk unless it was read recently.
+    fn text(&mut self, idx: usize) -> &str {
+        if self.cache.get(idx).is_none() {
+            let text = self.read_text(idx).unwrap_or_else(|e| format!("<{}>", e));
+            self.cache.insert(idx, text);
+        }
+        self.cache.get(idx).unwrap_or_default()
+    }
+
+    fn read_text(&mut self, idx: usize) -> Result<String, String> {
+        let start = self.offsets[idx];
+        let end = match self.offsets.get(idx + 1) {
+            Some(&next) => next,
+            None if self.is_indexing() => self.indexed.load(Ordering::Relaxed),
+            None => self.size,
+        };
+        self.file.seek(SeekFrom::Start(start)).map_err(|e| format!("Failed to seek: {}", e))?;
+        let mut buffer = Vec::new();
+        (&mut self.file)
+            .take(end.saturating_sub(start).min(MAX_ENTRY_LEN))
+            .read_to_end(&mut buffer)
+            .map_err(|e| format!("Failed to read: {}", e))?;
+        // Blank lines between entries aren't part of either
+        let text = String::from_utf8_lossy(&buffer);
+        Ok(text.trim_end().lines().collect::<Vec<_>>().join("\n"))
+    }
+
+    /// Draw the shown entries, one row per entry with its first line; the
+    /// whole entry is shown on hover. The scrollbar spans the whole file, so
+    /// dragging it goes to any part, indexed or not.
+    pub fn show(&mut self, ui: &mut egui::Ui, font_size: f32, palette: &ColorPalette, redactor: &Redactor) {
+        let font = FontId::monospace(font_size);
+        let row_height = ui.fonts(|f| f.row_height(&font));
+        let rect = ui.available_rect_before_wrap();
+        let bar_width = ui.spacing().scroll_bar_width + 4.0;
+        let bar_rect = egui::Rect::from_min_max(egui::pos2(rect.right() - bar_width, rect.top()), rect.max);
+        let content_rect = egui::Rect::from_min_max(rect.min, egui::pos2(bar_rect.left(), rect.bottom()));
+        ui.allocate_ui_at_rect(content_rect, |ui| {
+            if let Some(read) = self.region_read.as_ref().filter(|read| !read.append) {
+                let target = read.target;
+                self.show_preview(ui, target, &font, redactor);
+            } else if self.region.is_some() {
+                self.show_region(ui, row_height, &font, palette, redactor);
+            } else {
+                self.show_indexed(ui, row_height, &font, palette, redactor);
+            }
+        });
+        self.show_scrollbar(ui, bar_rect, redactor);
+    }
+
+    fn show_indexed(&mut self, ui: &mut egui::Ui, row_height: f32, font: &FontId, palette: &ColorPalette, redactor: &Redactor) {
+        if self.filtered.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label(if self.is_indexing() { "Indexing…" } else { "No entries match the current filters." });
+            });
+            return;
+        }
+        let mut scroll_area = ScrollArea::vertical()
+            .auto_shrink([true, false])
+            .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
+            .id_source("disk_log_scroll_area");
+        if let Some(row) = self.scroll_to.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+        ScrollArea::horizontal().auto_shrink([false; 2]).id_source("disk_log_hscroll").show(ui, |ui| {
+            scroll_area.show_rows(ui, row_height, self.filtered.len(), |ui, rows| {
+                let first = self.filtered[rows.start];
+                let last = self.filtered[rows.end.saturating_sub(1).max(rows.start)];
+                self.visible = (self.offsets[first], self.offsets.get(last + 1).copied().unwrap_or(self.size));
+                for row in rows {
+                    let idx = self.filtered[row];
+                    let number = format!("{:6}   ", self.line_numbers[idx]);
+                    let level = self.levels[idx].clone();
+                    let text = redactor.redact(self.text(idx)).into_owned();
+                    entry_row(ui, &number, &text, &level, font, palette);
+                }
+            });
+        });
+    }
+
+    /// The entries read from ahead of the index, read on as they are scrolled to the end.
+    fn show_region(&mut self, ui: &mut egui::Ui, row_height: f32, font: &FontId, palette: &ColorPalette, redactor: &Redactor) {
+        let Some(ref region) = self.region else {
+            return;
+        };
+        let (start, end) = (region.start, region.end);
+        let reading = self.region_read.is_some();
+        let mut back = false;
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("Not indexed yet: showing the file from {:.1}%", percent(start, self.size))).weak());
+            if reading {
+                ui.spinner();
+            }
+            back = ui.small_button("Back to Indexed Entries").clicked();
+        });
+        if back {
+            self.region = None;
+            return;
+        }
+
+        let mut scroll_area = ScrollArea::vertical()
+            .auto_shrink([true, false])
+            .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
+            .id_source("disk_log_region_scroll");
+        if let Some(row) = self.scroll_to.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+        let mut read_on = false;
+        let Some(ref region) = self.region else {
+            return;
+        };
+        ScrollArea::horizontal().auto_shrink([false; 2]).id_source("disk_log_region_hscroll").show(ui, |ui| {
+            scroll_area.show_rows(ui, row_height, region.filtered.len(), |ui, rows| {
+                if let Some(&first) = region.filtered.get(rows.start) {
+                    let last = region.filtered[rows.end.saturating_sub(1).max(rows.start)];
+                    self.visible = (region.entries[first].offset, region.entries.get(last + 1).map_or(end, |entry| entry.offset));
+                }
+                read_on = rows.end >= region.filtered.len() && end < self.size;
+                for row in rows {
+                    let entry = &region.entries[region.filtered[row]];
+                    entry_row(ui, "     ·   ", &redactor.redact(&entry.text), &entry.level, font, palette);
+                }
+            });
+        });
+        if read_on && !reading {
+            self.read_region(end, end, true);
+        }
+    }
+
+    /// The raw lines at `offset`, shown while the region around it is read.
+    fn show_preview(&mut self, ui: &mut egui::Ui, offset: u64, font: &FontId, redactor: &Redactor) {
+        let size = self.size;
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(RichText::new(format!("Reading the file from {:.1}%…", percent(offset, size))).weak());
+        });
+        let lines = self.preview(offset);
+        ScrollArea::both().auto_shrink([false; 2]).id_source("disk_log_preview_scroll").show(ui, |ui| {
+            for line in lines {
+                ui.add(egui::Label::new(RichText::new(redactor.redact(line)).font(font.clone()).weak()).wrap(false));
+            }
+        });
+    }
+
+    /// A scrollbar for the whole file by bytes. Dragging previews the lines
+    /// under the thumb; releasing goes there.
+    fn show_scrollbar(&mut self, ui: &mut egui::Ui, rect: egui::Rect, redactor: &Redactor) {
+        let response = ui.interact(rect, ui.id().with("disk_log_file_scrollbar"), Sense::click_and_drag());
+        let size = self.size.max(1);
+        let track = rect.shrink2(egui::vec2(2.0, 0.0));
+        let visible = self.visible.1.saturating_sub(self.visible.0) as f32 / size as f32;
+        let thumb_height = (visible * track.height()).clamp(24.0_f32.min(track.height()), track.height());
+        let travel = (track.height() - thumb_height).max(1.0);
+
+        let pointer = response.interact_pointer_pos().filter(|_| response.dragged() || response.clicked() || response.drag_released());
+        let target = pointer.map(|pos| (((pos.y - track.top() - thumb_height / 2.0) / travel).clamp(0.0, 1.0) as f64 * size as f64) as u64);
+        let fraction = match target {
+            Some(target) => target as f32 / size as f32,
+            None if self.visible.1 >= self.size && self.visible.0 > 0 => 1.0,
+            None => self.visible.0 as f32 / size as f32,
+        };
+
+        let visuals = ui.style().interact(&response);
+        let thumb = egui::Rect::from_min_size(
+            egui::pos2(track.left(), track.top() + fraction.min(1.0) * travel),
+            egui::vec2(track.width(), thumb_height),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(track, track.width() / 2.0, ui.visuals().extreme_bg_color);
+        // How far indexing has got, so it's clear which parts show right away
+        if self.is_indexing() {
+            let indexed = egui::Rect::from_min_size(track.min, egui::vec2(track.width(), track.height() * self.progress()));
+            painter.rect_filled(indexed, track.width() / 2.0, ui.visuals().faint_bg_color);
+        }
+        painter.rect_filled(thumb, track.width() / 2.0, visuals.bg_fill);
+
+        if let Some(target) = target {
+            if response.dragged() {
+                let lines: Vec<String> = self.preview(target).iter().take(3).map(|line| redactor.redact(line).into_owned()).collect();
+                egui::show_tooltip_at_pointer(ui.ctx(), ui.id().with("disk_log_scrollbar_preview"), |ui| {
+                    ui.label(RichText::new(format!("{:.1}%", percent(target, self.size))).strong());
+                    for line in lines {
+                        ui.add(egui::Label::new(RichText::new(line).monospace()).wrap(false));
+                    }
+                });
+            } else {
+                self.seek(target);
+            }
+        }
+    }
+}
+
+/// An entry's row: its line number and first line, the whole entry on hover.
+pub fn entry_row(ui: &mut egui::Ui, number: &str, text: &str, level: &LogLevel, font: &FontId, palette: &ColorPalette) {
+    let (color, background) = palette.for_level(level);
+    let first_line = text.lines().next().unwrap_or("");
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(number).font(FontId::monospace(font.size * 0.85)).color(color));
+        let line = RichText::new(first_line).font(font.clone()).color(color).background_color(background);
+        let response = ui.add(egui::Label::new(line).wrap(false));
+        if text.len() > first_line.len() {
+            response.on_hover_text(RichText::new(text).monospace());
+        }
+    });
+}
+
+fn percent(offset: u64, size: u64) -> f64 {
+    offset as f64 * 100.0 / size.max(1) as f64
+}
+
+fn filter_region(entries: &[RegionEntry], levels: &HashSet<LogLevel>) -> Vec<usize> {
+    (0..entries.len()).filter(|&i| levels.contains(&entries[i].level)).collect()
+}
+
+/// Read and parse the entries in about `REGION_LEN` bytes from `start`, from
+/// the first line that starts an entry.
+fn read_region(path: &Path, parser: &LogParser, start: u64) -> Result<Region, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    file.seek(SeekFrom::Start(start)).map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut reader = BufReader::with_capacity(1 << 16, file);
+    let mut buffer = Vec::new();
+    let mut offset = start;
+    // Starting mid-line, skip to the next one
+    if start > 0 {
+        offset += reader.read_until(b'\n', &mut buffer).map_err(|e| format!("Failed to read: {}", e))? as u64;
+    }
+    let mut entries: Vec<RegionEntry> = Vec::new();
+    let mut first = None;
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).map_err(|e| format!("Failed to read: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if parser.starts_entry(line) {
+            if offset - start >= REGION_LEN {
+                break;
+            }
+            first.get_or_insert(offset);
+            entries.push(RegionEntry {
+                offset,
+                level: parser.parse_line(line, 0).level,
+                text: line.to_string(),
+            });
+        } else if let Some(entry) = entries.last_mut() {
+            // Continuation lines, skipped before the first entry since it began earlier
+            if !line.trim().is_empty() && (entry.text.len() as u64) < MAX_ENTRY_LEN {
+                entry.text.push('\n');
+                entry.text.push_str(line);
+            }
+        }
+        offset += read as u64;
+    }
+    Ok(Region {
+        start: first.unwrap_or(offset),
+        end: offset,
+        entries,
+        filtered: Vec::new(),
+    })
+}
+
+/// Find where each entry of `path` starts, sending them in chunks until the
+/// end of the file or until the receiver is gone.
+fn index_file(
+    path: &Path,
+    parser: &LogParser,
+    sender: &mpsc::Sender<Vec<IndexedEntry>>,
+    indexed: &AtomicU64,
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::with_capacity(1 << 20, file);
+    let mut buffer = Vec::new();
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).map_err(|e| format!("Failed to read: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if parser.starts_entry(line) {
+            let entry = parser.parse_line(line, line_number);
+            chunk.push(IndexedEntry {
+                offset,
+                line_number,
+                time_ms: entry.time_ms(),
+                level: entry.level,
+            });
+            if chunk.len() >= CHUNK_SIZE {
+                indexed.store(offset + read as u64, Ordering::Relaxed);
+                if sender.send(std::mem::take(&mut chunk)).is_err() {
+                    return Ok(());
+                }
+                file_open::wake_ui();
+            }
+        }
+        offset += read as u64;
+    }
+    indexed.store(offset, Ordering::Relaxed);
+    if !chunk.is_empty() {
+        sender.send(chunk).ok();
+    }
+    Ok(())
+}
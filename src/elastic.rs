@@ -0,0 +1,264 @@
+use serde_json::{json, Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use url::Url;
+
+use crate::file_open;
+use crate::live_stream::LiveStream;
+use crate::source_event::{self, SourceAction, SourceEvent};
+
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Most hits a search can page through with `from`/`size` (the default
+/// `index.max_result_window`).
+pub const MAX_RESULTS: u32 = 10_000;
+
+/// A search of an Elasticsearch or OpenSearch index, as edited in its window.
+#[derive(Debug, Clone)]
+pub struct ElasticQuery {
+    /// The cluster's base URL, e.g. `http://localhost:9200`
+    pub url: String,
+    /// Index name or pattern, e.g. `logs-*`
+    pub index: String,
+    /// Query string (Lucene syntax); empty matches everything
+    pub query: String,
+    pub time_field: String,
+    /// How far back to search, in minutes
+    pub lookback_minutes: u32,
+    /// Hits fetched per request
+    pub page_size: u32,
+    /// Most hits shown, up to `MAX_RESULTS`
+    pub max_results: u32,
+    /// Basic authentication, when a user is set
+    pub user: String,
+    pub password: String,
+    /// Sent as `Authorization: ApiKey ...` when set, instead of basic authentication
+    pub api_key: String,
+}
+
+impl Default for ElasticQuery {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:9200".to_string(),
+            index: "logs-*".to_string(),
+            query: String::new(),
+            time_field: "@timestamp".to_string(),
+            lookback_minutes: 60,
+            page_size: 500,
+            max_results: MAX_RESULTS,
+            user: String::new(),
+            password: String::new(),
+            api_key: String::new(),
+        }
+    }
+}
+
+impl ElasticQuery {
+    /// `index: query @ host`, as shown in the header.
+    pub fn description(&self) -> String {
+        let host = Url::parse(self.url.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| self.url.trim().to_string());
+        let query = if self.query.trim().is_empty() { "*" } else { self.query.trim() };
+        format!("{}: {} @ {}", self.index.trim(), query, host)
+    }
+}
+
+/// The Search Elasticsearch window. A search ends after its last page, so
+/// there is nothing to disconnect.
+#[derive(Default)]
+pub struct ElasticDialog {
+    pub open: bool,
+    pub query: ElasticQuery,
+    /// Why the last search couldn't be started
+    pub error: Option<String>,
+}
+
+impl ElasticDialog {
+    pub fn show(&mut self, ctx: &egui::Context, stream: Option<&LiveStream>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Search Elasticsearch")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let query = &mut self.query;
+                egui::Grid::new("elastic_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("URL:");
+                    ui.add(egui::TextEdit::singleline(&mut query.url).desired_width(300.0));
+                    ui.end_row();
+                    ui.label("Index:");
+                    ui.text_edit_singleline(&mut query.index).on_hover_text("Name or pattern, e.g. logs-*");
+                    ui.end_row();
+                    ui.label("Query:");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut query.query)
+                            .desired_width(300.0)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("level:error AND service:api"),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        action = Some(SourceAction::Open);
+                    }
+                    ui.end_row();
+                    ui.label("Time field:");
+                    ui.text_edit_singleline(&mut query.time_field);
+                    ui.end_row();
+                    ui.label("Last:");
+                    ui.horizontal(|ui| source_event::lookback_choices(ui, &mut query.lookback_minutes));
+                    ui.end_row();
+                    ui.label("Results:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut query.max_results).speed(100.0).clamp_range(1..=MAX_RESULTS));
+                        ui.label("in pages of");
+                        ui.add(egui::DragValue::new(&mut query.page_size).speed(50.0).clamp_range(1..=MAX_RESULTS));
+                    });
+                    ui.end_row();
+                    ui.label("User:");
+                    ui.text_edit_singleline(&mut query.user);
+                    ui.end_row();
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut query.password).password(true));
+                    ui.end_row();
+                    ui.label("API key:");
+                    ui.add(egui::TextEdit::singleline(&mut query.api_key).password(true))
+                        .on_hover_text("Used instead of the user and password when set");
+                    ui.end_row();
+                });
+                ui.label("Hits are shown oldest first, with their fields as JSON entries.");
+                source_event::show_errors(ui, stream.map(LiveStream::events), self.error.as_deref());
+                if ui.button("Search").clicked() {
+                    action = Some(SourceAction::Open);
+                }
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// The search endpoint of the query's index, checked, with any credentials.
+pub fn search_url(query: &ElasticQuery) -> Result<Url, String> {
+    if query.index.trim().is_empty() || query.time_field.trim().is_empty() {
+        return Err("Index and time field are required".to_string());
+    }
+    let mut url = Url::parse(query.url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported scheme {}: use http or https", url.scheme()));
+    }
+    let path = format!("{}/{}/_search", url.path().trim_end_matches('/'), query.index.trim());
+    url.set_path(&path);
+    if query.api_key.trim().is_empty() && !query.user.trim().is_empty() {
+        url.set_username(query.user.trim()).map_err(|_| "Invalid URL for a user".to_string())?;
+        url.set_password(Some(&query.password)).map_err(|_| "Invalid URL for a password".to_string())?;
+    }
+    Ok(url)
+}
+
+/// Page through the hits of the lookback window, oldest first, sending each
+/// page as it arrives. Each hit becomes one JSON line of its source fields,
+/// nested objects flattened to dotted names (`log.level`).
+pub fn run(url: &Url, query: &ElasticQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    if let Err(e) = search_pages(url, query, sender, stop) {
+        sender.send(SourceEvent::Disconnected(e)).ok();
+    }
+    sender.send(SourceEvent::Ended).ok();
+    file_open::wake_ui();
+}
+
+fn search_pages(url: &Url, query: &ElasticQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) -> Result<(), String> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::minutes(i64::from(query.lookback_minutes));
+    let query_string = if query.query.trim().is_empty() { "*" } else { query.query.trim() };
+    let time_field = query.time_field.trim();
+    let max_results = query.max_results.clamp(1, MAX_RESULTS);
+    let page_size = query.page_size.clamp(1, max_results);
+
+    let mut from = 0;
+    while from < max_results && !stop.load(Ordering::Relaxed) {
+        let body = json!({
+            "query": {
+                "bool": {
+                    "must": [{ "query_string": { "query": query_string, "default_operator": "AND" } }],
+                    "filter": [{ "range": { time_field: { "gte": start.to_rfc3339(), "lte": end.to_rfc3339() } } }],
+                }
+            },
+            "sort": [{ time_field: { "order": "asc" } }],
+            "from": from,
+            "size": page_size.min(max_results - from),
+            "track_total_hits": true,
+        });
+        let response = search(url, query, &body)?;
+        if from == 0 && sender.send(SourceEvent::Connected).is_err() {
+            return Ok(());
+        }
+        let hits = response["hits"]["hits"].as_array().cloned().unwrap_or_default();
+        let lines: Vec<String> = hits.iter().map(hit_line).collect();
+        from += hits.len() as u32;
+        let total = response["hits"]["total"]["value"].as_u64().or_else(|| response["hits"]["total"].as_u64());
+        let done = hits.len() < page_size as usize || total.is_some_and(|total| u64::from(from) >= total);
+        if !lines.is_empty() && sender.send(SourceEvent::Lines(lines)).is_err() {
+            return Ok(());
+        }
+        file_open::wake_ui();
+        if done {
+            return Ok(());
+        }
+        if from >= max_results {
+            let note = format!(
+                "──────── Showing the first {} of {} hits; narrow the query or time range to see the rest ────────",
+                from,
+                total.map_or_else(|| "more".to_string(), |total| total.to_string())
+            );
+            sender.send(SourceEvent::Lines(vec![note])).ok();
+        }
+    }
+    Ok(())
+}
+
+fn search(url: &Url, query: &ElasticQuery, body: &Value) -> Result<Value, String> {
+    let mut request = ureq::request_url("POST", url).timeout(SEARCH_TIMEOUT).set("Content-Type", "application/json");
+    if !query.api_key.trim().is_empty() {
+        request = request.set("Authorization", &format!("ApiKey {}", query.api_key.trim()));
+    }
+    let response = request.send_string(&body.to_string()).map_err(|e| match e {
+        ureq::Error::Status(status, response) => {
+            let text = response.into_string().unwrap_or_default();
+            // Errors come as {"error": {"reason": ...}} from both ES and OpenSearch
+            let reason = serde_json::from_str::<Value>(&text)
+                .ok()
+                .and_then(|error| error["error"]["root_cause"][0]["reason"].as_str().or(error["error"]["reason"].as_str()).map(str::to_string))
+                .unwrap_or(text);
+            format!("Search failed ({}): {}", status, reason.trim())
+        }
+        e => format!("Failed to reach the cluster: {}", e),
+    })?;
+    serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the response: {}", e))
+}
+
+/// A hit's source as one JSON line with flattened field names.
+fn hit_line(hit: &Value) -> String {
+    let mut fields = Map::new();
+    if let Some(source) = hit["_source"].as_object() {
+        flatten("", source, &mut fields);
+    }
+    Value::Object(fields).to_string()
+}
+
+/// Copy `object`'s fields into `fields`, nested objects flattened to dotted
+/// names under `prefix`.
+pub fn flatten(prefix: &str, object: &Map<String, Value>, fields: &mut Map<String, Value>) {
+    for (key, value) in object {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(nested) => flatten(&name, nested, fields),
+            value => {
+                fields.insert(name, value.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Entries written out as raw lines, CSV with one column per parsed field,
+//! or NDJSON with one normalized object per entry, so other tools can use
+//! them without parsing the raw text again.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::log_parser::LogEntry;
+use crate::scripting::ScriptVerdict;
+
+/// Columns every CSV row has; fields computed by the entry script follow.
+const COLUMNS: [&str; 6] = ["line", "timestamp", "level", "thread", "class", "message"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The entries' text as in the file
+    Lines,
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Lines, ExportFormat::Csv, ExportFormat::Ndjson];
+
+    /// Name for the file dialog's filter.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Lines => "Log",
+            ExportFormat::Csv => "CSV (parsed columns)",
+            ExportFormat::Ndjson => "NDJSON (normalized entries)",
+        }
+    }
+
+    /// File extensions, the usual one first.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ExportFormat::Lines => &["log", "txt"],
+            ExportFormat::Csv => &["csv"],
+            ExportFormat::Ndjson => &["ndjson", "jsonl"],
+        }
+    }
+
+    /// The format a file name asks for; anything unknown gets raw lines.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extensions().contains(&extension.as_str()))
+            .unwrap_or(ExportFormat::Lines)
+    }
+}
+
+/// The message with the entry's continuation lines (a stack trace, a wrapped message).
+fn full_message(entry: &LogEntry) -> String {
+    let mut message = entry.message().to_string();
+    for line in entry.raw_line.lines().skip(1) {
+        message.push('\n');
+        message.push_str(line);
+    }
+    message
+}
+
+/// The entries at `indices` as CSV. `verdicts` (parallel to `entries`, possibly
+/// shorter) adds a column for every script field; `text` maps each value
+/// written, e.g. to redact it.
+pub fn to_csv(entries: &[LogEntry], indices: &[usize], verdicts: &[ScriptVerdict], text: impl Fn(&str) -> String) -> String {
+    // Script fields in the order they first appear
+    let mut fields: Vec<&str> = Vec::new();
+    let mut seen = HashSet::new();
+    for verdict in indices.iter().filter_map(|&idx| verdicts.get(idx)) {
+        for (key, _) in &verdict.fields {
+            if seen.insert(key.as_str()) {
+                fields.push(key);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let header: Vec<&str> = COLUMNS.iter().copied().chain(fields.iter().copied()).collect();
+    push_row(&mut out, header.iter().map(|name| field(name)));
+
+    for &idx in indices {
+        let Some(entry) = entries.get(idx) else {
+            continue;
+        };
+        let verdict = verdicts.get(idx);
+        let extra = fields.iter().map(|name| {
+            let value = verdict.and_then(|v| v.fields.iter().find(|(key, _)| key == name)).map_or("", |(_, value)| value);
+            field(&text(value))
+        });
+        let row = [
+            entry.line_number.to_string(),
+            field(&text(entry.timestamp().unwrap_or(""))),
+            entry.level.as_str().to_string(),
+            field(&text(entry.thread().unwrap_or(""))),
+            field(&text(entry.class().unwrap_or(""))),
+            field(&text(&full_message(entry))),
+        ];
+        push_row(&mut out, row.into_iter().chain(extra));
+    }
+    out
+}
+
+fn push_row(out: &mut String, values: impl Iterator<Item = String>) {
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&value);
+    }
+    // RFC 4180 line ends, which Excel expects
+    out.push_str("\r\n");
+}
+
+/// A value quoted when it holds a separator, quote or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The entries at `indices` as NDJSON, one object per line with the same
+/// keys whatever the log's format: `source` (the file each came from, named
+/// by `source`), `line`, `time` (ISO 8601, when the timestamp was understood),
+/// `timestamp` as written, `level`, `thread`, `class`, `message`, `fields`
+/// from the entry script and the `raw` text. `text` maps each string, e.g. to
+/// redact it.
+pub fn to_ndjson(
+    entries: &[LogEntry],
+    indices: &[usize],
+    verdicts: &[ScriptVerdict],
+    source: impl Fn(usize) -> String,
+    text: impl Fn(&str) -> String,
+) -> String {
+    let mut out = String::new();
+    for &idx in indices {
+        let Some(entry) = entries.get(idx) else {
+            continue;
+        };
+        let time = entry
+            .time_ms()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|time| time.naive_utc().format("%Y-%m-%dT%H:%M:%S%.3f").to_string());
+        let fields: serde_json::Map<String, serde_json::Value> = verdicts
+            .get(idx)
+            .map(|verdict| verdict.fields.iter().map(|(key, value)| (key.clone(), text(value).into())).collect())
+            .unwrap_or_default();
+        let object = serde_json::json!({
+            "source": source(idx),
+            "line": entry.line_number,
+            "time": time,
+            "timestamp": entry.timestamp().map(&text),
+            "level": entry.level.as_str(),
+            "thread": entry.thread().map(&text),
+            "class": entry.class().map(&text),
+            "message": text(&full_message(entry)),
+            "fields": fields,
+            "raw": text(&entry.raw_line),
+        });
+        out.push_str(&object.to_string());
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::log_parser::{LogFormat, LogLevel};
+
+pub const URL_SCHEME: &str = "logrocket";
+
+/// A file to open, optionally at a given 1-based line, and how to view it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenRequest {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    /// Levels to show; empty leaves the filter as it is
+    pub levels: Vec<LogLevel>,
+    pub search: Option<String>,
+    pub regex: bool,
+    /// Format to parse the file with; `Auto` keeps the one remembered for it
+    pub format: LogFormat,
+    /// Tail the file and keep scrolled to the end
+    pub follow: bool,
+}
+
+impl OpenRequest {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, ..Self::default() }
+    }
+
+    /// Parse `file.log`, `file.log:12345` or `logrocket://open?path=...&line=...`.
+    pub fn parse(target: &str) -> Option<Self> {
+        if target.starts_with(&format!("{}:", URL_SCHEME)) {
+            return Self::parse_url(target);
+        }
+        
+        // Only treat a ":N" suffix as a line number when the full name isn't a real file
+        if !Path::new(target).exists() {
+            if let Some((path, line)) = target.rsplit_once(':') {
+                if let Ok(line) = line.parse::<usize>() {
+                    return Some(Self { line: Some(line), ..Self::new(PathBuf::from(path)) });
+                }
+            }
+        }
+        Some(Self::new(PathBuf::from(target)))
+    }
+
+    fn parse_url(target: &str) -> Option<Self> {
+        let url = url::Url::parse(target).ok()?;
+        if url.host_str() != Some("open") {
+            return None;
+        }
+        let mut path = None;
+        let mut request = Self::default();
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "path" => path = Some(PathBuf::from(value.as_ref())),
+                "line" => request.line = value.parse().ok(),
+                "level" => request.levels = value.split(',').filter_map(|level| level.parse().ok()).collect(),
+                "search" => request.search = Some(value.into_owned()),
+                "regex" => request.regex = value == "1",
+                "format" => request.format = value.parse().unwrap_or_default(),
+                "follow" => request.follow = value == "1",
+                _ => {}
+            }
+        }
+        Some(Self { path: path?, ..request })
+    }
+
+    /// The `logrocket://` form, also used to hand requests between instances.
+    pub fn to_url(&self) -> String {
+        let mut url = url::Url::parse(&format!("{}://open", URL_SCHEME)).expect("valid base url");
+        let mut query = url.query_pairs_mut();
+        query.append_pair("path", &self.path.to_string_lossy());
+        if let Some(line) = self.line {
+            query.append_pair("line", &line.to_string());
+        }
+        if !self.levels.is_empty() {
+            let levels: Vec<String> = self.levels.iter().map(|level| format!("{:?}", level).to_lowercase()).collect();
+            query.append_pair("level", &levels.join(","));
+        }
+        if let Some(ref search) = self.search {
+            query.append_pair("search", search);
+        }
+        if self.regex {
+            query.append_pair("regex", "1");
+        }
+        if self.format != LogFormat::Auto {
+            query.append_pair("format", &self.format.to_string());
+        }
+        if self.follow {
+            query.append_pair("follow", "1");
+        }
+        drop(query);
+        url.into()
+    }
+}
+
+// Files handed to us by the OS (Finder "Open With", double-click on an associated file,
+// logrocket:// links) are queued here and drained by the app on the next frame, in the
+// groups they were handed over in.
+static PENDING: Mutex<Vec<Vec<OpenRequest>>> = Mutex::new(Vec::new());
+static FOCUS_REQUESTED: AtomicBool = AtomicBool::new(false);
+static REPAINT_CTX: OnceLock<egui::Context> = OnceLock::new();
+
+/// Queue a file to be opened by the running app and wake up the UI.
+pub fn push(request: OpenRequest) {
+    push_together(vec![request]);
+}
+
+/// Queue files handed over together, e.g. by a multi-file "Open With", to be
+/// merged into one view.
+pub fn push_together(requests: Vec<OpenRequest>) {
+    if requests.is_empty() {
+        return;
+    }
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(requests);
+    }
+    request_focus();
+}
+
+/// Ask the app window to come to the front on the next frame.
+pub fn request_focus() {
+    FOCUS_REQUESTED.store(true, Ordering::SeqCst);
+    wake_ui();
+}
+
+/// Returns true once per focus request.
+pub fn take_focus_request() -> bool {
+    FOCUS_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Take all requests queued since the last call, grouped as they were handed over.
+pub fn take_pending() -> Vec<Vec<OpenRequest>> {
+    PENDING
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default()
+}
+
+/// Repaint the UI from another thread, e.g. when a watched file changed.
+pub fn wake_ui() {
+    if let Some(ctx) = REPAINT_CTX.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Remember the egui context so events arriving while idle trigger a repaint.
+pub fn set_repaint_context(ctx: &egui::Context) {
+    let _ = REPAINT_CTX.set(ctx.clone());
+}
+
+/// Install the platform hooks for file-open events. Must be called before the
+/// event loop starts so documents passed at launch are not missed.
+pub fn install() {
+    #[cfg(target_os = "macos")]
+    macos::install();
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::path::PathBuf;
+
+    use super::OpenRequest;
+
+    const K_CORE_EVENT_CLASS: u32 = u32::from_be_bytes(*b"aevt");
+    const K_AE_OPEN_DOCUMENTS: u32 = u32::from_be_bytes(*b"odoc");
+    const KEY_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+    const TYPE_FILE_URL: u32 = u32::from_be_bytes(*b"furl");
+    const K_INTERNET_EVENT_CLASS: u32 = u32::from_be_bytes(*b"GURL");
+    const K_AE_GET_URL: u32 = u32::from_be_bytes(*b"GURL");
+
+    pub fn install() {
+        // NSApplication installs its own "open documents" Apple Event handler while
+        // launching, so we register ours from applicationWillFinishLaunching:, which
+        // is the documented point to override it. winit's delegate doesn't implement
+        // application:openFiles:, so without this Finder opens are silently dropped.
+        // The same handler object receives logrocket:// URLs (GetURL events).
+        let superclass = class!(NSObject);
+        let Some(mut decl) = ClassDecl::new("LogRocketOpenHandler", superclass) else {
+            return;
+        };
+        unsafe {
+            decl.add_method(
+                sel!(applicationWillFinishLaunching:),
+                will_finish_launching as extern "C" fn(&Object, Sel, *mut Object),
+            );
+            decl.add_method(
+                sel!(handleOpenDocuments:withReplyEvent:),
+                handle_open_documents as extern "C" fn(&Object, Sel, *mut Object, *mut Object),
+            );
+            decl.add_method(
+                sel!(handleGetURL:withReplyEvent:),
+                handle_get_url as extern "C" fn(&Object, Sel, *mut Object, *mut Object),
+            );
+        }
+        let cls = decl.register();
+
+        unsafe {
+            // Leaked on purpose: the handler lives as long as the process
+            let handler: *mut Object = msg_send![cls, new];
+            let name: *mut Object = msg_send![class!(NSString),
+                stringWithUTF8String: b"NSApplicationWillFinishLaunchingNotification\0".as_ptr()];
+            let center: *mut Object = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![center,
+                addObserver: handler
+                selector: sel!(applicationWillFinishLaunching:)
+                name: name
+                object: std::ptr::null_mut::<Object>()];
+        }
+    }
+
+    extern "C" fn will_finish_launching(this: &Object, _sel: Sel, _notification: *mut Object) {
+        unsafe {
+            let manager: *mut Object = msg_send![class!(NSAppleEventManager), sharedAppleEventManager];
+            let _: () = msg_send![manager,
+                setEventHandler: this
+                andSelector: sel!(handleOpenDocuments:withReplyEvent:)
+                forEventClass: K_CORE_EVENT_CLASS
+                andEventID: K_AE_OPEN_DOCUMENTS];
+            let _: () = msg_send![manager,
+                setEventHandler: this
+                andSelector: sel!(handleGetURL:withReplyEvent:)
+                forEventClass: K_INTERNET_EVENT_CLASS
+                andEventID: K_AE_GET_URL];
+        }
+    }
+
+    extern "C" fn handle_get_url(_this: &Object, _sel: Sel, event: *mut Object, _reply: *mut Object) {
+        unsafe {
+            let descriptor: *mut Object = msg_send![event, paramDescriptorForKeyword: KEY_DIRECT_OBJECT];
+            if descriptor.is_null() {
+                return;
+            }
+            let url: *mut Object = msg_send![descriptor, stringValue];
+            if let Some(request) = nsstring_to_string(url).and_then(|url| OpenRequest::parse(&url)) {
+                super::push(request);
+            }
+        }
+    }
+
+    extern "C" fn handle_open_documents(_this: &Object, _sel: Sel, event: *mut Object, _reply: *mut Object) {
+        unsafe {
+            let list: *mut Object = msg_send![event, paramDescriptorForKeyword: KEY_DIRECT_OBJECT];
+            if list.is_null() {
+                return;
+            }
+            let count: isize = msg_send![list, numberOfItems];
+            let mut requests = Vec::new();
+            // Apple Event descriptor lists are 1-based
+            for i in 1..=count {
+                let item: *mut Object = msg_send![list, descriptorAtIndex: i];
+                if item.is_null() {
+                    continue;
+                }
+                if let Some(path) = descriptor_to_path(item) {
+                    requests.push(OpenRequest::new(path));
+                }
+            }
+            super::push_together(requests);
+        }
+    }
+
+    unsafe fn descriptor_to_path(descriptor: *mut Object) -> Option<PathBuf> {
+        let file_url: *mut Object = msg_send![descriptor, coerceToDescriptorType: TYPE_FILE_URL];
+        if file_url.is_null() {
+            return None;
+        }
+        let data: *mut Object = msg_send![file_url, data];
+        let url: *mut Object = msg_send![class!(NSURL),
+            URLWithDataRepresentation: data
+            relativeToURL: std::ptr::null_mut::<Object>()];
+        if url.is_null() {
+            return None;
+        }
+        let path: *mut Object = msg_send![url, path];
+        nsstring_to_string(path).map(PathBuf::from)
+    }
+
+    unsafe fn nsstring_to_string(string: *mut Object) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![string, UTF8String];
+        if utf8.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_the_view_options_over_in_the_url() {
+        let request = OpenRequest {
+            path: PathBuf::from("/var/log/app one.log"),
+            line: Some(42),
+            levels: vec![LogLevel::Error, LogLevel::Warn],
+            search: Some("timeout & retry".to_string()),
+            regex: true,
+            format: LogFormat::Custom("Sling Jobs".to_string()),
+            follow: true,
+        };
+        assert_eq!(OpenRequest::parse(&request.to_url()), Some(request));
+
+        let plain = OpenRequest::new(PathBuf::from("/var/log/app.log"));
+        assert_eq!(plain.to_url(), "logrocket://open?path=%2Fvar%2Flog%2Fapp.log");
+        assert_eq!(OpenRequest::parse(&plain.to_url()), Some(plain));
+    }
+
+    #[test]
+    fn keeps_files_handed_over_together_in_one_group() {
+        let request = |name: &str| OpenRequest::new(PathBuf::from(name));
+        push_together(vec![request("api.log"), request("db.log")]);
+        push(request("other.log"));
+        push_together(Vec::new());
+        assert_eq!(take_pending(), [vec![request("api.log"), request("db.log")], vec![request("other.log")]]);
+        assert!(take_pending().is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config;
+use crate::log_parser::{EntryStart, LogFormat, LogLevel};
+
+/// Files remembered at most; the least recently viewed are forgotten first.
+const MAX_REMEMBERED: usize = 200;
+
+/// How a file was last viewed, restored when it is opened again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileState {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// How entries were told apart, when it was overridden for the file
+    #[serde(default)]
+    pub entry_start: EntryStart,
+    #[serde(default)]
+    pub levels: Vec<LogLevel>,
+    #[serde(default)]
+    pub search_query: String,
+    #[serde(default)]
+    pub search_regex: bool,
+    #[serde(default)]
+    pub scroll_offset: f32,
+    /// Unix time of the last visit, for pruning
+    #[serde(default)]
+    pub last_viewed: i64,
+}
+
+/// Key under which a file's state is stored: its canonical path.
+pub fn key(path: &Path) -> String {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).display().to_string()
+}
+
+/// Load remembered file states. A missing or unreadable file yields an empty map.
+pub fn load_file_states() -> HashMap<String, FileState> {
+    config::load_json("file_state.json")
+}
+
+pub fn save_file_states(states: &mut HashMap<String, FileState>) -> Result<(), String> {
+    if states.len() > MAX_REMEMBERED {
+        let mut by_age: Vec<(String, i64)> = states.iter().map(|(k, s)| (k.clone(), s.last_viewed)).collect();
+        by_age.sort_by_key(|(_, last_viewed)| *last_viewed);
+        for (key, _) in by_age.into_iter().take(states.len() - MAX_REMEMBERED) {
+            states.remove(&key);
+        }
+    }
+
+    config::save_json("file_state.json", states)
+}
@@ -1,19 +1,92 @@
 use notify::{Watcher, RecommendedWatcher, RecursiveMode, Event, EventKind};
-use std::path::PathBuf;
+use std::fs;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
+use crate::file_open;
+use crate::log_parser::{LogEntry, LogParser};
+use crate::text_encoding::{self, TextFormat, PARTIAL_LINE_WAIT};
+
+/// Entries appended to a tailed file, read by its tail thread.
+pub struct TailBatch {
+    /// Which tailed file they were read from, as passed to `tail_file`
+    pub source: usize,
+    /// New entries, with the lines continuing them. They should be
+    /// renumbered after the entries already shown.
+    pub entries: Vec<LogEntry>,
+    /// The rest of the last line read before, which was taken as it was
+    /// before it ended, then lines continuing its entry; for
+    /// `LogParser::continue_entry` on the source's last entry, ahead of `entries`
+    pub continuation: Option<String>,
+    /// Where reading continues, to resume from if tailing is restarted
+    pub offset: u64,
+    pub file_id: Option<u64>,
+    /// The file was deleted and created again. Its directory may have been
+    /// recreated too, which ends the watch, so tailing should be restarted.
+    pub recreated: bool,
+    /// The tail that read it, so batches of a replaced tail can be dropped
+    tail_id: u64,
+}
+
+/// How often a deleted file is checked for, until it is created again.
+const MISSING_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Most lines read into one batch. A file far ahead is read a batch at a time.
+const MAX_BATCH_LINES: usize = 10_000;
+/// Batches held for the app before tail threads wait, leaving the rest in the
+/// file until the app catches up.
+const MAX_WAITING_BATCHES: usize = 16;
+
+/// Filesystem types (as in /proc/mounts) whose changes often aren't reported to watchers.
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: [&str; 11] = [
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs", "fuse.sshfs", "fuse.rclone",
+];
+
+/// How a file is tailed.
+#[derive(Clone)]
+pub struct TailOptions {
+    pub parser: LogParser,
+    /// Read what was written to a rotated file before continuing with the new one
+    pub backfill_rotated: bool,
+    /// Check the file this often even without change events
+    pub poll_interval: Option<Duration>,
+}
+
+/// A file being tailed. Its thread ends once the watcher, and with it the
+/// event sender, is dropped.
+struct TailHandle {
+    id: u64,
+    source: usize,
+    _watcher: RecommendedWatcher,
+}
+
+/// Watches one file for changes, or tails any number of files at once (e.g.
+/// every file of a merged view), each on its own thread. Batches from all
+/// tails arrive together, tagged with the source they were read from.
 pub struct FileWatcher {
     watcher: Option<RecommendedWatcher>,
     receiver: Option<mpsc::Receiver<notify::Result<Event>>>,
     path: Option<PathBuf>,
+    tails: Vec<TailHandle>,
+    next_tail_id: u64,
+    batch_sender: mpsc::SyncSender<TailBatch>,
+    batches: mpsc::Receiver<TailBatch>,
 }
 
 impl FileWatcher {
     pub fn new() -> Self {
+        let (batch_sender, batches) = mpsc::sync_channel(MAX_WAITING_BATCHES);
         Self {
             watcher: None,
             receiver: None,
             path: None,
+            tails: Vec::new(),
+            next_tail_id: 0,
+            batch_sender,
+            batches,
         }
     }
 
@@ -22,7 +95,12 @@ impl FileWatcher {
         self.stop();
         
         let (tx, rx) = mpsc::channel();
-        let mut watcher = notify::recommended_watcher(tx)?;
+        // Wake the UI so the change is picked up without polling
+        let mut watcher = notify::recommended_watcher(move |event| {
+            if tx.send(event).is_ok() {
+                file_open::wake_ui();
+            }
+        })?;
         
         // Watch the parent directory to catch file modifications
         if let Some(parent) = path.parent() {
@@ -36,22 +114,85 @@ impl FileWatcher {
         Ok(())
     }
 
+    /// Tail `path` as `source` from byte `offset` on a background thread,
+    /// replacing any tail of that source. The thread reads and
+    /// parses what is appended whenever the file changes. Rotation (the file
+    /// shrinking or being replaced) adds a marker and continues from the start
+    /// of the new file, first reading what was missed from the rotated one
+    /// when `backfill_rotated` is set. A deleted file is read from its start
+    /// once it is created again. With a `poll_interval`, the file is also
+    /// checked whenever no change event arrived for that long, for filesystems
+    /// that don't report changes.
+    pub fn tail_file(
+        &mut self,
+        source: usize,
+        path: PathBuf,
+        offset: u64,
+        file_id: Option<u64>,
+        options: TailOptions,
+    ) -> Result<(), notify::Error> {
+        self.stop_tail(source);
+        
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            tx.send(event).ok();
+        })?;
+        if let Some(parent) = path.parent() {
+            watcher.watch(parent, RecursiveMode::NonRecursive)?;
+        }
+        
+        let id = self.next_tail_id;
+        self.next_tail_id += 1;
+        let batch_tx = self.batch_sender.clone();
+        let mut tail = Tail {
+            id,
+            source,
+            path,
+            offset,
+            file_id,
+            parser: options.parser,
+            backfill_rotated: options.backfill_rotated,
+            poll_interval: options.poll_interval,
+            missing: false,
+            partial: false,
+            format: None,
+            entry_open: offset > 0,
+        };
+        thread::spawn(move || tail.run(&rx, &batch_tx));
+        
+        self.tails.push(TailHandle { id, source, _watcher: watcher });
+        Ok(())
+    }
+
+    /// Stop tailing `source`, if it is tailed.
+    pub fn stop_tail(&mut self, source: usize) {
+        self.tails.retain(|tail| tail.source != source);
+    }
+
+    /// Batches the tail threads read since the last call, oldest first.
+    /// Those of tails stopped since are dropped.
+    pub fn take_batches(&mut self) -> Vec<TailBatch> {
+        let tails = &self.tails;
+        self.batches
+            .try_iter()
+            .filter(|batch| tails.iter().any(|tail| tail.id == batch.tail_id))
+            .collect()
+    }
+
+    /// Stop watching and tailing everything.
     pub fn stop(&mut self) {
         self.watcher = None;
         self.receiver = None;
         self.path = None;
+        self.tails.clear();
     }
 
     pub fn check_for_changes(&mut self) -> bool {
         if let Some(receiver) = &self.receiver {
             let mut changed = false;
             while let Ok(Ok(event)) = receiver.try_recv() {
-                if let EventKind::Modify(_) = event.kind {
-                    if let Some(ref path) = self.path {
-                        if event.paths.iter().any(|p| p == path) {
-                            changed = true;
-                        }
-                    }
+                if let Some(ref path) = self.path {
+                    changed |= is_change(&event, path);
                 }
             }
             changed
@@ -61,10 +202,294 @@ impl FileWatcher {
     }
 
     pub fn is_watching(&self) -> bool {
-        self.watcher.is_some()
+        self.watcher.is_some() || !self.tails.is_empty()
+    }
+}
+
+/// Whether `path` is on a network filesystem such as NFS or SMB, where change
+/// events often don't arrive. Only detected on Linux.
+pub fn is_network_fs(path: &Path) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let (Ok(path), Ok(mounts)) = (fs::canonicalize(path), fs::read_to_string("/proc/mounts")) else {
+            return false;
+        };
+        // The path is on the longest mount point that contains it
+        mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().skip(1);
+                let mount_point = fields.next()?.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+                Some((PathBuf::from(mount_point), fields.next()?))
+            })
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+            .is_some_and(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        false
     }
 }
 
+/// Whether `event` may have changed `path`. Rotation shows up as the file
+/// being removed, renamed or created.
+fn is_change(event: &Event, path: &Path) -> bool {
+    !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|p| p == path)
+}
+
+/// The tail thread's state: what is being read and how far.
+struct Tail {
+    id: u64,
+    source: usize,
+    path: PathBuf,
+    offset: u64,
+    file_id: Option<u64>,
+    parser: LogParser,
+    backfill_rotated: bool,
+    poll_interval: Option<Duration>,
+    /// The file was deleted and hasn't been read since
+    missing: bool,
+    /// Its last line was left unfinished, to read again shortly
+    partial: bool,
+    /// How the file's text is stored, once known; found again for a new file
+    format: Option<TextFormat>,
+    /// Lines that don't start an entry still go on the last one read
+    entry_open: bool,
+}
+
+/// What one read of a tailed file found.
+#[derive(Default)]
+struct Appended {
+    entries: Vec<LogEntry>,
+    /// The rest of a line read before in part, and lines continuing the
+    /// entry read before
+    continuation: Option<String>,
+}
+
+impl Tail {
+    fn run(&mut self, events: &mpsc::Receiver<notify::Result<Event>>, batches: &mpsc::SyncSender<TailBatch>) {
+        // Catch up on anything written before the watch started
+        let mut changed = true;
+        loop {
+            if changed {
+                let was_missing = self.missing;
+                let Appended { entries, continuation } = self.read_appended();
+                let full = entries.len() >= MAX_BATCH_LINES;
+                if !entries.is_empty() || continuation.is_some() {
+                    let recreated = was_missing && !self.missing;
+                    let batch = TailBatch {
+                        source: self.source,
+                        entries,
+                        continuation,
+                        offset: self.offset,
+                        file_id: self.file_id,
+                        recreated,
+                        tail_id: self.id,
+                    };
+                    if batches.send(batch).is_err() {
+                        return;
+                    }
+                    file_open::wake_ui();
+                }
+                // Read on without waiting for a change
+                if full {
+                    continue;
+                }
+            }
+            // A deleted file is looked for until it is back, as the watch
+            // ends with its directory
+            let interval = match (self.missing, self.poll_interval) {
+                (true, Some(interval)) => Some(interval.min(MISSING_POLL_INTERVAL)),
+                (true, None) => Some(MISSING_POLL_INTERVAL),
+                (false, interval) => interval,
+            };
+            // An unfinished line is read as it is once the writer leaves it be
+            let interval = match interval {
+                _ if !self.partial => interval,
+                Some(interval) => Some(interval.min(PARTIAL_LINE_WAIT)),
+                None => Some(PARTIAL_LINE_WAIT),
+            };
+            let event = match interval {
+                Some(interval) => match events.recv_timeout(interval) {
+                    Ok(event) => Some(event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                },
+                None => match events.recv() {
+                    Ok(event) => Some(event),
+                    Err(_) => return,
+                },
+            };
+            // Polling, a quiet interval means checking the file anyway
+            changed = match event {
+                Some(event) => event.is_ok_and(|event| self.note_change(&event)),
+                None => true,
+            };
+            // A burst of writes is read at once
+            for event in events.try_iter().flatten() {
+                changed |= self.note_change(&event);
+            }
+        }
+    }
+
+    /// Whether `event` may have changed the file, remembering when it was
+    /// deleted: a new file can reuse the old one's id and look unchanged.
+    fn note_change(&mut self, event: &Event) -> bool {
+        let changed = is_change(event, &self.path);
+        if changed && matches!(event.kind, EventKind::Remove(_)) {
+            self.missing = true;
+        }
+        changed
+    }
+
+    /// Entries appended since the last read, with a marker where the file was
+    /// rotated, and the end of a line already read in part.
+    fn read_appended(&mut self) -> Appended {
+        // The file can be missing for a moment while it is rotated, or until
+        // it is recreated after being deleted
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            self.missing = true;
+            return Appended::default();
+        };
+        let new_size = metadata.len();
+        let new_id = file_id(&metadata);
+        let mut appended = Appended::default();
+        let recreated = std::mem::take(&mut self.missing);
+        
+        // Rotation either truncates the file in place or replaces it with a new one;
+        // either way, read what is there now from the start
+        if recreated || new_size < self.offset || new_id != self.file_id {
+            let mut marker = if recreated {
+                "──────── File was recreated, reading it from the start ────────".to_string()
+            } else {
+                "──────── File was rotated, continuing from the start of the new file ────────".to_string()
+            };
+            // A file moved away may have been written to since the last read
+            let rotated_to = self.file_id
+                .filter(|_| new_id != self.file_id && self.backfill_rotated)
+                .and_then(|id| find_by_id(&self.path, id));
+            if let Some(rotated_to) = rotated_to {
+                // Nothing is written to it anymore, so its last line is complete
+                self.read_lines(&rotated_to, self.offset, &mut appended, usize::MAX, false);
+                let name = rotated_to.file_name().unwrap_or_default().to_string_lossy();
+                marker = format!("──────── File was rotated to {}, continuing from the start of the new file ────────", name);
+            }
+            appended.entries.push(LogEntry::note(&marker, appended.entries.len() + 1));
+            self.offset = 0;
+            self.format = None;
+            self.entry_open = false;
+            self.file_id = new_id;
+        }
+        
+        if new_size > self.offset {
+            let path = self.path.clone();
+            (self.offset, self.partial) = self.read_lines(&path, self.offset, &mut appended, MAX_BATCH_LINES, true);
+        }
+        appended
+    }
+
+    /// Parse the lines of `path` from byte `offset` on onto `appended`, until
+    /// it holds `max_entries`, grouping lines into entries by the parser's
+    /// entry start rule. When `offset` is within a line, its rest, and lines
+    /// continuing the entry it ends, become the continuation instead. The
+    /// file's format is found unless already known. Returns the offset after
+    /// the last byte read, and whether an unfinished last line was left to
+    /// read once it ends; with `hold_partial`, it is while the file is still
+    /// being written.
+    fn read_lines(
+        &mut self,
+        path: &Path,
+        offset: u64,
+        appended: &mut Appended,
+        max_entries: usize,
+        hold_partial: bool,
+    ) -> (u64, bool) {
+        let Ok(mut file) = fs::File::open(path) else {
+            return (offset, false);
+        };
+        let known = match self.format {
+            Some(known) => known,
+            None => match TextFormat::of_file(&mut file) {
+                Ok(found) => found,
+                Err(_) => return (offset, false),
+            },
+        };
+        // Until a line has ended, it isn't known how lines end
+        self.format = known.line_ending.map(|_| known);
+        let hold_partial = hold_partial && file.metadata().is_ok_and(|metadata| text_encoding::is_being_written(&metadata));
+        // Past the byte order mark, and to a whole character
+        let offset = known.encoding.align(offset.max(known.bom as u64));
+        // A line taken before it ended may have gone on since
+        let unit = known.encoding.unit_len() as u64;
+        let mut previous = Vec::new();
+        let mut within_line = offset >= known.bom as u64 + unit
+            && file.seek(SeekFrom::Start(offset - unit)).is_ok()
+            && file.by_ref().take(unit).read_to_end(&mut previous).is_ok()
+            && !known.ends_line(&previous);
+        let mut reader = BufReader::new(file);
+        if reader.seek(SeekFrom::Start(offset)).is_err() {
+            return (offset, false);
+        }
+        
+        let mut end = offset;
+        let mut line_buf = Vec::new();
+        while appended.entries.len() < max_entries {
+            let Ok(read) = known.read_line(&mut reader, &mut line_buf) else {
+                break;
+            };
+            if read == 0 {
+                break;
+            }
+            if hold_partial && !known.ends_line(&line_buf) {
+                return (end, true);
+            }
+            end += read as u64;
+            let text = known.decode(&line_buf);
+            let mut lines = text.lines().map(str::trim_end);
+            if std::mem::take(&mut within_line) {
+                if let Some(rest) = lines.next().filter(|rest| !rest.is_empty()) {
+                    appended.continuation.get_or_insert_with(String::new).push_str(rest);
+                }
+            }
+            for line in lines {
+                if let Some(line) = self.parser.append_line(line, &mut appended.entries, &mut self.entry_open) {
+                    let continuation = appended.continuation.get_or_insert_with(String::new);
+                    continuation.push('\n');
+                    continuation.push_str(&line);
+                }
+            }
+            line_buf.clear();
+        }
+        (end, false)
+    }
+}
+
+/// Identifies the file behind a path, to notice when it is replaced (e.g. by
+/// log rotation). This is the inode number, so only available on Unix.
+pub fn file_id(metadata: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Find the file next to `path` with id `id`, i.e. where a rotated log was moved.
+pub fn find_by_id(path: &Path, id: u64) -> Option<PathBuf> {
+    fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|other| other != path && fs::metadata(other).ok().and_then(|m| file_id(&m)) == Some(id))
+}
+
 impl Default for FileWatcher {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,385 @@
+use flate2::read::MultiGzDecoder;
+use rmpv::Value as Pack;
+use serde_json::{json, Map, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::elastic;
+use crate::file_open;
+
+/// How often the receiving threads check whether they should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// A message must arrive in full within this long once it has started.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Batches of records held for the app. Beyond it connections stop being
+/// read, so shippers buffer and retry instead of losing records.
+const MAX_WAITING_BATCHES: usize = 64;
+
+/// Where to listen, as edited in the Fluent Forward window.
+#[derive(Debug, Clone)]
+pub struct ForwardConfig {
+    pub bind: String,
+    pub port: u16,
+}
+
+impl Default for ForwardConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1".to_string(),
+            port: 24224,
+        }
+    }
+}
+
+/// Receives records from Fluentd or Fluent Bit `forward` outputs (and
+/// fluent-logger clients) over TCP, in any of the protocol's modes. Each
+/// record becomes a JSON line with its fields, the event time as
+/// `timestamp`, `log` as `message`, and the tag as `logger` unless the
+/// record has its own. Shared-key authentication and TLS aren't supported.
+pub struct ForwardListener {
+    description: String,
+    receiver: mpsc::Receiver<Vec<String>>,
+    stop: Arc<AtomicBool>,
+    received: usize,
+}
+
+impl ForwardListener {
+    pub fn start(config: &ForwardConfig) -> Result<Self, String> {
+        let address = format!("{}:{}", config.bind, config.port);
+        let listener = TcpListener::bind(&address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        listener.set_nonblocking(true).map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(MAX_WAITING_BATCHES);
+        let thread_stop = stop.clone();
+        thread::spawn(move || accept(listener, sender, thread_stop));
+        Ok(Self {
+            description: address,
+            receiver,
+            stop,
+            received: 0,
+        })
+    }
+
+    /// The address listened on, e.g. "127.0.0.1:24224".
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Records received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Lines received since the last call, one per record, in arrival order.
+    pub fn take_lines(&mut self) -> Vec<String> {
+        let lines: Vec<String> = self.receiver.try_iter().flatten().collect();
+        self.received += lines.len();
+        lines
+    }
+}
+
+impl Drop for ForwardListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn accept(listener: TcpListener, sender: mpsc::SyncSender<Vec<String>>, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (sender, stop) = (sender.clone(), stop.clone());
+                thread::spawn(move || {
+                    if let Err(e) = read_forward(stream, &sender, &stop) {
+                        app_error!("Error reading Fluent Forward connection from {}: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if is_timeout(&e) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                app_error!("Error accepting Fluent Forward connection: {}", e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Read messages until the shipper disconnects, acknowledging those that ask for it.
+fn read_forward(stream: TcpStream, sender: &mpsc::SyncSender<Vec<String>>, stop: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    while !stop.load(Ordering::Relaxed) {
+        // Wait for the next message in short steps, then give it time to arrive in full
+        reader.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+        match reader.fill_buf() {
+            Ok([]) => return Ok(()),
+            Ok(_) => {}
+            Err(e) if is_timeout(&e) => continue,
+            Err(e) => return Err(e),
+        }
+        reader.get_ref().set_read_timeout(Some(MESSAGE_TIMEOUT))?;
+        let message = rmpv::decode::read_value(&mut reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let (lines, chunk) = message_lines(&message)?;
+        // Waits while the app is behind, which holds back the shipper too
+        if !lines.is_empty() && sender.send(lines).is_err() {
+            return Ok(());
+        }
+        file_open::wake_ui();
+        if let Some(chunk) = chunk {
+            let ack = Pack::Map(vec![(Pack::from("ack"), Pack::from(chunk))]);
+            let mut response = Vec::new();
+            rmpv::encode::write_value(&mut response, &ack).map_err(io::Error::other)?;
+            writer.write_all(&response)?;
+        }
+    }
+    Ok(())
+}
+
+/// The lines of one `[tag, ...]` message, and the chunk id to acknowledge if asked.
+fn message_lines(message: &Pack) -> io::Result<(Vec<String>, Option<String>)> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("not a forward message: {}", what));
+    let parts = message.as_array().ok_or_else(|| invalid("expected an array"))?;
+    let tag = parts.first().and_then(text).ok_or_else(|| invalid("missing tag"))?;
+    let mut lines = Vec::new();
+    let option = match parts.get(1) {
+        // Forward mode: [tag, [[time, record], ...], option]
+        Some(Pack::Array(entries)) => {
+            for entry in entries {
+                if let Some([time, record]) = entry.as_array().map(Vec::as_slice) {
+                    lines.push(record_line(&tag, time, record));
+                }
+            }
+            parts.get(2)
+        }
+        // PackedForward mode: [tag, <concatenated [time, record]s>, option], maybe gzipped
+        Some(Pack::Binary(packed)) => {
+            lines = packed_lines(&tag, packed, parts.get(2))?;
+            parts.get(2)
+        }
+        Some(Pack::String(packed)) => {
+            lines = packed_lines(&tag, packed.as_bytes(), parts.get(2))?;
+            parts.get(2)
+        }
+        // Message mode: [tag, time, record, option]
+        Some(time) => {
+            lines.push(record_line(&tag, time, parts.get(2).unwrap_or(&Pack::Nil)));
+            parts.get(3)
+        }
+        None => return Err(invalid("missing entries")),
+    };
+    let chunk = option.and_then(|option| map_get(option, "chunk")).and_then(text);
+    Ok((lines, chunk))
+}
+
+fn packed_lines(tag: &str, packed: &[u8], option: Option<&Pack>) -> io::Result<Vec<String>> {
+    let compressed = option.and_then(|option| map_get(option, "compressed")).and_then(text);
+    let mut data = Vec::new();
+    if compressed.as_deref() == Some("gzip") {
+        MultiGzDecoder::new(packed).read_to_end(&mut data)?;
+    } else {
+        data.extend_from_slice(packed);
+    }
+    let mut lines = Vec::new();
+    let mut reader = &data[..];
+    while !reader.is_empty() {
+        let entry = rmpv::decode::read_value(&mut reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some([time, record]) = entry.as_array().map(Vec::as_slice) {
+            lines.push(record_line(tag, time, record));
+        }
+    }
+    Ok(lines)
+}
+
+fn map_get<'a>(map: &'a Pack, key: &str) -> Option<&'a Pack> {
+    map.as_map()?.iter().find(|(name, _)| name.as_str() == Some(key)).map(|(_, value)| value)
+}
+
+/// A string, or bytes as text since older shippers send strings as binary.
+fn text(value: &Pack) -> Option<String> {
+    match value {
+        Pack::String(text) => Some(String::from_utf8_lossy(text.as_bytes()).into_owned()),
+        Pack::Binary(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        _ => None,
+    }
+}
+
+/// The event time: whole seconds, or the EventTime extension (seconds and
+/// nanoseconds, big-endian).
+fn event_time(time: &Pack) -> Option<chrono::DateTime<chrono::Local>> {
+    let (seconds, nanos) = match time {
+        Pack::Ext(0, bytes) if bytes.len() == 8 => {
+            let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            let nanos = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+            (i64::from(seconds), nanos)
+        }
+        Pack::Integer(seconds) => (seconds.as_i64()?, 0),
+        Pack::F64(seconds) => (*seconds as i64, (seconds.fract() * 1e9) as u32),
+        _ => return None,
+    };
+    chrono::DateTime::from_timestamp(seconds, nanos).map(|time| time.with_timezone(&chrono::Local))
+}
+
+fn to_json(value: &Pack) -> Value {
+    match value {
+        Pack::Nil => Value::Null,
+        Pack::Boolean(flag) => json!(flag),
+        Pack::Integer(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(number), _) => json!(number),
+            (_, Some(number)) => json!(number),
+            _ => Value::Null,
+        },
+        Pack::F32(number) => json!(number),
+        Pack::F64(number) => json!(number),
+        Pack::String(_) | Pack::Binary(_) => json!(text(value)),
+        Pack::Array(items) => Value::Array(items.iter().map(to_json).collect()),
+        Pack::Map(pairs) => {
+            let fields = pairs.iter().map(|(key, value)| (text(key).unwrap_or_else(|| key.to_string()), to_json(value)));
+            Value::Object(fields.collect())
+        }
+        Pack::Ext(..) => match event_time(value) {
+            Some(time) => json!(time.to_rfc3339_opts(chrono::SecondsFormat::Millis, false)),
+            None => Value::Null,
+        },
+    }
+}
+
+/// A record as one JSON line with the parser's `timestamp`, `message` and
+/// `logger`. A `log` field holding a JSON line (e.g. from a container) is
+/// unpacked into the record.
+fn record_line(tag: &str, time: &Pack, record: &Pack) -> String {
+    let mut fields = Map::new();
+    if let Value::Object(record) = to_json(record) {
+        elastic::flatten("", &record, &mut fields);
+    }
+    if let Some(Value::String(log)) = fields.remove("log") {
+        let log = log.trim_end();
+        match serde_json::from_str::<Value>(log) {
+            Ok(Value::Object(inner)) => elastic::flatten("", &inner, &mut fields),
+            _ => {
+                fields.entry("message").or_insert_with(|| json!(log));
+            }
+        }
+    }
+    if let Some(time) = event_time(time) {
+        fields.insert("timestamp".to_string(), json!(time.to_rfc3339_opts(chrono::SecondsFormat::Millis, false)));
+    }
+    if !["logger", "logger_name", "class", "log.logger"].iter().any(|name| fields.contains_key(*name)) {
+        fields.insert("logger".to_string(), json!(tag));
+    }
+    Value::Object(fields).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+
+    fn record(pairs: &[(&str, &str)]) -> Pack {
+        Pack::Map(pairs.iter().map(|(key, value)| (Pack::from(*key), Pack::from(*value))).collect())
+    }
+
+    /// `[time, record]` entries one after another, as PackedForward sends them.
+    fn packed(entries: &[Pack]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for entry in entries {
+            rmpv::encode::write_value(&mut data, entry).unwrap();
+        }
+        data
+    }
+
+    fn parsed(line: &str) -> Map<String, Value> {
+        match serde_json::from_str(line).unwrap() {
+            Value::Object(fields) => fields,
+            other => panic!("not an object: {}", other),
+        }
+    }
+
+    fn seconds(fields: &Map<String, Value>) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(fields["timestamp"].as_str().unwrap()).unwrap().timestamp()
+    }
+
+    #[test]
+    fn reads_message_mode_with_a_chunk_to_acknowledge() {
+        let option = Pack::Map(vec![(Pack::from("chunk"), Pack::from("p8n9gmxTQVC8/nh2wlKKeQ=="))]);
+        let message = Pack::Array(vec![
+            Pack::from("app.access"),
+            Pack::from(1_441_588_984),
+            record(&[("message", "GET /index.html 200"), ("level", "info")]),
+            option,
+        ]);
+        let (lines, chunk) = message_lines(&message).unwrap();
+        assert_eq!(chunk.as_deref(), Some("p8n9gmxTQVC8/nh2wlKKeQ=="));
+        let fields = parsed(&lines[0]);
+        assert_eq!(fields["message"], "GET /index.html 200");
+        assert_eq!(fields["logger"], "app.access");
+        assert_eq!(seconds(&fields), 1_441_588_984);
+    }
+
+    #[test]
+    fn reads_forward_mode_with_event_times() {
+        let mut event_time = 1_700_000_000u32.to_be_bytes().to_vec();
+        event_time.extend_from_slice(&500_000_000u32.to_be_bytes());
+        let entries = Pack::Array(vec![
+            Pack::Array(vec![Pack::Ext(0, event_time), record(&[("message", "first")])]),
+            Pack::Array(vec![Pack::from(1_700_000_001), record(&[("message", "second"), ("logger", "db")])]),
+            // Not a [time, record] pair
+            Pack::from("stray"),
+        ]);
+        let (lines, chunk) = message_lines(&Pack::Array(vec![Pack::from("svc"), entries])).unwrap();
+        assert_eq!(chunk, None);
+        assert_eq!(lines.len(), 2);
+        let first = parsed(&lines[0]);
+        assert_eq!(first["message"], "first");
+        assert!(first["timestamp"].as_str().unwrap().contains(".500"));
+        let second = parsed(&lines[1]);
+        assert_eq!(second["logger"], "db");
+        assert_eq!(seconds(&second), 1_700_000_001);
+    }
+
+    #[test]
+    fn reads_packed_forward_mode_gzipped_or_not() {
+        // A container's JSON line in `log` is unpacked into the record
+        let data = packed(&[
+            Pack::Array(vec![Pack::from(1_700_000_000), record(&[("log", "{\"level\":\"warn\",\"msg\":\"slow\"}\n"), ("stream", "stdout")])]),
+            Pack::Array(vec![Pack::from(1_700_000_002), record(&[("log", "plain text\n")])]),
+        ]);
+        let (lines, _) = message_lines(&Pack::Array(vec![Pack::from("k8s"), Pack::Binary(data.clone())])).unwrap();
+        assert_eq!(lines.len(), 2);
+        let first = parsed(&lines[0]);
+        assert_eq!(first["level"], "warn");
+        assert_eq!(first["msg"], "slow");
+        assert_eq!(first["stream"], "stdout");
+        assert_eq!(parsed(&lines[1])["message"], "plain text");
+
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&data).unwrap();
+        let option = Pack::Map(vec![(Pack::from("compressed"), Pack::from("gzip"))]);
+        let message = Pack::Array(vec![Pack::from("k8s"), Pack::Binary(gzip.finish().unwrap()), option]);
+        assert_eq!(message_lines(&message).unwrap().0, lines);
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        assert!(message_lines(&Pack::from("not an array")).is_err());
+        assert!(message_lines(&Pack::Array(vec![])).is_err());
+        assert!(message_lines(&Pack::Array(vec![Pack::from(42), Pack::Array(vec![])])).is_err());
+        assert!(message_lines(&Pack::Array(vec![Pack::from("tag")])).is_err());
+        // Packed entries cut off halfway
+        let mut data = packed(&[Pack::Array(vec![Pack::from(1), record(&[("message", "cut")])])]);
+        data.truncate(data.len() - 2);
+        assert!(message_lines(&Pack::Array(vec![Pack::from("tag"), Pack::Binary(data)])).is_err());
+        // Said to be gzipped but isn't
+        let option = Pack::Map(vec![(Pack::from("compressed"), Pack::from("gzip"))]);
+        assert!(message_lines(&Pack::Array(vec![Pack::from("tag"), Pack::Binary(vec![1, 2, 3]), option])).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use egui::text::LayoutJob;
+use egui::{Color32, FontId, TextFormat};
+
+use crate::custom_format::{CustomFormat, FIELD_GROUPS};
+
+const SAMPLE_HINT: &str = "Paste a few sample lines here";
+const DEFAULT_PATTERN: &str =
+    r"^(?P<timestamp>\S+ \S+)\s+(?P<level>[A-Z]+)\s+\[(?P<thread>[^\]]*)\]\s+(?P<class>\S+)\s+(?P<message>.*)$";
+
+fn group_color(name: &str) -> Color32 {
+    match name {
+        "timestamp" => Color32::from_rgb(0x7a, 0xb8, 0xff),
+        "level" => Color32::from_rgb(0xff, 0xb8, 0x5c),
+        "thread" => Color32::from_rgb(0xb4, 0x8e, 0xff),
+        "class" => Color32::from_rgb(0x6f, 0xd0, 0x8c),
+        "message" => Color32::from_rgb(0xd0, 0xd0, 0xd0),
+        _ => Color32::from_rgb(0xff, 0x8f, 0xc8),
+    }
+}
+
+/// Dialog for writing a custom format regex against pasted sample lines,
+/// with the captured groups highlighted live.
+pub struct FormatBuilder {
+    pub open: bool,
+    name: String,
+    pattern: String,
+    timestamp_format: String,
+    entry_start: String,
+    sample: String,
+}
+
+impl FormatBuilder {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            name: String::new(),
+            pattern: DEFAULT_PATTERN.to_string(),
+            timestamp_format: "%Y-%m-%d %H:%M:%S%.3f".to_string(),
+            entry_start: String::new(),
+            sample: String::new(),
+        }
+    }
+
+    /// Open the dialog, seeded with sample lines (e.g. the head of the current file).
+    pub fn open_with_sample(&mut self, sample: String) {
+        if self.sample.trim().is_empty() {
+            self.sample = sample;
+        }
+        self.open = true;
+    }
+
+    /// Open the dialog to edit an existing format.
+    pub fn edit(&mut self, format: &CustomFormat) {
+        self.name = format.name.clone();
+        self.pattern = format.pattern.clone();
+        self.timestamp_format = format.timestamp_format.clone();
+        self.entry_start = format.entry_start.clone();
+        self.open = true;
+    }
+
+    /// Show the dialog. Returns the format when the user saves it.
+    pub fn show(&mut self, ctx: &egui::Context, font_size: f32) -> Option<CustomFormat> {
+        if !self.open {
+            return None;
+        }
+        let mut open = self.open;
+        let mut saved = None;
+
+        egui::Window::new("Custom Format Builder")
+            .open(&mut open)
+            .default_width(720.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("format_builder_fields").num_columns(2).show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut self.name);
+                    ui.end_row();
+
+                    ui.label("Regex:");
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.pattern)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_rows(2)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+
+                    ui.label("Timestamp format:");
+                    ui.add(egui::TextEdit::singleline(&mut self.timestamp_format).font(egui::TextStyle::Monospace))
+                        .on_hover_text("chrono format, e.g. %d.%m.%Y %H:%M:%S%.3f");
+                    ui.end_row();
+
+                    ui.label("Entry start:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.entry_start)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("Lines matching the regex above")
+                            .desired_width(f32::INFINITY),
+                    )
+                    .on_hover_text("Regex for lines that begin an entry, e.g. ^\\d{4}-; the lines after one continue it");
+                    ui.end_row();
+                });
+                ui.label(
+                    egui::RichText::new(format!("Named groups used as fields: {}", FIELD_GROUPS.join(", ")))
+                        .size(12.0)
+                        .weak(),
+                );
+
+                ui.add_space(6.0);
+                ui.label("Sample lines:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.sample)
+                        .font(egui::TextStyle::Monospace)
+                        .hint_text(SAMPLE_HINT)
+                        .desired_rows(5)
+                        .desired_width(f32::INFINITY),
+                );
+
+                ui.add_space(6.0);
+                ui.separator();
+                ui.label(egui::RichText::new("Preview").strong());
+
+                let format = CustomFormat {
+                    name: self.name.trim().to_string(),
+                    pattern: self.pattern.clone(),
+                    timestamp_format: self.timestamp_format.clone(),
+                    entry_start: self.entry_start.trim().to_string(),
+                };
+                let regex = match format.compile() {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        ui.colored_label(Color32::from_rgb(0xe0, 0x50, 0x50), e);
+                        return;
+                    }
+                };
+                if let Err(e) = format.compile_entry_start() {
+                    ui.colored_label(Color32::from_rgb(0xe0, 0x50, 0x50), format!("Entry start: {}", e));
+                    return;
+                }
+
+                egui::ScrollArea::vertical().max_height(260.0).show(ui, |ui| {
+                    let lines: Vec<&str> = self.sample.lines().filter(|l| !l.trim().is_empty()).collect();
+                    let mut matched = 0;
+                    for line in &lines {
+                        let Some(caps) = regex.captures(line) else {
+                            ui.label(
+                                egui::RichText::new(format!("✗ {}", line))
+                                    .monospace()
+                                    .color(Color32::from_rgb(0xe0, 0x50, 0x50)),
+                            );
+                            continue;
+                        };
+                        matched += 1;
+
+                        // The line with every named group painted in its color
+                        let mut spans: Vec<(usize, usize, &str)> = regex
+                            .capture_names()
+                            .flatten()
+                            .filter_map(|name| caps.name(name).map(|m| (m.start(), m.end(), name)))
+                            .collect();
+                        spans.sort_by_key(|(start, _, _)| *start);
+
+                        let mut job = LayoutJob::default();
+                        let plain = TextFormat {
+                            font_id: FontId::monospace(font_size),
+                            color: ui.visuals().text_color(),
+                            ..Default::default()
+                        };
+                        let mut pos = 0;
+                        for (start, end, name) in spans {
+                            if start < pos {
+                                continue; // nested group, already painted by its parent
+                            }
+                            job.append(&line[pos..start], 0.0, plain.clone());
+                            job.append(
+                                &line[start..end],
+                                0.0,
+                                TextFormat {
+                                    font_id: FontId::monospace(font_size),
+                                    color: Color32::BLACK,
+                                    background: group_color(name),
+                                    ..Default::default()
+                                },
+                            );
+                            pos = end;
+                        }
+                        job.append(&line[pos..], 0.0, plain);
+                        ui.label(job);
+
+                        // Field -> captured value table
+                        ui.indent(line.to_string(), |ui| {
+                            egui::Grid::new(("format_builder_groups", *line)).num_columns(2).show(ui, |ui| {
+                                for name in regex.capture_names().flatten() {
+                                    let value = caps.name(name).map(|m| m.as_str()).unwrap_or("");
+                                    ui.label(egui::RichText::new(name).color(group_color(name)));
+                                    let mut text = egui::RichText::new(value).monospace();
+                                    if !FIELD_GROUPS.contains(&name) {
+                                        text = text.weak();
+                                    }
+                                    ui.label(text);
+                                    ui.end_row();
+                                }
+                                if let Some(ts) = caps.name("timestamp") {
+                                    ui.label("parsed time");
+                                    match format.parse_timestamp(ts.as_str()) {
+                                        Ok(time) => ui.label(time.to_string()),
+                                        Err(e) => ui.colored_label(Color32::from_rgb(0xe0, 0x50, 0x50), e),
+                                    };
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                        ui.add_space(4.0);
+                    }
+                    if !lines.is_empty() {
+                        ui.label(format!("{} of {} lines matched", matched, lines.len()));
+                    }
+                });
+
+                ui.separator();
+                let can_save = !format.name.is_empty();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save Format"))
+                    .on_disabled_hover_text("Give the format a name first")
+                    .clicked()
+                {
+                    saved = Some(format);
+                }
+            });
+
+        self.open = open && saved.is_none();
+        saved
+    }
+}
+
+impl Default for FormatBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,326 @@
+use serde_json::{json, Map, Value};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::azure::AzureQuery;
+use crate::elastic;
+use crate::file_open;
+use crate::live_stream::LiveStream;
+use crate::source_event::{self, SourceAction, SourceEvent};
+
+const ENTRIES_URL: &str = "https://logging.googleapis.com/v2/entries:list";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Most entries Cloud Logging returns per page.
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// A Google Cloud Logging query, as edited in the cloud logs window.
+#[derive(Debug, Clone)]
+pub struct GcpQuery {
+    /// Project id, or a full resource name (`projects/..`, `folders/..`, `organizations/..`)
+    pub project: String,
+    /// Monitored resource type, e.g. `k8s_container`; empty for all
+    pub resource_type: String,
+    /// Logging query language filter, e.g. `severity>=WARNING`
+    pub filter: String,
+    /// How far back to read, in minutes
+    pub lookback_minutes: u32,
+    /// Most entries read
+    pub max_entries: u32,
+}
+
+impl Default for GcpQuery {
+    fn default() -> Self {
+        Self {
+            project: String::new(),
+            resource_type: String::new(),
+            filter: String::new(),
+            lookback_minutes: 60,
+            max_entries: 10_000,
+        }
+    }
+}
+
+impl GcpQuery {
+    /// `project: filter`, as shown in the header.
+    pub fn description(&self) -> String {
+        let filter = if self.filter.trim().is_empty() { "*" } else { self.filter.trim() };
+        format!("{}: {}", self.project.trim(), filter)
+    }
+
+    /// The resource name entries are read from.
+    fn resource_name(&self) -> String {
+        let project = self.project.trim();
+        if project.contains('/') {
+            project.to_string()
+        } else {
+            format!("projects/{}", project)
+        }
+    }
+
+    /// The full filter: the lookback window, the resource type and the user's filter.
+    fn full_filter(&self) -> String {
+        let start = chrono::Utc::now() - chrono::Duration::minutes(i64::from(self.lookback_minutes));
+        let mut filter = format!("timestamp >= \"{}\"", start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        if !self.resource_type.trim().is_empty() {
+            filter.push_str(&format!(" AND resource.type = \"{}\"", self.resource_type.trim()));
+        }
+        if !self.filter.trim().is_empty() {
+            filter.push_str(&format!(" AND ({})", self.filter.trim()));
+        }
+        filter
+    }
+}
+
+/// Which service the cloud logs window queries.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CloudLogs {
+    #[default]
+    Google,
+    Azure,
+}
+
+/// The Query Cloud Logs window, for Google Cloud Logging or Azure Monitor.
+/// Either query ends with its results, so there is nothing to disconnect.
+#[derive(Default)]
+pub struct CloudLogsDialog {
+    pub open: bool,
+    pub service: CloudLogs,
+    pub gcp_query: GcpQuery,
+    pub azure_query: AzureQuery,
+    /// Why the last query couldn't be run
+    pub error: Option<String>,
+}
+
+impl CloudLogsDialog {
+    pub fn show(&mut self, ctx: &egui::Context, stream: Option<&LiveStream>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Query Cloud Logs")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.service, CloudLogs::Google, "Google Cloud Logging");
+                    ui.selectable_value(&mut self.service, CloudLogs::Azure, "Azure Monitor");
+                });
+                ui.separator();
+                let lookback_minutes = match self.service {
+                    CloudLogs::Google => {
+                        let query = &mut self.gcp_query;
+                        egui::Grid::new("gcp_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Project:");
+                            ui.text_edit_singleline(&mut query.project)
+                                .on_hover_text("Project id, or folders/.. or organizations/.. for their logs");
+                            ui.end_row();
+                            ui.label("Resource type:");
+                            ui.add(egui::TextEdit::singleline(&mut query.resource_type).hint_text("all, or e.g. k8s_container"));
+                            ui.end_row();
+                            ui.label("Filter:");
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut query.filter)
+                                    .desired_width(300.0)
+                                    .font(egui::TextStyle::Monospace)
+                                    .hint_text("severity>=WARNING"),
+                            );
+                            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                action = Some(SourceAction::Open);
+                            }
+                            ui.end_row();
+                            ui.label("Limit:");
+                            ui.add(egui::DragValue::new(&mut query.max_entries).speed(100.0).clamp_range(1..=100_000).suffix(" entries"));
+                            ui.end_row();
+                        });
+                        ui.label("Signs in with GOOGLE_OAUTH_ACCESS_TOKEN or gcloud's credentials.");
+                        &mut query.lookback_minutes
+                    }
+                    CloudLogs::Azure => {
+                        let query = &mut self.azure_query;
+                        egui::Grid::new("azure_grid").num_columns(2).show(ui, |ui| {
+                            ui.label("Workspace id:");
+                            ui.add(egui::TextEdit::singleline(&mut query.workspace).desired_width(300.0));
+                            ui.end_row();
+                            ui.label("Query:");
+                            ui.add(
+                                egui::TextEdit::multiline(&mut query.query)
+                                    .desired_width(300.0)
+                                    .desired_rows(4)
+                                    .font(egui::TextStyle::Monospace)
+                                    .hint_text("AppTraces\n| where SeverityLevel >= 2"),
+                            );
+                            ui.end_row();
+                        });
+                        ui.label("Signs in with AZURE_LOG_ANALYTICS_TOKEN or the Azure CLI's login.");
+                        &mut query.lookback_minutes
+                    }
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Last:");
+                    source_event::lookback_choices(ui, lookback_minutes);
+                });
+                source_event::show_errors(ui, stream.map(LiveStream::events), self.error.as_deref());
+                if ui.button("Query").clicked() {
+                    action = Some(SourceAction::Open);
+                }
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// Page through the query's entries, oldest first, each becoming one JSON
+/// line with `timestamp`, `level` and `message` fields.
+pub fn run(query: &GcpQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    if let Err(e) = read_entries(query, sender, stop) {
+        sender.send(SourceEvent::Disconnected(e)).ok();
+    }
+    sender.send(SourceEvent::Ended).ok();
+    file_open::wake_ui();
+}
+
+fn read_entries(query: &GcpQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) -> Result<(), String> {
+    let token = access_token()?;
+    let max_entries = query.max_entries.max(1);
+    let mut body = json!({
+        "resourceNames": [query.resource_name()],
+        "filter": query.full_filter(),
+        "orderBy": "timestamp asc",
+    });
+    let mut read = 0;
+    let mut first = true;
+    while read < max_entries && !stop.load(Ordering::Relaxed) {
+        body["pageSize"] = json!((max_entries - read).min(MAX_PAGE_SIZE));
+        let response = post(ENTRIES_URL, &token, &body)?;
+        if first && sender.send(SourceEvent::Connected).is_err() {
+            return Ok(());
+        }
+        first = false;
+        let entries = response["entries"].as_array().cloned().unwrap_or_default();
+        read += entries.len() as u32;
+        let lines: Vec<String> = entries.iter().map(entry_line).collect();
+        if !lines.is_empty() && sender.send(SourceEvent::Lines(lines)).is_err() {
+            return Ok(());
+        }
+        file_open::wake_ui();
+        match response["nextPageToken"].as_str() {
+            Some(token) if !token.is_empty() => body["pageToken"] = json!(token),
+            _ => return Ok(()),
+        }
+    }
+    if read >= max_entries {
+        let note = format!("──────── Showing the first {} entries; narrow the filter or time range to see the rest ────────", read);
+        sender.send(SourceEvent::Lines(vec![note])).ok();
+    }
+    Ok(())
+}
+
+/// Post `body` and read the JSON response, with Google's error message on failure.
+fn post(url: &str, token: &str, body: &Value) -> Result<Value, String> {
+    let response = ureq::post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                let text = response.into_string().unwrap_or_default();
+                // Errors come as {"error": {"message": ...}}
+                let message = serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .and_then(|error| error["error"]["message"].as_str().map(str::to_string))
+                    .unwrap_or(text);
+                format!("Query failed ({}): {}", status, message.trim())
+            }
+            e => format!("Failed to reach Cloud Logging: {}", e),
+        })?;
+    serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the response: {}", e))
+}
+
+/// An OAuth token from `GOOGLE_OAUTH_ACCESS_TOKEN`, else from gcloud's
+/// application default credentials, else from its logged-in account.
+pub fn access_token() -> Result<String, String> {
+    if let Ok(token) = std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let gcloud = if cfg!(windows) { "gcloud.cmd" } else { "gcloud" };
+    let mut error = String::new();
+    for args in [&["auth", "application-default", "print-access-token"][..], &["auth", "print-access-token"][..]] {
+        match Command::new(gcloud).args(args).output() {
+            Ok(output) if output.status.success() => return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+            Ok(output) => error = String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            Err(e) => return Err(format!("Failed to run gcloud for a token: {}", e)),
+        }
+    }
+    Err(format!("No Google Cloud credentials: run `gcloud auth login` ({})", error))
+}
+
+/// An entry as one JSON line: its payload's fields plus the entry's
+/// timestamp, level, message, log name and resource.
+fn entry_line(entry: &Value) -> String {
+    let mut fields = Map::new();
+    if let Some(payload) = entry["jsonPayload"].as_object() {
+        elastic::flatten("", payload, &mut fields);
+    }
+    let payload_message = fields.remove("message").or_else(|| fields.remove("msg"));
+    let message = entry["textPayload"]
+        .as_str()
+        .or_else(|| payload_message.as_ref().and_then(Value::as_str))
+        .map(str::to_string)
+        .or_else(|| audit_summary(&entry["protoPayload"]))
+        .or_else(|| request_summary(&entry["httpRequest"]))
+        .unwrap_or_default();
+    fields.insert("timestamp".to_string(), entry["timestamp"].clone());
+    if let Some(level) = entry["severity"].as_str().and_then(level_name) {
+        fields.insert("level".to_string(), json!(level));
+    }
+    fields.insert("message".to_string(), json!(message));
+    if let Some(log) = entry["logName"].as_str() {
+        // projects/p/logs/<url-encoded name>
+        let name = log.rsplit("/logs/").next().unwrap_or(log);
+        fields.insert("logger".to_string(), json!(name.replace("%2F", "/")));
+    }
+    if let Some(resource_type) = entry["resource"]["type"].as_str() {
+        fields.insert("resource.type".to_string(), json!(resource_type));
+    }
+    if let Some(labels) = entry["resource"]["labels"].as_object() {
+        elastic::flatten("resource.labels", labels, &mut fields);
+    }
+    Value::Object(fields).to_string()
+}
+
+/// `service method by principal` for audit log entries.
+fn audit_summary(payload: &Value) -> Option<String> {
+    let method = payload["methodName"].as_str()?;
+    let mut summary = format!("{} {}", payload["serviceName"].as_str().unwrap_or_default(), method);
+    if let Some(principal) = payload["authenticationInfo"]["principalEmail"].as_str() {
+        summary.push_str(&format!(" by {}", principal));
+    }
+    if let Some(status) = payload["status"]["message"].as_str() {
+        summary.push_str(&format!(": {}", status));
+    }
+    Some(summary.trim().to_string())
+}
+
+/// `METHOD url status` for request log entries without a payload.
+fn request_summary(request: &Value) -> Option<String> {
+    let url = request["requestUrl"].as_str()?;
+    Some(format!("{} {} {}", request["requestMethod"].as_str().unwrap_or_default(), url, request["status"]).trim().to_string())
+}
+
+/// The parser's level for a Cloud Logging severity; none for `DEFAULT`.
+fn level_name(severity: &str) -> Option<&'static str> {
+    match severity {
+        "DEBUG" => Some("DEBUG"),
+        "INFO" | "NOTICE" => Some("INFO"),
+        "WARNING" => Some("WARN"),
+        "ERROR" | "CRITICAL" | "ALERT" | "EMERGENCY" => Some("ERROR"),
+        _ => None,
+    }
+}
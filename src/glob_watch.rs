@@ -0,0 +1,123 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use crate::file_open;
+
+/// True when the file name part of `path` has `*` or `?` wildcards.
+pub fn is_glob(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().contains(['*', '?']))
+        .unwrap_or(false)
+}
+
+/// A regex matching the whole file names a pattern like `app-*.log` stands for.
+pub fn name_regex(pattern: &str) -> Result<Regex, String> {
+    let regex = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", "."));
+    Regex::new(&regex).map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// The directory a pattern's files are in; the current one for a bare name.
+fn pattern_dir(pattern: &Path) -> &Path {
+    pattern.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."))
+}
+
+/// Follows the newest file matching a pattern like `/var/log/myapp/app-*.log`,
+/// for logs that start a new file per day or per run. Wildcards are only
+/// supported in the file name.
+pub struct GlobWatch {
+    pattern: PathBuf,
+    name_regex: Regex,
+    /// Files that existed when the current one was picked; only files
+    /// appearing after that count as newer
+    seen: HashSet<PathBuf>,
+    _watcher: RecommendedWatcher,
+    receiver: mpsc::Receiver<notify::Result<Event>>,
+}
+
+impl GlobWatch {
+    pub fn new(pattern: PathBuf) -> Result<Self, String> {
+        let name = pattern.file_name().ok_or("The pattern has no file name")?.to_string_lossy();
+        let dir = pattern_dir(&pattern);
+        if !dir.is_dir() {
+            return Err(format!("{} is not a directory", dir.display()));
+        }
+        let name_regex = name_regex(&name)?;
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            if tx.send(event).is_ok() {
+                file_open::wake_ui();
+            }
+        })
+        .map_err(|e| format!("Failed to watch directory: {}", e))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+        Ok(Self {
+            pattern,
+            name_regex,
+            seen: HashSet::new(),
+            _watcher: watcher,
+            receiver: rx,
+        })
+    }
+
+    pub fn pattern(&self) -> &Path {
+        &self.pattern
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        path.parent() == self.pattern.parent()
+            && path.file_name().is_some_and(|name| self.name_regex.is_match(&name.to_string_lossy()))
+    }
+
+    /// Matching files, oldest first by modification time (then name).
+    fn matching_files(&self) -> Vec<PathBuf> {
+        let Ok(read_dir) = fs::read_dir(pattern_dir(&self.pattern)) else {
+            return Vec::new();
+        };
+        let mut files: Vec<_> = read_dir
+            .flatten()
+            .filter(|e| self.name_regex.is_match(&e.file_name().to_string_lossy()))
+            .filter_map(|e| {
+                let metadata = e.metadata().ok()?;
+                metadata.is_file().then(|| (metadata.modified().ok(), self.pattern.with_file_name(e.file_name())))
+            })
+            .collect();
+        files.sort();
+        files.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Pick the newest matching file to follow.
+    pub fn newest(&mut self) -> Option<PathBuf> {
+        let files = self.matching_files();
+        let newest = files.last().cloned();
+        self.seen = files.into_iter().collect();
+        newest
+    }
+
+    /// Drain watcher events. Returns the newest matching file that appeared
+    /// since the current one was picked, if any.
+    pub fn check_for_newer(&mut self) -> Option<PathBuf> {
+        let mut changed = false;
+        while let Ok(event) = self.receiver.try_recv() {
+            if let Ok(event) = event {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return None;
+        }
+        let files = self.matching_files();
+        let newest = files.iter().rev().find(|file| !self.seen.contains(*file)).cloned()?;
+        self.seen = files.into_iter().collect();
+        Some(newest)
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::io::{self, Write};
+
+use crate::cli::Cli;
+use crate::config::AppConfig;
+use crate::custom_format;
+use crate::devtools;
+use crate::plugins;
+use crate::log_parser::{LogLevel, LogParser};
+use crate::redaction::Redactor;
+use crate::search::SearchState;
+use crate::text_encoding::TextFormat;
+
+/// Run the parse/filter/search pipeline without a window and print the result.
+/// Returns the process exit code: 0 if anything was printed, 1 if nothing matched.
+pub fn run(cli: &Cli) -> Result<i32, String> {
+    let request = cli.open_request().ok_or("--no-gui needs a file to read")?;
+    let path = &request.path;
+    let content = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let content = TextFormat::detect(&content).decode(&content);
+    let content = devtools::convert(&content).unwrap_or(content);
+
+    let mut parser = LogParser::new();
+    parser.set_custom_formats(&custom_format::load_custom_formats());
+    parser.format = cli.format.clone();
+    parser.plugins = plugins::load_plugins();
+    let entries = parser.parse_file(&content);
+
+    let mut search = SearchState::new();
+    if let Some(ref query) = cli.search {
+        search.query = query.clone();
+        search.use_regex = cli.regex;
+        search.update_search(&entries);
+        if let Some(error) = search.regex_error {
+            return Err(format!("Invalid --search regex: {}", error));
+        }
+    }
+
+    // Output is masked like the viewer's exports
+    let redactor = AppConfig::try_load().map(|config| Redactor::new(&config.redaction)).unwrap_or_default();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut printed = 0;
+
+    for (idx, entry) in entries.iter().enumerate() {
+        if !cli.level.is_empty() && !cli.level.contains(&entry.level) {
+            continue;
+        }
+        if !search.query.is_empty() && !search.is_match(idx) {
+            continue;
+        }
+
+        let result = if cli.json {
+            writeln!(out, "{}", redactor.redact(&entry.to_json().to_string()))
+        } else if cli.color {
+            writeln!(out, "{}{}\x1b[0m", ansi_color(&entry.level), redactor.redact(&entry.raw_line))
+        } else {
+            writeln!(out, "{}", redactor.redact(&entry.raw_line))
+        };
+
+        // Stop quietly when the reader goes away (e.g. piped into `head`)
+        if result.is_err() {
+            break;
+        }
+        printed += 1;
+    }
+
+    Ok(if printed > 0 { 0 } else { 1 })
+}
+
+fn ansi_color(level: &LogLevel) -> &'static str {
+    match level {
+        LogLevel::Error => "\x1b[31m",
+        LogLevel::Warn => "\x1b[33m",
+        LogLevel::Debug => "\x1b[32m",
+        LogLevel::Trace => "\x1b[2m",
+        LogLevel::Info | LogLevel::Unknown => "",
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{NaiveDate, Timelike};
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+use std::collections::BTreeMap;
+
+use crate::log_parser::{LogEntry, LogLevel};
+
+const CELL: f32 = 18.0;
+const LABEL_WIDTH: f32 = 84.0;
+
+#[derive(Default, Clone, Copy)]
+struct Cell {
+    total: usize,
+    errors: usize,
+    first_entry: Option<usize>,
+}
+
+/// Entry and error counts per calendar day and hour.
+pub struct Heatmap {
+    days: Vec<(NaiveDate, [Cell; 24])>,
+}
+
+pub fn build(entries: &[LogEntry]) -> Heatmap {
+    let mut days: BTreeMap<NaiveDate, [Cell; 24]> = BTreeMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        let Some(time) = entry.time_ms().and_then(chrono::DateTime::from_timestamp_millis) else {
+            continue;
+        };
+        let cell = &mut days.entry(time.date_naive()).or_insert([Cell::default(); 24])[time.hour() as usize];
+        cell.total += 1;
+        if entry.level == LogLevel::Error {
+            cell.errors += 1;
+        }
+        cell.first_entry.get_or_insert(idx);
+    }
+    Heatmap { days: days.into_iter().collect() }
+}
+
+impl Heatmap {
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty()
+    }
+
+    /// Draw one row per day and one column per hour, shaded by entry count (or
+    /// error count). Returns the first entry of the clicked cell.
+    pub fn show(&self, ui: &mut egui::Ui, errors_only: bool, color: Color32) -> Option<usize> {
+        let count = |cell: &Cell| if errors_only { cell.errors } else { cell.total };
+        let max = self.days.iter().flat_map(|(_, hours)| hours.iter().map(count)).max().unwrap_or(0).max(1);
+
+        // Hour labels
+        let (header, _) = ui.allocate_exact_size(Vec2::new(LABEL_WIDTH + CELL * 24.0, 14.0), Sense::hover());
+        for hour in (0..24).step_by(3) {
+            ui.painter().text(
+                Pos2::new(header.left() + LABEL_WIDTH + hour as f32 * CELL, header.top()),
+                egui::Align2::LEFT_TOP,
+                format!("{:02}", hour),
+                egui::FontId::proportional(11.0),
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        let mut clicked = None;
+        for (day, hours) in &self.days {
+            let (row, _) = ui.allocate_exact_size(Vec2::new(LABEL_WIDTH + CELL * 24.0, CELL), Sense::hover());
+            ui.painter().text(
+                Pos2::new(row.left(), row.center().y),
+                egui::Align2::LEFT_CENTER,
+                day.format("%a %Y-%m-%d").to_string(),
+                egui::FontId::proportional(11.0),
+                ui.visuals().text_color(),
+            );
+            for (hour, cell) in hours.iter().enumerate() {
+                let rect = Rect::from_min_size(
+                    Pos2::new(row.left() + LABEL_WIDTH + hour as f32 * CELL, row.top()),
+                    Vec2::splat(CELL - 2.0),
+                );
+                let response = ui.interact(rect, ui.id().with((day, hour)), Sense::click());
+                let fill = if count(cell) == 0 {
+                    ui.visuals().extreme_bg_color
+                } else {
+                    // Keep even a single entry visible against the background
+                    color.gamma_multiply(0.15 + 0.85 * count(cell) as f32 / max as f32)
+                };
+                ui.painter().rect_filled(rect, 2.0, fill);
+                if response.hovered() {
+                    ui.painter().rect_stroke(rect, 2.0, Stroke::new(1.0, ui.visuals().strong_text_color()));
+                }
+                let response = response.on_hover_text(format!(
+                    "{} {:02}:00–{:02}:59\n{} entries, {} errors",
+                    day, hour, hour, cell.total, cell.errors
+                ));
+                if response.clicked() {
+                    clicked = cell.first_entry;
+                }
+            }
+        }
+        clicked
+    }
+}
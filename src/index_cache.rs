@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::log_parser::{self, EntrySummary, LogEntry, LogLevel, LogParser};
+
+/// Smaller logs parse quickly enough that an index isn't worth the disk space.
+const MIN_CONTENT_LEN: usize = 4 * 1024 * 1024;
+
+const MAGIC: &[u8] = b"LOGROCKET-INDEX 1\n";
+
+/// Bytes per entry: offset, length, line number, level, kind and time.
+const RECORD_LEN: usize = 8 + 4 + 8 + 1 + 1 + 8;
+
+/// Stored in place of a missing timestamp.
+const NO_TIME: i64 = i64::MIN;
+
+/// Most bytes the saved indexes take together; the least recently used go first.
+const MAX_CACHE_LEN: u64 = 1024 * 1024 * 1024;
+
+/// What an index was built from. Any difference means the file changed (or is
+/// parsed differently) and the index is ignored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexKey {
+    path: PathBuf,
+    size: u64,
+    modified_ms: u64,
+    /// Bytes of the file that were parsed; less than `size` for a tail window
+    content_len: usize,
+    parser: String,
+}
+
+impl IndexKey {
+    /// Key for `content` read from `path`, or None when the content is too
+    /// small to index or plugins may change how lines read.
+    pub fn new(path: &Path, content: &str, parser: &LogParser) -> Option<Self> {
+        if content.len() < MIN_CONTENT_LEN || !parser.reads_lines_verbatim() {
+            return None;
+        }
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified_ms: modified.as_millis() as u64,
+            content_len: content.len(),
+            parser: parser.signature(),
+        })
+    }
+
+    /// Where the index of the file is saved, named by a hash of its path
+    /// that stays the same across builds and Rust versions.
+    fn cache_path(&self) -> Option<PathBuf> {
+        let name = format!("{:016x}.idx", fnv1a(self.path.to_string_lossy().as_bytes()));
+        config::config_dir().map(|dir| dir.join("index").join(name))
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// One entry of an index, located by its byte span in the content.
+struct Record {
+    offset: u64,
+    len: u32,
+    summary: EntrySummary,
+}
+
+/// Collects the index of a parse as its entries go by, to save once complete.
+pub struct IndexBuilder {
+    key: IndexKey,
+    line_starts: Vec<usize>,
+    records: Vec<Record>,
+    /// False once an entry didn't match its span in the content
+    valid: bool,
+    /// Whether the parser collapses progress lines, as the spans' text must be
+    collapse_progress: bool,
+}
+
+impl IndexBuilder {
+    pub fn new(key: IndexKey, content: &str, parser: &LogParser) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { key, line_starts, records: Vec::new(), valid: true, collapse_progress: parser.collapse_progress }
+    }
+
+    /// Record `entry`. Also parses its time, which the index stores.
+    pub fn push(&mut self, content: &str, entry: &LogEntry) {
+        if !self.valid {
+            return;
+        }
+        let first = entry.line_number.saturating_sub(1);
+        let last = first + entry.raw_line.matches('\n').count();
+        let (Some(&start), Some(&last_start)) = (self.line_starts.get(first), self.line_starts.get(last)) else {
+            self.valid = false;
+            return;
+        };
+        let mut end = self.line_starts.get(last + 1).map_or(content.len(), |next| next - 1);
+        if content[last_start..end].ends_with('\r') {
+            end -= 1;
+        }
+        if entry_text(content, start, end, self.collapse_progress) != entry.raw_line.as_str() {
+            self.valid = false;
+            return;
+        }
+        self.records.push(Record {
+            offset: start as u64,
+            len: (end - start) as u32,
+            summary: entry.summary(),
+        });
+    }
+
+    /// Write the index, replacing any older one for the same file.
+    pub fn save(self) -> Result<(), String> {
+        if !self.valid {
+            return Err("Entries don't match the file's lines".to_string());
+        }
+        let path = self.key.cache_path().ok_or("No config directory available")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create index directory: {}", e))?;
+        }
+        let key = serde_json::to_string(&self.key).map_err(|e| format!("Failed to serialize index key: {}", e))?;
+        let file = fs::File::create(&path).map_err(|e| format!("Failed to create index: {}", e))?;
+        let mut out = BufWriter::new(file);
+        let mut write = || -> std::io::Result<()> {
+            out.write_all(MAGIC)?;
+            out.write_all(key.as_bytes())?;
+            out.write_all(b"\n")?;
+            for record in &self.records {
+                out.write_all(&record.offset.to_le_bytes())?;
+                out.write_all(&record.len.to_le_bytes())?;
+                out.write_all(&(record.summary.line_number as u64).to_le_bytes())?;
+                out.write_all(&[level_code(&record.summary.level), record.summary.kind])?;
+                out.write_all(&record.summary.time_ms.unwrap_or(NO_TIME).to_le_bytes())?;
+            }
+            out.flush()
+        };
+        write().map_err(|e| format!("Failed to write index: {}", e))?;
+        if let Some(dir) = path.parent() {
+            prune(dir, MAX_CACHE_LEN);
+        }
+        Ok(())
+    }
+}
+
+/// Delete the least recently used indexes in `dir` until they take at most
+/// `max_len` bytes together. Loading an index marks it used.
+fn prune(dir: &Path, max_len: u64) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut indexes: Vec<(SystemTime, u64, PathBuf)> = read_dir
+        .flatten()
+        .filter(|item| item.path().extension().is_some_and(|ext| ext == "idx"))
+        .filter_map(|item| {
+            let metadata = item.metadata().ok()?;
+            Some((metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len(), item.path()))
+        })
+        .collect();
+    let mut total: u64 = indexes.iter().map(|(_, len, _)| len).sum();
+    // Oldest first
+    indexes.sort();
+    for (_, len, path) in indexes {
+        if total <= max_len {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            Err(e) => app_warn!("Error removing index {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Rebuild the entries of `content` from a saved index, or None when there is
+/// no index for `key` or it doesn't fit the content.
+pub fn load(key: &IndexKey, content: &str, parser: &LogParser) -> Option<Vec<LogEntry>> {
+    let path = key.cache_path()?;
+    let data = fs::read(&path).ok()?;
+    let data = data.strip_prefix(MAGIC)?;
+    let key_end = data.iter().position(|&b| b == b'\n')?;
+    let saved: IndexKey = serde_json::from_slice(&data[..key_end]).ok()?;
+    if saved != *key {
+        return None;
+    }
+    let records = &data[key_end + 1..];
+    if records.len() % RECORD_LEN != 0 {
+        return None;
+    }
+
+    let mut entries = Vec::with_capacity(records.len() / RECORD_LEN);
+    for record in records.chunks_exact(RECORD_LEN) {
+        let offset = u64::from_le_bytes(record[0..8].try_into().ok()?) as usize;
+        let len = u32::from_le_bytes(record[8..12].try_into().ok()?) as usize;
+        let line_number = u64::from_le_bytes(record[12..20].try_into().ok()?) as usize;
+        let level = LogLevel::ALL.get(record[20] as usize)?.clone();
+        let time = i64::from_le_bytes(record[22..30].try_into().ok()?);
+        let end = offset.checked_add(len)?;
+        if end > content.len() || !content.is_char_boundary(offset) || !content.is_char_boundary(end) {
+            return None;
+        }
+        let summary = EntrySummary {
+            line_number,
+            level,
+            kind: record[21],
+            time_ms: (time != NO_TIME).then_some(time),
+        };
+        entries.push(parser.restore_entry(entry_text(content, offset, end, parser.collapse_progress).into_owned(), &summary));
+    }
+    // Recently used indexes are the last to be pruned
+    if let Err(e) = fs::File::options().write(true).open(&path).and_then(|file| file.set_modified(SystemTime::now())) {
+        app_warn!("Error marking index {} used: {}", path.display(), e);
+    }
+    Some(entries)
+}
+
+/// The text of an entry spanning `start..end`: its lines joined by `\n`, as
+/// the parser builds them, collapsed like progress lines when `collapse` is set.
+fn entry_text(content: &str, start: usize, end: usize, collapse: bool) -> Cow<'_, str> {
+    let text = &content[start..end];
+    if text.contains('\r') {
+        let lines: Vec<Cow<str>> = text
+            .lines()
+            .map(|line| if collapse { log_parser::collapse_progress(line) } else { Cow::Borrowed(line) })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn level_code(level: &LogLevel) -> u8 {
+    LogLevel::ALL.iter().position(|l| l == level).unwrap_or(0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_indexes_by_a_stable_hash() {
+        // Reference values of 64-bit FNV-1a
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn prunes_the_least_recently_used_indexes() {
+        let dir = std::env::temp_dir().join(format!("logrocket-index-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (name, age_secs) in [("old.idx", 300), ("used.idx", 10), ("mid.idx", 100)] {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_len(100).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age_secs)).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), [0; 500]).unwrap();
+        
+        prune(&dir, 150);
+        assert!(!dir.join("old.idx").exists());
+        assert!(!dir.join("mid.idx").exists());
+        assert!(dir.join("used.idx").exists());
+        assert!(dir.join("notes.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::log_parser::LogEntry;
+
+/// Entries from live sources (tailing, syslog, streams) waiting to be shown.
+/// They are released at most `refresh_hz` times a second and `max_per_refresh`
+/// at a time, so a chatty log re-lays out the view a few times a second
+/// instead of every frame. The queue can be capped, dropping the oldest
+/// entries when a burst outgrows it.
+pub struct IngestQueue {
+    pending: VecDeque<LogEntry>,
+    last_release: Option<Instant>,
+    /// Entries released recently, by release time, for the rate shown
+    released: VecDeque<(Instant, usize)>,
+    /// Dropped since the last release, noted before the next entries
+    dropped_unnoted: usize,
+    dropped: usize,
+}
+
+impl IngestQueue {
+    pub fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            last_release: None,
+            released: VecDeque::new(),
+            dropped_unnoted: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Queue entries after those already waiting. Line numbers are assigned on release.
+    pub fn push(&mut self, entries: impl IntoIterator<Item = LogEntry>) {
+        self.pending.extend(entries);
+    }
+
+    /// The last waiting entry from `source`.
+    pub fn last_from(&mut self, source: usize) -> Option<&mut LogEntry> {
+        self.pending.iter_mut().rev().find(|entry| entry.source == source)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether `capacity` entries or more are waiting (never with a capacity of 0).
+    pub fn is_full(&self, capacity: usize) -> bool {
+        capacity > 0 && self.pending.len() >= capacity
+    }
+
+    /// Drop the oldest waiting entries beyond `capacity` (0 = no limit).
+    pub fn enforce_capacity(&mut self, capacity: usize) {
+        if capacity == 0 || self.pending.len() <= capacity {
+            return;
+        }
+        let excess = self.pending.len() - capacity;
+        self.pending.drain(..excess);
+        self.dropped_unnoted += excess;
+        self.dropped += excess;
+    }
+
+    /// Entries dropped since the source was opened.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Drop the waiting entries, e.g. when another source is opened.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.released.clear();
+        self.dropped_unnoted = 0;
+        self.dropped = 0;
+    }
+
+    /// The entries due now, or none until the next refresh. A rate of 0
+    /// releases every frame and a cap of 0 releases everything waiting.
+    /// Entries dropped before them are noted first.
+    pub fn take_due(&mut self, refresh_hz: u32, max_per_refresh: usize) -> Vec<LogEntry> {
+        if self.pending.is_empty() || self.time_to_next(refresh_hz).is_some() {
+            return Vec::new();
+        }
+        let count = if max_per_refresh == 0 { self.pending.len() } else { self.pending.len().min(max_per_refresh) };
+        let now = Instant::now();
+        self.last_release = Some(now);
+        self.released.push_back((now, count));
+        let mut entries = Vec::with_capacity(count + 1);
+        if self.dropped_unnoted > 0 {
+            let marker = format!(
+                "──────── {} lines dropped, they arrived faster than they could be shown ────────",
+                std::mem::take(&mut self.dropped_unnoted)
+            );
+            entries.push(LogEntry::note(&marker, 0));
+        }
+        entries.extend(self.pending.drain(..count));
+        entries
+    }
+
+    /// How long until entries can be released again, if that is in the future.
+    pub fn time_to_next(&self, refresh_hz: u32) -> Option<Duration> {
+        let last = self.last_release?;
+        let interval = Duration::from_secs(1).checked_div(refresh_hz)?;
+        interval.checked_sub(last.elapsed()).filter(|wait| !wait.is_zero())
+    }
+
+    /// Entries released over the last second.
+    pub fn lines_per_sec(&mut self) -> usize {
+        while self.released.front().is_some_and(|(time, _)| time.elapsed() > Duration::from_secs(1)) {
+            self.released.pop_front();
+        }
+        self.released.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl Default for IngestQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<LogEntry> {
+        texts.iter().map(|text| LogEntry::note(text, 0)).collect()
+    }
+
+    fn texts(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.raw_line.as_str()).collect()
+    }
+
+    #[test]
+    fn releases_at_most_the_cap_per_refresh() {
+        let mut queue = IngestQueue::new();
+        queue.push(lines(&["a", "b", "c"]));
+        assert_eq!(texts(&queue.take_due(0, 2)), ["a", "b"]);
+        assert_eq!(texts(&queue.take_due(0, 2)), ["c"]);
+        assert!(queue.take_due(0, 2).is_empty());
+        assert_eq!(queue.lines_per_sec(), 3);
+
+        queue.push(lines(&["d", "e"]));
+        assert_eq!(texts(&queue.take_due(0, 0)), ["d", "e"]);
+    }
+
+    #[test]
+    fn waits_for_the_next_refresh() {
+        let mut queue = IngestQueue::new();
+        queue.push(lines(&["a"]));
+        assert_eq!(queue.take_due(1, 0).len(), 1);
+        queue.push(lines(&["b"]));
+        assert!(queue.take_due(1, 0).is_empty());
+        assert!(queue.time_to_next(1).is_some_and(|wait| wait <= Duration::from_secs(1)));
+        assert_eq!(queue.pending(), 1);
+        // Every frame without a rate
+        assert_eq!(queue.time_to_next(0), None);
+        assert_eq!(queue.take_due(0, 0).len(), 1);
+    }
+
+    #[test]
+    fn drops_the_oldest_beyond_capacity_and_notes_it() {
+        let mut queue = IngestQueue::new();
+        queue.push(lines(&["a", "b", "c", "d", "e"]));
+        assert!(queue.is_full(5));
+        assert!(!queue.is_full(0));
+        queue.enforce_capacity(0);
+        assert_eq!(queue.pending(), 5);
+        queue.enforce_capacity(2);
+        assert_eq!(queue.pending(), 2);
+        assert_eq!(queue.dropped(), 3);
+
+        let released = queue.take_due(0, 0);
+        assert_eq!(released.len(), 3);
+        assert!(released[0].raw_line.contains("3 lines dropped"));
+        assert_eq!(texts(&released[1..]), ["d", "e"]);
+        // Noted once; the total stays until the source changes
+        queue.push(lines(&["f"]));
+        assert_eq!(texts(&queue.take_due(0, 0)), ["f"]);
+        assert_eq!(queue.dropped(), 3);
+        queue.clear();
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn finds_the_last_entry_from_a_source() {
+        let mut queue = IngestQueue::new();
+        let mut entries = lines(&["one", "two", "three"]);
+        entries[1].source = 1;
+        queue.push(entries);
+        assert_eq!(queue.last_from(0).map(|entry| entry.raw_line.clone()).as_deref(), Some("three"));
+        assert_eq!(queue.last_from(1).map(|entry| entry.raw_line.clone()).as_deref(), Some("two"));
+        assert!(queue.last_from(2).is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::log_parser::{EntryStart, LogFormat, LogLevel};
+use crate::scratchpad::PinnedEntry;
+
+/// A saved debugging session: what was open, how it was filtered and where
+/// it was read, with the pinned evidence, to pick it up again later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Investigation {
+    pub name: String,
+    /// Unix time it was saved
+    #[serde(default)]
+    pub saved_at: i64,
+    /// The file viewed, or the files of a merged view
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    #[serde(default)]
+    pub format: LogFormat,
+    #[serde(default)]
+    pub entry_start: EntryStart,
+    #[serde(default)]
+    pub levels: Vec<LogLevel>,
+    #[serde(default)]
+    pub search_query: String,
+    #[serde(default)]
+    pub search_regex: bool,
+    #[serde(default)]
+    pub search_case_sensitive: bool,
+    #[serde(default)]
+    pub show_only_matches: bool,
+    /// Messages hidden as noise
+    #[serde(default)]
+    pub excluded_messages: Vec<String>,
+    #[serde(default)]
+    pub pins: Vec<PinnedEntry>,
+    /// Line number of the entry at the top of the view
+    #[serde(default)]
+    pub line: Option<usize>,
+}
+
+impl Investigation {
+    /// The files' names, e.g. `app.log + db.log`.
+    pub fn files_label(&self) -> String {
+        let names: Vec<String> = self
+            .files
+            .iter()
+            .map(|path| path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned()))
+            .collect();
+        if names.is_empty() {
+            "No file".to_string()
+        } else {
+            names.join(" + ")
+        }
+    }
+}
+
+/// Load saved investigations. A missing or unreadable file yields an empty list.
+pub fn load_investigations() -> Vec<Investigation> {
+    config::load_json("investigations.json")
+}
+
+pub fn save_investigations(investigations: &[Investigation]) -> Result<(), String> {
+    config::save_json("investigations.json", investigations)
+}
@@ -0,0 +1,282 @@
+#[cfg(feature = "kafka")]
+use rdkafka::config::ClientConfig;
+#[cfg(feature = "kafka")]
+use rdkafka::consumer::{BaseConsumer, Consumer};
+#[cfg(feature = "kafka")]
+use rdkafka::{Message, Offset, TopicPartitionList};
+#[cfg(feature = "kafka")]
+use std::sync::atomic::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "kafka")]
+use std::thread;
+#[cfg(feature = "kafka")]
+use std::time::Duration;
+
+#[cfg(feature = "kafka")]
+use crate::file_open;
+use crate::source_event::{self, SourceAction, SourceEvent, SourceEvents};
+
+/// How long a poll waits for records before checking whether to stop.
+#[cfg(feature = "kafka")]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+#[cfg(feature = "kafka")]
+const METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait between attempts to reach the brokers.
+#[cfg(feature = "kafka")]
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Most records handed on at once.
+#[cfg(feature = "kafka")]
+const MAX_BATCH_RECORDS: usize = 5_000;
+/// Batches held for the app before consuming waits.
+const MAX_WAITING_BATCHES: usize = 64;
+
+/// Where a consumer starts reading the topic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KafkaStart {
+    /// Where the group left off, or new records if it has no offsets yet
+    Committed,
+    /// Every record still in the topic
+    Earliest,
+    /// Only records produced from now on
+    Latest,
+}
+
+/// What to consume, as edited in the Kafka window.
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    /// Comma-separated `host:port` list
+    pub brokers: String,
+    pub topic: String,
+    pub group: String,
+    pub start: KafkaStart,
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            brokers: "localhost:9092".to_string(),
+            topic: String::new(),
+            group: "log-rocket".to_string(),
+            start: KafkaStart::Committed,
+        }
+    }
+}
+
+impl KafkaConfig {
+    /// `topic @ brokers`, as shown in the header.
+    pub fn description(&self) -> String {
+        format!("{} @ {}", self.topic.trim(), self.brokers.trim())
+    }
+}
+
+/// The Consume Kafka Topic window.
+#[derive(Default)]
+pub struct KafkaDialog {
+    pub open: bool,
+    pub config: KafkaConfig,
+    /// Why the last consumer couldn't be started
+    pub error: Option<String>,
+}
+
+impl KafkaDialog {
+    pub fn show(&mut self, ctx: &egui::Context, source: Option<&KafkaSource>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Consume Kafka Topic")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let config = &mut self.config;
+                egui::Grid::new("kafka_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Brokers:");
+                    ui.text_edit_singleline(&mut config.brokers).on_hover_text("host:port, comma-separated");
+                    ui.end_row();
+                    ui.label("Topic:");
+                    ui.text_edit_singleline(&mut config.topic);
+                    ui.end_row();
+                    ui.label("Group:");
+                    ui.text_edit_singleline(&mut config.group);
+                    ui.end_row();
+                    ui.label("Start at:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut config.start, KafkaStart::Committed, "Group offset")
+                            .on_hover_text("Where this group left off, or new records if it has none");
+                        ui.radio_value(&mut config.start, KafkaStart::Earliest, "Earliest");
+                        ui.radio_value(&mut config.start, KafkaStart::Latest, "Latest");
+                    });
+                    ui.end_row();
+                });
+                ui.label("Each record value is read as log lines or a JSON entry.");
+                source_event::show_errors(ui, source.map(KafkaSource::events), self.error.as_deref());
+                ui.horizontal(|ui| {
+                    if ui.button("Consume").clicked() {
+                        action = Some(SourceAction::Open);
+                    }
+                    if source.is_some() && ui.button("Disconnect").clicked() {
+                        action = Some(SourceAction::Disconnect);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// Consumes a Kafka topic, each record's value being one or more log lines.
+/// Offsets are committed for the group as records are read, so a consumer
+/// started with `KafkaStart::Committed` picks up where the last one stopped.
+pub struct KafkaSource {
+    description: String,
+    events: SourceEvents,
+}
+
+impl KafkaSource {
+    pub fn start(config: &KafkaConfig) -> Result<Self, String> {
+        if config.brokers.trim().is_empty() || config.topic.trim().is_empty() || config.group.trim().is_empty() {
+            return Err("Brokers, topic and group are required".to_string());
+        }
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_BATCHES);
+        spawn(config, sender, stop)?;
+        Ok(Self { description: config.description(), events })
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn events(&self) -> &SourceEvents {
+        &self.events
+    }
+
+    /// Events since the last call, in order.
+    pub fn take_events(&mut self) -> Vec<SourceEvent> {
+        self.events.take()
+    }
+}
+
+/// Create the consumer and read the topic with it on a background thread.
+#[cfg(feature = "kafka")]
+fn spawn(config: &KafkaConfig, sender: mpsc::SyncSender<SourceEvent>, stop: Arc<AtomicBool>) -> Result<(), String> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", config.brokers.trim())
+        .set("group.id", config.group.trim())
+        .set("enable.auto.commit", "true")
+        .set("auto.offset.reset", "latest")
+        .create()
+        .map_err(|e| format!("Failed to create consumer: {}", e))?;
+    let config = config.clone();
+    thread::spawn(move || run(&consumer, &config, &sender, &stop));
+    Ok(())
+}
+
+#[cfg(not(feature = "kafka"))]
+fn spawn(_config: &KafkaConfig, _sender: mpsc::SyncSender<SourceEvent>, _stop: Arc<AtomicBool>) -> Result<(), String> {
+    Err("Log Rocket was built without Kafka support; rebuild it with `--features kafka`".to_string())
+}
+
+#[cfg(feature = "kafka")]
+fn run(consumer: &BaseConsumer, config: &KafkaConfig, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    // Wait for the brokers and the topic before consuming
+    let mut delay = Duration::from_secs(1);
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        match join(consumer, config) {
+            Ok(()) => break,
+            Err(e) => {
+                if sender.send(SourceEvent::Disconnected(e)).is_err() {
+                    return;
+                }
+                file_open::wake_ui();
+            }
+        }
+        // Sleep in short steps so stopping isn't held up by the delay
+        let mut waited = Duration::ZERO;
+        while waited < delay && !stop.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            waited += POLL_INTERVAL;
+        }
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+    if sender.send(SourceEvent::Connected).is_err() {
+        return;
+    }
+    file_open::wake_ui();
+
+    let mut connected = true;
+    while !stop.load(Ordering::Relaxed) {
+        let mut lines = Vec::new();
+        let mut error = None;
+        // Take what has already arrived along with the first record
+        let mut timeout = POLL_INTERVAL;
+        for _ in 0..MAX_BATCH_RECORDS {
+            match consumer.poll(timeout) {
+                Some(Ok(message)) => {
+                    if let Some(payload) = message.payload() {
+                        let text = String::from_utf8_lossy(payload);
+                        lines.extend(text.lines().filter(|l| !l.trim().is_empty()).map(|l| l.trim_end().to_string()));
+                    }
+                }
+                Some(Err(e)) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+                None => break,
+            }
+            timeout = Duration::ZERO;
+        }
+
+        // The client reconnects by itself; a record means it is back
+        if !lines.is_empty() && !connected {
+            connected = true;
+            if sender.send(SourceEvent::Connected).is_err() {
+                return;
+            }
+        }
+        if !lines.is_empty() && sender.send(SourceEvent::Lines(lines)).is_err() {
+            return;
+        }
+        if let Some(e) = error {
+            connected = false;
+            if sender.send(SourceEvent::Disconnected(e)).is_err() {
+                return;
+            }
+        }
+        file_open::wake_ui();
+    }
+}
+
+/// Check that the topic exists, then subscribe with the group or, to start
+/// at either end, assign every partition.
+#[cfg(feature = "kafka")]
+fn join(consumer: &BaseConsumer, config: &KafkaConfig) -> Result<(), String> {
+    let topic = config.topic.trim();
+    let metadata = consumer
+        .fetch_metadata(Some(topic), METADATA_TIMEOUT)
+        .map_err(|e| format!("Failed to reach {}: {}", config.brokers.trim(), e))?;
+    let partitions: Vec<i32> = match metadata.topics().iter().find(|t| t.name() == topic) {
+        Some(t) if t.error().is_none() && !t.partitions().is_empty() => t.partitions().iter().map(|p| p.id()).collect(),
+        _ => return Err(format!("Topic {} not found", topic)),
+    };
+
+    let offset = match config.start {
+        KafkaStart::Committed => {
+            return consumer.subscribe(&[topic]).map_err(|e| format!("Failed to subscribe to {}: {}", topic, e));
+        }
+        KafkaStart::Earliest => Offset::Beginning,
+        KafkaStart::Latest => Offset::End,
+    };
+    let mut assignment = TopicPartitionList::new();
+    for partition in partitions {
+        assignment
+            .add_partition_offset(topic, partition, offset)
+            .map_err(|e| format!("Failed to assign partition {}: {}", partition, e))?;
+    }
+    consumer.assign(&assignment).map_err(|e| format!("Failed to assign {}: {}", topic, e))
+}
@@ -0,0 +1,61 @@
+//! Log Rocket's viewer and log processing, shared by the binary and the benches.
+
+#[macro_use]
+pub mod diagnostics;
+pub mod app;
+pub mod log_parser;
+pub mod file_watcher;
+pub mod config;
+pub mod search;
+pub mod redaction;
+pub mod share;
+pub mod update_check;
+pub mod file_open;
+pub mod single_instance;
+pub mod cli;
+pub mod headless;
+pub mod dir_browser;
+pub mod profiles;
+pub mod custom_format;
+pub mod format_builder;
+pub mod merge;
+pub mod source_pane;
+pub mod scratchpad;
+pub mod investigations;
+pub mod plugins;
+pub mod scripting;
+pub mod metrics;
+pub mod timeline;
+pub mod patterns;
+pub mod noise;
+pub mod transactions;
+pub mod heatmap;
+pub mod report;
+pub mod export;
+pub mod file_state;
+pub mod window_state;
+pub mod settings;
+pub mod parse_worker;
+pub mod unparsed;
+pub mod index_cache;
+pub mod disk_log;
+pub mod devtools;
+pub mod text_encoding;
+pub mod glob_watch;
+pub mod syslog;
+pub mod otlp;
+pub mod fluent;
+pub mod adb;
+pub mod unified_log;
+pub mod remote;
+pub mod source_event;
+pub mod live_stream;
+pub mod kafka;
+pub mod loki;
+pub mod elastic;
+pub mod aws;
+pub mod cloudwatch;
+pub mod gcp;
+pub mod azure;
+pub mod object_store;
+pub mod ingest;
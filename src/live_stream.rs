@@ -0,0 +1,377 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::file_open;
+use crate::source_event::{self, SourceAction, SourceEvent, SourceEvents};
+use crate::azure::{self, AzureQuery};
+use crate::cloudwatch::{self, CloudWatchQuery};
+use crate::elastic::{self, ElasticQuery};
+use crate::gcp::{self, GcpQuery};
+use crate::loki::{self, LokiQuery};
+
+/// How often a quiet WebSocket checks whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Longest wait between reconnect attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Messages held for the app before reading waits, which in turn holds up
+/// the server or the pipe's writer.
+const MAX_WAITING_MESSAGES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    WebSocket,
+    ServerSentEvents,
+}
+
+/// True for named pipes and character devices, which have no size to tail
+/// and block until written to.
+pub fn is_pipe(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo() || m.file_type().is_char_device())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// A live log stream from a WebSocket (`ws://`, `wss://`) or Server-Sent
+/// Events (`http://`, `https://`) endpoint, reconnecting when it drops, or
+/// from a named pipe until its writer closes it. Each message's lines are
+/// parsed like lines tailed from a file.
+pub struct LiveStream {
+    url: String,
+    events: SourceEvents,
+}
+
+impl LiveStream {
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+        let protocol = match parsed.scheme() {
+            "ws" | "wss" => Protocol::WebSocket,
+            "http" | "https" => Protocol::ServerSentEvents,
+            scheme => return Err(format!("Unsupported scheme {}: use ws, wss, http or https", scheme)),
+        };
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_url = parsed.to_string();
+        thread::spawn(move || run(&thread_url, protocol, &sender, &stop));
+        Ok(Self::new(parsed.to_string(), events))
+    }
+
+    /// Read lines from a named pipe or character device as they are written.
+    pub fn pipe(path: &Path) -> Self {
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_path = path.to_path_buf();
+        thread::spawn(move || {
+            if let Err(e) = read_pipe(&thread_path, &sender, &stop) {
+                sender.send(SourceEvent::Disconnected(e)).ok();
+            }
+            sender.send(SourceEvent::Ended).ok();
+            file_open::wake_ui();
+        });
+        Self::new(path.display().to_string(), events)
+    }
+
+    /// Run a LogQL query against Loki, then keep following it with Loki's
+    /// live tail when the query asks to.
+    pub fn loki(query: &LokiQuery) -> Result<Self, String> {
+        let base = loki::base_url(query)?;
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_query = query.clone();
+        thread::spawn(move || loki::run(&base, &thread_query, &sender, &stop));
+        Ok(Self::new(query.description(), events))
+    }
+
+    /// Page through the hits of an Elasticsearch or OpenSearch search; the
+    /// stream ends after the last page.
+    pub fn elastic(query: &ElasticQuery) -> Result<Self, String> {
+        let url = elastic::search_url(query)?;
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_query = query.clone();
+        thread::spawn(move || elastic::run(&url, &thread_query, &sender, &stop));
+        Ok(Self::new(query.description(), events))
+    }
+
+    /// Read a CloudWatch Logs group over its lookback window, then keep
+    /// polling it for new events when the query asks to.
+    pub fn cloudwatch(query: &CloudWatchQuery) -> Result<Self, String> {
+        if query.group.trim().is_empty() {
+            return Err("Enter or pick a log group".to_string());
+        }
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_query = query.clone();
+        thread::spawn(move || cloudwatch::run(&thread_query, &sender, &stop));
+        Ok(Self::new(query.description(), events))
+    }
+
+    /// Read the entries matching a Google Cloud Logging filter; the stream
+    /// ends after the last page.
+    pub fn gcp(query: &GcpQuery) -> Result<Self, String> {
+        if query.project.trim().is_empty() {
+            return Err("Enter a project id".to_string());
+        }
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_query = query.clone();
+        thread::spawn(move || gcp::run(&thread_query, &sender, &stop));
+        Ok(Self::new(query.description(), events))
+    }
+
+    /// Run a KQL query against a Log Analytics workspace; the stream ends
+    /// with its result.
+    pub fn azure(query: &AzureQuery) -> Result<Self, String> {
+        let (events, sender, _stop) = SourceEvents::channel(MAX_WAITING_MESSAGES);
+        let thread_query = query.clone();
+        thread::spawn(move || azure::run(&thread_query, &sender));
+        Ok(Self::new(query.description(), events))
+    }
+
+    fn new(url: String, events: SourceEvents) -> Self {
+        Self { url, events }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn events(&self) -> &SourceEvents {
+        &self.events
+    }
+
+    /// Events since the last call, in order.
+    pub fn take_events(&mut self) -> Vec<SourceEvent> {
+        self.events.take()
+    }
+}
+
+/// The Connect to Log Stream window.
+#[derive(Default)]
+pub struct StreamDialog {
+    pub open: bool,
+    pub url: String,
+    /// Why the last stream couldn't be connected
+    pub error: Option<String>,
+}
+
+impl StreamDialog {
+    pub fn show(&mut self, ctx: &egui::Context, stream: Option<&LiveStream>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Connect to Log Stream")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("A WebSocket (ws://, wss://) or Server-Sent Events (http://, https://) endpoint sending log lines or JSON entries.");
+                ui.horizontal(|ui| {
+                    ui.label("URL:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.url).desired_width(300.0));
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        action = Some(SourceAction::Open);
+                    }
+                });
+                source_event::show_errors(ui, stream.map(LiveStream::events), self.error.as_deref());
+                ui.horizontal(|ui| {
+                    if ui.button("Connect").clicked() {
+                        action = Some(SourceAction::Open);
+                    }
+                    if stream.is_some() && ui.button("Disconnect").clicked() {
+                        action = Some(SourceAction::Disconnect);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+fn run(url: &str, protocol: Protocol, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    let mut last_event_id = None;
+    retry(sender, stop, |delay| match protocol {
+        Protocol::WebSocket => read_websocket(url, delay, sender, stop),
+        Protocol::ServerSentEvents => read_events(url, &mut last_event_id, delay, sender, stop),
+    });
+}
+
+/// Run `attempt` until it returns `Ok` (the receiver is gone) or the stream
+/// is stopped, reporting each failure and waiting longer after each one. An
+/// attempt resets the delay it is given once it has connected.
+pub fn retry(
+    sender: &mpsc::SyncSender<SourceEvent>,
+    stop: &AtomicBool,
+    mut attempt: impl FnMut(&mut Duration) -> Result<(), String>,
+) {
+    let mut delay = Duration::from_secs(1);
+    while !stop.load(Ordering::Relaxed) {
+        let result = attempt(&mut delay);
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let error = match result {
+            Ok(()) => return, // The receiver is gone
+            Err(e) => e,
+        };
+        if sender.send(SourceEvent::Disconnected(error)).is_err() {
+            return;
+        }
+        file_open::wake_ui();
+        // Sleep in short steps so stopping isn't held up by the delay
+        let mut waited = Duration::ZERO;
+        while waited < delay && !stop.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            waited += POLL_INTERVAL;
+        }
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+/// Send the non-empty lines of `text`. False once the receiver is gone.
+fn send_lines(text: &str, sender: &mpsc::SyncSender<SourceEvent>) -> bool {
+    let lines: Vec<String> = text.lines().filter(|l| !l.trim().is_empty()).map(|l| l.trim_end().to_string()).collect();
+    if lines.is_empty() {
+        return true;
+    }
+    let sent = sender.send(SourceEvent::Lines(lines)).is_ok();
+    file_open::wake_ui();
+    sent
+}
+
+/// Report a connection and reset the retry delay. False once the receiver is gone.
+pub fn connected(retry_delay: &mut Duration, sender: &mpsc::SyncSender<SourceEvent>) -> bool {
+    *retry_delay = Duration::from_secs(1);
+    let sent = sender.send(SourceEvent::Connected).is_ok();
+    file_open::wake_ui();
+    sent
+}
+
+/// Whether a read gave up waiting rather than failed.
+pub fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+/// Open a WebSocket whose reads time out now and then, so a quiet
+/// connection still notices when it should stop.
+pub fn connect_websocket(request: impl IntoClientRequest) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+    let (socket, _) = tungstenite::connect(request).map_err(|e| format!("Failed to connect: {}", e))?;
+    let tcp: Option<&TcpStream> = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => Some(stream),
+        MaybeTlsStream::Rustls(stream) => Some(stream.get_ref()),
+        _ => None,
+    };
+    if let Some(tcp) = tcp {
+        tcp.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| format!("Failed to configure socket: {}", e))?;
+    }
+    Ok(socket)
+}
+
+fn read_websocket(
+    url: &str,
+    retry_delay: &mut Duration,
+    sender: &mpsc::SyncSender<SourceEvent>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let mut socket = connect_websocket(url)?;
+    if !connected(retry_delay, sender) {
+        return Ok(());
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        let sent = match socket.read() {
+            Ok(Message::Text(text)) => send_lines(&text, sender),
+            Ok(Message::Binary(data)) => send_lines(&String::from_utf8_lossy(&data), sender),
+            Ok(Message::Close(_)) => return Err("Closed by the server".to_string()),
+            Ok(_) => true,
+            Err(tungstenite::Error::Io(e)) if is_timeout(&e) => true,
+            Err(e) => return Err(format!("Connection lost: {}", e)),
+        };
+        if !sent {
+            break;
+        }
+    }
+    socket.close(None).ok();
+    Ok(())
+}
+
+/// Read a Server-Sent Events stream. The last event ID is kept so a
+/// reconnect can ask the server to resume after it.
+fn read_events(
+    url: &str,
+    last_event_id: &mut Option<String>,
+    retry_delay: &mut Duration,
+    sender: &mpsc::SyncSender<SourceEvent>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let mut request = ureq::get(url).set("Accept", "text/event-stream");
+    if let Some(ref id) = last_event_id {
+        request = request.set("Last-Event-ID", id);
+    }
+    let response = request.call().map_err(|e| format!("Failed to connect: {}", e))?;
+    if !connected(retry_delay, sender) {
+        return Ok(());
+    }
+
+    // A quiet stream only notices it should stop at the next line; servers
+    // usually send keep-alive comments often enough
+    let mut data = Vec::new();
+    for line in BufReader::new(response.into_reader()).lines() {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let line = line.map_err(|e| format!("Connection lost: {}", e))?;
+        if line.is_empty() {
+            if !data.is_empty() && !send_lines(&data.join("\n"), sender) {
+                return Ok(());
+            }
+            data.clear();
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((&line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => data.push(value.to_string()),
+            "id" => *last_event_id = Some(value.to_string()),
+            _ => {} // Comments, event types and retry hints
+        }
+    }
+    Err("Closed by the server".to_string())
+}
+
+/// Read a pipe until its writer closes it. Opening waits for a writer, and a
+/// read waits for data, so stopping takes effect at the next line.
+fn read_pipe(path: &Path, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open pipe: {}", e))?;
+    sender.send(SourceEvent::Connected).ok();
+    file_open::wake_ui();
+    let mut reader = BufReader::new(file);
+    let mut buffer = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).map_err(|e| format!("Failed to read pipe: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        // Lines already buffered go in the same message
+        while reader.buffer().contains(&b'\n') {
+            reader.read_until(b'\n', &mut buffer).map_err(|e| format!("Failed to read pipe: {}", e))?;
+        }
+        if !send_lines(&String::from_utf8_lossy(&buffer), sender) {
+            break;
+        }
+    }
+    Ok(())
+}
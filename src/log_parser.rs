@@ -1,6 +1,15 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+use crate::custom_format::CustomFormat;
+use crate::plugins::Plugin;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LogLevel {
     Info,
     Warn,
@@ -10,196 +19,905 @@ pub enum LogLevel {
     Unknown,
 }
 
+impl LogLevel {
+    pub const ALL: [LogLevel; 6] = [
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+        LogLevel::Debug,
+        LogLevel::Trace,
+        LogLevel::Unknown,
+    ];
+    
+    pub fn from_name(name: &str) -> LogLevel {
+        match name.to_uppercase().as_str() {
+            "INFO" => LogLevel::Info,
+            "WARN" | "WARNING" => LogLevel::Warn,
+            "ERROR" | "ERR" | "FATAL" | "SEVERE" => LogLevel::Error,
+            "DEBUG" => LogLevel::Debug,
+            "TRACE" => LogLevel::Trace,
+            _ => LogLevel::Unknown,
+        }
+    }
+
+    /// Rank from Trace (0) up to Error (4); Unknown has none.
+    pub fn severity(&self) -> Option<u8> {
+        match self {
+            LogLevel::Trace => Some(0),
+            LogLevel::Debug => Some(1),
+            LogLevel::Info => Some(2),
+            LogLevel::Warn => Some(3),
+            LogLevel::Error => Some(4),
+            LogLevel::Unknown => None,
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match LogLevel::from_name(s.trim()) {
+            LogLevel::Unknown if !s.trim().eq_ignore_ascii_case("unknown") => {
+                Err(format!("unknown log level '{}'", s))
+            }
+            level => Ok(level),
+        }
+    }
+}
+
+/// Which line format the parser expects. `Auto` tries every known format per line.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LogFormat {
+    #[default]
+    Auto,
+    ErrorLog,
+    AccessLog,
+    Json,
+    /// A user-defined format from formats.json, by name
+    Custom(String),
+}
+
+impl LogFormat {
+    pub const BUILT_IN: [LogFormat; 4] = [LogFormat::Auto, LogFormat::ErrorLog, LogFormat::AccessLog, LogFormat::Json];
+    
+    pub fn label(&self) -> &str {
+        match self {
+            LogFormat::Auto => "Auto-detect",
+            LogFormat::ErrorLog => "AEM error log",
+            LogFormat::AccessLog => "Access log",
+            LogFormat::Json => "JSON lines",
+            LogFormat::Custom(name) => name,
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(LogFormat::Auto),
+            "error" | "error-log" | "aem" => Ok(LogFormat::ErrorLog),
+            "access" | "access-log" => Ok(LogFormat::AccessLog),
+            "json" => Ok(LogFormat::Json),
+            other if other.starts_with("custom:") => Ok(LogFormat::Custom(s.trim()["custom:".len()..].to_string())),
+            _ => Err(format!("unknown format '{}' (expected auto, error, access, json or custom:NAME)", s)),
+        }
+    }
+}
+
+/// The name `--format` and `logrocket://` URLs take, read back by `from_str`.
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogFormat::Auto => write!(f, "auto"),
+            LogFormat::ErrorLog => write!(f, "error"),
+            LogFormat::AccessLog => write!(f, "access"),
+            LogFormat::Json => write!(f, "json"),
+            LogFormat::Custom(name) => write!(f, "custom:{}", name),
+        }
+    }
+}
+
+/// How the parser tells a line that begins an entry from one continuing the
+/// entry before it (a stack trace, a wrapped message).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EntryStart {
+    /// Lines the format parses start entries; when auto-detecting, so does
+    /// any line starting with a timestamp
+    #[default]
+    Format,
+    /// Indented lines continue the entry before them
+    Unindented,
+    /// Every line is an entry of its own
+    EveryLine,
+    /// Lines matching the regex start entries
+    Pattern(String),
+}
+
+impl EntryStart {
+    pub fn label(&self) -> &str {
+        match self {
+            EntryStart::Format => "Lines in the format",
+            EntryStart::Unindented => "Unindented lines",
+            EntryStart::EveryLine => "Every line",
+            EntryStart::Pattern(_) => "Lines matching a regex",
+        }
+    }
+}
+
+/// A parsed field's text. Fields cut from the line itself are kept as a byte
+/// range of `raw_line` instead of a second copy.
+#[derive(Debug, Clone)]
+enum FieldText {
+    Span(u32, u32),
+    Owned(Box<str>),
+}
+
+impl FieldText {
+    /// Refer to `value` by position when it is a slice of `line`, else copy it.
+    fn new(line: &str, value: &str) -> Self {
+        let start = (value.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+        match (u32::try_from(start), u32::try_from(start + value.len())) {
+            (Ok(start), Ok(end)) if end as usize <= line.len() => FieldText::Span(start, end),
+            _ => FieldText::Owned(value.into()),
+        }
+    }
+
+    fn get<'a>(&'a self, raw_line: &'a str) -> &'a str {
+        match self {
+            FieldText::Span(start, end) => &raw_line[*start as usize..*end as usize],
+            FieldText::Owned(text) => text,
+        }
+    }
+}
+
+impl From<String> for FieldText {
+    fn from(text: String) -> Self {
+        FieldText::Owned(text.into_boxed_str())
+    }
+}
+
+/// Shares one allocation between equal thread and class names.
+#[derive(Debug, Default)]
+struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Names kept at most; beyond this the set starts over, so logs with
+    /// per-request thread names don't grow it without bound.
+    const MAX_STRINGS: usize = 50_000;
+
+    fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(text) {
+            return existing.clone();
+        }
+        if self.strings.len() >= Self::MAX_STRINGS {
+            self.strings.clear();
+        }
+        let text: Arc<str> = text.into();
+        self.strings.insert(text.clone());
+        text
+    }
+}
+
+fn intern(name: &str) -> Arc<str> {
+    static NAMES: OnceLock<Mutex<Interner>> = OnceLock::new();
+    let names = NAMES.get_or_init(Mutex::default);
+    match names.lock() {
+        Ok(mut names) => names.intern(name),
+        Err(_) => name.into(),
+    }
+}
+
+// Error log format: DD.MM.YYYY HH:MM:SS.mmm *LEVEL* [thread] class message
+// We capture the prefix up to the level, and then capture the rest of the line to parse thread manualy
+// because thread names can contain nested brackets like [TarMK ... [...]]
+fn error_log_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^(\d{2}\.\d{2}\.\d{4}\s+\d{2}:\d{2}:\d{2}\.\d{3})\s+\*(\w+)\*\s+(.+)$").unwrap())
+}
+
+/// What a terminal shows for a line that returns to its start with `\r` to
+/// redraw itself, as progress bars do: each part is written over the ones
+/// before it, so `"10%\r100%"` becomes `"100%"`. Lines without `\r` are
+/// returned as they are.
+pub fn collapse_progress(line: &str) -> Cow<'_, str> {
+    if !line.contains('\r') {
+        return Cow::Borrowed(line);
+    }
+    let mut screen: Vec<char> = Vec::new();
+    for part in line.split('\r') {
+        for (column, c) in part.chars().enumerate() {
+            match screen.get_mut(column) {
+                Some(cell) => *cell = c,
+                None => screen.push(c),
+            }
+        }
+    }
+    Cow::Owned(screen.into_iter().collect())
+}
+
+// Lines starting with a timestamp (DD.MM.YYYY or DD/MMM/YYYY) begin a new entry
+fn timestamp_start_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^\d{2}[./]").unwrap())
+}
+
+// Access log format: IP - user DD/MMM/YYYY:HH:MM:SS +TZ "METHOD PATH HTTP/VERSION" STATUS SIZE "referer" "user-agent"
+fn access_log_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^([^\s]+)\s+-\s+(\S+)\s+(\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2}\s+[+-]\d{4})\s+(.+)$").unwrap())
+}
+
+/// Parse a timestamp in one of the built-in formats into milliseconds.
+fn builtin_timestamp_millis(timestamp: &str) -> Option<i64> {
+    const FORMATS: [&str; 4] = [
+        "%d.%m.%Y %H:%M:%S%.3f", // AEM error log
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S,%3f", // log4j default
+    ];
+    let timestamp = timestamp.trim();
+    
+    // Timezone-aware formats keep their wall-clock time so merged files compare as written
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(timestamp) {
+        return Some(time.naive_local().and_utc().timestamp_millis());
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_str(timestamp, "%d/%b/%Y:%H:%M:%S %z") {
+        return Some(time.naive_local().and_utc().timestamp_millis());
+    }
+    FORMATS
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(timestamp, format).ok())
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+/// The fields of an entry besides its level, parsed from its first line.
+#[derive(Debug, Clone)]
+struct EntryFields {
+    timestamp: Option<FieldText>,
+    thread: Option<Arc<str>>,
+    class: Option<Arc<str>>,
+    message: FieldText,
+}
+
+impl EntryFields {
+    /// Fields of a line that matched no format: the whole line is the message.
+    fn plain(line: &str) -> Self {
+        Self {
+            timestamp: None,
+            thread: None,
+            class: None,
+            message: FieldText::new(line, line),
+        }
+    }
+
+    fn error_log(line: &str) -> Self {
+        let Some(caps) = error_log_regex().captures(line) else {
+            return Self::plain(line);
+        };
+        let timestamp = caps.get(1).map(|m| m.as_str());
+        let rest = caps.get(3).map(|m| m.as_str()).unwrap_or("");
+        
+        // Manual parsing for thread (handling nested brackets)
+        let mut thread = None;
+        let mut class_and_message = rest;
+        
+        if rest.starts_with('[') {
+            let mut bracket_count = 0;
+            let mut end_index = 0;
+            let mut found_end = false;
+            
+            for (i, c) in rest.char_indices() {
+                if c == '[' {
+                    bracket_count += 1;
+                } else if c == ']' {
+                    bracket_count -= 1;
+                    if bracket_count == 0 {
+                        end_index = i;
+                        found_end = true;
+                        break;
+                    }
+                }
+            }
+            
+            if found_end {
+                // Extract thread content (excluding outer brackets)
+                if end_index > 1 {
+                    thread = Some(intern(&rest[1..end_index]));
+                }
+                // The rest is class and message (skip the closing bracket and following space)
+                if end_index + 1 < rest.len() {
+                    class_and_message = &rest[end_index + 1..];
+                } else {
+                    class_and_message = "";
+                }
+            }
+        }
+        
+        let class_and_message = class_and_message.trim();
+        
+        // Extract class and message
+        let parts: Vec<&str> = class_and_message.splitn(2, ' ').collect();
+        let class = parts.get(0).map(|s| intern(s));
+        let message = parts.get(1).copied().unwrap_or(class_and_message);
+        
+        Self {
+            timestamp: timestamp.map(|t| FieldText::new(line, t)),
+            thread,
+            class,
+            message: FieldText::new(line, message),
+        }
+    }
+
+    fn access_log(line: &str) -> Self {
+        let Some(caps) = access_log_regex().captures(line) else {
+            return Self::plain(line);
+        };
+        let ip = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+        let user = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        let timestamp = caps.get(3).map(|m| m.as_str());
+        let rest = caps.get(4).map(|m| m.as_str()).unwrap_or("");
+        
+        Self {
+            timestamp: timestamp.map(|t| FieldText::new(line, t)),
+            thread: None,
+            class: None,
+            message: FieldText::from(format!("{} - {} - {}", ip, user, rest)),
+        }
+    }
+}
+
+/// How to find the fields of an entry that wasn't fully parsed at load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+    Plain,
+    ErrorLog,
+    AccessLog,
+    /// All fields were parsed up front (JSON, custom formats, plugins)
+    Parsed,
+}
+
+impl LineKind {
+    const ALL: [LineKind; 4] = [LineKind::Plain, LineKind::ErrorLog, LineKind::AccessLog, LineKind::Parsed];
+}
+
+/// What the index cache keeps of an entry: everything but its text.
+#[derive(Debug, Clone)]
+pub struct EntrySummary {
+    pub line_number: usize,
+    pub level: LogLevel,
+    /// How the entry's fields are parsed, as stored in the index
+    pub kind: u8,
+    pub time_ms: Option<i64>,
+}
+
+/// Fields of an entry as given by its source rather than parsed from a line.
+#[derive(Debug, Clone)]
+pub struct SourceFields<'a> {
+    pub level: LogLevel,
+    pub timestamp: Option<&'a str>,
+    pub time_ms: Option<i64>,
+    pub thread: Option<&'a str>,
+    pub class: Option<&'a str>,
+    pub message: &'a str,
+}
+
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub line_number: usize,
-    pub timestamp: Option<String>,
     pub level: LogLevel,
-    pub thread: Option<String>,
-    pub class: Option<String>,
-    pub message: String,
     pub raw_line: String,
     pub is_error_log: bool,
+    /// Index of the source this entry came from (0 unless several files are merged)
+    pub source: usize,
+    kind: LineKind,
+    /// Parsed on first access for the built-in formats, so loading only finds
+    /// entry boundaries and levels
+    fields: OnceLock<EntryFields>,
+    /// Timestamp in milliseconds (local wall-clock time, no timezone shift),
+    /// parsed on first access like the fields
+    time: OnceLock<Option<i64>>,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+            LogLevel::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl LogEntry {
+    /// An entry whose fields are parsed from `line` when first needed.
+    fn lazy(line: &str, line_number: usize, level: LogLevel, kind: LineKind) -> Self {
+        Self {
+            line_number,
+            level,
+            raw_line: line.to_string(),
+            is_error_log: matches!(kind, LineKind::ErrorLog),
+            source: 0,
+            kind,
+            fields: OnceLock::new(),
+            time: OnceLock::new(),
+        }
+    }
+
+    /// A line added by the viewer itself, e.g. a marker where the file was rotated.
+    pub fn note(text: &str, line_number: usize) -> Self {
+        Self::lazy(text, line_number, LogLevel::Unknown, LineKind::Plain)
+    }
+
+    /// An entry whose source already split it into fields, e.g. a syslog
+    /// message. Fields that are slices of `line` aren't copied.
+    pub fn from_source(line: &str, line_number: usize, fields: SourceFields) -> Self {
+        Self::parsed(line, line_number, fields.level, EntryFields {
+            timestamp: fields.timestamp.map(|t| FieldText::new(line, t)),
+            thread: fields.thread.map(intern),
+            class: fields.class.map(intern),
+            message: FieldText::new(line, fields.message),
+        }, fields.time_ms)
+    }
+
+    fn parsed(line: &str, line_number: usize, level: LogLevel, fields: EntryFields, time_ms: Option<i64>) -> Self {
+        Self {
+            fields: OnceLock::from(fields),
+            time: OnceLock::from(time_ms),
+            ..Self::lazy(line, line_number, level, LineKind::Parsed)
+        }
+    }
+
+    fn fields(&self) -> &EntryFields {
+        self.fields.get_or_init(|| {
+            let line = self.raw_line.lines().next().unwrap_or("");
+            match self.kind {
+                LineKind::Plain | LineKind::Parsed => EntryFields::plain(line),
+                LineKind::ErrorLog => EntryFields::error_log(line),
+                LineKind::AccessLog => EntryFields::access_log(line),
+            }
+        })
+    }
+
+    pub fn timestamp(&self) -> Option<&str> {
+        self.fields().timestamp.as_ref().map(|t| t.get(&self.raw_line))
+    }
+
+    pub fn thread(&self) -> Option<&str> {
+        self.fields().thread.as_deref()
+    }
+
+    pub fn class(&self) -> Option<&str> {
+        self.fields().class.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        self.fields().message.get(&self.raw_line)
+    }
+
+    /// Roughly how many bytes the entry takes in memory; fields shared
+    /// between entries aren't counted.
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.raw_line.capacity()
+    }
+
+    pub fn time_ms(&self) -> Option<i64> {
+        *self.time.get_or_init(|| self.timestamp().and_then(builtin_timestamp_millis))
+    }
+
+    /// Move the entry's timestamp by `offset_ms`, e.g. to line up merged sources.
+    pub fn shift_time(&mut self, offset_ms: i64) {
+        self.time_ms();
+        if let Some(time) = self.time.get_mut() {
+            *time = time.map(|t| t + offset_ms);
+        }
+    }
+
+    /// Summarize the entry for the index cache. Parses its time if that hasn't happened yet.
+    pub fn summary(&self) -> EntrySummary {
+        EntrySummary {
+            line_number: self.line_number,
+            level: self.level.clone(),
+            kind: LineKind::ALL.iter().position(|k| *k == self.kind).unwrap_or(0) as u8,
+            time_ms: self.time_ms(),
+        }
+    }
+
+    /// Normalized JSON representation used by headless output and exports.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "line": self.line_number,
+            "timestamp": self.timestamp(),
+            "level": self.level.as_str(),
+            "thread": self.thread(),
+            "class": self.class(),
+            "message": self.message(),
+            "raw": self.raw_line,
+        })
+    }
 }
 
+#[derive(Clone)]
 pub struct LogParser {
-    error_log_regex: Regex,
-    access_log_regex: Regex,
+    /// Known custom formats with their line regex and entry start regex
+    custom_formats: Vec<(CustomFormat, Regex, Option<Regex>)>,
+    pub format: LogFormat,
+    pub plugins: Vec<Plugin>,
+    entry_start: EntryStart,
+    entry_start_regex: Option<Regex>,
+    /// Show lines redrawn with `\r` (progress bars) as they ended up, see
+    /// `collapse_progress`
+    pub collapse_progress: bool,
 }
 
 impl LogParser {
     pub fn new() -> Self {
-        // Error log format: DD.MM.YYYY HH:MM:SS.mmm *LEVEL* [thread] class message
-        // We capture the prefix up to the level, and then capture the rest of the line to parse thread manualy
-        // because thread names can contain nested brackets like [TarMK ... [...]]
-        let error_log_pattern = r"^(\d{2}\.\d{2}\.\d{4}\s+\d{2}:\d{2}:\d{2}\.\d{3})\s+\*(\w+)\*\s+(.+)$";
-        
-        // Access log format: IP - user DD/MMM/YYYY:HH:MM:SS +TZ "METHOD PATH HTTP/VERSION" STATUS SIZE "referer" "user-agent"
-        let access_log_pattern = r"^([^\s]+)\s+-\s+(\S+)\s+(\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2}\s+[+-]\d{4})\s+(.+)$";
-        
         Self {
-            error_log_regex: Regex::new(error_log_pattern).unwrap(),
-            access_log_regex: Regex::new(access_log_pattern).unwrap(),
+            custom_formats: Vec::new(),
+            format: LogFormat::Auto,
+            plugins: Vec::new(),
+            entry_start: EntryStart::Format,
+            entry_start_regex: None,
+            collapse_progress: true,
+        }
+    }
+    
+    /// Replace the known custom formats. Formats with an invalid regex are skipped.
+    pub fn set_custom_formats(&mut self, formats: &[CustomFormat]) {
+        self.custom_formats = formats
+            .iter()
+            .filter_map(|f| Some((f.clone(), f.compile().ok()?, f.compile_entry_start().ok()?)))
+            .collect();
+    }
+
+    pub fn entry_start(&self) -> &EntryStart {
+        &self.entry_start
+    }
+
+    /// Change how entry boundaries are found. Fails on an invalid regex,
+    /// leaving the rule as it was.
+    pub fn set_entry_start(&mut self, rule: EntryStart) -> Result<(), String> {
+        self.entry_start_regex = match rule {
+            EntryStart::Pattern(ref pattern) => Some(Regex::new(pattern).map_err(|e| e.to_string())?),
+            _ => None,
+        };
+        self.entry_start = rule;
+        Ok(())
+    }
+
+    fn accepts(&self, format: LogFormat) -> bool {
+        self.format == LogFormat::Auto || self.format == format
+    }
+    
+    fn active_custom_format(&self) -> Option<&(CustomFormat, Regex, Option<Regex>)> {
+        match self.format {
+            LogFormat::Custom(ref name) => self.custom_formats.iter().find(|(f, _, _)| &f.name == name),
+            _ => None,
         }
     }
 
+    fn active_custom_regex(&self) -> Option<&Regex> {
+        self.active_custom_format().map(|(_, re, _)| re)
+    }
+    
+    /// Whether a line starts an entry of the selected custom format: it
+    /// matches the format's entry start regex, or its line regex without one.
+    fn is_custom_line(&self, line: &str) -> bool {
+        self.active_custom_format()
+            .is_some_and(|(_, re, entry_start)| entry_start.as_ref().unwrap_or(re).is_match(line))
+    }
+
+    fn is_error_log_line(&self, line: &str) -> bool {
+        self.accepts(LogFormat::ErrorLog) && error_log_regex().is_match(line)
+    }
+
+    fn is_access_log_line(&self, line: &str) -> bool {
+        self.accepts(LogFormat::AccessLog) && access_log_regex().is_match(line)
+    }
+
+    fn is_json_line(&self, line: &str) -> bool {
+        self.accepts(LogFormat::Json) && line.trim_start().starts_with('{')
+    }
+
     pub fn parse_line(&self, line: &str, line_number: usize) -> LogEntry {
-        // Try error log format first
-        if let Some(caps) = self.error_log_regex.captures(line) {
-            let timestamp = caps.get(1).map(|m| m.as_str().to_string());
-            let level_str = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-            let rest = caps.get(3).map(|m| m.as_str()).unwrap_or("");
-            
-            // Manual parsing for thread (handling nested brackets)
-            let mut thread = None;
-            let mut class_and_message = rest;
-            
-            if rest.starts_with('[') {
-                let mut bracket_count = 0;
-                let mut end_index = 0;
-                let mut found_end = false;
-                
-                for (i, c) in rest.char_indices() {
-                    if c == '[' {
-                        bracket_count += 1;
-                    } else if c == ']' {
-                        bracket_count -= 1;
-                        if bracket_count == 0 {
-                            end_index = i;
-                            found_end = true;
-                            break;
-                        }
-                    }
-                }
-                
-                if found_end {
-                    // Extract thread content (excluding outer brackets)
-                    if end_index > 1 {
-                        thread = Some(rest[1..end_index].to_string());
-                    }
-                    // The rest is class and message (skip the closing bracket and following space)
-                    if end_index + 1 < rest.len() {
-                        class_and_message = &rest[end_index + 1..];
-                    } else {
-                        class_and_message = "";
-                    }
-                }
+        let line = self.preprocess(line);
+        self.parse_entry(&line, line_number)
+    }
+    
+    /// Run plugin `transform`s over a physical line before it is parsed.
+    fn preprocess<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = self.line_text(line);
+        for plugin in &self.plugins {
+            if let Some(transformed) = plugin.transform(&line) {
+                line = Cow::Owned(transformed);
             }
-            
-            let class_and_message = class_and_message.trim();
-            
-            // Extract class and message
-            let parts: Vec<&str> = class_and_message.splitn(2, ' ').collect();
-            let class = parts.get(0).map(|s| s.to_string());
-            let message = parts.get(1).map(|s| s.to_string()).unwrap_or_else(|| class_and_message.to_string());
-            
-            let level = match level_str.to_uppercase().as_str() {
-                "INFO" => LogLevel::Info,
-                "WARN" => LogLevel::Warn,
-                "ERROR" => LogLevel::Error,
-                "DEBUG" => LogLevel::Debug,
-                "TRACE" => LogLevel::Trace,
-                _ => LogLevel::Unknown,
-            };
-            
-            return LogEntry {
-                line_number,
-                timestamp,
-                level,
-                thread,
-                class,
-                message,
-                raw_line: line.to_string(),
-                is_error_log: true,
-            };
-        }
-        
-        // Try access log format
-        if let Some(caps) = self.access_log_regex.captures(line) {
-            let ip = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            let user = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-            let timestamp = caps.get(3).map(|m| m.as_str().to_string());
-            let rest = caps.get(4).map(|m| m.as_str()).unwrap_or("");
-            
-            let message = format!("{} - {} - {}", ip, user, rest);
-            
-            return LogEntry {
-                line_number,
-                timestamp,
-                level: LogLevel::Info, // Access logs are typically INFO level
-                thread: None,
-                class: None,
-                message,
-                raw_line: line.to_string(),
-                is_error_log: false,
-            };
+        }
+        line
+    }
+    
+    /// A line of the file as entries hold it: collapsed to its final state
+    /// when it redraws itself with carriage returns and that is turned on.
+    pub fn line_text<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.collapse_progress {
+            collapse_progress(line)
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+    
+    fn is_plugin_line(&self, line: &str) -> bool {
+        self.plugins.iter().any(|p| p.parses_lines() && p.parse_line(line).is_some())
+    }
+    
+    fn parse_entry(&self, line: &str, line_number: usize) -> LogEntry {
+        let plugin_entry = self.plugins.iter().find_map(|p| p.parse_line(line)).map(|fields| {
+            let level = fields.level.map(|l| LogLevel::from_name(&l)).unwrap_or(LogLevel::Unknown);
+            let time_ms = fields.timestamp.as_deref().and_then(|ts| self.timestamp_millis(ts));
+            LogEntry::parsed(line, line_number, level, EntryFields {
+                timestamp: fields.timestamp.map(FieldText::from),
+                thread: fields.thread.as_deref().map(intern),
+                class: fields.class.as_deref().map(intern),
+                message: fields.message.map_or_else(|| FieldText::new(line, line), FieldText::from),
+            }, time_ms)
+        });
+        let mut entry = plugin_entry.unwrap_or_else(|| self.parse_line_fields(line, line_number));
+        
+        if let Some(level) = self.plugins.iter().find_map(|p| p.classify(line)) {
+            entry.level = level;
+        }
+        entry
+    }
+    
+    /// Parse a timestamp string from any supported format into milliseconds.
+    pub fn timestamp_millis(&self, timestamp: &str) -> Option<i64> {
+        builtin_timestamp_millis(timestamp).or_else(|| {
+            let timestamp = timestamp.trim();
+            self.custom_formats
+                .iter()
+                .map(|(f, _, _)| f.timestamp_format.as_str())
+                .filter(|f| !f.is_empty())
+                .find_map(|format| chrono::NaiveDateTime::parse_from_str(timestamp, format).ok())
+                .map(|time| time.and_utc().timestamp_millis())
+        })
+    }
+    
+    /// Parse what's needed up front (the level); the built-in formats leave
+    /// the other fields for `LogEntry` to parse on first access.
+    fn parse_line_fields(&self, line: &str, line_number: usize) -> LogEntry {
+        // A selected custom format takes over completely
+        if let Some(regex) = self.active_custom_regex() {
+            if let Some(caps) = regex.captures(line) {
+                let group = |name: &str| caps.name(name).map(|m| m.as_str());
+                let level = group("level").map(LogLevel::from_name).unwrap_or(LogLevel::Unknown);
+                return LogEntry::parsed(line, line_number, level, EntryFields {
+                    timestamp: group("timestamp").map(|t| FieldText::new(line, t)),
+                    thread: group("thread").map(intern),
+                    class: group("class").map(intern),
+                    message: FieldText::new(line, group("message").unwrap_or(line)),
+                }, group("timestamp").and_then(|t| self.timestamp_millis(t)));
+            }
+        }
+        
+        // Try JSON lines first (cheap check on the leading brace)
+        if self.is_json_line(line) {
+            if let Some(entry) = self.parse_json_line(line, line_number) {
+                return entry;
+            }
+        }
+        
+        // Error log: the level sits between the first pair of asterisks
+        if self.is_error_log_line(line) {
+            let level = LogLevel::from_name(line.split('*').nth(1).unwrap_or(""));
+            return LogEntry::lazy(line, line_number, level, LineKind::ErrorLog);
+        }
+        
+        // Access logs are typically INFO level
+        if self.is_access_log_line(line) {
+            return LogEntry::lazy(line, line_number, LogLevel::Info, LineKind::AccessLog);
         }
         
         // Default: unparsed line
-        LogEntry {
-            line_number,
-            timestamp: None,
-            level: LogLevel::Unknown,
-            thread: None,
-            class: None,
-            message: line.to_string(),
-            raw_line: line.to_string(),
-            is_error_log: false,
+        LogEntry::lazy(line, line_number, LogLevel::Unknown, LineKind::Plain)
+    }
+
+    // JSON lines: pick the usual field names used by logback/log4j2/bunyan/etc. encoders
+    fn parse_json_line(&self, line: &str, line_number: usize) -> Option<LogEntry> {
+        let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+        let object = value.as_object()?;
+        let field = |names: &[&str]| {
+            names.iter().find_map(|name| match object.get(*name)? {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Null => None,
+                other => Some(other.to_string()),
+            })
+        };
+        
+        let level = field(&["level", "severity", "lvl", "log.level"])
+            .map(|l| LogLevel::from_name(&l))
+            .unwrap_or(LogLevel::Unknown);
+        let timestamp = field(&["timestamp", "@timestamp", "time", "ts"]);
+        
+        let time_ms = timestamp.as_deref().and_then(|ts| self.timestamp_millis(ts));
+        Some(LogEntry::parsed(line, line_number, level, EntryFields {
+            timestamp: timestamp.map(FieldText::from),
+            thread: field(&["thread", "thread_name", "process.thread.name"]).as_deref().map(intern),
+            class: field(&["logger", "logger_name", "class", "log.logger"]).as_deref().map(intern),
+            message: field(&["message", "msg"]).map_or_else(|| FieldText::new(line, line), FieldText::from),
+        }, time_ms))
+    }
+
+    /// Rebuild an entry from its index cache summary and text, without
+    /// detecting its format again.
+    pub fn restore_entry(&self, raw_line: String, summary: &EntrySummary) -> LogEntry {
+        match LineKind::ALL.get(summary.kind as usize) {
+            Some(&kind) if kind != LineKind::Parsed => LogEntry {
+                line_number: summary.line_number,
+                level: summary.level.clone(),
+                raw_line,
+                is_error_log: kind == LineKind::ErrorLog,
+                source: 0,
+                kind,
+                fields: OnceLock::new(),
+                time: OnceLock::from(summary.time_ms),
+            },
+            // Fully parsed formats are parsed again for their fields
+            _ => {
+                let mut entry = self.parse_entry(raw_line.lines().next().unwrap_or(""), summary.line_number);
+                entry.raw_line = raw_line;
+                entry
+            }
+        }
+    }
+
+    /// Everything that decides how lines are parsed, so caches of parse
+    /// results can tell when they are stale.
+    pub fn signature(&self) -> String {
+        let mut signature = format!("{:?}\n{:?}\n{}", self.format, self.entry_start, self.collapse_progress);
+        for (format, _, _) in &self.custom_formats {
+            signature.push_str(&format!(
+                "\n{}\t{}\t{}\t{}",
+                format.name, format.pattern, format.timestamp_format, format.entry_start
+            ));
         }
+        signature
+    }
+
+    /// True when entries come out exactly as the file's lines, which the index
+    /// cache relies on: no plugin rewrites or parses lines.
+    pub fn reads_lines_verbatim(&self) -> bool {
+        !self.plugins.iter().any(|p| p.enabled)
     }
 
     pub fn parse_file(&self, content: &str) -> Vec<LogEntry> {
-        let lines: Vec<&str> = content.lines().collect();
         let mut entries = Vec::new();
+        self.parse_each(content, |entry| {
+            entries.push(entry);
+            true
+        });
+        entries.shrink_to_fit();
+        entries
+    }
+
+    /// True when `line` begins a new entry rather than continuing the
+    /// previous one, by the `EntryStart` rule. Blank lines never do.
+    pub fn starts_entry(&self, line: &str) -> bool {
+        match self.entry_start {
+            EntryStart::Format => self.format_starts_entry(line),
+            EntryStart::Unindented => !line.trim().is_empty() && !line.starts_with(char::is_whitespace),
+            EntryStart::EveryLine => !line.trim().is_empty(),
+            EntryStart::Pattern(_) => self.entry_start_regex.as_ref().is_some_and(|re| re.is_match(line)),
+        }
+    }
+
+    /// Whether the format parses `line` as an entry. Auto-detection also takes
+    /// any line starting with a timestamp; a chosen format only its own lines,
+    /// so messages starting with numbers aren't split off.
+    fn format_starts_entry(&self, line: &str) -> bool {
+        self.is_plugin_line(line)
+            || self.is_custom_line(line)
+            || self.is_json_line(line)
+            || self.is_error_log_line(line)
+            || self.is_access_log_line(line)
+            || (self.format == LogFormat::Auto && timestamp_start_regex().is_match(line))
+    }
+
+    /// Add a line read live to `entries`, grouped as `parse_each` groups a
+    /// file: a line that doesn't start an entry goes on the last one while it
+    /// is `open`, which lasts until a blank line. With no last entry in
+    /// `entries`, the line is returned, to go on the entry read before them.
+    pub fn append_line(&self, line: &str, entries: &mut Vec<LogEntry>, open: &mut bool) -> Option<String> {
+        let line = self.preprocess(line);
+        if self.starts_entry(&line) {
+            entries.push(self.parse_entry(&line, entries.len() + 1));
+            *open = true;
+            return None;
+        }
+        if line.trim().is_empty() {
+            *open = false;
+        }
+        if !*open {
+            return None;
+        }
+        match entries.last_mut() {
+            Some(entry) => {
+                entry.raw_line.push('\n');
+                entry.raw_line.push_str(&line);
+                None
+            }
+            None => Some(line.into_owned()),
+        }
+    }
+
+    /// `entry`, whose last line was read before it ended, continued with
+    /// `rest`: the rest of that line, then any lines `append_line` returned
+    /// for it, each after a newline. It is parsed again from its first line.
+    pub fn continue_entry(&self, entry: &LogEntry, rest: &str) -> LogEntry {
+        let (line_end, more) = rest.split_once('\n').unwrap_or((rest, ""));
+        let mut raw_line = entry.raw_line.clone();
+        if !line_end.is_empty() {
+            let start = raw_line.rfind('\n').map_or(0, |i| i + 1);
+            let line = format!("{}{}", &raw_line[start..], line_end);
+            raw_line.truncate(start);
+            raw_line.push_str(&self.line_text(&line));
+        }
+        if rest.contains('\n') {
+            raw_line.push('\n');
+            raw_line.push_str(more);
+        }
+        let mut continued = self.parse_entry(raw_line.lines().next().unwrap_or(""), entry.line_number);
+        continued.raw_line = raw_line;
+        continued.source = entry.source;
+        continued
+    }
+
+    /// Parse `content`, handing each entry to `emit` as soon as it is complete.
+    /// Stops early when `emit` returns false.
+    pub fn parse_each(&self, content: &str, mut emit: impl FnMut(LogEntry) -> bool) {
+        let lines: Vec<Cow<str>> = content.lines().map(|line| self.preprocess(line)).collect();
         let mut i = 0;
         
-        // Pattern to detect if a line starts with a timestamp (DD.MM.YYYY or DD/MMM/YYYY)
-        let timestamp_start_pattern = Regex::new(r"^\d{2}[./]").unwrap();
-        
         while i < lines.len() {
-            let line = lines[i];
+            let line: &str = &lines[i];
             let line_number = i + 1;
             
-            // Check if this line starts a new log entry (has timestamp pattern or matches regex)
-            let starts_new_entry = self.error_log_regex.is_match(line) || 
-                                   self.access_log_regex.is_match(line) ||
-                                   timestamp_start_pattern.is_match(line);
-            
-            if starts_new_entry {
+            if self.starts_entry(line) {
                 // Parse the main entry
-                let mut entry = self.parse_line(line, line_number);
-                let mut full_text = line.to_string();
+                let mut entry = self.parse_entry(line, line_number);
                 i += 1;
                 
                 // Collect continuation lines (lines that don't start with a timestamp)
                 while i < lines.len() {
-                    let next_line = lines[i];
+                    let next_line: &str = &lines[i];
                     // Check if next line is a continuation
                     // It's a continuation if it doesn't match entry patterns and doesn't start with timestamp
-                    let is_continuation = !self.error_log_regex.is_match(next_line) && 
-                                         !self.access_log_regex.is_match(next_line) &&
-                                         !timestamp_start_pattern.is_match(next_line) &&
-                                         !next_line.trim().is_empty();
+                    let is_continuation = !self.starts_entry(next_line) && !next_line.trim().is_empty();
                     
                     if is_continuation {
-                        full_text.push('\n');
-                        full_text.push_str(next_line);
+                        entry.raw_line.push('\n');
+                        entry.raw_line.push_str(next_line);
                         i += 1;
                     } else {
                         break;
                     }
                 }
                 
-                // Update the entry with the full multi-line text
-                entry.raw_line = full_text;
-                entries.push(entry);
+                // Growing the text for continuation lines leaves spare capacity
+                entry.raw_line.shrink_to_fit();
+                if !emit(entry) {
+                    return;
+                }
             } else {
                 // Skip empty lines or unparseable lines
                 i += 1;
             }
         }
-        
-        entries
     }
 }
 
@@ -209,3 +927,77 @@ impl Default for LogParser {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn append_all(parser: &LogParser, lines: &[&str], entries: &mut Vec<LogEntry>, open: &mut bool) -> Vec<String> {
+        lines.iter().filter_map(|line| parser.append_line(line, entries, open)).collect()
+    }
+
+    #[test]
+    fn groups_live_lines_like_a_file() {
+        let parser = LogParser::new();
+        let lines = [
+            "15.01.2024 10:00:00.123 *ERROR* [main] com.example.App Failed",
+            "java.lang.IllegalStateException: boom",
+            "    at App.run(App.java:10)",
+            "15.01.2024 10:00:01.456 *INFO* [main] com.example.App Retrying",
+        ];
+        let (mut entries, mut open) = (Vec::new(), true);
+        assert!(append_all(&parser, &lines, &mut entries, &mut open).is_empty());
+        let loaded = parser.parse_file(&lines.join("\n"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries.iter().map(|e| &e.raw_line).collect::<Vec<_>>(), loaded.iter().map(|e| &e.raw_line).collect::<Vec<_>>());
+        assert_eq!(entries[0].level, LogLevel::Error);
+    }
+
+    #[test]
+    fn hands_back_lines_continuing_an_earlier_entry() {
+        let parser = LogParser::new();
+        let (mut entries, mut open) = (Vec::new(), true);
+        let rest = append_all(&parser, &["    at App.run(App.java:10)", "15.01.2024 10:00:01.456 *INFO* [main] com.example.App Next"], &mut entries, &mut open);
+        assert_eq!(rest, ["    at App.run(App.java:10)"]);
+        assert_eq!(entries.len(), 1);
+        
+        // A blank line ends the entry, as when loading
+        let (mut entries, mut open) = (Vec::new(), true);
+        assert!(append_all(&parser, &["", "stray text"], &mut entries, &mut open).is_empty());
+        assert!(entries.is_empty() && !open);
+    }
+
+    #[test]
+    fn every_line_rule_keeps_lines_apart() {
+        let mut parser = LogParser::new();
+        parser.set_entry_start(EntryStart::EveryLine).unwrap();
+        let (mut entries, mut open) = (Vec::new(), false);
+        assert!(append_all(&parser, &["first", "  second"], &mut entries, &mut open).is_empty());
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn continues_an_entry_read_in_part() {
+        let parser = LogParser::new();
+        let entry = parser.parse_line("15.01.2024 10:00:00.123 *WA", 7);
+        let continued = parser.continue_entry(&entry, "RN* [main] com.example.Disk Low disk\n    free: 1%");
+        assert_eq!(continued.raw_line, "15.01.2024 10:00:00.123 *WARN* [main] com.example.Disk Low disk\n    free: 1%");
+        assert_eq!(continued.level, LogLevel::Warn);
+        assert_eq!(continued.line_number, 7);
+        
+        let more = parser.continue_entry(&continued, "\n    used: 99%");
+        assert_eq!(more.raw_line.lines().count(), 3);
+        assert_eq!(more.message(), continued.message());
+    }
+
+    #[test]
+    fn collapses_progress_lines_like_a_terminal() {
+        assert!(matches!(collapse_progress("no carriage return"), Cow::Borrowed("no carriage return")));
+        assert_eq!(collapse_progress("10%\r100%"), "100%");
+        // A shorter redraw leaves the end of the longer one
+        assert_eq!(collapse_progress("Downloading 100 files\rDone"), "Doneloading 100 files");
+        assert_eq!(collapse_progress("[==  ] 50%\r[====] 100%\r"), "[====] 100%");
+        assert_eq!(collapse_progress("\r\r"), "");
+        assert_eq!(collapse_progress("日本語\r英"), "英本語");
+    }
+}
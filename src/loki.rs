@@ -0,0 +1,276 @@
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::Message;
+use url::Url;
+
+use crate::file_open;
+use crate::live_stream::{self, LiveStream};
+use crate::source_event::{self, SourceAction, SourceEvent};
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A LogQL query against a Loki server, as edited in the Loki window.
+#[derive(Debug, Clone)]
+pub struct LokiQuery {
+    /// Loki's base URL, e.g. `http://localhost:3100`
+    pub url: String,
+    /// LogQL log query, e.g. `{app="api"} |= "error"`
+    pub query: String,
+    /// How far back to query, in minutes
+    pub lookback_minutes: u32,
+    /// Most lines returned by the query; Loki caps this at 5000 by default
+    pub limit: u32,
+    /// Keep following the query with Loki's live tail
+    pub live: bool,
+    /// Tenant for multi-tenant Loki (the X-Scope-OrgID header), if any
+    pub tenant: String,
+}
+
+impl Default for LokiQuery {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3100".to_string(),
+            query: String::new(),
+            lookback_minutes: 60,
+            limit: 1000,
+            live: true,
+            tenant: String::new(),
+        }
+    }
+}
+
+impl LokiQuery {
+    /// `query @ host`, as shown in the header.
+    pub fn description(&self) -> String {
+        let host = Url::parse(self.url.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| self.url.trim().to_string());
+        format!("{} @ {}", self.query.trim(), host)
+    }
+}
+
+/// The Query Loki window.
+#[derive(Default)]
+pub struct LokiDialog {
+    pub open: bool,
+    pub query: LokiQuery,
+    /// Why the last query couldn't be run
+    pub error: Option<String>,
+}
+
+impl LokiDialog {
+    pub fn show(&mut self, ctx: &egui::Context, stream: Option<&LiveStream>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Query Loki")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let query = &mut self.query;
+                egui::Grid::new("loki_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("URL:");
+                    ui.add(egui::TextEdit::singleline(&mut query.url).desired_width(300.0));
+                    ui.end_row();
+                    ui.label("Query:");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut query.query)
+                            .desired_width(300.0)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("{app=\"api\"} |= \"error\""),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        action = Some(SourceAction::Open);
+                    }
+                    ui.end_row();
+                    ui.label("Last:");
+                    ui.horizontal(|ui| source_event::lookback_choices(ui, &mut query.lookback_minutes));
+                    ui.end_row();
+                    ui.label("Limit:");
+                    ui.add(egui::DragValue::new(&mut query.limit).speed(100.0).clamp_range(1..=50_000).suffix(" lines"))
+                        .on_hover_text("The newest lines in the range are shown. Loki allows at most 5000 unless configured otherwise.");
+                    ui.end_row();
+                    ui.label("Tenant:");
+                    ui.text_edit_singleline(&mut query.tenant).on_hover_text("X-Scope-OrgID, for multi-tenant Loki; leave empty otherwise");
+                    ui.end_row();
+                });
+                ui.checkbox(&mut query.live, "Follow new lines (live tail)");
+                source_event::show_errors(ui, stream.map(LiveStream::events), self.error.as_deref());
+                ui.horizontal(|ui| {
+                    if ui.button("Run").clicked() {
+                        action = Some(SourceAction::Open);
+                    }
+                    if stream.is_some() && ui.button("Disconnect").clicked() {
+                        action = Some(SourceAction::Disconnect);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// The query's base URL, checked.
+pub fn base_url(query: &LokiQuery) -> Result<Url, String> {
+    if query.query.trim().is_empty() {
+        return Err("Enter a LogQL query, e.g. {app=\"api\"}".to_string());
+    }
+    let url = Url::parse(query.url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        scheme => Err(format!("Unsupported scheme {}: use http or https", scheme)),
+    }
+}
+
+/// `base` with Loki's API `path` appended, keeping any path prefix (e.g. behind a proxy).
+fn endpoint(base: &Url, path: &str) -> Url {
+    let mut url = base.clone();
+    url.set_path(&format!("{}/loki/api/v1/{}", base.path().trim_end_matches('/'), path));
+    url
+}
+
+/// Run the query over its lookback window, then follow it when live.
+pub fn run(base: &Url, query: &LokiQuery, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    let now = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    let start = now - i64::from(query.lookback_minutes) * 60_000_000_000;
+    let mut last = match query_range(base, query, start, now) {
+        Ok(lines) => {
+            let mut delay = Duration::ZERO;
+            if !live_stream::connected(&mut delay, sender) || !send(&lines, sender) {
+                return;
+            }
+            lines.last().map_or(start, |(time, _)| *time)
+        }
+        Err(e) => {
+            sender.send(SourceEvent::Disconnected(e)).ok();
+            sender.send(SourceEvent::Ended).ok();
+            file_open::wake_ui();
+            return;
+        }
+    };
+    if !query.live {
+        sender.send(SourceEvent::Ended).ok();
+        file_open::wake_ui();
+        return;
+    }
+    live_stream::retry(sender, stop, |delay| tail(base, query, &mut last, delay, sender, stop));
+}
+
+/// Send lines, oldest first. False once the receiver is gone.
+fn send(lines: &[(i64, String)], sender: &mpsc::SyncSender<SourceEvent>) -> bool {
+    if lines.is_empty() {
+        return true;
+    }
+    let lines = lines.iter().map(|(_, line)| line.clone()).collect();
+    let sent = sender.send(SourceEvent::Lines(lines)).is_ok();
+    file_open::wake_ui();
+    sent
+}
+
+/// The newest `limit` lines between `start` and `end` (nanoseconds), oldest first.
+fn query_range(base: &Url, query: &LokiQuery, start: i64, end: i64) -> Result<Vec<(i64, String)>, String> {
+    let url = endpoint(base, "query_range");
+    let mut request = ureq::request_url("GET", &url)
+        .timeout(QUERY_TIMEOUT)
+        .query("query", query.query.trim())
+        .query("start", &start.to_string())
+        .query("end", &end.to_string())
+        .query("limit", &query.limit.max(1).to_string())
+        .query("direction", "backward");
+    if !query.tenant.trim().is_empty() {
+        request = request.set("X-Scope-OrgID", query.tenant.trim());
+    }
+    let response = request.call().map_err(|e| match e {
+        ureq::Error::Status(_, response) => format!("Query failed: {}", response.into_string().unwrap_or_default().trim()),
+        e => format!("Failed to reach Loki: {}", e),
+    })?;
+    let body: Value =
+        serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the response: {}", e))?;
+    if body["data"]["resultType"] != "streams" {
+        return Err("Not a log query: use a log selector, not a metric query".to_string());
+    }
+    Ok(stream_lines(&body["data"]["result"]))
+}
+
+/// Follow the query with Loki's live tail from just after `last`, moving
+/// `last` on so a reconnect resumes there.
+fn tail(
+    base: &Url,
+    query: &LokiQuery,
+    last: &mut i64,
+    retry_delay: &mut Duration,
+    sender: &mpsc::SyncSender<SourceEvent>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let mut url = endpoint(base, "tail");
+    let scheme = if base.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme).map_err(|_| "Invalid URL for the live tail".to_string())?;
+    url.query_pairs_mut()
+        .append_pair("query", query.query.trim())
+        .append_pair("start", &(*last + 1).to_string());
+    let mut request = url.as_str().into_client_request().map_err(|e| format!("Invalid URL: {}", e))?;
+    if !query.tenant.trim().is_empty() {
+        let tenant = query.tenant.trim().parse().map_err(|_| "Invalid tenant".to_string())?;
+        request.headers_mut().insert("X-Scope-OrgID", tenant);
+    }
+    let mut socket = live_stream::connect_websocket(request)?;
+    if !live_stream::connected(retry_delay, sender) {
+        return Ok(());
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return Err("Closed by Loki".to_string()),
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e)) if live_stream::is_timeout(&e) => continue,
+            Err(e) => return Err(format!("Connection lost: {}", e)),
+        };
+        let message: Value = serde_json::from_str(&text).map_err(|e| format!("Unexpected tail message: {}", e))?;
+        let mut lines = stream_lines(&message["streams"]);
+        // Lines at or before the last one sent were already shown
+        lines.retain(|(time, _)| *time > *last);
+        if let Some((time, _)) = lines.last() {
+            *last = *time;
+        }
+        if !send(&lines, sender) {
+            break;
+        }
+        let dropped = message["dropped_entries"].as_array().map_or(0, Vec::len);
+        if dropped > 0 {
+            let marker = format!("──────── Loki dropped {} lines, the tail fell behind ────────", dropped);
+            if sender.send(SourceEvent::Lines(vec![marker])).is_err() {
+                break;
+            }
+        }
+    }
+    socket.close(None).ok();
+    Ok(())
+}
+
+/// The lines of Loki's streams (`[{"stream": {..}, "values": [["<ns>", "<line>"], ..]}, ..]`),
+/// interleaved by timestamp.
+fn stream_lines(streams: &Value) -> Vec<(i64, String)> {
+    let mut lines: Vec<(i64, String)> = streams
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|stream| stream["values"].as_array())
+        .flatten()
+        .filter_map(|value| {
+            let time = value.get(0)?.as_str()?.parse().ok()?;
+            let line = value.get(1)?.as_str()?.trim_end();
+            Some((time, line.to_string()))
+        })
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    lines.sort_by_key(|(time, _)| *time);
+    lines
+}
@@ -1,11 +1,9 @@
-mod app;
-mod log_parser;
-mod file_watcher;
-mod config;
-mod search;
-
+use clap::Parser;
 use eframe::egui;
-use app::LogViewerApp;
+use log_rocket::app::LogViewerApp;
+use log_rocket::cli::Cli;
+use log_rocket::log_parser::LogFormat;
+use log_rocket::{config, diagnostics, file_open, glob_watch, headless, live_stream, object_store, single_instance, window_state};
 
 fn load_icon() -> eframe::IconData {
     let (icon_rgba, icon_width, icon_height) = {
@@ -26,36 +24,70 @@ fn load_icon() -> eframe::IconData {
 }
 
 fn main() -> eframe::Result<()> {
+    let window = window_state::load();
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(1200.0, 800.0)),
-        maximized: true,
+        initial_window_size: Some(egui::vec2(window.width, window.height)),
+        initial_window_pos: window.x.zip(window.y).map(|(x, y)| egui::pos2(x, y)),
+        maximized: window.maximized,
         icon_data: Some(load_icon()),
         ..Default::default()
     };
     
-    // Check for command line arguments (file to open)
-    let args: Vec<String> = std::env::args().collect();
-    let file_to_open = if args.len() > 1 {
-        Some(std::path::PathBuf::from(&args[1]))
-    } else {
-        None
-    };
+    let cli = Cli::parse();
+    config::set_overrides(cli.config.clone(), cli.theme.clone());
+    diagnostics::install_panic_hook();
+    if cli.no_gui {
+        let code = headless::run(&cli).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            2
+        });
+        std::process::exit(code);
+    }
+    let request = cli.open_request();
+    
+    // If Log Rocket is already running, hand the file over and let that window open it.
+    // Pipes (e.g. process substitution's /dev/fd/63) can only be read from here
+    let is_pipe = request.as_ref().is_some_and(|r| live_stream::is_pipe(&r.path));
+    if !is_pipe && single_instance::send_to_running(request.as_ref()) {
+        return Ok(());
+    }
+    single_instance::listen();
+    
+    // Register for OS file-open events (macOS Finder / "Open With") before the
+    // event loop starts, so documents passed at launch are delivered too
+    file_open::install();
     
     eframe::run_native(
         "Log Rocket",
         options,
         Box::new(move |cc| {
+            file_open::set_repaint_context(&cc.egui_ctx);
             let mut app = LogViewerApp::default();
+            if cli.follow {
+                app.set_follow(true);
+            }
             
             // If a file was provided via CLI, load it
-            if let Some(path) = file_to_open {
-                if path.exists() {
-                    if let Err(e) = app.load_file(path) {
-                        eprintln!("Error loading file from CLI: {}", e);
+            if let Some(ref request) = request {
+                if request.path.exists() || glob_watch::is_glob(&request.path) || object_store::is_object_url(&request.path) {
+                    if let Err(e) = app.open_path(request.path.clone()) {
+                        log_rocket::app_error!("Error loading file from CLI: {}", e);
                     }
                 }
             }
             
+            // After loading, so an explicit --format beats the file's remembered one
+            if cli.format != LogFormat::Auto {
+                app.set_format(cli.format.clone());
+            }
+            app.set_level_filter(&cli.level);
+            if let Some(ref query) = cli.search {
+                app.set_search(query, cli.regex);
+            }
+            if let Some(line) = request.and_then(|r| r.line) {
+                app.goto_line(line);
+            }
+            
             Box::new(app)
         }),
     )
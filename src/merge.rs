@@ -0,0 +1,133 @@
+use egui::Color32;
+use std::path::PathBuf;
+
+use crate::log_parser::LogEntry;
+use crate::parse_worker::ParseJob;
+
+/// Longest source label shown on merged rows.
+const LABEL_LEN: usize = 12;
+
+/// Source colors, readable on both themes; they repeat past eight sources.
+const SOURCE_COLORS: [Color32; 8] = [
+    Color32::from_rgb(0x4e, 0x9a, 0xf0),
+    Color32::from_rgb(0xe0, 0x8a, 0x2e),
+    Color32::from_rgb(0x3a, 0xb8, 0x6c),
+    Color32::from_rgb(0xb0, 0x6a, 0xe0),
+    Color32::from_rgb(0xe0, 0x5a, 0x9a),
+    Color32::from_rgb(0x2e, 0xb8, 0xb8),
+    Color32::from_rgb(0xc8, 0xb0, 0x2e),
+    Color32::from_rgb(0x8a, 0x8a, 0x8a),
+];
+
+/// The color identifying source `idx` in merged views.
+pub fn source_color(idx: usize) -> Color32 {
+    SOURCE_COLORS[idx % SOURCE_COLORS.len()]
+}
+
+/// One file taking part in a merged view.
+pub struct MergeSource {
+    pub path: PathBuf,
+    /// Clock-skew correction added to every timestamp of this source
+    pub offset_ms: i64,
+    pub entries: Vec<LogEntry>,
+    /// Where tailing the file continues, and the id of the file read
+    pub tail_offset: u64,
+    pub file_id: Option<u64>,
+    /// Entries still arriving from a background parse
+    pub parse_job: Option<ParseJob>,
+}
+
+impl MergeSource {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
+    /// The file name without its extension, shortened for row labels.
+    pub fn label(&self) -> String {
+        let stem = self.path.file_stem().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| self.name());
+        if stem.chars().count() > LABEL_LEN {
+            format!("{}…", stem.chars().take(LABEL_LEN - 1).collect::<String>())
+        } else {
+            stem
+        }
+    }
+}
+
+/// Drop from each source the entries of it among `dropped`, the oldest
+/// entries of the merged view, so the files' copies don't outgrow the view.
+pub fn drop_front(sources: &mut [MergeSource], dropped: &[LogEntry]) {
+    for (idx, source) in sources.iter_mut().enumerate() {
+        let Some(last) = dropped.iter().filter(|entry| entry.source == idx).map(|entry| entry.line_number).max() else {
+            continue;
+        };
+        let count = source.entries.partition_point(|entry| entry.line_number <= last);
+        source.entries.drain(..count);
+    }
+}
+
+/// Interleave the entries of all sources by timestamp, after shifting each
+/// source by its offset. Entries without a timestamp stay right after the
+/// entry that preceded them in their own file.
+pub fn merge_sources(sources: &[MergeSource]) -> Vec<LogEntry> {
+    let mut keyed: Vec<(i64, usize, usize)> = Vec::new();
+    for (source_idx, source) in sources.iter().enumerate() {
+        let mut last_key = i64::MIN;
+        for (entry_idx, entry) in source.entries.iter().enumerate() {
+            if let Some(time) = entry.time_ms() {
+                last_key = time + source.offset_ms;
+            }
+            keyed.push((last_key, source_idx, entry_idx));
+        }
+    }
+
+    // Stable: equal timestamps keep file order, with earlier sources first
+    keyed.sort_by_key(|(key, _, _)| *key);
+
+    keyed
+        .into_iter()
+        .map(|(_, source_idx, entry_idx)| {
+            let source = &sources[source_idx];
+            let mut entry = source.entries[entry_idx].clone();
+            entry.source = source_idx;
+            entry.shift_time(source.offset_ms);
+            entry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::LogParser;
+
+    fn source(name: &str, entries: Vec<LogEntry>) -> MergeSource {
+        MergeSource { path: PathBuf::from(name), offset_ms: 0, entries, tail_offset: 0, file_id: None, parse_job: None }
+    }
+
+    fn line_numbers(source: &MergeSource) -> Vec<usize> {
+        source.entries.iter().map(|entry| entry.line_number).collect()
+    }
+
+    #[test]
+    fn drops_the_oldest_entries_of_each_file() {
+        let parser = LogParser::new();
+        let entry = |minute: usize, line| parser.parse_line(&format!("15.01.2024 10:{:02}:00.000 *INFO* [main] com.example.Api Request", minute), line);
+        let mut sources = [
+            source("api.log", vec![entry(0, 1), entry(2, 2), entry(4, 3)]),
+            source("db.log", vec![entry(1, 1), entry(3, 2)]),
+        ];
+        let merged = merge_sources(&sources);
+
+        // api.log's first two lines and db.log's first
+        drop_front(&mut sources, &merged[..3]);
+        assert_eq!(line_numbers(&sources[0]), [3]);
+        assert_eq!(line_numbers(&sources[1]), [2]);
+
+        // A note isn't a line of any file
+        drop_front(&mut sources, &[LogEntry::note("──────── 10 lines dropped ────────", 0)]);
+        assert_eq!(line_numbers(&sources[0]), [3]);
+    }
+}
@@ -0,0 +1,168 @@
+use egui::{Color32, Pos2, Sense, Stroke, Vec2};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::log_parser::LogEntry;
+
+const SERIES_COLORS: [Color32; 4] = [
+    Color32::from_rgb(0x7a, 0xb8, 0xff),
+    Color32::from_rgb(0xff, 0xb8, 0x5c),
+    Color32::from_rgb(0x6f, 0xd0, 0x8c),
+    Color32::from_rgb(0xb4, 0x8e, 0xff),
+];
+
+pub fn series_color(index: usize) -> Color32 {
+    SERIES_COLORS[index % SERIES_COLORS.len()]
+}
+
+/// Pulls a number out of matching lines, e.g. `took (\d+)ms`. The `value`
+/// named group is used if present, otherwise the first capture group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricExtractor {
+    pub name: String,
+    pub pattern: String,
+}
+
+impl MetricExtractor {
+    pub fn compile(&self) -> Result<Regex, String> {
+        let regex = Regex::new(&self.pattern).map_err(|e| e.to_string())?;
+        if regex.captures_len() < 2 {
+            return Err("Pattern needs a capture group around the number".to_string());
+        }
+        Ok(regex)
+    }
+}
+
+pub struct MetricPoint {
+    pub time_ms: i64,
+    pub value: f64,
+    pub entry_idx: usize,
+}
+
+pub struct MetricSeries {
+    pub name: String,
+    pub points: Vec<MetricPoint>,
+    regex: Regex,
+}
+
+impl MetricSeries {
+    /// Add the points of the entries from index `start` on.
+    pub fn extend(&mut self, entries: &[LogEntry], start: usize) {
+        let found = points(&self.regex, entries, start);
+        self.points.extend(found);
+    }
+
+    /// Forget the points of the first `dropped` entries, which were
+    /// discarded, and renumber the rest to match.
+    pub fn drop_front(&mut self, dropped: usize) {
+        self.points.retain(|point| point.entry_idx >= dropped);
+        self.points.iter_mut().for_each(|point| point.entry_idx -= dropped);
+    }
+
+    fn value_range(&self) -> (f64, f64) {
+        let min = self.points.iter().map(|p| p.value).fold(f64::INFINITY, f64::min);
+        let max = self.points.iter().map(|p| p.value).fold(f64::NEG_INFINITY, f64::max);
+        (min, max)
+    }
+}
+
+/// Run every extractor over the entries. Entries without a timestamp can't be
+/// placed on the timeline and are skipped.
+pub fn extract(extractors: &[MetricExtractor], entries: &[LogEntry]) -> Vec<MetricSeries> {
+    extractors
+        .iter()
+        .filter_map(|extractor| {
+            let regex = extractor.compile().ok()?;
+            let points = points(&regex, entries, 0);
+            Some(MetricSeries { name: extractor.name.clone(), points, regex })
+        })
+        .collect()
+}
+
+/// The values `regex` finds in the entries from index `start` on.
+fn points(regex: &Regex, entries: &[LogEntry], start: usize) -> Vec<MetricPoint> {
+    entries
+        .iter()
+        .enumerate()
+        .skip(start)
+        .filter_map(|(entry_idx, entry)| {
+            let time_ms = entry.time_ms()?;
+            let caps = regex.captures(&entry.raw_line)?;
+            let value = caps.name("value").or_else(|| caps.get(1))?;
+            let value = value.as_str().replace(',', "").parse::<f64>().ok()?;
+            Some(MetricPoint { time_ms, value, entry_idx })
+        })
+        .collect()
+}
+
+/// Draw a small line chart of the series over `range`. Returns the entry index
+/// of the point the user clicked.
+pub fn show_chart(ui: &mut egui::Ui, series: &MetricSeries, range: (i64, i64), color: Color32) -> Option<usize> {
+    let size = Vec2::new(ui.available_width(), 48.0);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    if series.points.is_empty() {
+        return None;
+    }
+    let (min, max) = series.value_range();
+    let span_ms = (range.1 - range.0).max(1) as f32;
+    let span_value = if max > min { (max - min) as f32 } else { 1.0 };
+    let to_screen = |point: &MetricPoint| {
+        let x = rect.left() + rect.width() * (point.time_ms - range.0) as f32 / span_ms;
+        let y = rect.bottom() - 4.0 - (rect.height() - 8.0) * (point.value - min) as f32 / span_value;
+        Pos2::new(x, y)
+    };
+
+    let line: Vec<Pos2> = series.points.iter().map(to_screen).collect();
+    if line.len() == 1 {
+        painter.circle_filled(line[0], 2.0, color);
+    } else {
+        painter.add(egui::Shape::line(line, Stroke::new(1.5, color)));
+    }
+    painter.text(
+        rect.left_top() + Vec2::new(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{}  {}…{}", series.name, format_value(min), format_value(max)),
+        egui::FontId::proportional(11.0),
+        ui.visuals().weak_text_color(),
+    );
+
+    // Nearest point to the pointer, by time
+    let pointer = response.hover_pos()?;
+    let nearest = series
+        .points
+        .iter()
+        .min_by(|a, b| {
+            let da = (to_screen(a).x - pointer.x).abs();
+            let db = (to_screen(b).x - pointer.x).abs();
+            da.total_cmp(&db)
+        })?;
+    let pos = to_screen(nearest);
+    painter.circle_stroke(pos, 3.0, Stroke::new(1.0, ui.visuals().strong_text_color()));
+    let time = chrono::DateTime::from_timestamp_millis(nearest.time_ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_default();
+    let clicked = response.clicked();
+    response.on_hover_text(format!("{} = {}\n{}\nClick to jump to the entry", series.name, format_value(nearest.value), time));
+    clicked.then_some(nearest.entry_idx)
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Load saved metric extractors. A missing or unreadable file yields an empty list.
+pub fn load_extractors() -> Vec<MetricExtractor> {
+    config::load_json("metrics.json")
+}
+
+pub fn save_extractors(extractors: &[MetricExtractor]) -> Result<(), String> {
+    config::save_json("metrics.json", extractors)
+}
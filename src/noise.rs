@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::log_parser::LogEntry;
+
+/// An exact message and how often it repeats.
+pub struct RepeatedMessage {
+    pub message: String,
+    pub count: usize,
+    pub first_entry: usize,
+}
+
+/// The entry's message as compared for repetition: its first line.
+pub fn message_key(entry: &LogEntry) -> &str {
+    entry.message().lines().next().unwrap_or("").trim()
+}
+
+/// The `limit` most repeated exact messages, most frequent first.
+pub fn top_messages(entries: &[LogEntry], limit: usize) -> Vec<RepeatedMessage> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        counts.entry(message_key(entry)).or_insert((0, idx)).0 += 1;
+    }
+
+    let mut repeated: Vec<RepeatedMessage> = counts
+        .into_iter()
+        .filter(|(_, (count, _))| *count > 1)
+        .map(|(message, (count, first_entry))| RepeatedMessage { message: message.to_string(), count, first_entry })
+        .collect();
+    repeated.sort_by(|a, b| b.count.cmp(&a.count).then(a.first_entry.cmp(&b.first_entry)));
+    repeated.truncate(limit);
+    repeated
+}
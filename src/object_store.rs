@@ -0,0 +1,312 @@
+use flate2::read::MultiGzDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::aws::{AwsClient, Signing};
+use crate::file_open;
+use crate::gcp;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// A stalled download fails after this long without data.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// The UI is woken to show progress after this many more bytes.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// Where an object lives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Store {
+    S3,
+    Gcs,
+}
+
+impl Store {
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Store::S3 => "s3",
+            Store::Gcs => "gs",
+        }
+    }
+}
+
+/// A parsed `s3://bucket/key` or `gs://bucket/key` URL.
+#[derive(Debug, Clone)]
+pub struct ObjectUrl {
+    pub store: Store,
+    pub bucket: String,
+    pub key: String,
+}
+
+impl ObjectUrl {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (scheme, rest) = text.trim().split_once("://")?;
+        let store = match scheme {
+            "s3" => Store::S3,
+            "gs" => Store::Gcs,
+            _ => return None,
+        };
+        let (bucket, key) = rest.split_once('/')?;
+        if bucket.is_empty() || key.is_empty() || key.ends_with('/') {
+            return None;
+        }
+        Some(Self {
+            store,
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for ObjectUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}://{}/{}", self.store.scheme(), self.bucket, self.key)
+    }
+}
+
+/// Whether a path given to open is really an object URL.
+pub fn is_object_url(path: &Path) -> bool {
+    path.to_str().and_then(ObjectUrl::parse).is_some()
+}
+
+/// An object being downloaded to the cache, gzip decompressed on the way.
+pub struct Download {
+    url: ObjectUrl,
+    received: Arc<AtomicU64>,
+    /// The object's size, 0 until known
+    total: Arc<AtomicU64>,
+    receiver: mpsc::Receiver<Result<PathBuf, String>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Download {
+    pub fn start(url: &str) -> Result<Self, String> {
+        let url = ObjectUrl::parse(url).ok_or_else(|| "Enter an s3://bucket/key or gs://bucket/key URL".to_string())?;
+        let received = Arc::new(AtomicU64::new(0));
+        let total = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let thread_url = url.clone();
+        let thread_received = received.clone();
+        let thread_total = total.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let result = download(&thread_url, &thread_received, &thread_total, &thread_stop);
+            sender.send(result).ok();
+            file_open::wake_ui();
+        });
+        Ok(Self {
+            url,
+            received,
+            total,
+            receiver,
+            stop,
+        })
+    }
+
+    pub fn url(&self) -> &ObjectUrl {
+        &self.url
+    }
+
+    /// Bytes of the object received so far.
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Fraction received, when the object's size is known.
+    pub fn progress(&self) -> Option<f32> {
+        match self.total.load(Ordering::Relaxed) {
+            0 => None,
+            total => Some((self.received() as f32 / total as f32).min(1.0)),
+        }
+    }
+
+    /// The downloaded file once done, or why it failed.
+    pub fn poll(&mut self) -> Option<Result<PathBuf, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Counts the bytes read through it, waking the UI now and then.
+struct Counted<R> {
+    reader: R,
+    received: Arc<AtomicU64>,
+    reported: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        let received = self.received.fetch_add(read as u64, Ordering::Relaxed) + read as u64;
+        if received >= self.reported + PROGRESS_STEP {
+            self.reported = received;
+            file_open::wake_ui();
+        }
+        Ok(read)
+    }
+}
+
+fn download(url: &ObjectUrl, received: &Arc<AtomicU64>, total: &AtomicU64, stop: &AtomicBool) -> Result<PathBuf, String> {
+    let agent = ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).redirects(0).build();
+    let response = match url.store {
+        Store::S3 => get_s3(&agent, url)?,
+        Store::Gcs => get_gcs(&agent, url)?,
+    };
+    if let Some(length) = response.header("Content-Length").and_then(|length| length.parse().ok()) {
+        total.store(length, Ordering::Relaxed);
+    }
+
+    let counted = Counted {
+        reader: response.into_reader(),
+        received: received.clone(),
+        reported: 0,
+    };
+    let mut reader = BufReader::new(counted);
+    // Rotated logs are usually gzipped; look at the data, as the store may already have decoded it
+    let gzip = reader.fill_buf().map_err(|e| format!("Failed to download {}: {}", url, e))?.starts_with(&[0x1f, 0x8b]);
+    let path = cache_path(url, gzip)?;
+    let mut partial = path.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut file = File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    let mut reader: Box<dyn Read> = if gzip { Box::new(MultiGzDecoder::new(reader)) } else { Box::new(reader) };
+
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            drop(file);
+            fs::remove_file(&partial).ok();
+            return Err("Download cancelled".to_string());
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                drop(file);
+                fs::remove_file(&partial).ok();
+                let what = if gzip { "download or decompress" } else { "download" };
+                return Err(format!("Failed to {} {}: {}", what, url, e));
+            }
+        };
+        file.write_all(&buffer[..read]).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+    }
+    drop(file);
+    fs::rename(&partial, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Where a downloaded object is kept: the cache directory mirrors the
+/// bucket's layout, without `.gz` for decompressed objects.
+fn cache_path(url: &ObjectUrl, decompressed: bool) -> Result<PathBuf, String> {
+    let mut path = dirs::cache_dir()
+        .ok_or_else(|| "No cache directory for downloads".to_string())?
+        .join("log-rocket")
+        .join("objects")
+        .join(url.store.scheme())
+        .join(&url.bucket);
+    // Keys are free-form; keep only plain names so nothing lands outside the cache
+    for part in url.key.split('/').filter(|part| !matches!(*part, "" | "." | "..")) {
+        path.push(part);
+    }
+    if decompressed && path.extension().is_some_and(|extension| extension == "gz") {
+        path.set_extension("");
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Percent-encode everything but unreserved characters, and `/` when `keep_slash`.
+fn encode(text: &str, keep_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// GET an S3 object with a signed request, retrying once in the bucket's
+/// own region when the configured one is wrong.
+fn get_s3(agent: &ureq::Agent, url: &ObjectUrl) -> Result<ureq::Response, String> {
+    let mut aws = AwsClient::new("", "")?;
+    if aws.region().is_none() {
+        aws.set_region("us-east-1");
+    }
+    let path = format!("{}/{}", encode(&url.bucket, false), encode(&url.key, true));
+
+    for attempt in 0..2 {
+        // Path-style addressing also works for bucket names with dots
+        let object = match aws.endpoint_url() {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), path),
+            None => format!("https://s3.{}.amazonaws.com/{}", aws.region().unwrap_or_default(), path),
+        };
+        let signature = aws.sign("s3", "GET", &object, &[], &[], Signing::S3)?;
+        let mut request = agent.get(&object);
+        for (name, value) in &signature {
+            request = request.set(name, value);
+        }
+        let response = match request.call() {
+            Ok(response) if response.status() < 300 => return Ok(response),
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(format!("Failed to reach S3: {}", e)),
+        };
+        let status = response.status();
+        let region = response.header("x-amz-bucket-region").map(str::to_string);
+        match region {
+            Some(region) if attempt == 0 && Some(region.as_str()) != aws.region() => aws.set_region(&region),
+            _ => {
+                // Errors come as <Error><Code>NoSuchKey</Code><Message>...</Message></Error>
+                let text = response.into_string().unwrap_or_default();
+                let tag = |name: &str| {
+                    let start = text.find(&format!("<{}>", name))? + name.len() + 2;
+                    let end = text[start..].find("</")? + start;
+                    Some(text[start..end].to_string())
+                };
+                let reason = match (tag("Code"), tag("Message")) {
+                    (Some(code), Some(message)) => format!("{}: {}", code, message),
+                    (Some(code), None) => code,
+                    _ => text.trim().to_string(),
+                };
+                return Err(format!("Failed to download {} ({}): {}", url, status, reason));
+            }
+        }
+    }
+    Err(format!("Failed to download {}: the bucket's region keeps changing", url))
+}
+
+/// GET a Cloud Storage object with the gcloud credentials.
+fn get_gcs(agent: &ureq::Agent, url: &ObjectUrl) -> Result<ureq::Response, String> {
+    let token = gcp::access_token()?;
+    let object = format!(
+        "https://storage.googleapis.com/storage/v1/b/{}/o/{}?alt=media",
+        encode(&url.bucket, false),
+        encode(&url.key, false)
+    );
+    agent.get(&object).set("Authorization", &format!("Bearer {}", token)).call().map_err(|e| match e {
+        ureq::Error::Status(status, response) => {
+            let text = response.into_string().unwrap_or_default();
+            // Errors come as {"error": {"message": ...}}
+            let message = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|error| error["error"]["message"].as_str().map(str::to_string))
+                .unwrap_or(text);
+            format!("Failed to download {} ({}): {}", url, status, message.trim())
+        }
+        e => format!("Failed to reach Cloud Storage: {}", e),
+    })
+}
@@ -0,0 +1,752 @@
+use flate2::read::MultiGzDecoder;
+use serde_json::{json, Map, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::file_open;
+use crate::log_parser::{LogEntry, LogLevel, SourceFields};
+
+/// How often the receiving threads check whether they should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// A request must arrive in full within this long once it has started.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Records held for the app. Exporters aren't made to wait, so records
+/// arriving beyond it are dropped.
+const MAX_WAITING_RECORDS: usize = 10_000;
+/// Largest request body accepted, before and after decompression.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+const LOGS_PATH: &str = "/v1/logs";
+/// Deepest nesting of list and map values decoded.
+const MAX_DEPTH: usize = 64;
+
+/// Where to listen, as edited in the OpenTelemetry window.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    pub bind: String,
+    pub port: u16,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1".to_string(),
+            port: 4318,
+        }
+    }
+}
+
+/// Receives OpenTelemetry log records exported over OTLP/HTTP, in protobuf
+/// or JSON. Each record becomes an entry with the service name as its
+/// thread, the instrumentation scope as its class, and its attributes and
+/// trace context after the body. OTLP/gRPC isn't served; exporters are
+/// pointed here with `OTEL_EXPORTER_OTLP_PROTOCOL=http/protobuf`.
+pub struct OtlpReceiver {
+    description: String,
+    receiver: mpsc::Receiver<LogEntry>,
+    stop: Arc<AtomicBool>,
+    received: usize,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl OtlpReceiver {
+    pub fn start(config: &OtlpConfig) -> Result<Self, String> {
+        let address = format!("{}:{}", config.bind, config.port);
+        let listener = TcpListener::bind(&address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        listener.set_nonblocking(true).map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(MAX_WAITING_RECORDS);
+        let queue = Queue { sender, dropped: Arc::new(AtomicUsize::new(0)) };
+        {
+            let (queue, stop) = (queue.clone(), stop.clone());
+            thread::spawn(move || accept(listener, queue, stop));
+        }
+        Ok(Self {
+            description: format!("http://{}{}", address, LOGS_PATH),
+            receiver,
+            stop,
+            received: 0,
+            dropped: queue.dropped,
+        })
+    }
+
+    /// The endpoint exporters post to, e.g. "http://127.0.0.1:4318/v1/logs".
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Records received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Records dropped because they weren't taken fast enough.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Entries received since the last call, in arrival order. Their line
+    /// numbers are left for the caller to assign.
+    pub fn take_entries(&mut self) -> Vec<LogEntry> {
+        let entries: Vec<_> = self.receiver.try_iter().collect();
+        self.received += entries.len();
+        entries
+    }
+}
+
+impl Drop for OtlpReceiver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Where the connection threads hand on records.
+#[derive(Clone)]
+struct Queue {
+    sender: mpsc::SyncSender<LogEntry>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Queue {
+    /// Queue a record, or count it dropped when the queue is full. False
+    /// once the receiver is gone.
+    fn send(&self, entry: LogEntry) -> bool {
+        match self.sender.try_send(entry) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn accept(listener: TcpListener, queue: Queue, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (queue, stop) = (queue.clone(), stop.clone());
+                thread::spawn(move || {
+                    if let Err(e) = serve(stream, &queue, &stop) {
+                        app_error!("Error reading OTLP connection from {}: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if is_timeout(&e) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                app_error!("Error accepting OTLP connection: {}", e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// One HTTP request, as far as the receiver cares.
+struct Request {
+    method: String,
+    path: String,
+    content_type: String,
+    gzip: bool,
+    close: bool,
+    body: Vec<u8>,
+}
+
+/// Answer requests on one connection until the client closes it, which
+/// exporters usually keep open between batches.
+fn serve(stream: TcpStream, queue: &Queue, stop: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    while !stop.load(Ordering::Relaxed) {
+        let Some(request) = read_request(&mut reader, stop)? else {
+            return Ok(());
+        };
+        let close = request.close;
+        let (status, content_type, body) = handle(request, queue);
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        if status.starts_with("204") {
+            // Browser SDKs ask first whether they may post
+            response.push_str("Access-Control-Allow-Methods: POST, OPTIONS\r\nAccess-Control-Allow-Headers: *\r\n");
+        }
+        if close {
+            response.push_str("Connection: close\r\n");
+        }
+        response.push_str("\r\n");
+        writer.write_all(response.as_bytes())?;
+        writer.write_all(&body)?;
+        writer.flush()?;
+        if close {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Read the next request; none once the client has closed the connection
+/// or the receiver stops while it is idle.
+fn read_request(reader: &mut BufReader<TcpStream>, stop: &AtomicBool) -> io::Result<Option<Request>> {
+    reader.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut line = Vec::new();
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        // A timed out read keeps what it got in `line`, so the line continues next time
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Ok(None),
+            Ok(_) if line.ends_with(b"\n") => break,
+            Ok(_) => return Ok(None),
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let line = String::from_utf8_lossy(&line).trim().to_string();
+    if line.starts_with("PRI * HTTP/2") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "OTLP/gRPC isn't supported; set OTEL_EXPORTER_OTLP_PROTOCOL=http/protobuf",
+        ));
+    }
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.split('?').next().unwrap_or_default().to_string(),
+        content_type: String::new(),
+        gzip: false,
+        close: parts.next() == Some("HTTP/1.0"),
+        body: Vec::new(),
+    };
+
+    // The rest of the request has to follow promptly
+    reader.get_ref().set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut length = 0;
+    let mut chunked = false;
+    loop {
+        let header = read_line(reader)?;
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => length = value.parse().map_err(|_| invalid("bad Content-Length"))?,
+            "content-type" => request.content_type = value,
+            "content-encoding" => request.gzip = value == "gzip",
+            "transfer-encoding" => chunked = value.contains("chunked"),
+            "connection" => request.close = value == "close",
+            _ => {}
+        }
+    }
+
+    if chunked {
+        loop {
+            let size = read_line(reader)?;
+            let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+                .map_err(|_| invalid("bad chunk size"))?;
+            if size == 0 {
+                // Skip any trailers
+                while !read_line(reader)?.is_empty() {}
+                break;
+            }
+            if request.body.len().checked_add(size).is_none_or(|total| total > MAX_BODY) {
+                return Err(invalid("request body too large"));
+            }
+            let start = request.body.len();
+            request.body.resize(start + size, 0);
+            reader.read_exact(&mut request.body[start..])?;
+            read_line(reader)?;
+        }
+    } else {
+        if length > MAX_BODY {
+            return Err(invalid("request body too large"));
+        }
+        request.body.resize(length, 0);
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(Some(request))
+}
+
+fn read_line(reader: &mut BufReader<TcpStream>) -> io::Result<String> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Queue a request's records; the status line, content type and body to answer with.
+fn handle(request: Request, queue: &Queue) -> (&'static str, &'static str, Vec<u8>) {
+    let reply = |status, message: String| (status, "text/plain", message.into_bytes());
+    if request.method == "OPTIONS" {
+        return ("204 No Content", "text/plain", Vec::new());
+    }
+    if request.path != LOGS_PATH {
+        return reply("404 Not Found", format!("Only logs are received, at {}", LOGS_PATH));
+    }
+    if request.method != "POST" {
+        return reply("405 Method Not Allowed", "Logs are posted".to_string());
+    }
+    let json = request.content_type.starts_with("application/json");
+    if !json && !request.content_type.starts_with("application/x-protobuf") {
+        return reply("415 Unsupported Media Type", "Send application/x-protobuf or application/json".to_string());
+    }
+    let body = if request.gzip {
+        let mut body = Vec::new();
+        match MultiGzDecoder::new(&request.body[..]).take(MAX_BODY as u64).read_to_end(&mut body) {
+            Ok(_) => body,
+            Err(e) => return reply("400 Bad Request", format!("Failed to decompress the request: {}", e)),
+        }
+    } else {
+        request.body
+    };
+    let logs = if json {
+        serde_json::from_slice(&body).map_err(|e| e.to_string())
+    } else {
+        decode_request(&body)
+    };
+    let logs = match logs {
+        Ok(logs) => logs,
+        Err(e) => return reply("400 Bad Request", format!("Failed to decode the logs: {}", e)),
+    };
+
+    for entry in request_entries(&logs) {
+        if !queue.send(entry) {
+            break;
+        }
+    }
+    file_open::wake_ui();
+    // An empty ExportLogsServiceResponse means everything was accepted
+    if json {
+        ("200 OK", "application/json", b"{}".to_vec())
+    } else {
+        ("200 OK", "application/x-protobuf", Vec::new())
+    }
+}
+
+/// The records of an ExportLogsServiceRequest in its JSON form, as entries.
+fn request_entries(logs: &Value) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    for resource_logs in logs["resourceLogs"].as_array().into_iter().flatten() {
+        let service = resource_logs["resource"]["attributes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|attribute| attribute["key"] == "service.name")
+            .map(|attribute| value_text(&attribute["value"]));
+        for scope_logs in resource_logs["scopeLogs"].as_array().into_iter().flatten() {
+            let scope = scope_logs["scope"]["name"].as_str().filter(|name| !name.is_empty());
+            for record in scope_logs["logRecords"].as_array().into_iter().flatten() {
+                entries.push(record_entry(record, service.as_deref(), scope));
+            }
+        }
+    }
+    entries
+}
+
+/// A record as `time SEVERITY [service] scope: body key=value ... trace_id=.. span_id=..`,
+/// with multi-line attributes such as stack traces on the lines after it.
+fn record_entry(record: &Value, service: Option<&str>, scope: Option<&str>) -> LogEntry {
+    let time = ["timeUnixNano", "observedTimeUnixNano"]
+        .iter()
+        .filter_map(|name| match &record[*name] {
+            Value::String(nanos) => nanos.parse::<i64>().ok(),
+            nanos => nanos.as_i64(),
+        })
+        .find(|nanos| *nanos > 0)
+        .map(|nanos| chrono::DateTime::from_timestamp_nanos(nanos).with_timezone(&chrono::Local));
+    let number = record["severityNumber"].as_u64().unwrap_or_default();
+    let severity = record["severityText"].as_str().filter(|text| !text.is_empty());
+    let level = match (number, severity) {
+        (1..=4, _) => LogLevel::Trace,
+        (5..=8, _) => LogLevel::Debug,
+        (9..=12, _) => LogLevel::Info,
+        (13..=16, _) => LogLevel::Warn,
+        (17..=24, _) => LogLevel::Error,
+        (_, Some(text)) => LogLevel::from_name(text),
+        _ => LogLevel::Unknown,
+    };
+    let severity = severity.unwrap_or(match number {
+        1..=4 => "TRACE",
+        5..=8 => "DEBUG",
+        9..=12 => "INFO",
+        13..=16 => "WARN",
+        17..=20 => "ERROR",
+        21..=24 => "FATAL",
+        _ => "-",
+    });
+
+    let mut line = String::new();
+    if let Some(time) = time {
+        line.push_str(&format!("{} ", time.format("%Y-%m-%d %H:%M:%S%.3f")));
+    }
+    let timestamp_end = line.len().saturating_sub(1);
+    line.push_str(severity);
+    if let Some(service) = service {
+        line.push_str(&format!(" [{}]", service));
+    }
+    if let Some(scope) = scope {
+        line.push_str(&format!(" {}:", scope));
+    }
+    line.push(' ');
+    let message_start = line.len();
+
+    let mut parts = Vec::new();
+    let mut extra_lines = Vec::new();
+    let body = value_text(&record["body"]);
+    if !body.is_empty() {
+        parts.push(body);
+    }
+    for attribute in record["attributes"].as_array().into_iter().flatten() {
+        let key = attribute["key"].as_str().unwrap_or_default();
+        let value = value_text(&attribute["value"]);
+        if value.contains('\n') {
+            extra_lines.push(value.trim_end().to_string());
+        } else if value.is_empty() || value.contains([' ', '"']) {
+            parts.push(format!("{}={:?}", key, value));
+        } else {
+            parts.push(format!("{}={}", key, value));
+        }
+    }
+    for (name, key) in [("eventName", "event"), ("traceId", "trace_id"), ("spanId", "span_id")] {
+        if let Some(id) = record[name].as_str().filter(|id| !id.is_empty()) {
+            parts.push(format!("{}={}", key, id));
+        }
+    }
+    line.push_str(&parts.join(" "));
+    let message_end = line.len();
+    for extra in extra_lines {
+        line.push('\n');
+        line.push_str(&extra);
+    }
+
+    let fields = SourceFields {
+        level,
+        timestamp: time.map(|_| &line[..timestamp_end]),
+        time_ms: time.map(|time| time.naive_local().and_utc().timestamp_millis()),
+        thread: service,
+        class: scope,
+        message: &line[message_start..message_end],
+    };
+    LogEntry::from_source(&line, 0, fields)
+}
+
+/// An AnyValue as text: strings as they are, lists as `[a, b]` and maps as `{k=v, ..}`.
+fn value_text(value: &Value) -> String {
+    if let Some(text) = value["stringValue"].as_str().or(value["bytesValue"].as_str()) {
+        return text.to_string();
+    }
+    if let Some(flag) = value["boolValue"].as_bool() {
+        return flag.to_string();
+    }
+    // 64-bit integers come as strings in OTLP/JSON
+    for name in ["intValue", "doubleValue"] {
+        match &value[name] {
+            Value::String(number) => return number.clone(),
+            Value::Number(number) => return number.to_string(),
+            _ => {}
+        }
+    }
+    if let Some(values) = value["arrayValue"]["values"].as_array() {
+        let items: Vec<String> = values.iter().map(value_text).collect();
+        return format!("[{}]", items.join(", "));
+    }
+    if let Some(values) = value["kvlistValue"]["values"].as_array() {
+        let items: Vec<String> =
+            values.iter().map(|kv| format!("{}={}", kv["key"].as_str().unwrap_or_default(), value_text(&kv["value"]))).collect();
+        return format!("{{{}}}", items.join(", "));
+    }
+    String::new()
+}
+
+/// A field of a protobuf message, by wire type.
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+fn varint(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or("truncated message")?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("malformed varint".to_string())
+}
+
+/// A message's fields with their numbers, in order.
+fn fields(data: &[u8]) -> Result<Vec<(u64, Wire<'_>)>, String> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let key = varint(data, &mut pos)?;
+        let size = match key & 7 {
+            0 => {
+                fields.push((key >> 3, Wire::Varint(varint(data, &mut pos)?)));
+                continue;
+            }
+            1 => 8,
+            2 => varint(data, &mut pos)? as usize,
+            5 => 4,
+            wire => return Err(format!("unsupported wire type {}", wire)),
+        };
+        let bytes = pos.checked_add(size).and_then(|end| data.get(pos..end)).ok_or("truncated message")?;
+        pos += size;
+        let wire = match key & 7 {
+            1 => Wire::Fixed64(u64::from_le_bytes(bytes.try_into().unwrap_or_default())),
+            2 => Wire::Bytes(bytes),
+            _ => Wire::Fixed32,
+        };
+        fields.push((key >> 3, wire));
+    }
+    Ok(fields)
+}
+
+/// The messages in field `number`, each decoded.
+fn repeated(data: &[u8], number: u64, decode: impl Fn(&[u8]) -> Result<Value, String>) -> Result<Vec<Value>, String> {
+    let mut values = Vec::new();
+    for (field, wire) in fields(data)? {
+        if let (true, Wire::Bytes(bytes)) = (field == number, wire) {
+            values.push(decode(bytes)?);
+        }
+    }
+    Ok(values)
+}
+
+fn text(bytes: &[u8]) -> Value {
+    json!(String::from_utf8_lossy(bytes))
+}
+
+fn hex(bytes: &[u8]) -> Value {
+    json!(bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+/// An ExportLogsServiceRequest in protobuf, turned into its OTLP/JSON form
+/// so both encodings are read the same way.
+fn decode_request(data: &[u8]) -> Result<Value, String> {
+    Ok(json!({ "resourceLogs": repeated(data, 1, decode_resource_logs)? }))
+}
+
+fn decode_resource_logs(data: &[u8]) -> Result<Value, String> {
+    let mut resource = json!({});
+    for (field, wire) in fields(data)? {
+        if let (1, Wire::Bytes(bytes)) = (field, wire) {
+            resource = json!({ "attributes": repeated(bytes, 1, |bytes| decode_key_value(bytes, 0))? });
+        }
+    }
+    Ok(json!({ "resource": resource, "scopeLogs": repeated(data, 2, decode_scope_logs)? }))
+}
+
+fn decode_scope_logs(data: &[u8]) -> Result<Value, String> {
+    let mut scope = json!({});
+    for (field, wire) in fields(data)? {
+        if let (1, Wire::Bytes(bytes)) = (field, wire) {
+            for (field, wire) in fields(bytes)? {
+                if let (1, Wire::Bytes(name)) = (field, wire) {
+                    scope["name"] = text(name);
+                }
+            }
+        }
+    }
+    Ok(json!({ "scope": scope, "logRecords": repeated(data, 2, decode_record)? }))
+}
+
+fn decode_record(data: &[u8]) -> Result<Value, String> {
+    let mut record = Map::new();
+    for (field, wire) in fields(data)? {
+        let (name, value) = match (field, wire) {
+            (1, Wire::Fixed64(nanos)) => ("timeUnixNano", json!(nanos.to_string())),
+            (2, Wire::Varint(number)) => ("severityNumber", json!(number)),
+            (3, Wire::Bytes(bytes)) => ("severityText", text(bytes)),
+            (5, Wire::Bytes(bytes)) => ("body", decode_any_value(bytes, 0)?),
+            (9, Wire::Bytes(bytes)) => ("traceId", hex(bytes)),
+            (10, Wire::Bytes(bytes)) => ("spanId", hex(bytes)),
+            (11, Wire::Fixed64(nanos)) => ("observedTimeUnixNano", json!(nanos.to_string())),
+            (12, Wire::Bytes(bytes)) => ("eventName", text(bytes)),
+            _ => continue,
+        };
+        record.insert(name.to_string(), value);
+    }
+    record.insert("attributes".to_string(), json!(repeated(data, 6, |bytes| decode_key_value(bytes, 0))?));
+    Ok(Value::Object(record))
+}
+
+/// A KeyValue `depth` values deep in attributes or lists.
+fn decode_key_value(data: &[u8], depth: usize) -> Result<Value, String> {
+    let mut key_value = json!({});
+    for (field, wire) in fields(data)? {
+        match (field, wire) {
+            (1, Wire::Bytes(bytes)) => key_value["key"] = text(bytes),
+            (2, Wire::Bytes(bytes)) => key_value["value"] = decode_any_value(bytes, depth)?,
+            _ => {}
+        }
+    }
+    Ok(key_value)
+}
+
+/// An AnyValue nested in `depth` lists or maps, which are limited so a
+/// crafted message can't exhaust the stack.
+fn decode_any_value(data: &[u8], depth: usize) -> Result<Value, String> {
+    if depth >= MAX_DEPTH {
+        return Err("values nested too deeply".to_string());
+    }
+    let mut value = json!({});
+    for (field, wire) in fields(data)? {
+        value = match (field, wire) {
+            (1, Wire::Bytes(bytes)) => json!({ "stringValue": text(bytes) }),
+            (2, Wire::Varint(flag)) => json!({ "boolValue": flag != 0 }),
+            (3, Wire::Varint(number)) => json!({ "intValue": (number as i64).to_string() }),
+            (4, Wire::Fixed64(bits)) => json!({ "doubleValue": f64::from_bits(bits) }),
+            (5, Wire::Bytes(bytes)) => json!({ "arrayValue": { "values": repeated(bytes, 1, |bytes| decode_any_value(bytes, depth + 1))? } }),
+            (6, Wire::Bytes(bytes)) => json!({ "kvlistValue": { "values": repeated(bytes, 1, |bytes| decode_key_value(bytes, depth + 1))? } }),
+            // Shown as hex rather than OTLP/JSON's base64
+            (7, Wire::Bytes(bytes)) => json!({ "bytesValue": hex(bytes) }),
+            _ => continue,
+        };
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A length-delimited protobuf field.
+    fn bytes_field(number: u8, bytes: &[u8]) -> Vec<u8> {
+        let mut field = vec![number << 3 | 2];
+        let mut len = bytes.len();
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                field.push(byte);
+                break;
+            }
+            field.push(byte | 0x80);
+        }
+        field.extend_from_slice(bytes);
+        field
+    }
+
+    /// An AnyValue holding `depth` nested single-item lists around a string.
+    fn nested_value(depth: usize) -> Vec<u8> {
+        let mut value = bytes_field(1, b"deep");
+        for _ in 0..depth {
+            value = bytes_field(5, &bytes_field(1, &value));
+        }
+        value
+    }
+
+    #[test]
+    fn decodes_nested_values_up_to_the_limit() {
+        let value = decode_any_value(&nested_value(3), 0).unwrap();
+        assert_eq!(value_text(&value), "[[[deep]]]");
+        assert!(decode_any_value(&nested_value(MAX_DEPTH - 1), 0).is_ok());
+    }
+
+    #[test]
+    fn rejects_values_nested_too_deeply() {
+        assert!(decode_any_value(&nested_value(1_000), 0).is_err());
+    }
+
+    fn varint_field(number: u8, value: u64) -> Vec<u8> {
+        let mut field = vec![number << 3];
+        let mut value = value;
+        while value >= 0x80 {
+            field.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        field.push(value as u8);
+        field
+    }
+
+    fn fixed64_field(number: u8, value: u64) -> Vec<u8> {
+        let mut field = vec![number << 3 | 1];
+        field.extend_from_slice(&value.to_le_bytes());
+        field
+    }
+
+    fn attribute(key: &str, value: &[u8]) -> Vec<u8> {
+        [bytes_field(1, key.as_bytes()), bytes_field(2, value)].concat()
+    }
+
+    /// An ExportLogsServiceRequest with one record from `checkout`.
+    fn export_request() -> Vec<u8> {
+        let record = [
+            fixed64_field(1, 1_700_000_000_123_000_000),
+            varint_field(2, 17),
+            bytes_field(3, b"ERROR"),
+            bytes_field(5, &bytes_field(1, b"payment failed")),
+            bytes_field(6, &attribute("order", &varint_field(3, 42))),
+            bytes_field(6, &attribute("exception.stacktrace", &bytes_field(1, b"Error: declined\n    at pay()\n"))),
+            bytes_field(9, &[0xab; 16]),
+            bytes_field(10, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]),
+        ]
+        .concat();
+        let scope_logs = [bytes_field(1, &bytes_field(1, b"payments")), bytes_field(2, &record)].concat();
+        let resource = bytes_field(1, &attribute("service.name", &bytes_field(1, b"checkout")));
+        let resource_logs = [bytes_field(1, &resource), bytes_field(2, &scope_logs)].concat();
+        bytes_field(1, &resource_logs)
+    }
+
+    #[test]
+    fn decodes_an_export_request() {
+        let logs = decode_request(&export_request()).unwrap();
+        let entries = request_entries(&logs);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.thread(), Some("checkout"));
+        assert_eq!(entry.class(), Some("payments"));
+        assert_eq!(
+            entry.message(),
+            format!("payment failed order=42 trace_id={} span_id=0102030405060708", "ab".repeat(16))
+        );
+        assert!(entry.raw_line.ends_with("\nError: declined\n    at pay()"));
+        let time = chrono::DateTime::from_timestamp_millis(1_700_000_000_123).unwrap().with_timezone(&chrono::Local);
+        assert_eq!(entry.time_ms(), Some(time.naive_local().and_utc().timestamp_millis()));
+        assert!(entry.raw_line.contains(" ERROR [checkout] payments: payment failed"));
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let request = export_request();
+        // Cut off inside a field
+        assert!(decode_request(&request[..request.len() - 3]).is_err());
+        // A length running past the end
+        assert!(decode_request(&[0x0a, 0x05, 0x01]).is_err());
+        // A varint that never ends
+        assert!(decode_request(&[0x08; 11]).is_err());
+        assert!(decode_request(&[0x08, 0xff]).is_err());
+        // Wire types 3 and 4 (groups) aren't used by OTLP
+        assert!(decode_request(&[0x0b]).is_err());
+        // Nothing to decode is an empty request
+        assert_eq!(request_entries(&decode_request(&[]).unwrap()).len(), 0);
+    }
+}
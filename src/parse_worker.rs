@@ -0,0 +1,122 @@
+use std::sync::mpsc;
+use std::thread;
+
+use crate::file_open;
+use crate::index_cache::{self, IndexBuilder, IndexKey};
+use crate::log_parser::{LogEntry, LogParser};
+use crate::timeline::Timeline;
+use crate::unparsed::{Unparsed, UnparsedTracker};
+
+/// Entries sent to the UI per message.
+const CHUNK_SIZE: usize = 5_000;
+
+/// A log being parsed on a background thread. Dropping it cancels the parse.
+pub struct ParseJob {
+    receiver: mpsc::Receiver<Vec<LogEntry>>,
+    /// The lines left out of every entry, sent once the parse completes
+    unparsed: mpsc::Receiver<Unparsed>,
+    /// The entries over time, also sent once the parse completes
+    timeline: mpsc::Receiver<Option<Timeline>>,
+    finished: bool,
+}
+
+impl ParseJob {
+    /// Parse `content` in the background. With an index key, the entries are
+    /// restored from the saved index when there is one, else the index is
+    /// saved once the whole content was parsed.
+    pub fn start(parser: LogParser, content: String, index: Option<IndexKey>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (unparsed_sender, unparsed) = mpsc::channel();
+        let (timeline_sender, timeline) = mpsc::channel();
+        thread::spawn(move || {
+            let restored = index.as_ref().and_then(|key| index_cache::load(key, &content, &parser));
+            let mut index = index.filter(|_| restored.is_none()).map(|key| IndexBuilder::new(key, &content, &parser));
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+            let mut connected = true;
+            let mut tracker = UnparsedTracker::new();
+            // Timestamps are parsed here rather than when the UI first needs them
+            let mut timeline = Timeline::default();
+            let mut parsed = 0;
+            let mut emit = |entry: LogEntry| {
+                if let Some(ref mut index) = index {
+                    index.push(&content, &entry);
+                }
+                tracker.note(&entry);
+                timeline.add(parsed, &entry);
+                parsed += 1;
+                chunk.push(entry);
+                if chunk.len() >= CHUNK_SIZE {
+                    // The receiver is gone when the job was cancelled
+                    connected = sender.send(std::mem::take(&mut chunk)).is_ok();
+                    file_open::wake_ui();
+                }
+                connected
+            };
+            match restored {
+                Some(entries) => {
+                    for entry in entries {
+                        if !emit(entry) {
+                            break;
+                        }
+                    }
+                }
+                None => parser.parse_each(&content, &mut emit),
+            }
+            if connected && !chunk.is_empty() {
+                sender.send(chunk).ok();
+            }
+            // Before the entries' sender goes, so it is there once the job is finished
+            if connected {
+                unparsed_sender.send(tracker.finish(&content)).ok();
+                timeline.mark_anomalies();
+                timeline_sender.send((!timeline.is_empty()).then_some(timeline)).ok();
+            }
+            // Dropping the sender marks the job finished; let the UI see it
+            drop(sender);
+            file_open::wake_ui();
+            if let (true, Some(index)) = (connected, index) {
+                if let Err(e) = index.save() {
+                    app_error!("Error saving index: {}", e);
+                }
+            }
+        });
+        Self { receiver, unparsed, timeline, finished: false }
+    }
+
+    /// Entries parsed since the last call, in file order.
+    pub fn take_ready(&mut self) -> Vec<LogEntry> {
+        let mut ready = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(chunk) => ready.extend(chunk),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        ready
+    }
+
+    /// Wait for the parse to complete and return all remaining entries.
+    pub fn take_all(&mut self) -> Vec<LogEntry> {
+        self.finished = true;
+        self.receiver.iter().flatten().collect()
+    }
+
+    /// The lines no entry holds, once the whole input was parsed.
+    pub fn take_unparsed(&mut self) -> Option<Unparsed> {
+        self.unparsed.try_recv().ok()
+    }
+
+    /// The timeline of the entries, once the whole input was parsed.
+    pub fn take_timeline(&mut self) -> Option<Option<Timeline>> {
+        self.timeline.try_recv().ok()
+    }
+
+    /// True once the whole input was parsed and every entry taken.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
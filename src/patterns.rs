@@ -0,0 +1,173 @@
+//! Drain-style message clustering: messages with the same token count and
+//! first token are grouped, and within a group a message joins the most
+//! similar template, turning the tokens that differ into wildcards.
+
+use std::collections::HashMap;
+
+use crate::log_parser::LogEntry;
+
+pub const WILDCARD: &str = "<*>";
+/// Share of tokens that must match for a message to join a template
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+pub struct Pattern {
+    tokens: Vec<String>,
+    pub count: usize,
+    pub first_entry: usize,
+    pub last_entry: usize,
+}
+
+impl Pattern {
+    pub fn template(&self) -> String {
+        self.tokens.join(" ")
+    }
+
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        if tokens.is_empty() {
+            return 1.0;
+        }
+        let matching = self
+            .tokens
+            .iter()
+            .zip(tokens)
+            .filter(|(template, token)| *template == WILDCARD || template == token)
+            .count();
+        matching as f64 / tokens.len() as f64
+    }
+}
+
+/// Numbers, IDs and hashes are never part of a template.
+fn mask(token: &str) -> String {
+    if token.chars().any(|c| c.is_ascii_digit()) {
+        WILDCARD.to_string()
+    } else {
+        token.to_string()
+    }
+}
+
+#[derive(Default)]
+pub struct PatternIndex {
+    pub patterns: Vec<Pattern>,
+    groups: HashMap<(usize, String), Vec<usize>>,
+    /// Pattern of every entry seen so far, parallel to the entries
+    pub entry_pattern: Vec<usize>,
+}
+
+impl PatternIndex {
+    /// Cluster the entries not seen yet, so tailed lines can be added incrementally.
+    pub fn extend(&mut self, entries: &[LogEntry]) {
+        let seen = self.entry_pattern.len();
+        for (idx, entry) in entries.iter().enumerate().skip(seen) {
+            self.add(idx, entry.message().lines().next().unwrap_or(""));
+        }
+    }
+
+    fn add(&mut self, idx: usize, message: &str) {
+        let tokens: Vec<String> = message.split_whitespace().map(mask).collect();
+        let key = (tokens.len(), tokens.first().cloned().unwrap_or_default());
+        let group = self.groups.entry(key).or_default();
+
+        let best = group
+            .iter()
+            .map(|&p| (p, self.patterns[p].similarity(&tokens)))
+            .filter(|(_, similarity)| *similarity >= SIMILARITY_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let pattern_idx = match best {
+            Some((p, _)) => {
+                let pattern = &mut self.patterns[p];
+                for (template, token) in pattern.tokens.iter_mut().zip(&tokens) {
+                    if template != token {
+                        *template = WILDCARD.to_string();
+                    }
+                }
+                pattern.count += 1;
+                pattern.last_entry = idx;
+                p
+            }
+            None => {
+                self.patterns.push(Pattern { tokens, count: 1, first_entry: idx, last_entry: idx });
+                group.push(self.patterns.len() - 1);
+                self.patterns.len() - 1
+            }
+        };
+        self.entry_pattern.push(pattern_idx);
+    }
+
+    /// Forget the first `dropped` entries, which were discarded, and renumber
+    /// the rest. Patterns keep their indices, so a pattern filter still applies.
+    pub fn drop_front(&mut self, dropped: usize) {
+        let dropped = dropped.min(self.entry_pattern.len());
+        for p in self.entry_pattern.drain(..dropped) {
+            self.patterns[p].count -= 1;
+        }
+        let mut seen = vec![false; self.patterns.len()];
+        for (idx, &p) in self.entry_pattern.iter().enumerate() {
+            if !seen[p] {
+                seen[p] = true;
+                self.patterns[p].first_entry = idx;
+            }
+        }
+        for pattern in &mut self.patterns {
+            pattern.last_entry = pattern.last_entry.saturating_sub(dropped);
+        }
+    }
+
+    /// Pattern indices, most frequent first. Patterns whose entries were all
+    /// dropped are left out.
+    pub fn by_count(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.patterns.len()).filter(|&p| self.patterns[p].count > 0).collect();
+        order.sort_by(|&a, &b| self.patterns[b].count.cmp(&self.patterns[a].count));
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(messages: &[&str]) -> PatternIndex {
+        let mut index = PatternIndex::default();
+        for (idx, message) in messages.iter().enumerate() {
+            index.add(idx, message);
+        }
+        index
+    }
+
+    #[test]
+    fn clusters_messages_into_templates() {
+        let index = index(&[
+            "User alice logged in from 10.0.0.1",
+            "Cache miss for key",
+            "User bob logged in from 10.0.0.2",
+            "User carol logged in from 10.0.0.3",
+        ]);
+        assert_eq!(index.entry_pattern, [0, 1, 0, 0]);
+        assert_eq!(index.patterns[0].template(), "User <*> logged in from <*>");
+        assert_eq!(index.by_count(), [0, 1]);
+    }
+
+    #[test]
+    fn renumbers_entries_after_dropping_the_oldest() {
+        let mut index = index(&["start 1", "tick", "start 2", "tick", "start 3"]);
+        assert_eq!(index.entry_pattern, [0, 1, 0, 1, 0]);
+
+        index.drop_front(2);
+        assert_eq!(index.entry_pattern, [0, 1, 0]);
+        assert_eq!(index.patterns[0].count, 2);
+        assert_eq!((index.patterns[0].first_entry, index.patterns[0].last_entry), (0, 2));
+        assert_eq!(index.patterns[1].count, 1);
+        assert_eq!((index.patterns[1].first_entry, index.patterns[1].last_entry), (1, 1));
+
+        // Patterns whose entries are all gone keep their index but aren't listed
+        index.drop_front(2);
+        assert_eq!(index.entry_pattern, [0]);
+        assert_eq!(index.patterns.len(), 2);
+        assert_eq!(index.by_count(), [0]);
+
+        // Dropping more than there is empties the index
+        index.drop_front(10);
+        assert!(index.entry_pattern.is_empty());
+        assert!(index.by_count().is_empty());
+    }
+}
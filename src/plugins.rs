@@ -0,0 +1,401 @@
+//! WASM plugins for niche formats and enrichments.
+//!
+//! A plugin is a `.wasm` file in the `plugins` folder of the config directory.
+//! It must export `memory`, `alloc(len: i32) -> i32` and `dealloc(ptr: i32, len: i32)`,
+//! and any of:
+//!
+//! - `transform(ptr: i32, len: i32) -> i64`: rewrite a physical line before parsing.
+//! - `parse_line(ptr: i32, len: i32) -> i64`: parse a line into a JSON object with
+//!   optional `timestamp`, `level`, `thread`, `class` and `message` fields.
+//! - `classify(ptr: i32, len: i32) -> i32`: pick the level of a parsed entry
+//!   (0 info, 1 warn, 2 error, 3 debug, 4 trace, 5 unknown, -1 no opinion).
+//!
+//! Functions returning `i64` pack a result string as `(ptr << 32) | len`; 0 means
+//! "no result". Strings are UTF-8 and are passed in memory obtained from `alloc`.
+//! The host hands both the argument and the result back to `dealloc` once
+//! it has read them.
+//!
+//! Each call may run for `FUEL_PER_CALL` units of fuel (about one per
+//! instruction). A plugin that runs out, or traps, is stopped and its error
+//! shown in the sidebar.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "plugins")]
+use wasmi::{Config, Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::config;
+use crate::log_parser::LogLevel;
+
+/// Fuel a plugin gets for each call, enough for tens of millions of
+/// instructions; a plugin stuck in a loop fails instead of hanging the parse.
+#[cfg(feature = "plugins")]
+const FUEL_PER_CALL: u64 = 50_000_000;
+
+/// Fields returned by a plugin's `parse_line`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginFields {
+    pub timestamp: Option<String>,
+    pub level: Option<String>,
+    pub thread: Option<String>,
+    pub class: Option<String>,
+    pub message: Option<String>,
+}
+
+#[cfg(feature = "plugins")]
+struct PluginRuntime {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+    transform: Option<TypedFunc<(i32, i32), i64>>,
+    parse_line: Option<TypedFunc<(i32, i32), i64>>,
+    classify: Option<TypedFunc<(i32, i32), i32>>,
+    /// The last line given to `parse_line` and what came of it, as the parser
+    /// asks about the same line to find entry starts and then to parse it
+    last_parse: Option<(String, Option<PluginFields>)>,
+    /// Why the plugin was stopped, after it trapped or ran out of fuel
+    failure: Option<String>,
+}
+
+#[cfg(feature = "plugins")]
+impl PluginRuntime {
+    fn load(bytes: &[u8]) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|e| e.to_string())?;
+        let mut store = Store::new(&engine, ());
+        store.add_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let linker = <Linker<()>>::new(&engine);
+        let instance: Instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| e.to_string())?;
+
+        let memory = instance.get_memory(&store, "memory").ok_or("missing `memory` export")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|_| "missing `alloc` export")?;
+        let dealloc = instance
+            .get_typed_func::<(i32, i32), ()>(&store, "dealloc")
+            .map_err(|_| "missing `dealloc` export")?;
+        let transform = instance.get_typed_func(&store, "transform").ok();
+        let parse_line = instance.get_typed_func(&store, "parse_line").ok();
+        let classify = instance.get_typed_func(&store, "classify").ok();
+
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            dealloc,
+            transform,
+            parse_line,
+            classify,
+            last_parse: None,
+            failure: None,
+        })
+    }
+
+    /// Call into the plugin with a fresh fuel budget. A trap stops the plugin.
+    fn call<Params: wasmi::WasmParams, Results: wasmi::WasmResults>(
+        &mut self,
+        func: TypedFunc<Params, Results>,
+        params: Params,
+    ) -> Option<Results> {
+        if self.failure.is_some() {
+            return None;
+        }
+        let left = self.store.consume_fuel(0).unwrap_or(0);
+        self.store.add_fuel(FUEL_PER_CALL.saturating_sub(left)).ok()?;
+        match func.call(&mut self.store, params) {
+            Ok(results) => Some(results),
+            Err(e) => {
+                app_error!("Plugin stopped: {}", e);
+                self.failure = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    fn write_str(&mut self, text: &str) -> Option<(i32, i32)> {
+        let len = i32::try_from(text.len()).ok()?;
+        let ptr = self.call(self.alloc, len)?;
+        if self.memory.write(&mut self.store, ptr as usize, text.as_bytes()).is_err() {
+            self.free(ptr, len);
+            return None;
+        }
+        Some((ptr, len))
+    }
+
+    fn free(&mut self, ptr: i32, len: i32) {
+        self.call(self.dealloc, (ptr, len));
+    }
+
+    /// The string a packed result points at, freed once read.
+    fn read_packed(&mut self, packed: i64) -> Option<String> {
+        if packed == 0 {
+            return None;
+        }
+        let ptr = (packed >> 32) as u32;
+        let len = packed as u32;
+        let mut buf = vec![0u8; len as usize];
+        let read = self.memory.read(&self.store, ptr as usize, &mut buf);
+        self.free(ptr as i32, len as i32);
+        read.ok()?;
+        String::from_utf8(buf).ok()
+    }
+
+    fn call_string(&mut self, func: Option<TypedFunc<(i32, i32), i64>>, text: &str) -> Option<String> {
+        let func = func?;
+        let (ptr, len) = self.write_str(text)?;
+        let packed = self.call(func, (ptr, len));
+        self.free(ptr, len);
+        self.read_packed(packed?)
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.clone()
+    }
+
+    fn parses_lines(&self) -> bool {
+        self.parse_line.is_some()
+    }
+
+    fn transform(&mut self, line: &str) -> Option<String> {
+        self.call_string(self.transform, line)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<PluginFields> {
+        if let Some((ref last, ref fields)) = self.last_parse {
+            if last == line {
+                return fields.clone();
+            }
+        }
+        let fields: Option<PluginFields> = self.call_string(self.parse_line, line).and_then(|json| serde_json::from_str(&json).ok());
+        self.last_parse = Some((line.to_string(), fields.clone()));
+        fields
+    }
+
+    fn classify(&mut self, text: &str) -> Option<LogLevel> {
+        let func = self.classify?;
+        let (ptr, len) = self.write_str(text)?;
+        let level = self.call(func, (ptr, len));
+        self.free(ptr, len);
+        match level? {
+            0 => Some(LogLevel::Info),
+            1 => Some(LogLevel::Warn),
+            2 => Some(LogLevel::Error),
+            3 => Some(LogLevel::Debug),
+            4 => Some(LogLevel::Trace),
+            5 => Some(LogLevel::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// Without the `plugins` feature there is no WASM runtime; every plugin is
+/// listed with the reason it didn't load.
+#[cfg(not(feature = "plugins"))]
+enum PluginRuntime {}
+
+#[cfg(not(feature = "plugins"))]
+impl PluginRuntime {
+    fn load(_bytes: &[u8]) -> Result<Self, String> {
+        Err("Log Rocket was built without plugin support; rebuild it with `--features plugins`".to_string())
+    }
+
+    fn failure(&self) -> Option<String> {
+        match *self {}
+    }
+
+    fn parses_lines(&self) -> bool {
+        match *self {}
+    }
+
+    fn transform(&mut self, _line: &str) -> Option<String> {
+        match *self {}
+    }
+
+    fn parse_line(&mut self, _line: &str) -> Option<PluginFields> {
+        match *self {}
+    }
+
+    fn classify(&mut self, _text: &str) -> Option<LogLevel> {
+        match *self {}
+    }
+}
+
+/// Clones share the loaded runtime, so a background parse uses the same instance.
+#[derive(Clone)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+    pub enabled: bool,
+    pub error: Option<String>,
+    runtime: Option<Arc<Mutex<PluginRuntime>>>,
+}
+
+impl Plugin {
+    fn with_runtime<T>(&self, f: impl FnOnce(&mut PluginRuntime) -> Option<T>) -> Option<T> {
+        if !self.enabled {
+            return None;
+        }
+        let mut runtime = self.runtime.as_ref()?.lock().ok()?;
+        f(&mut runtime)
+    }
+
+    /// Why the plugin was stopped while running, if it was.
+    pub fn failure(&self) -> Option<String> {
+        self.runtime.as_ref()?.lock().ok()?.failure()
+    }
+
+    /// True if this plugin is enabled and provides its own line parser.
+    pub fn parses_lines(&self) -> bool {
+        self.enabled
+            && self
+                .runtime
+                .as_ref()
+                .and_then(|rt| rt.lock().ok().map(|rt| rt.parses_lines()))
+                .unwrap_or(false)
+    }
+
+    pub fn transform(&self, line: &str) -> Option<String> {
+        self.with_runtime(|rt| rt.transform(line))
+    }
+
+    pub fn parse_line(&self, line: &str) -> Option<PluginFields> {
+        self.with_runtime(|rt| rt.parse_line(line))
+    }
+
+    pub fn classify(&self, text: &str) -> Option<LogLevel> {
+        self.with_runtime(|rt| rt.classify(text))
+    }
+}
+
+pub fn plugins_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("plugins"))
+}
+
+/// Load every `.wasm` file from the plugins folder. Plugins are enabled unless
+/// switched off in plugins.json; ones that fail to load are listed with their error.
+pub fn load_plugins() -> Vec<Plugin> {
+    let Some(dir) = plugins_dir() else {
+        return Vec::new();
+    };
+    let Ok(read_dir) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let enabled_state: HashMap<String, bool> = config::load_json("plugins.json");
+
+    let mut plugins: Vec<Plugin> = read_dir
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().map_or(false, |ext| ext == "wasm"))
+        .map(|path| {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let runtime = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| PluginRuntime::load(&bytes));
+            let (runtime, error) = match runtime {
+                Ok(runtime) => (Some(Arc::new(Mutex::new(runtime))), None),
+                Err(e) => {
+                    app_error!("Error loading plugin {}: {}", path.display(), e);
+                    (None, Some(e))
+                }
+            };
+            Plugin {
+                enabled: enabled_state.get(&name).copied().unwrap_or(true),
+                name,
+                path,
+                error,
+                runtime,
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+/// Persist the per-plugin enable toggles.
+pub fn save_enabled_state(plugins: &[Plugin]) -> Result<(), String> {
+    let state: HashMap<&str, bool> = plugins.iter().map(|p| (p.name.as_str(), p.enabled)).collect();
+    config::save_json("plugins.json", &state)
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::*;
+
+    /// A plugin with a bump allocator that counts the strings still allocated
+    /// at address 0. `parse_line` answers `{"level":"ERROR"}`, `transform`
+    /// loops forever.
+    const PLUGIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (global $next (mut i32) (i32.const 1024))
+        (data (i32.const 16) "{\"level\":\"ERROR\"}")
+        (func $alloc (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+            (local.get $ptr))
+        (func (export "dealloc") (param i32 i32)
+            (i32.store (i32.const 0) (i32.sub (i32.load (i32.const 0)) (i32.const 1))))
+        (func (export "parse_line") (param i32 i32) (result i64)
+            (local $ptr i32)
+            (local.set $ptr (call $alloc (i32.const 17)))
+            (memory.copy (local.get $ptr) (i32.const 16) (i32.const 17))
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.const 17)))
+        (func (export "transform") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))"#;
+
+    fn plugin() -> Plugin {
+        let runtime = PluginRuntime::load(&wat::parse_str(PLUGIN).unwrap()).unwrap();
+        Plugin {
+            name: "test".to_string(),
+            path: PathBuf::new(),
+            enabled: true,
+            error: None,
+            runtime: Some(Arc::new(Mutex::new(runtime))),
+        }
+    }
+
+    fn live_allocations(plugin: &Plugin) -> i32 {
+        let runtime = plugin.runtime.as_ref().unwrap().lock().unwrap();
+        let mut count = [0u8; 4];
+        runtime.memory.read(&runtime.store, 0, &mut count).unwrap();
+        i32::from_le_bytes(count)
+    }
+
+    #[test]
+    fn frees_arguments_and_results() {
+        let plugin = plugin();
+        for line in ["a", "b", "c"] {
+            assert_eq!(plugin.parse_line(line).and_then(|fields| fields.level).as_deref(), Some("ERROR"));
+        }
+        assert_eq!(live_allocations(&plugin), 0);
+    }
+
+    #[test]
+    fn parses_a_line_once() {
+        let plugin = plugin();
+        plugin.parse_line("same");
+        let consumed = plugin.runtime.as_ref().unwrap().lock().unwrap().store.fuel_consumed();
+        plugin.parse_line("same");
+        assert_eq!(plugin.runtime.as_ref().unwrap().lock().unwrap().store.fuel_consumed(), consumed);
+    }
+
+    #[test]
+    fn stops_a_plugin_that_runs_out_of_fuel() {
+        let plugin = plugin();
+        assert_eq!(plugin.transform("line"), None);
+        assert!(plugin.failure().is_some());
+        // Stopped plugins aren't called again
+        assert!(plugin.parse_line("line").is_none());
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::log_parser::{LogFormat, LogLevel};
+
+/// A named bundle of viewer settings, e.g. "AEM author" or "nginx edge".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub format: LogFormat,
+    #[serde(default)]
+    pub levels: Vec<LogLevel>,
+    #[serde(default)]
+    pub search_query: String,
+    #[serde(default)]
+    pub search_regex: bool,
+    #[serde(default)]
+    pub wrap_text: bool,
+    #[serde(default)]
+    pub default_directory: Option<PathBuf>,
+}
+
+/// Load saved profiles. A missing or unreadable file yields an empty list.
+pub fn load_profiles() -> Vec<Profile> {
+    config::load_json("profiles.json")
+}
+
+pub fn save_profiles(profiles: &[Profile]) -> Result<(), String> {
+    config::save_json("profiles.json", profiles)
+}
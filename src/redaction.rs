@@ -0,0 +1,130 @@
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// What is masked before log text is shown, copied or exported, as edited
+/// in ⚙ Settings → Privacy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionRules {
+    pub enabled: bool,
+    /// `user@example.com` → `[EMAIL]`
+    pub emails: bool,
+    /// IPv4 and IPv6 addresses → `[IP]`
+    pub ip_addresses: bool,
+    /// Card numbers passing the Luhn check → `[CARD]`
+    pub card_numbers: bool,
+    /// `Bearer`/`Basic` credentials and JWTs → `[TOKEN]`
+    pub tokens: bool,
+    /// Regexes whose matches become `[REDACTED]`
+    pub custom: Vec<String>,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            ip_addresses: true,
+            card_numbers: true,
+            tokens: true,
+            custom: Vec::new(),
+        }
+    }
+}
+
+const EMAIL: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}";
+const IPV4: &str = r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b";
+// Full or `::`-compressed forms only, so times like 10:15:30 aren't taken for addresses
+const IPV6: &str = r"(?i)(?:\b(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}\b|\b(?:[0-9a-f]{1,4}:){1,6}(?::[0-9a-f]{1,4}){1,6}\b|\b(?:[0-9a-f]{1,4}:){1,7}:|::(?:[0-9a-f]{1,4}:){0,5}[0-9a-f]{1,4}\b)";
+const CARD: &str = r"\b\d(?:[ -]?\d){12,18}\b";
+const AUTHORIZATION: &str = r"(?i)\b(bearer|basic)\s+[A-Za-z0-9._~+/-]{8,}=*";
+const JWT: &str = r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*";
+
+/// One pattern and what its matches become.
+struct Rule {
+    regex: Regex,
+    mask: &'static str,
+    /// Matches failing this are left alone
+    check: Option<fn(&str) -> bool>,
+}
+
+/// Masks the matches of the enabled rules. Built from `RedactionRules`;
+/// a disabled or empty one leaves text as it is.
+#[derive(Default)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// Compile the rules. Custom patterns that aren't valid regexes are skipped,
+    /// see `pattern_error`.
+    pub fn new(rules: &RedactionRules) -> Self {
+        if !rules.enabled {
+            return Self::default();
+        }
+        let builtin = |pattern: &str| Regex::new(pattern).expect("built-in redaction pattern");
+        let mut compiled = Vec::new();
+        // Tokens go first since JWTs and credentials can contain what looks like the others
+        if rules.tokens {
+            compiled.push(Rule { regex: builtin(AUTHORIZATION), mask: "$1 [TOKEN]", check: None });
+            compiled.push(Rule { regex: builtin(JWT), mask: "[TOKEN]", check: None });
+        }
+        if rules.emails {
+            compiled.push(Rule { regex: builtin(EMAIL), mask: "[EMAIL]", check: None });
+        }
+        if rules.card_numbers {
+            compiled.push(Rule { regex: builtin(CARD), mask: "[CARD]", check: Some(luhn) });
+        }
+        if rules.ip_addresses {
+            compiled.push(Rule { regex: builtin(IPV4), mask: "[IP]", check: None });
+            compiled.push(Rule { regex: builtin(IPV6), mask: "[IP]", check: None });
+        }
+        for pattern in rules.custom.iter().filter(|p| !p.is_empty()) {
+            match Regex::new(pattern) {
+                Ok(regex) => compiled.push(Rule { regex, mask: "[REDACTED]", check: None }),
+                Err(e) => app_warn!("Skipping redaction pattern {}: {}", pattern, e),
+            }
+        }
+        Self { rules: compiled }
+    }
+
+    /// `text` with every match masked; borrowed when nothing matched.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.rules {
+            let replaced = match rule.check {
+                Some(check) => rule.regex.replace_all(&text, |caps: &Captures| {
+                    if check(&caps[0]) { rule.mask.to_string() } else { caps[0].to_string() }
+                }),
+                None => rule.regex.replace_all(&text, rule.mask),
+            };
+            if let Cow::Owned(replaced) = replaced {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+}
+
+/// Why a custom pattern can't be used, if it can't.
+pub fn pattern_error(pattern: &str) -> Option<String> {
+    Regex::new(pattern).err().map(|e| e.to_string())
+}
+
+/// Whether the digits pass the Luhn checksum card numbers carry, which
+/// keeps most other long numbers (IDs, timestamps) unmasked.
+fn luhn(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
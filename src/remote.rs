@@ -0,0 +1,381 @@
+#[cfg(feature = "ssh")]
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+#[cfg(feature = "ssh")]
+use std::io::{self, Read};
+#[cfg(feature = "ssh")]
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(feature = "ssh")]
+use std::path::PathBuf;
+#[cfg(feature = "ssh")]
+use std::sync::atomic::Ordering;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "ssh")]
+use std::thread;
+#[cfg(feature = "ssh")]
+use std::time::Duration;
+
+#[cfg(feature = "ssh")]
+use crate::file_open;
+use crate::source_event::{self, SourceAction, SourceEvent, SourceEvents};
+
+/// How often the reading thread checks for output and whether it should stop.
+#[cfg(feature = "ssh")]
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+#[cfg(feature = "ssh")]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait between reconnect attempts.
+#[cfg(feature = "ssh")]
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Seconds between keepalives, so a dead connection is noticed while the file is quiet.
+#[cfg(feature = "ssh")]
+const KEEPALIVE_INTERVAL: u32 = 15;
+/// Chunks of output held for the app before reading waits; SSH flow control
+/// then pauses the remote `tail` too.
+const MAX_WAITING_CHUNKS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoteAuth {
+    Agent,
+    KeyFile,
+    Password,
+}
+
+/// Where to tail from, as edited in the Open Remote File window.
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub path: String,
+    pub auth: RemoteAuth,
+    pub key_file: String,
+    /// The password, or the key file's passphrase
+    pub secret: String,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 22,
+            user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default(),
+            path: "/var/log/".to_string(),
+            auth: RemoteAuth::Agent,
+            key_file: dirs::home_dir()
+                .map(|home| home.join(".ssh").join("id_ed25519").to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            secret: String::new(),
+        }
+    }
+}
+
+impl RemoteConfig {
+    /// `user@host:path`, as shown in the header.
+    pub fn description(&self) -> String {
+        format!("{}@{}:{}", self.user, self.host, self.path)
+    }
+}
+
+/// The Open Remote File window.
+#[derive(Default)]
+pub struct RemoteDialog {
+    pub open: bool,
+    pub config: RemoteConfig,
+    /// Why the last tail couldn't be started
+    pub error: Option<String>,
+}
+
+impl RemoteDialog {
+    pub fn show(&mut self, ctx: &egui::Context, tail: Option<&RemoteTail>) -> Option<SourceAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("Open Remote File")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let config = &mut self.config;
+                egui::Grid::new("remote_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Host:");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut config.host);
+                        ui.label("Port:");
+                        ui.add(egui::DragValue::new(&mut config.port).clamp_range(1..=65535));
+                    });
+                    ui.end_row();
+                    ui.label("User:");
+                    ui.text_edit_singleline(&mut config.user);
+                    ui.end_row();
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut config.path);
+                    ui.end_row();
+                    ui.label("Login:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut config.auth, RemoteAuth::Agent, "SSH agent");
+                        ui.radio_value(&mut config.auth, RemoteAuth::KeyFile, "Key file");
+                        ui.radio_value(&mut config.auth, RemoteAuth::Password, "Password");
+                    });
+                    ui.end_row();
+                    if config.auth == RemoteAuth::KeyFile {
+                        ui.label("Key file:");
+                        ui.text_edit_singleline(&mut config.key_file);
+                        ui.end_row();
+                    }
+                    if config.auth != RemoteAuth::Agent {
+                        ui.label(if config.auth == RemoteAuth::KeyFile { "Passphrase:" } else { "Password:" });
+                        ui.add(egui::TextEdit::singleline(&mut config.secret).password(true));
+                        ui.end_row();
+                    }
+                });
+                ui.label("The host must be in ~/.ssh/known_hosts.");
+                source_event::show_errors(ui, tail.map(RemoteTail::events), self.error.as_deref());
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        action = Some(SourceAction::Open);
+                    }
+                    if tail.is_some() && ui.button("Disconnect").clicked() {
+                        action = Some(SourceAction::Disconnect);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+/// Tails a file on another machine over SSH with `tail -F`, reconnecting when
+/// the connection drops. Reading resumes at the byte it stopped at, so no
+/// lines are lost or repeated across a reconnect.
+pub struct RemoteTail {
+    description: String,
+    events: SourceEvents,
+}
+
+impl RemoteTail {
+    /// Start tailing on a background thread, beginning up to `window` bytes
+    /// before the end of the file.
+    pub fn start(config: &RemoteConfig, window: u64) -> Result<Self, String> {
+        if config.host.trim().is_empty() || config.user.trim().is_empty() || config.path.trim().is_empty() {
+            return Err("Host, user and path are required".to_string());
+        }
+        let (events, sender, stop) = SourceEvents::channel(MAX_WAITING_CHUNKS);
+        spawn(config, window, sender, stop)?;
+        Ok(Self { description: config.description(), events })
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn events(&self) -> &SourceEvents {
+        &self.events
+    }
+
+    /// Events since the last call, in order.
+    pub fn take_events(&mut self) -> Vec<SourceEvent> {
+        self.events.take()
+    }
+}
+
+/// Tail on a background thread.
+#[cfg(feature = "ssh")]
+fn spawn(config: &RemoteConfig, window: u64, sender: mpsc::SyncSender<SourceEvent>, stop: Arc<AtomicBool>) -> Result<(), String> {
+    let config = config.clone();
+    thread::spawn(move || run(&config, window, &sender, &stop));
+    Ok(())
+}
+
+#[cfg(not(feature = "ssh"))]
+fn spawn(_config: &RemoteConfig, _window: u64, _sender: mpsc::SyncSender<SourceEvent>, _stop: Arc<AtomicBool>) -> Result<(), String> {
+    Err("Log Rocket was built without SSH support; rebuild it with `--features ssh`".to_string())
+}
+
+/// Bytes of the remote file handed on so far, to resume from after a reconnect.
+#[cfg(feature = "ssh")]
+struct Position {
+    offset: u64,
+    /// Starting mid-file, the first line read is partial and dropped
+    skip_partial: bool,
+}
+
+#[cfg(feature = "ssh")]
+fn run(config: &RemoteConfig, window: u64, sender: &mpsc::SyncSender<SourceEvent>, stop: &AtomicBool) {
+    let mut position: Option<Position> = None;
+    let mut delay = Duration::from_secs(1);
+    while !stop.load(Ordering::Relaxed) {
+        let result = tail(config, window, &mut position, &mut delay, sender, stop);
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let error = match result {
+            Ok(()) => "tail exited".to_string(),
+            Err(e) => e,
+        };
+        if sender.send(SourceEvent::Disconnected(error)).is_err() {
+            return;
+        }
+        file_open::wake_ui();
+        // Sleep in short steps so stopping isn't held up by the delay
+        let mut waited = Duration::ZERO;
+        while waited < delay && !stop.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            waited += POLL_INTERVAL;
+        }
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+/// Quote `text` for a POSIX shell.
+#[cfg(feature = "ssh")]
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(feature = "ssh")]
+fn connect(config: &RemoteConfig) -> Result<Session, String> {
+    let address = (config.host.as_str(), config.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", config.host, e))?
+        .next()
+        .ok_or_else(|| format!("Failed to resolve {}", config.host))?;
+    let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to {}: {}", config.host, e))?;
+    let mut session = Session::new().map_err(|e| format!("Failed to start SSH session: {}", e))?;
+    session.set_tcp_stream(stream);
+    session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+    session.handshake().map_err(|e| format!("SSH handshake failed: {}", e))?;
+    check_host_key(&session, config)?;
+
+    let secret = (!config.secret.is_empty()).then_some(config.secret.as_str());
+    let auth = match config.auth {
+        RemoteAuth::Agent => session.userauth_agent(&config.user),
+        RemoteAuth::KeyFile => session.userauth_pubkey_file(&config.user, None, &PathBuf::from(&config.key_file), secret),
+        RemoteAuth::Password => session.userauth_password(&config.user, &config.secret),
+    };
+    auth.map_err(|e| format!("Authentication failed: {}", e))?;
+    if !session.authenticated() {
+        return Err("Authentication failed".to_string());
+    }
+    session.set_keepalive(true, KEEPALIVE_INTERVAL);
+    Ok(session)
+}
+
+/// Only connect to hosts already trusted in `~/.ssh/known_hosts`.
+#[cfg(feature = "ssh")]
+fn check_host_key(session: &Session, config: &RemoteConfig) -> Result<(), String> {
+    let (key, _) = session.host_key().ok_or("The server sent no host key")?;
+    let mut known_hosts = session.known_hosts().map_err(|e| format!("Failed to read known hosts: {}", e))?;
+    let file = dirs::home_dir().ok_or("No home directory for known_hosts")?.join(".ssh").join("known_hosts");
+    known_hosts
+        .read_file(&file, KnownHostFileKind::OpenSSH)
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    match known_hosts.check_port(&config.host, config.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(format!("The host key of {} doesn't match known_hosts", config.host)),
+        CheckResult::NotFound => Err(format!("{} is not in known_hosts; connect once with ssh to trust it", config.host)),
+        CheckResult::Failure => Err("Failed to check the host key".to_string()),
+    }
+}
+
+/// Run a command and return its output.
+#[cfg(feature = "ssh")]
+fn run_command(session: &Session, command: &str) -> Result<String, String> {
+    let mut channel = session.channel_session().map_err(|e| format!("Failed to open channel: {}", e))?;
+    channel.exec(command).map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    let mut output = String::new();
+    channel.read_to_string(&mut output).map_err(|e| format!("Failed to read output: {}", e))?;
+    let mut errors = String::new();
+    channel.stderr().read_to_string(&mut errors).ok();
+    channel.wait_close().ok();
+    match channel.exit_status() {
+        Ok(0) => Ok(output),
+        _ => Err(errors.trim().to_string()),
+    }
+}
+
+/// One connection: find where to read from, then stream `tail -F` output
+/// until it ends, fails or the tail is stopped.
+#[cfg(feature = "ssh")]
+fn tail(
+    config: &RemoteConfig,
+    window: u64,
+    position: &mut Option<Position>,
+    retry_delay: &mut Duration,
+    sender: &mpsc::SyncSender<SourceEvent>,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let session = connect(config)?;
+    let path = shell_quote(&config.path);
+    let size: u64 = run_command(&session, &format!("wc -c < {}", path))?
+        .trim()
+        .parse()
+        .map_err(|_| format!("Failed to read the size of {}", config.path))?;
+    let position = position.get_or_insert_with(|| {
+        let start = size.saturating_sub(window);
+        Position { offset: start, skip_partial: start > 0 }
+    });
+    if size < position.offset {
+        position.offset = 0;
+        position.skip_partial = false;
+        sender.send(SourceEvent::Truncated).ok();
+    }
+
+    let mut channel = session.channel_session().map_err(|e| format!("Failed to open channel: {}", e))?;
+    channel
+        .exec(&format!("tail -c +{} -F -- {}", position.offset + 1, path))
+        .map_err(|e| format!("Failed to start tail: {}", e))?;
+    if sender.send(SourceEvent::Connected).is_err() {
+        return Ok(());
+    }
+    file_open::wake_ui();
+    *retry_delay = Duration::from_secs(1);
+    session.set_blocking(false);
+
+    let mut buffer = [0u8; 64 * 1024];
+    let mut pending = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        match channel.read(&mut buffer) {
+            Ok(0) if channel.eof() => {
+                session.set_blocking(true);
+                let mut errors = String::new();
+                channel.stderr().read_to_string(&mut errors).ok();
+                return Err(if errors.trim().is_empty() { "tail exited".to_string() } else { errors.trim().to_string() });
+            }
+            Ok(0) => thread::sleep(POLL_INTERVAL),
+            Ok(read) => {
+                pending.extend_from_slice(&buffer[..read]);
+                let Some(end) = pending.iter().rposition(|&b| b == b'\n') else {
+                    continue;
+                };
+                let complete: Vec<u8> = pending.drain(..=end).collect();
+                position.offset += complete.len() as u64;
+                let text = String::from_utf8_lossy(&complete);
+                let mut lines = text.lines();
+                if std::mem::take(&mut position.skip_partial) {
+                    lines.next();
+                }
+                let lines: Vec<String> = lines.filter(|l| !l.trim().is_empty()).map(|l| l.trim_end().to_string()).collect();
+                if !lines.is_empty() {
+                    if sender.send(SourceEvent::Lines(lines)).is_err() {
+                        return Ok(());
+                    }
+                    file_open::wake_ui();
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if let Err(e) = session.keepalive_send().map_err(io::Error::from) {
+                    if e.kind() != io::ErrorKind::WouldBlock {
+                        return Err(format!("Connection lost: {}", e));
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(format!("Connection lost: {}", e)),
+        }
+    }
+    Ok(())
+}
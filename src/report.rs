@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use crate::log_parser::{LogEntry, LogLevel};
+use crate::noise;
+use crate::patterns::PatternIndex;
+use crate::timeline::Timeline;
+use crate::transactions::{self, Transaction};
+
+const SPARK: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const TOP_N: usize = 15;
+
+/// Everything the analysis report is built from.
+pub struct ReportInput<'a> {
+    pub source: &'a str,
+    pub entries: &'a [LogEntry],
+    pub filtered: &'a [usize],
+    pub timeline: Option<&'a Timeline>,
+    pub patterns: &'a PatternIndex,
+    pub transactions: &'a [Transaction],
+}
+
+fn format_time(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn sparkline(values: impl Iterator<Item = usize> + Clone) -> String {
+    let max = values.clone().max().unwrap_or(0).max(1);
+    values
+        .map(|v| if v == 0 { ' ' } else { SPARK[(v * (SPARK.len() - 1)) / max] })
+        .collect()
+}
+
+/// Markdown cells can't contain pipes or newlines.
+fn cell(text: &str) -> String {
+    text.lines().next().unwrap_or("").replace('|', "\\|")
+}
+
+/// Build the postmortem report as a Markdown document.
+pub fn markdown(input: &ReportInput) -> String {
+    let mut out = String::new();
+    let entries = input.entries;
+    let _ = writeln!(out, "# Log report: {}\n", input.source);
+    let _ = writeln!(out, "Generated {}\n", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+
+    // Stats
+    let _ = writeln!(out, "## Summary\n");
+    let _ = writeln!(out, "| | |\n|---|---|");
+    let _ = writeln!(out, "| Entries | {} |", entries.len());
+    let _ = writeln!(out, "| Shown by current filters | {} |", input.filtered.len());
+    if let Some(timeline) = input.timeline {
+        let _ = writeln!(out, "| From | {} |", format_time(timeline.range.0));
+        let _ = writeln!(out, "| To | {} |", format_time(timeline.range.1));
+    }
+    for level in LogLevel::ALL {
+        let count = entries.iter().filter(|e| e.level == level).count();
+        if count > 0 {
+            let _ = writeln!(out, "| {} | {} ({:.1}%) |", level.as_str(), count, count as f32 * 100.0 / entries.len() as f32);
+        }
+    }
+
+    // Timeline
+    if let Some(timeline) = input.timeline {
+        let _ = writeln!(out, "\n## Timeline\n");
+        let _ = writeln!(out, "```");
+        let _ = writeln!(out, "entries  {}", sparkline(timeline.buckets.iter().map(|b| b.total)));
+        let _ = writeln!(out, "problems {}", sparkline(timeline.buckets.iter().map(|b| b.errors + b.warnings)));
+        let _ = writeln!(out, "```\n");
+        if timeline.anomalies.is_empty() {
+            let _ = writeln!(out, "No error/warning rate spikes detected.");
+        } else {
+            let _ = writeln!(out, "Error/warning rate spikes:\n");
+            for &i in &timeline.anomalies {
+                let bucket = &timeline.buckets[i];
+                let _ = writeln!(
+                    out,
+                    "- {}: {} errors, {} warnings in {} entries",
+                    format_time(bucket.start_ms),
+                    bucket.errors,
+                    bucket.warnings,
+                    bucket.total
+                );
+            }
+        }
+    }
+
+    // Templates
+    let _ = writeln!(out, "\n## Top message patterns\n");
+    let _ = writeln!(out, "| Count | % | First line | Pattern |\n|---:|---:|---:|---|");
+    for p in input.patterns.by_count().into_iter().take(TOP_N) {
+        let pattern = &input.patterns.patterns[p];
+        let line = entries.get(pattern.first_entry).map_or(0, |e| e.line_number);
+        let _ = writeln!(
+            out,
+            "| {} | {:.1} | {} | `{}` |",
+            pattern.count,
+            pattern.count as f32 * 100.0 / entries.len().max(1) as f32,
+            line,
+            cell(&pattern.template())
+        );
+    }
+
+    // Noise
+    let repeated = noise::top_messages(entries, TOP_N);
+    if !repeated.is_empty() {
+        let _ = writeln!(out, "\n## Most repeated messages\n");
+        let _ = writeln!(out, "| Count | Message |\n|---:|---|");
+        for message in &repeated {
+            let _ = writeln!(out, "| {} | `{}` |", message.count, cell(&message.message));
+        }
+    }
+
+    // Transactions
+    let mut slowest: Vec<&Transaction> = input.transactions.iter().filter(|t| t.duration_ms().is_some()).collect();
+    if !slowest.is_empty() {
+        slowest.sort_by_key(|t| std::cmp::Reverse(t.duration_ms()));
+        let _ = writeln!(out, "\n## Slowest transactions\n");
+        let _ = writeln!(out, "| ID | Start | Duration | Entries | Status |\n|---|---|---:|---:|---|");
+        for transaction in slowest.into_iter().take(TOP_N) {
+            let status = if transaction.failed {
+                "error"
+            } else if transaction.complete {
+                "ok"
+            } else {
+                "incomplete"
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                cell(&transaction.id),
+                transaction.start_ms.map(format_time).unwrap_or_default(),
+                transaction.duration_ms().map(transactions::format_duration).unwrap_or_default(),
+                transaction.entries.len(),
+                status
+            );
+        }
+    }
+
+    // First errors, for context
+    let errors: Vec<&LogEntry> = input.filtered.iter().map(|&i| &entries[i]).filter(|e| e.level == LogLevel::Error).take(TOP_N).collect();
+    if !errors.is_empty() {
+        let _ = writeln!(out, "\n## First errors\n");
+        let _ = writeln!(out, "```");
+        for entry in errors {
+            let _ = writeln!(out, "{:>7}  {}", entry.line_number, entry.raw_line.lines().next().unwrap_or(""));
+        }
+        let _ = writeln!(out, "```");
+    }
+    out
+}
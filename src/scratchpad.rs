@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::log_parser::{LogEntry, LogLevel};
+
+/// An entry sent to the scratchpad, with a link back to where it came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinnedEntry {
+    /// The file it was pinned from; None for pasted text and streams
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    pub source_name: String,
+    pub line_number: usize,
+    pub level: LogLevel,
+    /// The entry's time, clock offset included when it was merged
+    #[serde(default)]
+    pub time_ms: Option<i64>,
+    pub text: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+impl PinnedEntry {
+    /// `file.log:123`, how the entry is referred to.
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.source_name, self.line_number)
+    }
+}
+
+/// Load the pinned entries. A missing or unreadable file yields an empty list.
+pub fn load() -> Vec<PinnedEntry> {
+    config::load_json("scratchpad.json")
+}
+
+pub fn save(entries: &[PinnedEntry]) -> Result<(), String> {
+    config::save_json("scratchpad.json", entries)
+}
+
+/// Write the entries to `path` for someone else to import.
+pub fn export(entries: &[PinnedEntry], path: &Path) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(entries).map_err(|e| format!("Failed to serialize pins: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Read entries exported with `export`.
+pub fn import(path: &Path) -> Result<Vec<PinnedEntry>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{} isn't an exported scratchpad: {}", path.display(), e))
+}
+
+/// Point imported entries whose file doesn't exist here (it was on the
+/// exporter's machine) at an open file of the same name, and add the ones not
+/// pinned yet. Returns how many were added.
+pub fn merge_imported(entries: &mut Vec<PinnedEntry>, imported: Vec<PinnedEntry>, open_files: &[PathBuf]) -> usize {
+    let mut added = 0;
+    for mut pin in imported {
+        if let Some(ref path) = pin.path.clone().filter(|path| !path.exists()) {
+            if let Some(local) = open_files.iter().find(|open| open.file_name() == path.file_name()) {
+                pin.path = Some(local.clone());
+            }
+        }
+        let existing = entries
+            .iter_mut()
+            .find(|other| other.path == pin.path && other.source_name == pin.source_name && other.line_number == pin.line_number);
+        match existing {
+            // Keep the teammate's note unless there is one already
+            Some(other) => {
+                if other.note.is_empty() {
+                    other.note = pin.note;
+                }
+            }
+            None => {
+                entries.push(pin);
+                added += 1;
+            }
+        }
+    }
+    added
+}
+
+/// Find the entries pinned from `path` again after the file was read anew,
+/// as their lines may have moved (the file was rewritten, or parsed with other
+/// entry boundaries). A pin goes to the entry with its text nearest its old
+/// line, or failing that to one with its time and first line. `entries` are
+/// the file's entries. Returns how many pins moved.
+pub fn relink(pins: &mut [PinnedEntry], path: &Path, entries: &[&LogEntry]) -> usize {
+    let mut moved = 0;
+    for pin in pins.iter_mut().filter(|pin| pin.path.as_deref() == Some(path)) {
+        let unmoved = entries.iter().any(|entry| entry.line_number == pin.line_number && entry.raw_line == pin.text);
+        if unmoved {
+            continue;
+        }
+        let distance = |entry: &&&LogEntry| entry.line_number.abs_diff(pin.line_number);
+        let first_line = pin.text.lines().next().unwrap_or("");
+        let found = entries
+            .iter()
+            .filter(|entry| entry.raw_line == pin.text)
+            .min_by_key(distance)
+            .or_else(|| {
+                entries
+                    .iter()
+                    .filter(|entry| entry.time_ms() == pin.time_ms && entry.raw_line.lines().next() == Some(first_line))
+                    .min_by_key(distance)
+            });
+        if let Some(entry) = found.filter(|entry| entry.line_number != pin.line_number) {
+            pin.line_number = entry.line_number;
+            moved += 1;
+        }
+    }
+    moved
+}
+
+/// The entries as a Markdown evidence list: where each came from, its note
+/// and its text. `text` gives the text to use, e.g. redacted.
+pub fn to_markdown(entries: &[PinnedEntry], text: impl Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let _ = write!(out, "{}. **{}**", i + 1, entry.location());
+        if !entry.note.is_empty() {
+            let _ = write!(out, ": {}", entry.note);
+        }
+        let _ = writeln!(out, "\n   ```\n   {}\n   ```", text(&entry.text).replace('\n', "\n   "));
+    }
+    out
+}
@@ -0,0 +1,169 @@
+//! Rhai entry scripts: a lightweight alternative to WASM plugins.
+//!
+//! The script may define any of these functions, each receiving the entry as a
+//! map with `line`, `timestamp`, `level`, `thread`, `class`, `message` and `raw`:
+//!
+//! - `keep(entry)` returns `false` to hide the entry.
+//! - `color(entry)` returns a `"#rrggbb"` string to recolor the entry.
+//! - `fields(entry)` returns a map of extra fields shown after the first line.
+
+use egui::Color32;
+#[cfg(feature = "scripting")]
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::file_watcher::FileWatcher;
+use crate::log_parser::LogEntry;
+
+/// What the script decided for one entry.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptVerdict {
+    pub hidden: bool,
+    pub color: Option<Color32>,
+    pub fields: Vec<(String, String)>,
+}
+
+pub struct EntryScript {
+    pub path: PathBuf,
+    pub error: Option<String>,
+    program: Option<Program>,
+    watcher: FileWatcher,
+}
+
+impl EntryScript {
+    pub fn load(path: PathBuf) -> Self {
+        let mut watcher = FileWatcher::new();
+        if let Err(e) = watcher.watch_file(path.clone()) {
+            app_error!("Error watching script: {}", e);
+        }
+
+        let mut script = Self {
+            path,
+            error: None,
+            program: None,
+            watcher,
+        };
+        script.compile();
+        script
+    }
+
+    fn compile(&mut self) {
+        let result = fs::read_to_string(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| Program::compile(&source));
+        match result {
+            Ok(program) => {
+                self.program = Some(program);
+                self.error = None;
+            }
+            Err(e) => {
+                // Keep the last good version running while the file is being edited
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Recompile when the script file changed on disk. Returns true if it was reloaded.
+    pub fn reload_if_changed(&mut self) -> bool {
+        if self.watcher.check_for_changes() {
+            self.compile();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn evaluate(&self, entry: &LogEntry) -> ScriptVerdict {
+        match self.program {
+            Some(ref program) => program.evaluate(entry),
+            None => ScriptVerdict::default(),
+        }
+    }
+}
+
+/// A compiled script and which of the functions it defines.
+#[cfg(feature = "scripting")]
+struct Program {
+    engine: Engine,
+    ast: AST,
+    has_keep: bool,
+    has_color: bool,
+    has_fields: bool,
+}
+
+#[cfg(feature = "scripting")]
+impl Program {
+    fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        // Scripts run once per entry; keep a runaway loop from freezing the UI
+        engine.set_max_operations(100_000);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let has = |name: &str| ast.iter_functions().any(|f| f.name == name && f.params.len() == 1);
+        Ok(Self {
+            has_keep: has("keep"),
+            has_color: has("color"),
+            has_fields: has("fields"),
+            engine,
+            ast,
+        })
+    }
+
+    fn evaluate(&self, entry: &LogEntry) -> ScriptVerdict {
+        let mut verdict = ScriptVerdict::default();
+        let ast = &self.ast;
+
+        let mut map = Map::new();
+        map.insert("line".into(), Dynamic::from(entry.line_number as i64));
+        let text = |value: Option<&str>| value.map_or(Dynamic::UNIT, |v| Dynamic::from(v.to_string()));
+        map.insert("timestamp".into(), text(entry.timestamp()));
+        map.insert("level".into(), Dynamic::from(entry.level.as_str().to_string()));
+        map.insert("thread".into(), text(entry.thread()));
+        map.insert("class".into(), text(entry.class()));
+        map.insert("message".into(), Dynamic::from(entry.message().to_string()));
+        map.insert("raw".into(), Dynamic::from(entry.raw_line.clone()));
+
+        let mut scope = Scope::new();
+        if self.has_keep {
+            if let Ok(keep) = self.engine.call_fn::<bool>(&mut scope, ast, "keep", (map.clone(),)) {
+                verdict.hidden = !keep;
+            }
+        }
+        if self.has_color {
+            if let Ok(color) = self.engine.call_fn::<String>(&mut scope, ast, "color", (map.clone(),)) {
+                verdict.color = parse_hex_color(&color);
+            }
+        }
+        if self.has_fields {
+            if let Ok(fields) = self.engine.call_fn::<Map>(&mut scope, ast, "fields", (map,)) {
+                verdict.fields = fields.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            }
+        }
+        verdict
+    }
+}
+
+/// Without the `scripting` feature no script compiles; the error says why.
+#[cfg(not(feature = "scripting"))]
+enum Program {}
+
+#[cfg(not(feature = "scripting"))]
+impl Program {
+    fn compile(_source: &str) -> Result<Self, String> {
+        Err("Log Rocket was built without scripting support; rebuild it with `--features scripting`".to_string())
+    }
+
+    fn evaluate(&self, _entry: &LogEntry) -> ScriptVerdict {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn parse_hex_color(text: &str) -> Option<Color32> {
+    let hex = text.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color32::from_rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
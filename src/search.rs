@@ -1,6 +1,16 @@
-use regex::Regex;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use std::time::{Duration, Instant};
 use crate::log_parser::LogEntry;
 
+/// Entries searched per task; chunks are spread over rayon's threads.
+const CHUNK_SIZE: usize = 10_000;
+/// How long a progressive search runs per frame before handing back to the UI.
+const FRAME_BUDGET: Duration = Duration::from_millis(25);
+
+/// An entry index with the byte ranges of the query in it.
+type EntryMatch = (usize, Vec<(usize, usize)>);
+
 #[derive(Debug, Clone)]
 pub struct SearchState {
     pub query: String,
@@ -10,7 +20,14 @@ pub struct SearchState {
     pub matches: Vec<usize>,
     pub current_match: Option<usize>,
     pub regex: Option<Regex>,
+    /// Why the query isn't a valid regex, while regex search is on
+    pub regex_error: Option<String>,
     pub match_positions: Vec<(usize, Vec<(usize, usize)>)>, // (line_idx, vec of (start, end))
+    pub generation: u64, // Bumped on every search, so views know to redraw matches
+    /// The next entry a progressive search will look at; None once it is done
+    pending: Option<usize>,
+    /// Where a progressive search was cancelled, leaving the matches before it
+    stopped_at: Option<usize>,
 }
 
 impl SearchState {
@@ -23,78 +40,202 @@ impl SearchState {
             matches: Vec::new(),
             current_match: None,
             regex: None,
+            regex_error: None,
             match_positions: Vec::new(),
+            generation: 0,
+            pending: None,
+            stopped_at: None,
         }
     }
 
+    /// Search all entries now.
     pub fn update_search(&mut self, entries: &[LogEntry]) {
+        if self.reset() {
+            self.search_range(entries, 0, entries.len());
+        }
+    }
+
+    /// Start searching the entries a frame's worth at a time, see
+    /// `continue_search`. The matches found so far can be used meanwhile.
+    pub fn start_search(&mut self, entries: &[LogEntry]) {
+        if self.reset() {
+            self.pending = Some(0);
+            self.continue_search(entries);
+        }
+    }
+
+    /// Search on for about a frame. Returns whether matches were found.
+    pub fn continue_search(&mut self, entries: &[LogEntry]) -> bool {
+        // An invalid pattern matches nothing, rather than its text
+        if self.regex_error.is_some() {
+            return false;
+        }
+        let Some(mut next) = self.pending else {
+            return false;
+        };
+        let found = self.matches.len();
+        let started = Instant::now();
+        // One chunk for each thread per step, so every step keeps all cores busy
+        let step = CHUNK_SIZE * rayon::current_num_threads();
+        while next < entries.len() && started.elapsed() < FRAME_BUDGET {
+            let end = (next + step).min(entries.len());
+            self.search_range(entries, next, end);
+            next = end;
+        }
+        self.pending = (next < entries.len()).then_some(next);
+        if self.matches.len() > found {
+            self.generation += 1;
+        }
+        self.matches.len() > found
+    }
+
+    /// Stop a progressive search, keeping the matches found so far.
+    pub fn cancel_search(&mut self) {
+        self.stopped_at = self.pending.take();
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// How far a running or cancelled search got through `total` entries.
+    pub fn progress(&self, total: usize) -> Option<f32> {
+        let reached = self.pending.or(self.stopped_at)?;
+        Some(reached as f32 / total.max(1) as f32)
+    }
+
+    /// Forget the last search and compile the query. False when there is nothing to search for.
+    fn reset(&mut self) -> bool {
+        self.generation += 1;
         self.matches.clear();
         self.current_match = None;
         self.regex = None;
+        self.regex_error = None;
         self.match_positions.clear();
+        self.pending = None;
+        self.stopped_at = None;
 
         if self.query.is_empty() {
-            return;
+            return false;
         }
 
-        let pattern = if self.use_regex {
-            let pattern_str = if self.case_sensitive {
-                self.query.clone()
-            } else {
-                format!("(?i){}", self.query)
-            };
-            match Regex::new(&pattern_str) {
-                Ok(re) => {
-                    self.regex = Some(re.clone());
-                    Some(re)
+        if self.use_regex {
+            // Not a (?i) prefix, which would shift the error's position markers
+            match RegexBuilder::new(&self.query).case_insensitive(!self.case_sensitive).build() {
+                Ok(regex) => self.regex = Some(regex),
+                Err(e) => {
+                    // Searching the pattern as plain text would only hide the mistake
+                    self.regex_error = Some(e.to_string());
+                    return false;
                 }
-                Err(_) => None,
             }
-        } else {
-            None
-        };
+        } else if !self.case_sensitive {
+            // Searching a lowercased copy gives offsets that are off in the original
+            // wherever a character changes length, so fold case with a regex instead
+            self.regex = RegexBuilder::new(&regex::escape(&self.query)).case_insensitive(true).build().ok();
+        }
+        true
+    }
 
-        for (idx, entry) in entries.iter().enumerate() {
-            let text = &entry.raw_line;
-            let mut positions = Vec::new();
+    /// Search entries appended since the last search, from index `start` on.
+    /// A progressive search still running gets to them itself.
+    pub fn extend_search(&mut self, entries: &[LogEntry], start: usize) {
+        if self.pending.is_some() || self.regex_error.is_some() {
+            return;
+        }
+        self.generation += 1;
+        self.search_range(entries, start, entries.len());
+    }
 
-            if let Some(ref regex) = pattern {
-                // Regex search - find all matches
-                for mat in regex.find_iter(text) {
-                    positions.push((mat.start(), mat.end()));
-                }
-            } else {
-                // Simple text search - find all occurrences
-                let search_text = if self.case_sensitive {
-                    text.to_string()
-                } else {
-                    text.to_lowercase()
-                };
-                let search_query = if self.case_sensitive {
-                    self.query.clone()
-                } else {
-                    self.query.to_lowercase()
-                };
-                
-                let mut start = 0;
-                while let Some(pos) = search_text[start..].find(&search_query) {
-                    let actual_pos = start + pos;
-                    positions.push((actual_pos, actual_pos + self.query.len()));
-                    start = actual_pos + 1;
-                }
+    /// Search entry `idx` again after its text changed. A progressive search
+    /// that hasn't reached it gets to it itself.
+    pub fn research_entry(&mut self, entries: &[LogEntry], idx: usize) {
+        if self.query.is_empty() || self.regex_error.is_some() || self.pending.or(self.stopped_at).is_some_and(|next| next <= idx) {
+            return;
+        }
+        let Some(entry) = entries.get(idx) else {
+            return;
+        };
+        let positions = self.find_positions(&entry.raw_line);
+        let at = self.matches.partition_point(|&found| found < idx);
+        let matched = self.matches.get(at) == Some(&idx);
+        match (matched, positions.is_empty()) {
+            (true, false) => self.match_positions[at].1 = positions,
+            (true, true) => {
+                self.matches.remove(at);
+                self.match_positions.remove(at);
+                let last = self.matches.len().checked_sub(1);
+                self.current_match = self.current_match
+                    .map(|current| if current > at { current - 1 } else { current })
+                    .and_then(|current| last.map(|last| current.min(last)));
             }
-
-            if !positions.is_empty() {
-                self.matches.push(idx);
-                self.match_positions.push((idx, positions));
+            (false, false) => {
+                self.matches.insert(at, idx);
+                self.match_positions.insert(at, (idx, positions));
+                self.current_match = Some(self.current_match.map_or(0, |current| if current >= at { current + 1 } else { current }));
             }
+            (false, true) => return,
         }
+        self.generation += 1;
+    }
+
+    /// Forget the matches in the first `dropped` entries, which were discarded,
+    /// and renumber the rest to match.
+    pub fn drop_front(&mut self, dropped: usize) {
+        let gone = self.matches.partition_point(|&idx| idx < dropped);
+        self.matches.drain(..gone);
+        self.match_positions.drain(..gone);
+        self.matches.iter_mut().for_each(|idx| *idx -= dropped);
+        self.match_positions.iter_mut().for_each(|(idx, _)| *idx -= dropped);
+        self.current_match = self.current_match.map(|current| current.saturating_sub(gone)).filter(|_| !self.matches.is_empty());
+        self.pending = self.pending.map(|next| next.saturating_sub(dropped));
+        self.stopped_at = self.stopped_at.map(|next| next.saturating_sub(dropped));
+        self.generation += 1;
+    }
 
-        if !self.matches.is_empty() {
+    /// Search the entries from `start` to `end`, in chunks on all cores. Chunk
+    /// results come back in order, so the matches stay sorted by entry index.
+    fn search_range(&mut self, entries: &[LogEntry], start: usize, end: usize) {
+        let found: Vec<Vec<EntryMatch>> = entries
+            .get(start..end)
+            .unwrap_or_default()
+            .par_chunks(CHUNK_SIZE)
+            .enumerate()
+            .map(|(chunk, entries)| {
+                let first = start + chunk * CHUNK_SIZE;
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, entry)| {
+                        let positions = self.find_positions(&entry.raw_line);
+                        (!positions.is_empty()).then_some((first + i, positions))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for (idx, positions) in found.into_iter().flatten() {
+            self.matches.push(idx);
+            self.match_positions.push((idx, positions));
+        }
+
+        if self.current_match.is_none() && !self.matches.is_empty() {
             self.current_match = Some(0);
         }
     }
 
+    /// Where the query occurs in `text`, as byte ranges.
+    fn find_positions(&self, text: &str) -> Vec<(usize, usize)> {
+        match self.regex {
+            // Case-insensitive text search runs as an escaped regex too, see `reset`
+            Some(ref regex) => regex.find_iter(text).map(|mat| (mat.start(), mat.end())).collect(),
+            None => text
+                .match_indices(self.query.as_str())
+                .map(|(start, found)| (start, start + found.len()))
+                .collect(),
+        }
+    }
+
     pub fn next_match(&mut self) {
         if let Some(current) = self.current_match {
             let next = (current + 1) % self.matches.len();
@@ -122,7 +263,8 @@ impl SearchState {
     }
 
     pub fn is_match(&self, line_index: usize) -> bool {
-        self.matches.contains(&line_index)
+        // Matches are found in entry order
+        self.matches.binary_search(&line_index).is_ok()
     }
 
     pub fn is_current_match(&self, line_index: usize) -> bool {
@@ -131,10 +273,50 @@ impl SearchState {
     
     pub fn get_match_positions(&self, line_index: usize) -> Option<&Vec<(usize, usize)>> {
         self.match_positions
-            .iter()
-            .find(|(idx, _)| *idx == line_index)
-            .map(|(_, positions)| positions)
+            .binary_search_by_key(&line_index, |(idx, _)| *idx)
+            .ok()
+            .map(|i| &self.match_positions[i].1)
+    }
+}
+
+/// The match ranges that fall on one line of an entry, for a line starting
+/// `offset` bytes into the entry's text. The ranges are made relative to the
+/// line, widened to char boundaries and merged where they overlap, so each can
+/// be sliced out of `line` as is.
+pub fn line_ranges(positions: &[(usize, usize)], offset: usize, line: &str) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &(start, end) in positions {
+        let start = floor_char_boundary(line, start.saturating_sub(offset));
+        let end = ceil_char_boundary(line, end.saturating_sub(offset));
+        if start >= end {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(index) {
+        index -= 1;
     }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
 }
 
 impl Default for SearchState {
@@ -143,3 +325,82 @@ impl Default for SearchState {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::LogParser;
+
+    fn entries(lines: &[&str]) -> Vec<LogEntry> {
+        let parser = LogParser::new();
+        lines.iter().enumerate().map(|(i, line)| parser.parse_line(line, i + 1)).collect()
+    }
+
+    #[test]
+    fn an_invalid_regex_matches_nothing() {
+        let entries = entries(&["value (1", "other"]);
+        let mut search = SearchState::new();
+        search.query = "(1".to_string();
+        search.use_regex = true;
+        search.start_search(&entries);
+        assert!(search.regex_error.is_some());
+        assert!(!search.continue_search(&entries));
+        search.extend_search(&entries, 0);
+        assert!(search.matches.is_empty());
+    }
+
+    #[test]
+    fn searches_a_changed_entry_again() {
+        let mut entries = entries(&["timeout on a", "ok", "timeout on c"]);
+        let mut search = SearchState::new();
+        search.query = "timeout".to_string();
+        search.update_search(&entries);
+        assert_eq!(search.matches, [0, 2]);
+        search.current_match = Some(1);
+        
+        entries[1].raw_line.push_str(" after a timeout");
+        search.research_entry(&entries, 1);
+        assert_eq!(search.matches, [0, 1, 2]);
+        assert_eq!(search.get_match_positions(1), Some(&vec![(11, 18)]));
+        assert_eq!(search.get_current_match_index(), Some(2));
+        
+        entries[0].raw_line = "recovered".to_string();
+        search.research_entry(&entries, 0);
+        assert_eq!(search.matches, [1, 2]);
+        assert_eq!(search.get_current_match_index(), Some(2));
+    }
+
+    #[test]
+    fn makes_match_ranges_relative_to_the_line() {
+        // "ERROR one\nERROR two": the second line starts at byte 10
+        let positions = [(0, 5), (10, 15)];
+        assert_eq!(line_ranges(&positions, 0, "ERROR one"), [(0, 5)]);
+        assert_eq!(line_ranges(&positions, 10, "ERROR two"), [(0, 5)]);
+        // A match running on from the line before starts at the line's start
+        assert_eq!(line_ranges(&[(4, 14)], 10, "ERROR two"), [(0, 4)]);
+        // One running past the end stops there
+        assert_eq!(line_ranges(&[(6, 40)], 0, "ERROR one"), [(6, 9)]);
+        assert_eq!(line_ranges(&[], 0, "ERROR one"), []);
+    }
+
+    #[test]
+    fn merges_overlapping_match_ranges() {
+        assert_eq!(line_ranges(&[(0, 4), (2, 6), (6, 8), (10, 12)], 0, "aaaaaaaaaaaaaa"), [(0, 8), (10, 12)]);
+        // Ranges that end up empty on this line are left out
+        assert_eq!(line_ranges(&[(0, 3), (20, 25)], 10, "abcdef"), []);
+    }
+
+    #[test]
+    fn widens_match_ranges_to_char_boundaries() {
+        // "é" takes bytes 1..3 and "日" bytes 4..7
+        let line = "xé 日本";
+        assert_eq!(line_ranges(&[(2, 5)], 0, line), [(1, 7)]);
+        assert_eq!(&line[1..7], "é 日");
+        assert_eq!(floor_char_boundary(line, 2), 1);
+        assert_eq!(floor_char_boundary(line, 3), 3);
+        assert_eq!(floor_char_boundary(line, 99), line.len());
+        assert_eq!(ceil_char_boundary(line, 5), 7);
+        assert_eq!(ceil_char_boundary(line, 0), 0);
+        assert_eq!(ceil_char_boundary(line, line.len() - 1), line.len());
+    }
+}
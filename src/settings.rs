@@ -0,0 +1,350 @@
+use crate::config::{self, AppConfig, ColorPalette, OverflowPolicy, PaletteRule, PaletteVariant, StartPosition, Theme};
+use crate::custom_format::CustomFormat;
+use crate::redaction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsTab {
+    General,
+    Appearance,
+    Formats,
+    Privacy,
+    Shortcuts,
+}
+
+/// What the user asked for in the settings window.
+pub enum SettingsAction {
+    Apply(Box<AppConfig>),
+    NewFormat,
+    EditFormat(String),
+    DeleteFormat(String),
+}
+
+const SHORTCUTS: [(&str, &str); 6] = [
+    ("Cmd/Ctrl + F", "Toggle search"),
+    ("Enter / Shift + Enter", "Next / previous match"),
+    ("Esc", "Close search"),
+    ("Cmd/Ctrl + S", "Toggle sidebar"),
+    ("Cmd/Ctrl + ↑ / ↓", "Jump to top / bottom"),
+    ("Cmd/Ctrl + = / -", "Increase / decrease font size"),
+];
+
+/// Tabbed settings window editing a draft of the config until Apply.
+pub struct SettingsDialog {
+    pub open: bool,
+    tab: SettingsTab,
+    draft: AppConfig,
+}
+
+impl SettingsDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            tab: SettingsTab::General,
+            draft: AppConfig::default(),
+        }
+    }
+
+    pub fn open(&mut self, config: &AppConfig, tab: SettingsTab) {
+        self.draft = config.clone();
+        self.tab = tab;
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, formats: &[CustomFormat]) -> Option<SettingsAction> {
+        if !self.open {
+            return None;
+        }
+        let mut open = self.open;
+        let mut action = None;
+
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .default_width(460.0)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.tab, SettingsTab::General, "General");
+                    ui.selectable_value(&mut self.tab, SettingsTab::Appearance, "Appearance");
+                    ui.selectable_value(&mut self.tab, SettingsTab::Formats, "Formats");
+                    ui.selectable_value(&mut self.tab, SettingsTab::Privacy, "Privacy");
+                    ui.selectable_value(&mut self.tab, SettingsTab::Shortcuts, "Shortcuts");
+                });
+                ui.separator();
+
+                match self.tab {
+                    SettingsTab::General => self.general(ui),
+                    SettingsTab::Appearance => self.appearance(ui),
+                    SettingsTab::Formats => action = Self::formats(ui, formats),
+                    SettingsTab::Privacy => self.privacy(ui),
+                    SettingsTab::Shortcuts => Self::shortcuts(ui),
+                }
+
+                // Formats are saved as they are edited; the rest waits for Apply
+                if matches!(self.tab, SettingsTab::General | SettingsTab::Appearance | SettingsTab::Privacy) {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            action = Some(SettingsAction::Apply(Box::new(self.draft.clone())));
+                        }
+                        if ui.button("Reset to Defaults").clicked() {
+                            self.draft = AppConfig::default();
+                        }
+                    });
+                }
+            });
+
+        self.open = open;
+        action
+    }
+
+    fn general(&mut self, ui: &mut egui::Ui) {
+        let draft = &mut self.draft;
+        ui.checkbox(&mut draft.tail_log, "Tail log (auto-refresh) by default");
+        ui.checkbox(&mut draft.scroll_to_end, "Auto-scroll to end by default");
+        ui.horizontal(|ui| {
+            ui.label("Once a file has loaded, show:");
+            ui.selectable_value(&mut draft.start_position, StartPosition::End, "The end");
+            ui.selectable_value(&mut draft.start_position, StartPosition::FirstError, "The first error");
+            ui.selectable_value(&mut draft.start_position, StartPosition::AfterTime, "The first entry from");
+            ui.add_enabled(
+                draft.start_position == StartPosition::AfterTime,
+                egui::TextEdit::singleline(&mut draft.start_time).hint_text("2024-01-15 10:30 or 10:30").desired_width(150.0),
+            )
+            .on_hover_text("A date and time, or a time of day on the log's first day");
+        });
+        if draft.start_position == StartPosition::AfterTime && config::start_time_ms(&draft.start_time, 0).is_none() {
+            ui.label(egui::RichText::new("Enter a time like 2024-01-15 10:30:00 or 10:30").size(12.0).color(egui::Color32::from_rgb(0xe0, 0x50, 0x50)));
+        }
+        ui.checkbox(&mut draft.backfill_rotated, "Read lines missed in rotated files")
+            .on_hover_text("When a tailed log is moved away and recreated, first read what was written to the old file since the last update");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut draft.always_poll, "Poll tailed files every")
+                .on_hover_text("For filesystems that don't report changes. Files on NFS, SMB and similar mounts are polled regardless on Linux.");
+            ui.add(egui::DragValue::new(&mut draft.poll_interval_ms).speed(100.0).clamp_range(100..=60_000).suffix(" ms"));
+        });
+        egui::Grid::new("settings_tail_batching").num_columns(2).show(ui, |ui| {
+            ui.label("Add new lines at most:");
+            ui.add(egui::DragValue::new(&mut draft.tail_refresh_hz).speed(1.0).clamp_range(0..=60).suffix(" times/s"))
+                .on_hover_text("Fewer refreshes keep the view smooth for very chatty logs (0 = every frame)");
+            ui.end_row();
+            ui.label("Lines added per refresh:");
+            ui.add(egui::DragValue::new(&mut draft.max_lines_per_refresh).speed(1_000.0).clamp_range(0..=1_000_000))
+                .on_hover_text("The rest wait for the next refresh (0 = no limit)");
+            ui.end_row();
+            ui.label("Lines waiting at most:");
+            ui.add(egui::DragValue::new(&mut draft.max_queued_lines).speed(10_000.0).clamp_range(0..=10_000_000))
+                .on_hover_text("For bursts faster than lines can be shown (0 = no limit)");
+            ui.end_row();
+            ui.label("When more arrive:");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut draft.overflow_policy, OverflowPolicy::DropOldest, "Drop oldest")
+                    .on_hover_text("Keep up with the source; a divider shows how many lines were dropped");
+                ui.selectable_value(&mut draft.overflow_policy, OverflowPolicy::PauseSource, "Pause reading")
+                    .on_hover_text("Lose nothing; files, pipes and streams are read again once the backlog is shown. Syslog messages are still dropped.");
+            });
+            ui.end_row();
+        });
+
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Large files").strong());
+        let mut threshold_mb = draft.large_file_threshold as f64 / 1_000_000.0;
+        let mut window_mb = draft.tail_window_size as f64 / 1_000_000.0;
+        egui::Grid::new("settings_large_files").num_columns(2).show(ui, |ui| {
+            ui.label("Load only the end of files above:");
+            ui.add(egui::DragValue::new(&mut threshold_mb).speed(1.0).clamp_range(1.0..=100_000.0).suffix(" MB"));
+            ui.end_row();
+            ui.label("Amount loaded from the end:");
+            ui.add(egui::DragValue::new(&mut window_mb).speed(1.0).clamp_range(1.0..=threshold_mb).suffix(" MB"));
+            ui.end_row();
+            ui.label("Entries kept while tailing:");
+            ui.add(egui::DragValue::new(&mut draft.max_entries).speed(10_000.0).clamp_range(0..=100_000_000))
+                .on_hover_text("The oldest entries are dropped beyond this (0 = no limit)");
+            ui.end_row();
+            ui.label("Cut lines short after:");
+            ui.add(egui::DragValue::new(&mut draft.max_line_length).speed(1_000.0).clamp_range(0..=10_000_000).suffix(" chars"))
+                .on_hover_text("Very long lines (dumped payloads) are slow to lay out; click \"show full line\" to see one whole (0 = no limit)");
+            ui.end_row();
+        });
+        ui.checkbox(&mut draft.collapse_progress_lines, "Show progress lines as they ended up")
+            .on_hover_text("Lines that redraw themselves with carriage returns (\\r), like progress bars, show only their final state. Turn off to see every step.");
+        ui.horizontal(|ui| {
+            ui.label("Suggest another format when more than");
+            ui.add(egui::DragValue::new(&mut draft.unknown_level_warning).speed(1.0).clamp_range(0..=100).suffix("%"));
+            ui.label("of entries have no level");
+        })
+        .response
+        .on_hover_text("A format that doesn't fit the file leaves levels unknown, so level filters don't work (0 = never)");
+        ui.checkbox(&mut draft.low_memory_mode, "Open large files in full, reading lines from disk")
+            .on_hover_text("Keeps only an index in memory, so files larger than RAM can be browsed. Search, analysis and tailing are off for these files.");
+
+        ui.add_space(8.0);
+        ui.checkbox(&mut draft.check_for_updates, "Check for updates at startup")
+            .on_hover_text("Asks GitHub for the latest release; nothing about you or your logs is sent");
+        draft.large_file_threshold = (threshold_mb * 1_000_000.0) as u64;
+        draft.tail_window_size = (window_mb.min(threshold_mb) * 1_000_000.0) as u64;
+    }
+
+    fn appearance(&mut self, ui: &mut egui::Ui) {
+        let draft = &mut self.draft;
+        ui.horizontal(|ui| {
+            ui.label("Theme:");
+            // Picking a theme here ends any per-run override
+            if ui.selectable_label(draft.effective_theme() == &Theme::Dark, "Dark").clicked() {
+                draft.theme = Theme::Dark;
+                draft.theme_override = None;
+                draft.color_palette = ColorPalette::dark();
+            }
+            if ui.selectable_label(draft.effective_theme() == &Theme::Light, "Light").clicked() {
+                draft.theme = Theme::Light;
+                draft.theme_override = None;
+                draft.color_palette = ColorPalette::light();
+            }
+        });
+        if draft.theme_override.is_some() {
+            ui.label(egui::RichText::new("Theme set for this run by --theme / LOGROCKET_THEME").size(12.0).weak());
+        }
+        ui.horizontal(|ui| {
+            ui.label("Font size:");
+            ui.add(egui::DragValue::new(&mut draft.font_size).speed(0.5).clamp_range(8.0..=30.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Line spacing:");
+            ui.add(egui::DragValue::new(&mut draft.line_spacing).speed(0.05).clamp_range(1.0..=3.0).suffix("×"))
+                .on_hover_text("Extra room between rows of the log view");
+        });
+        ui.checkbox(&mut draft.zebra_stripes, "Stripe alternate entries")
+            .on_hover_text("A faint background on every other entry, to follow rows across a wide screen");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut draft.show_column_guides, "Column guides at")
+                .on_hover_text("Vertical lines at these character columns while lines don't wrap");
+            let mut remove = None;
+            for (i, column) in draft.column_guides.iter_mut().enumerate() {
+                ui.add(egui::DragValue::new(column).speed(1.0).clamp_range(1..=10_000))
+                    .on_hover_text("Right-click to remove")
+                    .context_menu(|ui| {
+                        if ui.button("Remove").clicked() {
+                            remove = Some(i);
+                            ui.close_menu();
+                        }
+                    });
+            }
+            if let Some(i) = remove {
+                draft.column_guides.remove(i);
+            }
+            if ui.small_button("+").on_hover_text("Add a guide").clicked() {
+                let next = draft.column_guides.iter().max().map_or(80, |last| last + 40);
+                draft.column_guides.push(next);
+            }
+        });
+        ui.checkbox(&mut draft.show_column_ruler, "Column ruler above the log")
+            .on_hover_text("Numbers the columns while lines don't wrap, showing how far the view is scrolled");
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Merged views").strong());
+        ui.checkbox(&mut draft.tint_merged_sources, "Tint rows with their source's color")
+            .on_hover_text("Rows with a level background keep it");
+        ui.checkbox(&mut draft.label_merged_sources, "Label rows with their source");
+
+        ui.add_space(8.0);
+        ui.label(egui::RichText::new("Palettes by file name").strong());
+        ui.label(egui::RichText::new("Files matching a pattern, e.g. access*.log, open with that palette; the first match wins").size(12.0).weak());
+        let mut remove = None;
+        for (i, rule) in draft.palette_rules.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut rule.pattern).font(egui::TextStyle::Monospace).desired_width(240.0));
+                egui::ComboBox::from_id_source(("palette_rule", i))
+                    .selected_text(rule.variant.label())
+                    .show_ui(ui, |ui| {
+                        for variant in PaletteVariant::ALL {
+                            ui.selectable_value(&mut rule.variant, variant, variant.label());
+                        }
+                    });
+                if ui.small_button("🗑").on_hover_text("Remove rule").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            draft.palette_rules.remove(i);
+        }
+        if ui.button("Add Rule").clicked() {
+            draft.palette_rules.push(PaletteRule { pattern: String::new(), variant: PaletteVariant::Muted });
+        }
+    }
+
+    fn privacy(&mut self, ui: &mut egui::Ui) {
+        let rules = &mut self.draft.redaction;
+        ui.checkbox(&mut rules.enabled, "Redact sensitive data")
+            .on_hover_text("Matches are masked in the log view, copied text, exports and reports");
+        ui.add_enabled_ui(rules.enabled, |ui| {
+            ui.checkbox(&mut rules.emails, "Email addresses  →  [EMAIL]");
+            ui.checkbox(&mut rules.ip_addresses, "IP addresses  →  [IP]");
+            ui.checkbox(&mut rules.card_numbers, "Credit card numbers  →  [CARD]")
+                .on_hover_text("13 to 19 digits, optionally grouped with spaces or dashes, passing the Luhn check");
+            ui.checkbox(&mut rules.tokens, "Bearer / Basic credentials and JWTs  →  [TOKEN]");
+
+            ui.add_space(8.0);
+            ui.label(egui::RichText::new("Custom patterns").strong());
+            ui.label(egui::RichText::new("Regexes whose matches become [REDACTED], e.g. customer_id=\\d+").size(12.0).weak());
+            let mut remove = None;
+            for (i, pattern) in rules.custom.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(pattern).font(egui::TextStyle::Monospace).desired_width(360.0));
+                    if ui.small_button("🗑").on_hover_text("Remove pattern").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                if let Some(error) = redaction::pattern_error(pattern) {
+                    ui.colored_label(egui::Color32::from_rgb(0xe0, 0x50, 0x50), error);
+                }
+            }
+            if let Some(i) = remove {
+                rules.custom.remove(i);
+            }
+            if ui.button("Add Pattern").clicked() {
+                rules.custom.push(String::new());
+            }
+        });
+    }
+
+    fn formats(ui: &mut egui::Ui, formats: &[CustomFormat]) -> Option<SettingsAction> {
+        let mut action = None;
+        if formats.is_empty() {
+            ui.label("No custom formats yet.");
+        }
+        egui::Grid::new("settings_formats").num_columns(2).striped(true).show(ui, |ui| {
+            for format in formats {
+                ui.label(egui::RichText::new(&format.name).strong()).on_hover_text(&format.pattern);
+                ui.horizontal(|ui| {
+                    if ui.small_button("Edit").clicked() {
+                        action = Some(SettingsAction::EditFormat(format.name.clone()));
+                    }
+                    if ui.small_button("Delete").clicked() {
+                        action = Some(SettingsAction::DeleteFormat(format.name.clone()));
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        ui.add_space(6.0);
+        if ui.button("New Custom Format…").clicked() {
+            action = Some(SettingsAction::NewFormat);
+        }
+        action
+    }
+
+    fn shortcuts(ui: &mut egui::Ui) {
+        egui::Grid::new("settings_shortcuts").num_columns(2).striped(true).show(ui, |ui| {
+            for (keys, description) in SHORTCUTS {
+                ui.label(egui::RichText::new(keys).monospace());
+                ui.label(description);
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl Default for SettingsDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
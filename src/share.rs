@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::file_open;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where shared lines are uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ShareTarget {
+    Gist,
+    PasteService,
+}
+
+/// How the Share Selection window uploads, saved in config.json.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    pub target: ShareTarget,
+    /// Takes the text as a POST body and replies with the paste's URL
+    /// (e.g. paste.rs, or a self-hosted pastebin)
+    pub paste_url: String,
+    /// Gists are secret unless this is set
+    pub public_gist: bool,
+    /// Mask the lines with the redaction rules, even while the view isn't redacted
+    pub redact: bool,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            target: ShareTarget::Gist,
+            paste_url: "https://paste.rs/".to_string(),
+            public_gist: false,
+            redact: true,
+        }
+    }
+}
+
+/// An upload running in the background, yielding the URL of the gist or paste.
+pub struct Upload {
+    receiver: mpsc::Receiver<Result<String, String>>,
+}
+
+impl Upload {
+    /// Upload `text`, named `file_name` where the service keeps names.
+    pub fn start(config: &ShareConfig, file_name: &str, text: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (config, file_name) = (config.clone(), file_name.to_string());
+        thread::spawn(move || {
+            let result = match config.target {
+                ShareTarget::Gist => create_gist(&file_name, &text, config.public_gist),
+                ShareTarget::PasteService => post_paste(&config.paste_url, &text),
+            };
+            sender.send(result).ok();
+            file_open::wake_ui();
+        });
+        Self { receiver }
+    }
+
+    /// The URL, or why the upload failed, once it is done.
+    pub fn poll(&self) -> Option<Result<String, String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("The upload stopped unexpectedly".to_string())),
+        }
+    }
+}
+
+/// A token from `GITHUB_TOKEN` or `GH_TOKEN`, else from the GitHub CLI's login.
+fn github_token() -> Result<String, String> {
+    for name in ["GITHUB_TOKEN", "GH_TOKEN"] {
+        if let Ok(token) = std::env::var(name) {
+            if !token.trim().is_empty() {
+                return Ok(token.trim().to_string());
+            }
+        }
+    }
+    match Command::new("gh").args(["auth", "token"]).output() {
+        Ok(output) if output.status.success() => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        Ok(output) => Err(format!(
+            "No GitHub credentials: run `gh auth login` or set GITHUB_TOKEN ({})",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(_) => Err("No GitHub credentials: set GITHUB_TOKEN to a token with the gist scope, or install the GitHub CLI and run `gh auth login`".to_string()),
+    }
+}
+
+fn create_gist(file_name: &str, text: &str, public: bool) -> Result<String, String> {
+    let token = github_token()?;
+    let body = json!({
+        "description": format!("{} (shared from Log Rocket)", file_name),
+        "public": public,
+        "files": { file_name: { "content": text } },
+    });
+    let response = ureq::post("https://api.github.com/gists")
+        .timeout(UPLOAD_TIMEOUT)
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "log-rocket")
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                let message = serde_json::from_reader::<_, serde_json::Value>(response.into_reader())
+                    .ok()
+                    .and_then(|body| body["message"].as_str().map(str::to_string))
+                    .unwrap_or_default();
+                format!("GitHub refused the gist ({}): {}", status, message)
+            }
+            e => format!("Failed to create the gist: {}", e),
+        })?;
+    let gist: serde_json::Value = serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read GitHub's reply: {}", e))?;
+    gist["html_url"].as_str().map(str::to_string).ok_or_else(|| "GitHub's reply has no gist URL".to_string())
+}
+
+/// POST the text and take the paste's URL from the reply's body or `Location`.
+fn post_paste(url: &str, text: &str) -> Result<String, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("Enter the paste service's URL".to_string());
+    }
+    let response = ureq::post(url)
+        .timeout(UPLOAD_TIMEOUT)
+        .set("Content-Type", "text/plain; charset=utf-8")
+        .send_string(text)
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => {
+                let body = response.into_string().unwrap_or_default();
+                format!("The paste service refused the upload ({}): {}", status, body.trim())
+            }
+            e => format!("Failed to upload to {}: {}", url, e),
+        })?;
+    let location = response.header("Location").map(str::to_string);
+    let body = response.into_string().map_err(|e| format!("Failed to read the reply: {}", e))?;
+    body.lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("http://") || line.starts_with("https://"))
+        .map(str::to_string)
+        .or(location)
+        .ok_or_else(|| "The paste service didn't reply with a URL".to_string())
+}
@@ -0,0 +1,152 @@
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::thread;
+
+use crate::file_open::{self, OpenRequest};
+
+// Each connection carries one logrocket:// URL per line. An empty line only asks
+// the running instance to come to the front. Over TCP the first line is the
+// running instance's token, which only the same user can read.
+
+/// Try to hand `request` over to an already running instance.
+/// Returns true if another instance accepted it, in which case this process should exit.
+pub fn send_to_running(request: Option<&OpenRequest>) -> bool {
+    let Some(mut stream) = connect() else {
+        return false;
+    };
+    let line = request
+        .map(|r| OpenRequest {
+            // The other instance has a different working directory
+            path: r.path.canonicalize().unwrap_or_else(|_| r.path.clone()),
+            ..r.clone()
+        })
+        .map(|r| r.to_url())
+        .unwrap_or_default();
+    writeln!(stream, "{}", line).is_ok()
+}
+
+/// Become the primary instance: listen for paths sent by later launches and
+/// queue them through `file_open` like any other OS open request.
+pub fn listen() {
+    let Some(listener) = bind() else {
+        app_warn!("Single-instance listener unavailable, running standalone");
+        return;
+    };
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+            if !authorized(&mut lines) {
+                app_warn!("Ignoring a single-instance connection without the token");
+                continue;
+            }
+            for line in lines {
+                let line = line.trim();
+                match OpenRequest::parse(line) {
+                    Some(request) if !line.is_empty() => file_open::push(request),
+                    _ => file_open::request_focus(),
+                }
+            }
+        }
+    });
+}
+
+/// A directory only this user can enter: in `$XDG_RUNTIME_DIR` or else the
+/// shared temp directory, where someone else could have made it first.
+#[cfg(unix)]
+fn socket_dir() -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    // SAFETY: getuid has no preconditions and can't fail
+    let uid = unsafe { libc::getuid() };
+    let base = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let dir = base.join(format!("log-rocket-{}", uid));
+    if let Err(e) = std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            return None;
+        }
+    }
+    let metadata = std::fs::symlink_metadata(&dir).ok()?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        app_warn!("Not using {} for the single-instance socket: it isn't private", dir.display());
+        return None;
+    }
+    Some(dir)
+}
+
+#[cfg(unix)]
+fn socket_path() -> Option<PathBuf> {
+    socket_dir().map(|dir| dir.join("instance.sock"))
+}
+
+#[cfg(unix)]
+fn connect() -> Option<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(socket_path()?).ok()
+}
+
+#[cfg(unix)]
+fn bind() -> Option<std::os::unix::net::UnixListener> {
+    let path = socket_path()?;
+    // Nobody answered on connect, so any socket file left behind is stale
+    let _ = std::fs::remove_file(&path);
+    std::os::unix::net::UnixListener::bind(&path).ok()
+}
+
+/// Only this user can reach the socket, so anyone connecting may hand files over.
+#[cfg(unix)]
+fn authorized(_lines: &mut impl Iterator<Item = String>) -> bool {
+    true
+}
+
+// Other platforms fall back to a loopback TCP port, which any local user can
+// reach, so connections start with a token kept in the config directory
+#[cfg(not(unix))]
+const INSTANCE_PORT: u16 = 47_391;
+
+#[cfg(not(unix))]
+const TOKEN_FILE: &str = "instance.token";
+
+#[cfg(not(unix))]
+static TOKEN: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+#[cfg(not(unix))]
+fn connect() -> Option<std::net::TcpStream> {
+    let token = std::fs::read_to_string(crate::config::config_dir()?.join(TOKEN_FILE)).ok()?;
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", INSTANCE_PORT)).ok()?;
+    writeln!(stream, "{}", token.trim()).ok()?;
+    Some(stream)
+}
+
+#[cfg(not(unix))]
+fn bind() -> Option<std::net::TcpListener> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", INSTANCE_PORT)).ok()?;
+    let token = new_token();
+    let dir = crate::config::config_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    std::fs::write(dir.join(TOKEN_FILE), &token).ok()?;
+    TOKEN.set(token).ok()?;
+    Some(listener)
+}
+
+#[cfg(not(unix))]
+fn authorized(lines: &mut impl Iterator<Item = String>) -> bool {
+    lines.next().is_some_and(|line| Some(line.trim()) == TOKEN.get().map(String::as_str))
+}
+
+/// 128 random bits in hex, from the per-process random keys of `RandomState`.
+#[cfg(not(unix))]
+fn new_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos();
+    (0..2)
+        .map(|i| {
+            let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+
+/// What a source read on a background thread (a remote tail, a live
+/// stream or query, a Kafka consumer) reports to the app.
+pub enum SourceEvent {
+    Connected,
+    /// Lines received, JSON entries included
+    Lines(Vec<String>),
+    /// The file shrank while disconnected; reading continues from its start
+    Truncated,
+    /// The connection failed or dropped; it is retried after a delay unless
+    /// the source ends
+    Disconnected(String),
+    /// The source ended for good, e.g. the last page of a query was read
+    Ended,
+}
+
+/// What the user asked for in a source's window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceAction {
+    Open,
+    Disconnect,
+}
+
+/// The app's end of a source's events, keeping track of its connection.
+/// Dropping it stops the source.
+pub struct SourceEvents {
+    receiver: mpsc::Receiver<SourceEvent>,
+    stop: Arc<AtomicBool>,
+    connected: bool,
+    ended: bool,
+    last_error: Option<String>,
+}
+
+impl SourceEvents {
+    /// The events of a new source, the sender it reports on, holding up to
+    /// `bound` events for the app, and the flag telling it to stop.
+    pub fn channel(bound: usize) -> (Self, mpsc::SyncSender<SourceEvent>, Arc<AtomicBool>) {
+        let (sender, receiver) = mpsc::sync_channel(bound);
+        let stop = Arc::new(AtomicBool::new(false));
+        let events = Self {
+            receiver,
+            stop: stop.clone(),
+            connected: false,
+            ended: false,
+            last_error: None,
+        };
+        (events, sender, stop)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn has_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Why the last connection attempt failed, while not connected.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Events since the last call, in order. Of the failures while retrying
+    /// only the one that ended a connection is passed on.
+    pub fn take(&mut self) -> Vec<SourceEvent> {
+        let mut events = Vec::new();
+        for event in self.receiver.try_iter() {
+            match event {
+                SourceEvent::Connected => {
+                    self.connected = true;
+                    self.last_error = None;
+                }
+                SourceEvent::Disconnected(ref e) => {
+                    self.last_error = Some(e.clone());
+                    if !std::mem::take(&mut self.connected) {
+                        continue;
+                    }
+                }
+                SourceEvent::Ended => {
+                    self.connected = false;
+                    self.ended = true;
+                }
+                SourceEvent::Lines(_) | SourceEvent::Truncated => {}
+            }
+            events.push(event);
+        }
+        events
+    }
+}
+
+impl Drop for SourceEvents {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Below the settings in a source's window: why the running source last
+/// failed, and why the last one couldn't be opened.
+pub fn show_errors(ui: &mut egui::Ui, source: Option<&SourceEvents>, open_error: Option<&str>) {
+    let color = egui::Color32::from_rgb(0xe0, 0x50, 0x50);
+    if let Some(source) = source {
+        if let Some(error) = source.last_error() {
+            let error = if source.has_ended() { error.to_string() } else { format!("{} (retrying)", error) };
+            ui.colored_label(color, error);
+        }
+    }
+    if let Some(error) = open_error {
+        ui.colored_label(color, error);
+    }
+}
+
+/// How far back a query reads, as a row of choices.
+pub fn lookback_choices(ui: &mut egui::Ui, minutes: &mut u32) {
+    for (choice, label) in [(15, "15 min"), (60, "1 h"), (360, "6 h"), (1440, "24 h"), (10080, "7 days")] {
+        ui.selectable_value(minutes, choice, label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_on_only_the_failure_that_ended_a_connection() {
+        let (mut events, sender, _stop) = SourceEvents::channel(16);
+        sender.send(SourceEvent::Disconnected("refused".to_string())).unwrap();
+        sender.send(SourceEvent::Disconnected("refused".to_string())).unwrap();
+        assert!(events.take().is_empty());
+        assert_eq!(events.last_error(), Some("refused"));
+
+        sender.send(SourceEvent::Connected).unwrap();
+        sender.send(SourceEvent::Lines(vec!["line".to_string()])).unwrap();
+        sender.send(SourceEvent::Disconnected("reset".to_string())).unwrap();
+        sender.send(SourceEvent::Disconnected("refused".to_string())).unwrap();
+        let taken = events.take();
+        assert_eq!(taken.len(), 3);
+        assert!(matches!(taken[2], SourceEvent::Disconnected(ref e) if e == "reset"));
+        assert!(!events.is_connected());
+        assert_eq!(events.last_error(), Some("refused"));
+    }
+
+    #[test]
+    fn stops_the_source_when_dropped() {
+        let (events, _sender, stop) = SourceEvents::channel(1);
+        drop(events);
+        assert!(stop.load(Ordering::Relaxed));
+    }
+}
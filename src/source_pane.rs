@@ -0,0 +1,140 @@
+use egui::{FontId, ScrollArea};
+
+use crate::config::ColorPalette;
+use crate::disk_log;
+use crate::merge::{self, MergeSource};
+use crate::redaction::Redactor;
+
+/// One file of a merged view, shown on its own beside it. With the time lock
+/// on, scrolling either one scrolls the other to the same moment; whichever
+/// the pointer is over leads.
+pub struct SourcePane {
+    pub source: usize,
+    pub sync_by_time: bool,
+    /// Row to scroll to on the next frame
+    scroll_to: Option<usize>,
+    /// The entry at the top of the pane in the last frame
+    top_row: usize,
+    /// The pointer was over the pane in the last frame
+    hovered: bool,
+}
+
+impl SourcePane {
+    pub fn new(source: usize) -> Self {
+        Self { source, sync_by_time: true, scroll_to: None, top_row: 0, hovered: false }
+    }
+
+    /// Whether the pane leads while the lock is on.
+    pub fn is_leading(&self) -> bool {
+        self.hovered
+    }
+
+    /// Scroll to the first entry at or after `time_ms`, on the merged view's
+    /// clock, when the lock is on. Returns whether the pane moves.
+    pub fn follow(&mut self, sources: &[MergeSource], time_ms: i64) -> bool {
+        let Some(source) = sources.get(self.source).filter(|_| self.sync_by_time && !self.hovered) else {
+            return false;
+        };
+        let row = source.entries.partition_point(|entry| entry.time_ms().is_none_or(|time| time + source.offset_ms < time_ms));
+        let row = row.min(source.entries.len().saturating_sub(1));
+        if row == self.top_row {
+            return false;
+        }
+        self.scroll_to = Some(row);
+        true
+    }
+
+    /// Draw the pane in a panel beside the view. Returns the moment, on the
+    /// merged view's clock, the user scrolled it to while the lock is on, and
+    /// whether it was closed.
+    pub fn show(&mut self, ctx: &egui::Context, sources: &[MergeSource], font_size: f32, palette: &ColorPalette, redactor: &Redactor) -> (Option<i64>, bool) {
+        let Some(source) = sources.get(self.source) else {
+            return (None, true);
+        };
+        let mut closed = false;
+        let mut chosen = self.source;
+        let top_row = self.top_row;
+        egui::SidePanel::right("source_pane")
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    let (swatch, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                    ui.painter().rect_filled(swatch, 2.0, merge::source_color(self.source));
+                    egui::ComboBox::from_id_source("source_pane_file")
+                        .selected_text(source.name())
+                        .show_ui(ui, |ui| {
+                            for (idx, source) in sources.iter().enumerate() {
+                                ui.selectable_value(&mut chosen, idx, source.name());
+                            }
+                        });
+                    ui.checkbox(&mut self.sync_by_time, "🔗 Sync by time")
+                        .on_hover_text("Scrolling either pane scrolls the other to the same moment");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        closed = ui.small_button("✖").on_hover_text("Close Pane").clicked();
+                    });
+                });
+                ui.separator();
+
+                let font = FontId::monospace(font_size);
+                let row_height = ui.fonts(|f| f.row_height(&font));
+                let mut scroll_area = ScrollArea::both().auto_shrink([false; 2]).id_source(("source_pane_scroll", self.source));
+                if let Some(row) = self.scroll_to.take() {
+                    scroll_area = scroll_area.vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+                }
+                let output = scroll_area.show_rows(ui, row_height, source.entries.len(), |ui, rows| {
+                    self.top_row = rows.start;
+                    for row in rows {
+                        let entry = &source.entries[row];
+                        let number = format!("{:6}   ", entry.line_number);
+                        disk_log::entry_row(ui, &number, &redactor.redact(&entry.raw_line), &entry.level, &font, palette);
+                    }
+                });
+                self.hovered = ui.rect_contains_pointer(output.inner_rect);
+            });
+
+        if chosen != self.source {
+            *self = Self { sync_by_time: self.sync_by_time, ..Self::new(chosen) };
+            return (None, closed);
+        }
+        // Untimed entries go with the last timed one before them
+        let moved = source.entries
+            .get(..=self.top_row)
+            .filter(|_| self.sync_by_time && self.hovered && self.top_row != top_row)
+            .and_then(|entries| entries.iter().rev().find_map(|entry| entry.time_ms()))
+            .map(|time| time + source.offset_ms);
+        (moved, closed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::LogParser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn follows_the_view_to_the_same_moment() {
+        let parser = LogParser::new();
+        let entries = (0..5)
+            .map(|minute| parser.parse_line(&format!("15.01.2024 10:{:02}:00.000 *INFO* [main] com.example.Api Request", minute * 10), minute + 1))
+            .collect();
+        // The file's clock runs a minute behind
+        let source = MergeSource { path: PathBuf::from("api.log"), offset_ms: 60_000, entries, tail_offset: 0, file_id: None, parse_job: None };
+        let second = source.entries[2].time_ms().unwrap();
+        let sources = [source];
+
+        let mut pane = SourcePane::new(0);
+        assert!(pane.follow(&sources, second + 60_000));
+        assert_eq!(pane.scroll_to, Some(2));
+        pane.top_row = 2;
+        assert!(!pane.follow(&sources, second + 60_000));
+        // Past the last entry it stays at the end
+        assert!(pane.follow(&sources, i64::MAX));
+        assert_eq!(pane.scroll_to, Some(4));
+
+        pane.sync_by_time = false;
+        assert!(!pane.follow(&sources, 0));
+    }
+}
@@ -0,0 +1,375 @@
+use chrono::Datelike;
+use regex::Regex;
+use std::io::{self, BufRead, BufReader};
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::file_open;
+use crate::log_parser::{LogEntry, LogLevel, SourceFields};
+
+/// How often the listening threads check whether they should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Messages held for the app. Senders can't be made to wait, so messages
+/// arriving beyond it are dropped.
+const MAX_WAITING_MESSAGES: usize = 10_000;
+
+/// Where to listen, as edited in the Syslog window.
+#[derive(Debug, Clone)]
+pub struct ListenConfig {
+    pub bind: String,
+    pub port: u16,
+    pub tcp: bool,
+    pub udp: bool,
+}
+
+impl Default for ListenConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1".to_string(),
+            port: 5514,
+            tcp: true,
+            udp: true,
+        }
+    }
+}
+
+/// Receives syslog messages (RFC 5424 or RFC 3164) over TCP and/or UDP.
+/// Each message becomes an entry with the sending host as its thread and
+/// the app name as its class. TCP messages are one per line.
+pub struct SyslogListener {
+    description: String,
+    receiver: mpsc::Receiver<LogEntry>,
+    stop: Arc<AtomicBool>,
+    received: usize,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl SyslogListener {
+    pub fn start(config: &ListenConfig) -> Result<Self, String> {
+        if !config.tcp && !config.udp {
+            return Err("Choose TCP, UDP or both".to_string());
+        }
+        let address = format!("{}:{}", config.bind, config.port);
+        // Bind both before starting any thread, so a failure leaves nothing running
+        let udp = if config.udp {
+            let socket = UdpSocket::bind(&address).map_err(|e| format!("Failed to listen on UDP {}: {}", address, e))?;
+            socket.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| format!("Failed to configure UDP socket: {}", e))?;
+            Some(socket)
+        } else {
+            None
+        };
+        let tcp = if config.tcp {
+            let listener = TcpListener::bind(&address).map_err(|e| format!("Failed to listen on TCP {}: {}", address, e))?;
+            listener.set_nonblocking(true).map_err(|e| format!("Failed to configure TCP listener: {}", e))?;
+            Some(listener)
+        } else {
+            None
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(MAX_WAITING_MESSAGES);
+        let queue = Queue { sender, dropped: Arc::new(AtomicUsize::new(0)) };
+        if let Some(socket) = udp {
+            let (queue, stop) = (queue.clone(), stop.clone());
+            thread::spawn(move || receive_udp(socket, queue, stop));
+        }
+        if let Some(listener) = tcp {
+            let (queue, stop) = (queue.clone(), stop.clone());
+            thread::spawn(move || accept_tcp(listener, queue, stop));
+        }
+
+        let protocols = match (config.tcp, config.udp) {
+            (true, true) => "TCP+UDP",
+            (true, false) => "TCP",
+            _ => "UDP",
+        };
+        Ok(Self {
+            description: format!("{} {}", protocols, address),
+            receiver,
+            stop,
+            received: 0,
+            dropped: queue.dropped,
+        })
+    }
+
+    /// Protocols and address listened on, e.g. "TCP+UDP 127.0.0.1:5514".
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Messages received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Messages dropped because they weren't taken fast enough.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Entries received since the last call, in arrival order. Their line
+    /// numbers are left for the caller to assign.
+    pub fn take_entries(&mut self) -> Vec<LogEntry> {
+        let entries: Vec<_> = self.receiver.try_iter().collect();
+        self.received += entries.len();
+        entries
+    }
+}
+
+impl Drop for SyslogListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Where the receiving threads hand on messages.
+#[derive(Clone)]
+struct Queue {
+    sender: mpsc::SyncSender<LogEntry>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Queue {
+    /// Queue a message, or count it dropped when the queue is full. False
+    /// once the listener is gone.
+    fn send(&self, entry: LogEntry) -> bool {
+        match self.sender.try_send(entry) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn receive_udp(socket: UdpSocket, queue: Queue, stop: Arc<AtomicBool>) {
+    let mut buffer = vec![0u8; 65_536];
+    while !stop.load(Ordering::Relaxed) {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, peer)) => {
+                let text = String::from_utf8_lossy(&buffer[..len]);
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    if !queue.send(parse_message(line, peer.ip())) {
+                        return;
+                    }
+                }
+                file_open::wake_ui();
+            }
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => {
+                app_error!("Error receiving syslog message: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+fn accept_tcp(listener: TcpListener, queue: Queue, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (queue, stop) = (queue.clone(), stop.clone());
+                thread::spawn(move || {
+                    if let Err(e) = read_tcp(stream, peer.ip(), &queue, &stop) {
+                        app_error!("Error reading syslog connection from {}: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if is_timeout(&e) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                app_error!("Error accepting syslog connection: {}", e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+fn read_tcp(stream: TcpStream, peer: IpAddr, queue: &Queue, stop: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut reader = BufReader::new(stream);
+    let mut buffer = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        // A timed out read keeps what it got in `buffer`, so the line continues next time
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buffer);
+                let line = strip_octet_count(line.trim_end_matches(['\n', '\r']));
+                if !line.trim().is_empty() {
+                    if !queue.send(parse_message(line, peer)) {
+                        return Ok(());
+                    }
+                    file_open::wake_ui();
+                }
+                buffer.clear();
+            }
+            Err(e) if is_timeout(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Drop an RFC 6587 octet count ("123 <34>1 ...") from a message.
+fn strip_octet_count(line: &str) -> &str {
+    match line.split_once(' ') {
+        Some((count, rest)) if !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()) && rest.starts_with('<') => rest,
+        _ => line,
+    }
+}
+
+fn rfc5424_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^<(\d{1,3})>1 (\S+) (\S+) (\S+) \S+ \S+ (?:-|(?:\[(?:[^\]\\]|\\.)*\])+) ?(.*)$").unwrap()
+    })
+}
+
+fn rfc3164_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^<(\d{1,3})>([A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?:(\S+) )?([^\s:\[]+)(?:\[[^\]]*\])?: ?(.*)$").unwrap()
+    })
+}
+
+/// The level of a syslog priority, from its severity part.
+fn severity_level(priority: &str) -> LogLevel {
+    match priority.parse::<u32>().map(|p| p % 8) {
+        Ok(0..=3) => LogLevel::Error,
+        Ok(4) => LogLevel::Warn,
+        Ok(7) => LogLevel::Debug,
+        Ok(_) => LogLevel::Info,
+        Err(_) => LogLevel::Unknown,
+    }
+}
+
+/// An RFC 3164 timestamp has no year; assume the current one.
+fn rfc3164_millis(timestamp: &str) -> Option<i64> {
+    let year = chrono::Local::now().year();
+    chrono::NaiveDateTime::parse_from_str(&format!("{} {}", year, timestamp), "%Y %b %e %H:%M:%S")
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+/// Turn one syslog message into an entry. Messages in neither format keep
+/// their whole text as the message, with the sender as host.
+fn parse_message(line: &str, peer: IpAddr) -> LogEntry {
+    let peer = peer.to_string();
+    let fields = if let Some(caps) = rfc5424_regex().captures(line) {
+        let field = |i: usize| caps.get(i).map(|m| m.as_str()).filter(|value| *value != "-");
+        let timestamp = field(2);
+        SourceFields {
+            level: severity_level(&caps[1]),
+            timestamp,
+            time_ms: timestamp
+                .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+                .map(|time| time.naive_local().and_utc().timestamp_millis()),
+            thread: field(3).or(Some(&peer)),
+            class: field(4),
+            message: caps.get(5).map_or("", |m| m.as_str()),
+        }
+    } else if let Some(caps) = rfc3164_regex().captures(line) {
+        let timestamp = caps.get(2).map(|m| m.as_str());
+        SourceFields {
+            level: severity_level(&caps[1]),
+            timestamp,
+            time_ms: timestamp.and_then(rfc3164_millis),
+            thread: caps.get(3).map(|m| m.as_str()).or(Some(&peer)),
+            class: caps.get(4).map(|m| m.as_str()),
+            message: caps.get(5).map_or("", |m| m.as_str()),
+        }
+    } else {
+        let (level, message) = match line.strip_prefix('<').and_then(|rest| rest.split_once('>')) {
+            Some((priority, message)) if priority.bytes().all(|b| b.is_ascii_digit()) => (severity_level(priority), message),
+            _ => (LogLevel::Unknown, line),
+        };
+        SourceFields {
+            level,
+            timestamp: None,
+            time_ms: None,
+            thread: Some(&peer),
+            class: None,
+            message,
+        }
+    };
+    LogEntry::from_source(line, 0, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 7));
+
+    #[test]
+    fn parses_rfc5424_messages() {
+        let line = r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application"] An application event log entry"#;
+        let entry = parse_message(line, PEER);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.timestamp(), Some("2003-10-11T22:14:15.003Z"));
+        assert_eq!(entry.time_ms(), Some(1_065_910_455_003));
+        assert_eq!(entry.thread(), Some("mymachine.example.com"));
+        assert_eq!(entry.class(), Some("evntslog"));
+        assert_eq!(entry.message(), "An application event log entry");
+
+        // Nil values, no structured data, an escaped bracket in a parameter
+        let entry = parse_message("<11>1 - - app - - - disk full", PEER);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.timestamp(), None);
+        assert_eq!(entry.thread(), Some("10.0.0.7"));
+        assert_eq!(entry.message(), "disk full");
+        let entry = parse_message(r#"<12>1 2024-01-02T03:04:05+01:00 host app 42 - [x a="[\]"] done"#, PEER);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message(), "done");
+    }
+
+    #[test]
+    fn parses_rfc3164_messages() {
+        let entry = parse_message("<34>Oct 11 22:14:15 mymachine su[230]: 'su root' failed for lonvick on /dev/pts/8", PEER);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.timestamp(), Some("Oct 11 22:14:15"));
+        assert!(entry.time_ms().is_some());
+        assert_eq!(entry.thread(), Some("mymachine"));
+        assert_eq!(entry.class(), Some("su"));
+        assert_eq!(entry.message(), "'su root' failed for lonvick on /dev/pts/8");
+
+        // No host, a single-digit day padded with a space
+        let entry = parse_message("<15>Feb  3 01:02:03 cron: job done", PEER);
+        assert_eq!(entry.level, LogLevel::Debug);
+        assert_eq!(entry.thread(), Some("10.0.0.7"));
+        assert_eq!(entry.class(), Some("cron"));
+        assert_eq!(entry.message(), "job done");
+    }
+
+    #[test]
+    fn keeps_malformed_messages_whole() {
+        let entry = parse_message("<13>not really syslog", PEER);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.message(), "not really syslog");
+        assert_eq!(entry.thread(), Some("10.0.0.7"));
+
+        for line in ["no priority at all", "<abc>1 bad priority", "<14", ""] {
+            let entry = parse_message(line, PEER);
+            assert_eq!(entry.level, LogLevel::Unknown, "{:?}", line);
+            assert_eq!(entry.message(), line);
+            assert_eq!(entry.time_ms(), None);
+        }
+    }
+
+    #[test]
+    fn strips_octet_counts() {
+        assert_eq!(strip_octet_count("57 <34>1 - - - - - - hello"), "<34>1 - - - - - - hello");
+        assert_eq!(strip_octet_count("57 words, not a message"), "57 words, not a message");
+        assert_eq!(strip_octet_count("<34>Oct 11 22:14:15 host app: hi"), "<34>Oct 11 22:14:15 host app: hi");
+    }
+}
@@ -0,0 +1,310 @@
+//! How log files are turned into text: the byte order mark picks UTF-8 or
+//! UTF-16, and Windows (CRLF) and classic Mac (lone CR) line endings become
+//! plain line feeds, so neither shows up as stray characters. Loading a file
+//! and tailing it share this, and both leave a last line that is still being
+//! written for the tail to read once it ends.
+
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+/// How long after its last write an unfinished last line is taken as it is,
+/// e.g. a file that simply doesn't end with a line break.
+pub const PARTIAL_LINE_WAIT: Duration = Duration::from_secs(1);
+/// Bytes from the start of a file looked at to tell how its lines end.
+const SAMPLE_LEN: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// The encoding text starting with `bytes` has, from its byte order mark,
+    /// with the mark's length. Without one it is taken as UTF-8.
+    pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+        match bytes {
+            [0xef, 0xbb, 0xbf, ..] => (Encoding::Utf8, 3),
+            [0xff, 0xfe, ..] => (Encoding::Utf16Le, 2),
+            [0xfe, 0xff, ..] => (Encoding::Utf16Be, 2),
+            _ => (Encoding::Utf8, 0),
+        }
+    }
+
+    /// Bytes per code unit.
+    pub fn unit_len(&self) -> usize {
+        match self {
+            Encoding::Utf8 => 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        }
+    }
+
+    /// `offset` moved forward to the start of a UTF-16 code unit.
+    pub fn align(&self, offset: u64) -> u64 {
+        match self {
+            Encoding::Utf8 => offset,
+            Encoding::Utf16Le | Encoding::Utf16Be => offset + offset % 2,
+        }
+    }
+
+    /// The code units of `bytes`, a trailing odd byte aside.
+    fn units<'a>(&self, bytes: &'a [u8]) -> impl Iterator<Item = u16> + 'a {
+        let encoding = *self;
+        bytes.chunks(encoding.unit_len()).filter(move |unit| unit.len() == encoding.unit_len()).map(move |unit| match unit {
+            [byte] => u16::from(*byte),
+            [low, high] if encoding == Encoding::Utf16Le => u16::from_le_bytes([*low, *high]),
+            [high, low] => u16::from_be_bytes([*high, *low]),
+            _ => 0,
+        })
+    }
+
+    /// `bytes` as text, without a byte order mark. Invalid sequences, such as
+    /// a character cut off at the end, are replaced.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let mut text = match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut text: String = char::decode_utf16(self.units(bytes)).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect();
+                if bytes.len() % 2 == 1 {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+                text
+            }
+        };
+        if text.starts_with('\u{feff}') {
+            text.drain(..'\u{feff}'.len_utf8());
+        }
+        text
+    }
+}
+
+/// What ends a file's lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// A line feed, possibly after carriage returns (Unix and Windows)
+    #[default]
+    Lf,
+    /// A carriage return alone (classic Mac)
+    Cr,
+}
+
+impl LineEnding {
+    /// How the lines of text starting with `bytes` end: by line feeds if
+    /// there are any, by carriage returns if there are only those. None
+    /// while there is neither.
+    pub fn detect(encoding: Encoding, bytes: &[u8]) -> Option<LineEnding> {
+        let mut carriage_return = false;
+        for unit in encoding.units(bytes) {
+            match unit {
+                0x0a => return Some(LineEnding::Lf),
+                0x0d => carriage_return = true,
+                _ => {}
+            }
+        }
+        carriage_return.then_some(LineEnding::Cr)
+    }
+
+    fn terminator(&self) -> u8 {
+        match self {
+            LineEnding::Lf => b'\n',
+            LineEnding::Cr => b'\r',
+        }
+    }
+}
+
+/// How a file's text is stored: its encoding, the length of its byte order
+/// mark and how its lines end. Found once per file, so loading and tailing
+/// read it alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    pub bom: usize,
+    /// None until a line ending was seen; lines are taken to end with line feeds meanwhile
+    pub line_ending: Option<LineEnding>,
+}
+
+impl TextFormat {
+    /// The format of text starting with `bytes`.
+    pub fn detect(bytes: &[u8]) -> Self {
+        let (encoding, bom) = Encoding::detect(bytes);
+        let line_ending = LineEnding::detect(encoding, &bytes[bom..]);
+        Self { encoding, bom, line_ending }
+    }
+
+    /// The format of the open `file`, from its start. Leaves the file at its start.
+    pub fn of_file(file: &mut fs::File) -> io::Result<Self> {
+        let mut start = Vec::new();
+        file.seek(SeekFrom::Start(0))?;
+        file.by_ref().take(SAMPLE_LEN).read_to_end(&mut start)?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self::detect(&start))
+    }
+
+    fn terminator(&self) -> [u8; 2] {
+        let byte = self.line_ending.unwrap_or_default().terminator();
+        match self.encoding {
+            Encoding::Utf16Be => [0, byte],
+            _ => [byte, 0],
+        }
+    }
+
+    /// Whether `bytes` end a line.
+    pub fn ends_line(&self, bytes: &[u8]) -> bool {
+        let terminator = self.terminator();
+        match self.encoding {
+            Encoding::Utf8 => bytes.ends_with(&terminator[..1]),
+            Encoding::Utf16Le | Encoding::Utf16Be => bytes.len().is_multiple_of(2) && bytes.ends_with(&terminator),
+        }
+    }
+
+    /// The length of `bytes` up to the end of their last complete line.
+    pub fn last_line_end(&self, bytes: &[u8]) -> Option<usize> {
+        let unit = self.encoding.unit_len();
+        (1..=bytes.len() / unit).rev().find(|&units| self.ends_line(&bytes[..units * unit])).map(|units| units * unit)
+    }
+
+    /// Read one line from `reader` onto `buffer`, including its line ending
+    /// unless the input ends first. Returns the number of bytes read.
+    pub fn read_line(&self, reader: &mut impl BufRead, buffer: &mut Vec<u8>) -> io::Result<usize> {
+        let start = buffer.len();
+        let byte = self.line_ending.unwrap_or_default().terminator();
+        while reader.read_until(byte, buffer)? > 0 {
+            // In UTF-16 the byte can be half of another character, or the
+            // first half of a little-endian line ending
+            if self.encoding == Encoding::Utf16Le && (buffer.len() - start) % 2 == 1 {
+                reader.by_ref().take(1).read_to_end(buffer)?;
+            }
+            if self.encoding == Encoding::Utf8 || self.ends_line(&buffer[start..]) {
+                break;
+            }
+        }
+        Ok(buffer.len() - start)
+    }
+
+    /// `bytes` as text with line feeds ending its lines.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        normalize_line_endings(self.encoding.decode(bytes), self.line_ending.unwrap_or_default())
+    }
+}
+
+/// `text` with every line ending a single line feed. With line feeds, the
+/// carriage returns before one are dropped; any other is a progress update
+/// within the line (see `log_parser::collapse_progress`).
+pub fn normalize_line_endings(text: String, line_ending: LineEnding) -> String {
+    if !text.contains('\r') {
+        return text;
+    }
+    if line_ending == LineEnding::Cr {
+        return text.replace('\r', "\n");
+    }
+    let mut normalized = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        match line.strip_suffix('\n') {
+            Some(body) => {
+                normalized.push_str(body.trim_end_matches('\r'));
+                normalized.push('\n');
+            }
+            None => normalized.push_str(line),
+        }
+    }
+    normalized
+}
+
+/// Whether a file was written to so recently that an unfinished last line
+/// is likely still being written.
+pub fn is_being_written(metadata: &fs::Metadata) -> bool {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < PARTIAL_LINE_WAIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xff, 0xfe];
+        text.encode_utf16().for_each(|unit| bytes.extend(unit.to_le_bytes()));
+        bytes
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xfe, 0xff];
+        text.encode_utf16().for_each(|unit| bytes.extend(unit.to_be_bytes()));
+        bytes
+    }
+
+    fn lines(format: &TextFormat, bytes: &[u8]) -> Vec<String> {
+        let mut reader = io::BufReader::new(&bytes[format.bom..]);
+        let mut lines = Vec::new();
+        loop {
+            let mut buffer = Vec::new();
+            if format.read_line(&mut reader, &mut buffer).unwrap() == 0 {
+                return lines;
+            }
+            lines.push(format.decode(&buffer));
+        }
+    }
+
+    #[test]
+    fn detects_byte_order_marks() {
+        assert_eq!(Encoding::detect(b"\xef\xbb\xbf12:00 INFO"), (Encoding::Utf8, 3));
+        assert_eq!(Encoding::detect(&utf16le("x")), (Encoding::Utf16Le, 2));
+        assert_eq!(Encoding::detect(&utf16be("x")), (Encoding::Utf16Be, 2));
+        assert_eq!(Encoding::detect(b"12:00 INFO"), (Encoding::Utf8, 0));
+        assert_eq!(Encoding::detect(b""), (Encoding::Utf8, 0));
+        assert_eq!(Encoding::Utf8.decode(b"\xef\xbb\xbfstarted"), "started");
+        assert_eq!(Encoding::Utf16Le.decode(&utf16le("started")), "started");
+    }
+
+    #[test]
+    fn detects_line_endings() {
+        let detect = |bytes: &[u8]| TextFormat::detect(bytes).line_ending;
+        assert_eq!(detect(b"a\r\nb\r\n"), Some(LineEnding::Lf));
+        assert_eq!(detect(b"a\nb"), Some(LineEnding::Lf));
+        assert_eq!(detect(b"a\rb\r"), Some(LineEnding::Cr));
+        assert_eq!(detect(&utf16le("a\rb\r")), Some(LineEnding::Cr));
+        assert_eq!(detect(b"no ending yet"), None);
+    }
+
+    #[test]
+    fn reads_utf16_lines_around_line_feed_bytes_in_characters() {
+        // U+0A0A is 0x0a 0x0a in either byte order, U+010A is 0x0a 0x01 in little-endian
+        let text = "a\u{0a0a}b\r\n\u{010a}c\r\nend";
+        let le = utf16le(text);
+        assert_eq!(lines(&TextFormat::detect(&le), &le), ["a\u{0a0a}b\n", "\u{010a}c\n", "end"]);
+        let be = utf16be(text);
+        assert_eq!(lines(&TextFormat::detect(&be), &be), ["a\u{0a0a}b\n", "\u{010a}c\n", "end"]);
+    }
+
+    #[test]
+    fn reads_carriage_return_lines() {
+        let bytes = b"10:00 one\r10:01 two\r10:02 thr";
+        let format = TextFormat::detect(bytes);
+        assert_eq!(lines(&format, bytes), ["10:00 one\n", "10:01 two\n", "10:02 thr"]);
+        assert_eq!(format.last_line_end(bytes), Some(20));
+        assert!(!format.ends_line(bytes));
+    }
+
+    #[test]
+    fn finds_the_end_of_the_last_complete_line() {
+        let format = TextFormat::detect(b"a\nb");
+        assert_eq!(format.last_line_end(b"first\nsecond\nthi"), Some(13));
+        assert_eq!(format.last_line_end(b"unfinished"), None);
+        let le = utf16le("a\nb");
+        assert_eq!(TextFormat::detect(&le).last_line_end(&le[2..]), Some(4));
+    }
+
+    #[test]
+    fn normalizes_line_endings() {
+        assert_eq!(normalize_line_endings("a\r\nb\r\r\nc\r".to_string(), LineEnding::Lf), "a\nb\nc\r");
+        assert_eq!(normalize_line_endings("a\rb\r".to_string(), LineEnding::Cr), "a\nb\n");
+        // A progress line keeps its carriage returns for collapsing, loaded or tailed
+        assert_eq!(normalize_line_endings("5%\r50%\r100%".to_string(), LineEnding::Lf), "5%\r50%\r100%");
+    }
+}
@@ -0,0 +1,332 @@
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Vec2};
+
+use crate::log_parser::{LogEntry, LogLevel};
+
+const BUCKET_COUNT: usize = 120;
+/// Buckets before the current one that make up the rolling baseline
+const BASELINE_WINDOW: usize = 10;
+/// A bucket needs at least this many errors/warnings to be flagged
+const MIN_PROBLEMS: usize = 3;
+
+pub struct Bucket {
+    pub start_ms: i64,
+    pub total: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub first_entry: Option<usize>,
+}
+
+impl Bucket {
+    fn new(start_ms: i64) -> Self {
+        Self { start_ms, total: 0, errors: 0, warnings: 0, first_entry: None }
+    }
+
+    fn problem_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.errors + self.warnings) as f64 / self.total as f64
+        }
+    }
+
+    /// Take in the counts of the bucket after this one.
+    fn merge(&mut self, next: Bucket) {
+        self.total += next.total;
+        self.errors += next.errors;
+        self.warnings += next.warnings;
+        self.first_entry = match (self.first_entry, next.first_entry) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Entries bucketed over time, with the buckets where the error/warn rate
+/// spikes. Built up as entries arrive: buckets are added as the range grows,
+/// and pairs of them merged into buckets twice as wide once there would be
+/// more than `BUCKET_COUNT`.
+#[derive(Default)]
+pub struct Timeline {
+    pub range: (i64, i64),
+    pub buckets: Vec<Bucket>,
+    /// Indices of buckets where a spike starts
+    pub anomalies: Vec<usize>,
+    bucket_ms: i64,
+}
+
+pub fn build(entries: &[LogEntry]) -> Option<Timeline> {
+    let mut timeline = Timeline::default();
+    timeline.extend(entries, 0);
+    (!timeline.is_empty()).then_some(timeline)
+}
+
+impl Timeline {
+    /// True until an entry with a timestamp was added.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Add the entries from index `start` on and look for spikes again.
+    pub fn extend(&mut self, entries: &[LogEntry], start: usize) {
+        for (idx, entry) in entries.iter().enumerate().skip(start) {
+            self.add(idx, entry);
+        }
+        self.mark_anomalies();
+    }
+
+    /// Count entry `idx` in its bucket. `mark_anomalies` should follow once
+    /// the entries are added.
+    pub fn add(&mut self, idx: usize, entry: &LogEntry) {
+        let Some(time) = entry.time_ms() else {
+            return;
+        };
+        let bucket = self.bucket_for(time);
+        let bucket = &mut self.buckets[bucket];
+        bucket.total += 1;
+        match entry.level {
+            LogLevel::Error => bucket.errors += 1,
+            LogLevel::Warn => bucket.warnings += 1,
+            _ => {}
+        }
+        if bucket.first_entry.is_none_or(|first| first > idx) {
+            bucket.first_entry = Some(idx);
+        }
+    }
+
+    /// Take the `dropped` entries, which were at the front of `remaining`,
+    /// out of the counts, and renumber the rest to match.
+    pub fn drop_front(&mut self, dropped: &[LogEntry], remaining: &[LogEntry]) {
+        for entry in dropped {
+            let Some(time) = entry.time_ms() else {
+                continue;
+            };
+            let bucket = self.index_of(time);
+            let bucket = &mut self.buckets[bucket];
+            bucket.total = bucket.total.saturating_sub(1);
+            match entry.level {
+                LogLevel::Error => bucket.errors = bucket.errors.saturating_sub(1),
+                LogLevel::Warn => bucket.warnings = bucket.warnings.saturating_sub(1),
+                _ => {}
+            }
+        }
+        let mut unknown_first = 0;
+        for bucket in &mut self.buckets {
+            bucket.first_entry = bucket.first_entry.and_then(|first| first.checked_sub(dropped.len())).filter(|_| bucket.total > 0);
+            if bucket.total > 0 && bucket.first_entry.is_none() {
+                unknown_first += 1;
+            }
+        }
+        // Buckets whose first entry was dropped start at the next one they hold
+        for (idx, entry) in remaining.iter().enumerate() {
+            if unknown_first == 0 {
+                break;
+            }
+            let Some(time) = entry.time_ms() else {
+                continue;
+            };
+            let bucket = self.index_of(time);
+            let bucket = &mut self.buckets[bucket];
+            if bucket.first_entry.is_none() && bucket.total > 0 {
+                bucket.first_entry = Some(idx);
+                unknown_first -= 1;
+            }
+        }
+        self.mark_anomalies();
+    }
+
+    /// The bucket `time` falls in, which must be within the range.
+    fn index_of(&self, time: i64) -> usize {
+        (((time - self.buckets[0].start_ms) / self.bucket_ms).max(0) as usize).min(self.buckets.len() - 1)
+    }
+
+    /// The bucket for `time`, adding buckets (and widening them) to reach it.
+    fn bucket_for(&mut self, time: i64) -> usize {
+        if self.buckets.is_empty() {
+            self.range = (time, time);
+            self.bucket_ms = 1;
+            self.buckets.push(Bucket::new(time));
+            return 0;
+        }
+        self.range = (self.range.0.min(time), self.range.1.max(time));
+        loop {
+            let first = self.buckets[0].start_ms;
+            let before = ((first - time).max(0) + self.bucket_ms - 1) / self.bucket_ms;
+            let after = ((time - first) / self.bucket_ms + 1).max(self.buckets.len() as i64);
+            if before + after > BUCKET_COUNT as i64 {
+                self.widen();
+                continue;
+            }
+            let new_first = first - before * self.bucket_ms;
+            if before > 0 {
+                let earlier = (0..before).map(|i| Bucket::new(new_first + i * self.bucket_ms));
+                self.buckets.splice(0..0, earlier);
+                self.anomalies.clear();
+            }
+            while (self.buckets.len() as i64) < before + after {
+                self.buckets.push(Bucket::new(new_first + self.buckets.len() as i64 * self.bucket_ms));
+            }
+            return ((time - new_first) / self.bucket_ms) as usize;
+        }
+    }
+
+    /// Merge each pair of buckets into one twice as wide.
+    fn widen(&mut self) {
+        self.bucket_ms *= 2;
+        let mut buckets = std::mem::take(&mut self.buckets).into_iter();
+        while let Some(mut bucket) = buckets.next() {
+            if let Some(next) = buckets.next() {
+                bucket.merge(next);
+            }
+            self.buckets.push(bucket);
+        }
+        self.anomalies.clear();
+    }
+
+    /// Compare each bucket's rate against the mean of the preceding non-empty buckets.
+    pub fn mark_anomalies(&mut self) {
+        self.anomalies.clear();
+        let mut history: Vec<f64> = Vec::new();
+        let mut in_spike = false;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            if bucket.total == 0 {
+                continue;
+            }
+            let rate = bucket.problem_rate();
+            let window = &history[history.len().saturating_sub(BASELINE_WINDOW)..];
+            let spiking = !window.is_empty() && bucket.errors + bucket.warnings >= MIN_PROBLEMS && {
+                let baseline = window.iter().sum::<f64>() / window.len() as f64;
+                rate > baseline * 2.5 + 0.05
+            };
+            if spiking && !in_spike {
+                self.anomalies.push(i);
+            }
+            in_spike = spiking;
+            // Spikes don't feed the baseline, so a long incident stays flagged
+            if !spiking {
+                history.push(rate);
+            }
+        }
+    }
+}
+
+/// Draw the timeline strip: entry counts per bucket with errors and warnings
+/// stacked on top, and a marker where each spike starts. Returns the first
+/// entry of the clicked bucket.
+pub fn show(ui: &mut egui::Ui, timeline: &Timeline, error_color: Color32, warn_color: Color32) -> Option<usize> {
+    let size = Vec2::new(ui.available_width(), 40.0);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let max_total = timeline.buckets.iter().map(|b| b.total).max().unwrap_or(0).max(1) as f32;
+    let bar_width = rect.width() / timeline.buckets.len() as f32;
+    let bar_rect = |i: usize, count: usize, below: usize| {
+        let x = rect.left() + i as f32 * bar_width;
+        let bottom = rect.bottom() - (rect.height() - 8.0) * below as f32 / max_total;
+        let top = bottom - (rect.height() - 8.0) * count as f32 / max_total;
+        Rect::from_min_max(Pos2::new(x, top), Pos2::new(x + (bar_width - 1.0).max(1.0), bottom))
+    };
+
+    for (i, bucket) in timeline.buckets.iter().enumerate() {
+        let others = bucket.total - bucket.errors - bucket.warnings;
+        painter.rect_filled(bar_rect(i, others, 0), 0.0, ui.visuals().weak_text_color().gamma_multiply(0.5));
+        painter.rect_filled(bar_rect(i, bucket.warnings, others), 0.0, warn_color);
+        painter.rect_filled(bar_rect(i, bucket.errors, others + bucket.warnings), 0.0, error_color);
+    }
+    for &i in &timeline.anomalies {
+        let x = rect.left() + (i as f32 + 0.5) * bar_width;
+        painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(1.0, error_color));
+        painter.add(egui::Shape::convex_polygon(
+            vec![Pos2::new(x - 4.0, rect.top()), Pos2::new(x + 4.0, rect.top()), Pos2::new(x, rect.top() + 6.0)],
+            error_color,
+            Stroke::NONE,
+        ));
+    }
+
+    let pointer = response.hover_pos()?;
+    let i = (((pointer.x - rect.left()) / bar_width) as usize).min(timeline.buckets.len() - 1);
+    let bucket = &timeline.buckets[i];
+    let time = chrono::DateTime::from_timestamp_millis(bucket.start_ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    let mut text = format!("{}\n{} entries, {} errors, {} warnings", time, bucket.total, bucket.errors, bucket.warnings);
+    if timeline.anomalies.contains(&i) {
+        text.push_str("\n⚠ Error/warning rate spike");
+    }
+    let clicked = response.clicked();
+    response.on_hover_text(text);
+    if clicked {
+        bucket.first_entry
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::LogParser;
+
+    fn entry(parser: &LogParser, minute: u32, level: &str) -> LogEntry {
+        let line = format!("15.01.2024 {:02}:{:02}:00.000 *{}* [main] com.example.App Message", 10 + minute / 60, minute % 60, level);
+        parser.parse_line(&line, 1)
+    }
+
+    #[test]
+    fn counts_entries_into_at_most_the_bucket_count() {
+        let parser = LogParser::new();
+        let entries: Vec<LogEntry> = (0..600).map(|minute| entry(&parser, minute % 300, "INFO")).collect();
+        let timeline = build(&entries).unwrap();
+        assert!(timeline.buckets.len() <= BUCKET_COUNT);
+        assert_eq!(timeline.buckets.iter().map(|b| b.total).sum::<usize>(), 600);
+        assert_eq!(timeline.range.1 - timeline.range.0, 299 * 60_000);
+        assert_eq!(timeline.buckets[0].first_entry, Some(0));
+    }
+
+    #[test]
+    fn grows_backwards_for_earlier_entries() {
+        let parser = LogParser::new();
+        let mut entries = vec![entry(&parser, 100, "INFO"), entry(&parser, 110, "INFO")];
+        let mut timeline = build(&entries).unwrap();
+        entries.push(entry(&parser, 5, "ERROR"));
+        timeline.extend(&entries, 2);
+        assert_eq!(timeline.range, (build(&entries[2..]).unwrap().range.0, entries[1].time_ms().unwrap()));
+        assert_eq!(timeline.buckets[0].errors, 1);
+        assert_eq!(timeline.buckets[0].first_entry, Some(2));
+        assert_eq!(timeline.buckets.iter().map(|b| b.total).sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn flags_an_error_spike() {
+        let parser = LogParser::new();
+        let mut entries: Vec<LogEntry> = (0..200).map(|minute| entry(&parser, minute, "INFO")).collect();
+        entries.extend((0..20).map(|_| entry(&parser, 150, "ERROR")));
+        let timeline = build(&entries).unwrap();
+        assert_eq!(timeline.anomalies.len(), 1);
+        let spike = &timeline.buckets[timeline.anomalies[0]];
+        assert_eq!(spike.errors, 20);
+    }
+
+    #[test]
+    fn drops_the_oldest_entries_from_the_counts() {
+        let parser = LogParser::new();
+        let mut entries: Vec<LogEntry> = (0..100).map(|minute| entry(&parser, minute, if minute < 10 { "ERROR" } else { "INFO" })).collect();
+        let mut timeline = build(&entries).unwrap();
+        let bucket = timeline.index_of(entries[0].time_ms().unwrap());
+        let in_first_bucket = timeline.buckets[bucket].total;
+        
+        timeline.drop_front(&entries[..1], &entries[1..]);
+        entries.remove(0);
+        assert_eq!(timeline.buckets.iter().map(|b| b.total).sum::<usize>(), 99);
+        assert_eq!(timeline.buckets.iter().map(|b| b.errors).sum::<usize>(), 9);
+        // The bucket now starts at the entry after the dropped one
+        assert_eq!(timeline.buckets[bucket].first_entry, (in_first_bucket > 1).then_some(0));
+        assert_eq!(timeline.buckets.last().unwrap().first_entry, build(&entries).unwrap().buckets.last().unwrap().first_entry);
+    }
+
+    #[test]
+    fn has_nothing_without_timestamps() {
+        let parser = LogParser::new();
+        assert!(build(&[parser.parse_line("plain text", 1)]).is_none());
+    }
+}
@@ -0,0 +1,187 @@
+use egui::{Color32, Pos2, Rect, Sense, Vec2};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config;
+use crate::log_parser::{LogEntry, LogLevel};
+
+/// How to recognise requests in a log: a regex capturing the correlation ID
+/// (`id` named group or the first group), plus optional start and end markers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactionRules {
+    pub id_pattern: String,
+    #[serde(default)]
+    pub start_pattern: String,
+    #[serde(default)]
+    pub end_pattern: String,
+}
+
+pub struct Transaction {
+    pub id: String,
+    /// Indices into the entries, in file order
+    pub entries: Vec<usize>,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    /// True once the end pattern was seen
+    pub complete: bool,
+    /// True if any of its entries is an error
+    pub failed: bool,
+}
+
+impl Transaction {
+    pub fn duration_ms(&self) -> Option<i64> {
+        Some(self.end_ms? - self.start_ms?)
+    }
+}
+
+const GANTT_ROW_HEIGHT: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortColumn {
+    Id,
+    Entries,
+    Start,
+    Duration,
+}
+
+fn compile_optional(pattern: &str) -> Result<Option<Regex>, String> {
+    if pattern.trim().is_empty() {
+        Ok(None)
+    } else {
+        Regex::new(pattern).map(Some).map_err(|e| e.to_string())
+    }
+}
+
+/// Group entries into transactions by correlation ID. An entry matching the
+/// start pattern opens a new transaction for its ID; one matching the end
+/// pattern closes it. Without a start pattern the first entry of an ID opens it.
+pub fn group(rules: &TransactionRules, entries: &[LogEntry]) -> Result<Vec<Transaction>, String> {
+    let id_regex = Regex::new(&rules.id_pattern).map_err(|e| format!("ID pattern: {}", e))?;
+    let start_regex = compile_optional(&rules.start_pattern).map_err(|e| format!("Start pattern: {}", e))?;
+    let end_regex = compile_optional(&rules.end_pattern).map_err(|e| format!("End pattern: {}", e))?;
+
+    let mut transactions: Vec<Transaction> = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        let Some(caps) = id_regex.captures(&entry.raw_line) else {
+            continue;
+        };
+        let Some(id) = caps.name("id").or_else(|| caps.get(1)) else {
+            continue;
+        };
+        let id = id.as_str();
+
+        let is_start = start_regex.as_ref().map_or(false, |r| r.is_match(&entry.raw_line));
+        let current = match open.get(id) {
+            Some(&t) if !is_start => t,
+            _ => {
+                if start_regex.is_some() && !is_start {
+                    continue; // Lines of a request whose start we didn't see
+                }
+                transactions.push(Transaction {
+                    id: id.to_string(),
+                    entries: Vec::new(),
+                    start_ms: None,
+                    end_ms: None,
+                    complete: false,
+                    failed: false,
+                });
+                open.insert(id.to_string(), transactions.len() - 1);
+                transactions.len() - 1
+            }
+        };
+
+        let transaction = &mut transactions[current];
+        transaction.entries.push(idx);
+        if let Some(time) = entry.time_ms() {
+            transaction.start_ms.get_or_insert(time);
+            transaction.end_ms = Some(time);
+        }
+        if entry.level == LogLevel::Error {
+            transaction.failed = true;
+        }
+        if end_regex.as_ref().map_or(false, |r| r.is_match(&entry.raw_line)) {
+            transaction.complete = true;
+            open.remove(id);
+        }
+    }
+    Ok(transactions)
+}
+
+pub fn sort(transactions: &mut [Transaction], column: SortColumn, descending: bool) {
+    transactions.sort_by(|a, b| {
+        let order = match column {
+            SortColumn::Id => a.id.cmp(&b.id),
+            SortColumn::Entries => a.entries.len().cmp(&b.entries.len()),
+            SortColumn::Start => a.start_ms.cmp(&b.start_ms),
+            SortColumn::Duration => a.duration_ms().cmp(&b.duration_ms()),
+        };
+        if descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+}
+
+/// Draw one bar per transaction over their shared time span, in start order,
+/// colored by outcome. Returns the first entry of the clicked transaction.
+pub fn show_gantt(ui: &mut egui::Ui, transactions: &[Transaction], ok: Color32, failed: Color32) -> Option<usize> {
+    let mut order: Vec<&Transaction> = transactions.iter().filter(|t| t.start_ms.is_some()).collect();
+    order.sort_by_key(|t| t.start_ms);
+    let first = order.iter().filter_map(|t| t.start_ms).min()?;
+    let last = order.iter().filter_map(|t| t.end_ms).max()?;
+    let span_ms = (last - first).max(1) as f32;
+
+    let mut clicked = None;
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show_rows(ui, GANTT_ROW_HEIGHT, order.len(), |ui, rows| {
+        for transaction in &order[rows] {
+            let (rect, response) =
+                ui.allocate_exact_size(Vec2::new(ui.available_width(), GANTT_ROW_HEIGHT), Sense::click());
+            let (Some(start), Some(end)) = (transaction.start_ms, transaction.end_ms) else {
+                continue;
+            };
+            let x = |time: i64| rect.left() + rect.width() * (time - first) as f32 / span_ms;
+            let bar = Rect::from_min_max(
+                Pos2::new(x(start), rect.top() + 1.0),
+                Pos2::new(x(end).max(x(start) + 2.0), rect.bottom() - 1.0),
+            );
+            let color = if transaction.failed {
+                failed
+            } else if transaction.complete {
+                ok
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            let color = if response.hovered() { color } else { color.gamma_multiply(0.8) };
+            ui.painter().rect_filled(bar, 1.0, color);
+
+            let duration = transaction.duration_ms().map(format_duration).unwrap_or_default();
+            let response = response.on_hover_text(format!("{}\n{} · {} entries", transaction.id, duration, transaction.entries.len()));
+            if response.clicked() {
+                clicked = transaction.entries.first().copied();
+            }
+        }
+    });
+    clicked
+}
+
+pub fn format_duration(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.2} s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+/// Load the saved grouping rules. A missing or unreadable file yields empty rules.
+pub fn load_rules() -> TransactionRules {
+    config::load_json("transactions.json")
+}
+
+pub fn save_rules(rules: &TransactionRules) -> Result<(), String> {
+    config::save_json("transactions.json", rules)
+}
@@ -0,0 +1,275 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::file_open;
+use crate::log_parser::{LogEntry, LogLevel, SourceFields};
+
+/// Batches of lines held for the app. Beyond it `log`'s output stops being
+/// read until the app catches up.
+const MAX_WAITING_BATCHES: usize = 256;
+/// Lines read before they are handed on together.
+const BATCH_LINES: usize = 500;
+
+/// The least severe messages shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogDetail {
+    Default,
+    Info,
+    Debug,
+}
+
+impl LogDetail {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogDetail::Default => "default",
+            LogDetail::Info => "info",
+            LogDetail::Debug => "debug",
+        }
+    }
+}
+
+/// What to read, as edited in the unified log window.
+#[derive(Debug, Clone)]
+pub struct UnifiedLogConfig {
+    /// `log` predicate, e.g. `subsystem == "com.example.app"`; empty for everything
+    pub predicate: String,
+    pub level: LogDetail,
+    /// Read the booted iOS simulator's log instead of the Mac's
+    pub simulator: bool,
+    /// Minutes of history shown before streaming; 0 for none
+    pub lookback_minutes: u32,
+}
+
+impl Default for UnifiedLogConfig {
+    fn default() -> Self {
+        Self {
+            predicate: String::new(),
+            level: LogDetail::Info,
+            simulator: false,
+            lookback_minutes: 0,
+        }
+    }
+}
+
+impl UnifiedLogConfig {
+    /// `predicate @ simulator`, as shown in the header.
+    pub fn description(&self) -> String {
+        let predicate = if self.predicate.trim().is_empty() { "all messages" } else { self.predicate.trim() };
+        let source = if self.simulator { "iOS Simulator" } else { "this Mac" };
+        format!("{} @ {}", predicate, source)
+    }
+
+    /// `log <action>` with the options, run in the simulator when asked.
+    fn command(&self, action: &str) -> Command {
+        let mut command = if self.simulator {
+            let mut command = Command::new("xcrun");
+            command.args(["simctl", "spawn", "booted", "log"]);
+            command
+        } else {
+            Command::new("log")
+        };
+        command.args([action, "--style", "ndjson"]);
+        match action {
+            "show" => {
+                command.args(["--last", &format!("{}m", self.lookback_minutes)]);
+                // `show` takes the levels as flags
+                match self.level {
+                    LogDetail::Default => {}
+                    LogDetail::Info => {
+                        command.arg("--info");
+                    }
+                    LogDetail::Debug => {
+                        command.args(["--info", "--debug"]);
+                    }
+                }
+            }
+            _ => {
+                command.args(["--level", self.level.name()]);
+            }
+        }
+        if !self.predicate.trim().is_empty() {
+            command.args(["--predicate", self.predicate.trim()]);
+        }
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        command
+    }
+}
+
+/// Streams the macOS unified log (`log stream`), or an iOS simulator's,
+/// after showing the recent history when asked. Each message becomes an
+/// entry with its type as level, its process as thread and its subsystem
+/// and category as class.
+pub struct UnifiedLog {
+    description: String,
+    receiver: mpsc::Receiver<Vec<LogEntry>>,
+    stop: Arc<AtomicBool>,
+    /// The `log` process currently running, killed on stop
+    child: Arc<Mutex<Option<Child>>>,
+    received: usize,
+}
+
+impl UnifiedLog {
+    pub fn start(config: &UnifiedLogConfig) -> Result<Self, String> {
+        if !cfg!(target_os = "macos") {
+            return Err("The unified log is only available on macOS".to_string());
+        }
+        let first = if config.lookback_minutes > 0 { "show" } else { "stream" };
+        let child = config.command(first).spawn().map_err(|e| format!("Failed to run log: {}", e))?;
+        let child = Arc::new(Mutex::new(Some(child)));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(MAX_WAITING_BATCHES);
+        let (thread_config, thread_child, thread_stop) = (config.clone(), child.clone(), stop.clone());
+        thread::spawn(move || run(&thread_config, &thread_child, &sender, &thread_stop));
+        Ok(Self {
+            description: config.description(),
+            receiver,
+            stop,
+            child,
+            received: 0,
+        })
+    }
+
+    /// The predicate and device read, e.g. "process == \"Mail\" @ this Mac".
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Messages received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Entries received since the last call, in order. Their line numbers
+    /// are left for the caller to assign.
+    pub fn take_entries(&mut self) -> Vec<LogEntry> {
+        let entries: Vec<LogEntry> = self.receiver.try_iter().flatten().collect();
+        self.received += entries.len();
+        entries
+    }
+}
+
+impl Drop for UnifiedLog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(mut child) = self.child.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            child.kill().ok();
+            child.wait().ok();
+        }
+    }
+}
+
+/// Read the history, if asked, then the stream, until either ends or the
+/// source is stopped.
+fn run(config: &UnifiedLogConfig, child: &Mutex<Option<Child>>, sender: &mpsc::SyncSender<Vec<LogEntry>>, stop: &AtomicBool) {
+    let mut streaming = config.lookback_minutes == 0;
+    loop {
+        let pipes = child.lock().unwrap_or_else(|e| e.into_inner()).as_mut().map(|child| (child.stdout.take(), child.stderr.take()));
+        let Some((Some(stdout), stderr)) = pipes else {
+            return;
+        };
+        if !read(stdout, sender) || stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut error = String::new();
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_string(&mut error).ok();
+        }
+        let status = child.lock().unwrap_or_else(|e| e.into_inner()).take().and_then(|mut child| child.wait().ok());
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let failed = !status.is_some_and(|status| status.success());
+        if streaming || failed {
+            let reason = if error.trim().is_empty() { "log exited".to_string() } else { error.trim().to_string() };
+            let note = format!("──────── Stream ended: {} ────────", reason);
+            sender.send(vec![LogEntry::note(&note, 0)]).ok();
+            file_open::wake_ui();
+            return;
+        }
+
+        // The history is shown; follow new messages from here
+        streaming = true;
+        match config.command("stream").spawn() {
+            Ok(stream) => *child.lock().unwrap_or_else(|e| e.into_inner()) = Some(stream),
+            Err(e) => {
+                let note = format!("──────── Failed to run log: {} ────────", e);
+                sender.send(vec![LogEntry::note(&note, 0)]).ok();
+                file_open::wake_ui();
+                return;
+            }
+        }
+    }
+}
+
+/// Hand on the messages `log` prints until it exits. False once the app is gone.
+fn read(stdout: impl Read, sender: &mpsc::SyncSender<Vec<LogEntry>>) -> bool {
+    let mut reader = BufReader::new(stdout);
+    let mut buffer = Vec::new();
+    let mut batch = Vec::new();
+    loop {
+        buffer.clear();
+        let done = !matches!(reader.read_until(b'\n', &mut buffer), Ok(n) if n > 0);
+        // Besides messages there's a "Filtering the log data..." banner and a closing summary
+        if let Ok(Value::Object(message)) = serde_json::from_slice::<Value>(&buffer) {
+            if message.contains_key("eventMessage") {
+                batch.push(message_entry(&Value::Object(message)));
+            }
+        }
+        if !batch.is_empty() && (done || batch.len() >= BATCH_LINES || reader.buffer().is_empty()) {
+            if sender.send(std::mem::take(&mut batch)).is_err() {
+                return false;
+            }
+            file_open::wake_ui();
+        }
+        if done {
+            return true;
+        }
+    }
+}
+
+/// A message as `timestamp Type process[pid:tid] (subsystem:category) message`.
+fn message_entry(message: &Value) -> LogEntry {
+    let text = |name: &str| message[name].as_str().unwrap_or_default();
+    // "/Applications/Mail.app/Contents/MacOS/Mail" shows as Mail
+    let process = text("processImagePath").rsplit('/').next().unwrap_or_default();
+    let kind = text("messageType");
+    let scope = match (text("subsystem"), text("category")) {
+        ("", _) => text("senderImagePath").rsplit('/').next().unwrap_or_default().to_string(),
+        (subsystem, "") => subsystem.to_string(),
+        (subsystem, category) => format!("{}:{}", subsystem, category),
+    };
+    let level = match kind {
+        "Fault" | "Error" => LogLevel::Error,
+        "Default" | "Info" => LogLevel::Info,
+        "Debug" => LogLevel::Debug,
+        _ => LogLevel::Unknown,
+    };
+
+    let timestamp = text("timestamp");
+    let mut line = format!("{} {:<7} {}[{}:{}]", timestamp, kind, process, message["processID"], message["threadID"]);
+    if !scope.is_empty() {
+        line.push_str(&format!(" ({})", scope));
+    }
+    line.push(' ');
+    let message_start = line.len();
+    line.push_str(text("eventMessage").trim_end());
+    let message_end = line[message_start..].find('\n').map_or(line.len(), |end| message_start + end);
+
+    let fields = SourceFields {
+        level,
+        timestamp: Some(&line[..timestamp.len()]).filter(|timestamp| !timestamp.is_empty()),
+        // 2024-01-15 10:00:00.123456-0800
+        time_ms: chrono::DateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f%z")
+            .ok()
+            .map(|time| time.naive_local().and_utc().timestamp_millis()),
+        thread: Some(process).filter(|process| !process.is_empty()),
+        class: Some(scope.as_str()).filter(|scope| !scope.is_empty()),
+        message: &line[message_start..message_end],
+    };
+    LogEntry::from_source(&line, 0, fields)
+}
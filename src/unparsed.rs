@@ -0,0 +1,63 @@
+//! Lines of a log that no entry holds: text before the first entry, or after
+//! a blank line that ended an entry, which the parser passes over. They are
+//! found from the gaps between entries' lines, so the parse itself is untouched.
+
+use std::ops::Range;
+
+use crate::log_parser::LogEntry;
+
+/// Unparsed lines kept to show; the rest are only counted.
+const MAX_LINES: usize = 1_000;
+
+/// The non-blank lines of a log left out of every entry.
+#[derive(Debug, Clone, Default)]
+pub struct Unparsed {
+    /// How many there are in all
+    pub count: usize,
+    /// The first of them, with their line numbers
+    pub lines: Vec<(usize, String)>,
+}
+
+/// Notes which lines entries cover as they go by, in file order.
+#[derive(Debug, Default)]
+pub struct UnparsedTracker {
+    /// The line after the last entry's
+    next_line: usize,
+    /// Lines between entries, as 1-based line numbers
+    gaps: Vec<Range<usize>>,
+}
+
+impl UnparsedTracker {
+    pub fn new() -> Self {
+        Self { next_line: 1, gaps: Vec::new() }
+    }
+
+    pub fn note(&mut self, entry: &LogEntry) {
+        if entry.line_number > self.next_line {
+            self.gaps.push(self.next_line..entry.line_number);
+        }
+        self.next_line = self.next_line.max(entry.line_number + entry.raw_line.lines().count().max(1));
+    }
+
+    /// The lines of `content` in the gaps, blank ones aside.
+    pub fn finish(mut self, content: &str) -> Unparsed {
+        self.gaps.push(self.next_line..usize::MAX);
+        let mut unparsed = Unparsed::default();
+        let mut gaps = self.gaps.iter().peekable();
+        for (i, line) in content.lines().enumerate() {
+            let number = i + 1;
+            while gaps.next_if(|gap| gap.end <= number).is_some() {}
+            let Some(gap) = gaps.peek() else {
+                break;
+            };
+            if number < gap.start || line.trim().is_empty() {
+                continue;
+            }
+            unparsed.count += 1;
+            if unparsed.lines.len() < MAX_LINES {
+                unparsed.lines.push((number, line.to_string()));
+            }
+        }
+        unparsed
+    }
+}
@@ -0,0 +1,228 @@
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::file_open;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/jmanuelbr/LogRocket/releases/latest";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A release newer than this build.
+#[derive(Debug, Clone)]
+pub struct Release {
+    /// e.g. "0.2.0", without the tag's `v`
+    pub version: String,
+    /// The release notes (Markdown)
+    pub notes: String,
+    pub page_url: String,
+    /// The build for this platform, if one is attached: its file name and URL
+    pub asset: Option<(String, String)>,
+}
+
+/// Asks GitHub for the latest release in the background.
+pub struct UpdateCheck {
+    receiver: mpsc::Receiver<Result<Option<Release>, String>>,
+}
+
+impl UpdateCheck {
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            sender.send(latest_release()).ok();
+            file_open::wake_ui();
+        });
+        Self { receiver }
+    }
+
+    /// Once done: the newer release if there is one, or why the check failed.
+    pub fn poll(&self) -> Option<Result<Option<Release>, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+fn latest_release() -> Result<Option<Release>, String> {
+    let response = ureq::get(LATEST_RELEASE_URL)
+        .timeout(CHECK_TIMEOUT)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "log-rocket")
+        .call()
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    let release: Value = serde_json::from_reader(response.into_reader()).map_err(|e| format!("Failed to read the latest release: {}", e))?;
+    let tag = release["tag_name"].as_str().ok_or("The latest release has no tag")?;
+    let version = tag.trim_start_matches(['v', 'V']).to_string();
+    if !is_newer(&version, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let assets = release["assets"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let asset = assets
+        .iter()
+        .filter_map(|asset| Some((asset["name"].as_str()?.to_string(), asset["browser_download_url"].as_str()?.to_string())))
+        .find(|(name, _)| is_for_this_platform(name));
+    Ok(Some(Release {
+        version,
+        notes: release["body"].as_str().unwrap_or_default().trim().to_string(),
+        page_url: release["html_url"].as_str().unwrap_or_default().to_string(),
+        asset,
+    }))
+}
+
+/// Whether `version` is later than `current`, comparing the dotted numbers.
+/// Pre-releases (`1.0.0-beta`) count as earlier than their release.
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |version: &str| {
+        let (numbers, pre) = match version.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre.to_string())),
+            None => (version, None),
+        };
+        let numbers: Vec<u64> = numbers.split('.').map(|part| part.parse().unwrap_or(0)).collect();
+        (numbers, pre.is_none())
+    };
+    let (mut version, released) = parse(version);
+    let (mut current, current_released) = parse(current);
+    let len = version.len().max(current.len());
+    version.resize(len, 0);
+    current.resize(len, 0);
+    (version, released) > (current, current_released)
+}
+
+/// Guess from an asset's name whether it is the build for this OS.
+fn is_for_this_platform(name: &str) -> bool {
+    let name = name.to_lowercase();
+    if cfg!(target_os = "macos") {
+        name.ends_with(".dmg") || name.contains("mac") || name.contains("darwin")
+    } else if cfg!(windows) {
+        name.ends_with(".msi") || name.ends_with(".exe") || name.contains("windows")
+    } else {
+        name.ends_with(".appimage") || name.ends_with(".deb") || name.contains("linux")
+    }
+}
+
+/// A release build being saved to the Downloads folder.
+pub struct UpdateDownload {
+    received: Arc<AtomicU64>,
+    /// The build's size, 0 until known
+    total: Arc<AtomicU64>,
+    receiver: mpsc::Receiver<Result<PathBuf, String>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl UpdateDownload {
+    pub fn start(name: &str, url: &str) -> Self {
+        let received = Arc::new(AtomicU64::new(0));
+        let total = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let (name, url) = (name.to_string(), url.to_string());
+        let (thread_received, thread_total, thread_stop) = (received.clone(), total.clone(), stop.clone());
+        thread::spawn(move || {
+            let result = download(&name, &url, &thread_received, &thread_total, &thread_stop);
+            sender.send(result).ok();
+            file_open::wake_ui();
+        });
+        Self {
+            received,
+            total,
+            receiver,
+            stop,
+        }
+    }
+
+    /// Fraction received, when the size is known.
+    pub fn progress(&self) -> Option<f32> {
+        match self.total.load(Ordering::Relaxed) {
+            0 => None,
+            total => Some((self.received.load(Ordering::Relaxed) as f32 / total as f32).min(1.0)),
+        }
+    }
+
+    /// Where the build was saved once done, or why it failed.
+    pub fn poll(&self) -> Option<Result<PathBuf, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for UpdateDownload {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn download(name: &str, url: &str, received: &AtomicU64, total: &AtomicU64, stop: &AtomicBool) -> Result<PathBuf, String> {
+    let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or("No Downloads folder found")?;
+    let path = dir.join(name);
+    let partial = dir.join(format!("{}.part", name));
+    let agent = ureq::AgentBuilder::new().timeout_connect(CHECK_TIMEOUT).timeout_read(READ_TIMEOUT).build();
+    let response = agent
+        .get(url)
+        .set("User-Agent", "log-rocket")
+        .call()
+        .map_err(|e| format!("Failed to download {}: {}", name, e))?;
+    if let Some(length) = response.header("Content-Length").and_then(|length| length.parse().ok()) {
+        total.store(length, Ordering::Relaxed);
+    }
+
+    let mut file = File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0; 64 * 1024];
+    let failed = |file: File, error: String| {
+        drop(file);
+        fs::remove_file(&partial).ok();
+        Err(error)
+    };
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return failed(file, "Download cancelled".to_string());
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return failed(file, format!("Failed to download {}: {}", name, e)),
+        };
+        if let Err(e) = file.write_all(&buffer[..read]) {
+            return failed(file, format!("Failed to write {}: {}", partial.display(), e));
+        }
+        received.fetch_add(read as u64, Ordering::Relaxed);
+        file_open::wake_ui();
+    }
+    drop(file);
+    fs::rename(&partial, &path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_dotted_versions_by_number() {
+        assert!(is_newer("0.2.0", "0.1.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("1.0.1", "1.0"));
+        assert!(!is_newer("1.0", "1.0.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.9.9", "0.10.0"));
+    }
+
+    #[test]
+    fn counts_pre_releases_before_their_release() {
+        assert!(is_newer("1.0.0", "1.0.0-beta"));
+        assert!(!is_newer("1.0.0-beta", "1.0.0"));
+        assert!(is_newer("1.0.0-beta", "0.9.0"));
+        assert!(!is_newer("1.0.0-rc.1", "1.0.0-rc.1"));
+    }
+
+    #[test]
+    fn reads_malformed_parts_as_zero() {
+        assert!(!is_newer("", "0.1.0"));
+        assert!(!is_newer("latest", "0.1.0"));
+        assert!(is_newer("1.x", "0.9"));
+        assert!(!is_newer("0.1.x", "0.1.0"));
+    }
+}
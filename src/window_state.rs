@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// Window geometry and panel sizes, restored on the next start.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowState {
+    pub width: f32,
+    pub height: f32,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub maximized: bool,
+    pub sidebar_width: f32,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self {
+            width: 1200.0,
+            height: 800.0,
+            x: None,
+            y: None,
+            maximized: true,
+            sidebar_width: 250.0,
+        }
+    }
+}
+
+impl WindowState {
+    /// Track the window as it is now. Size and position are only taken from a
+    /// normal window, so un-maximizing later restores the previous geometry.
+    pub fn update(&mut self, info: &eframe::WindowInfo) {
+        self.maximized = info.maximized;
+        if info.maximized || info.minimized || info.fullscreen {
+            return;
+        }
+        self.width = info.size.x;
+        self.height = info.size.y;
+        if let Some(position) = info.position {
+            self.x = Some(position.x);
+            self.y = Some(position.y);
+        }
+    }
+}
+
+/// Load the saved window state, or the defaults if there is none.
+pub fn load() -> WindowState {
+    config::load_json("window.json")
+}
+
+pub fn save(state: &WindowState) -> Result<(), String> {
+    config::save_json("window.json", state)
+}